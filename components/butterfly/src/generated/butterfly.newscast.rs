@@ -40,6 +40,9 @@ pub struct Service {
     pub cfg: ::std::option::Option<Vec<u8>>,
     #[prost(message, optional, tag="12")]
     pub sys: ::std::option::Option<SysInfo>,
+    /// Whether the member is a canary holding back the rest of its service group while it soaks.
+    #[prost(bool, optional, tag="13")]
+    pub soaking: ::std::option::Option<bool>,
 }
 #[derive(Clone, PartialEq, Message)]
 #[derive(Serialize, Deserialize)]
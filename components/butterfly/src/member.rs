@@ -25,7 +25,7 @@ use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::RwLock;
 
 use habitat_core::util::ToI64;
-use prometheus::IntGaugeVec;
+use prometheus::{IntCounterVec, IntGaugeVec};
 use rand::{
     seq::{IteratorRandom, SliceRandom},
     thread_rng,
@@ -53,6 +53,12 @@ lazy_static! {
         &["health"]
     )
     .unwrap();
+    static ref PEER_HEALTH_TRANSITIONS: IntCounterVec = register_int_counter_vec!(
+        "hab_butterfly_peer_health_transitions_total",
+        "Number of times a butterfly peer's health changed",
+        &["from", "to"]
+    )
+    .unwrap();
 }
 
 /// Wraps a `u64` to represent the "incarnation number" of a
@@ -541,6 +547,7 @@ impl MemberList {
             hash_map::Entry::Occupied(mut entry) => {
                 let val = entry.get_mut();
                 if incoming.newer_or_less_healthy_than(val.member.incarnation, val.health) {
+                    count_health_transition(val.health, incoming.health);
                     *val = member_list::Entry {
                         member: incoming.member,
                         health: incoming.health,
@@ -579,10 +586,13 @@ impl MemberList {
                 health,
                 Health::Departed
             );
+            count_health_transition(*health, Health::Departed);
             *health = Health::Departed;
         } else {
             trace!("set_departed called on unknown member {}", member_id);
+            return;
         }
+        self.calculate_peer_health_metrics();
     }
 
     fn calculate_peer_health_metrics(&self) {
@@ -773,6 +783,7 @@ impl MemberList {
                     ..
                 } = v;
                 if *health == precursor_health && now >= *health_updated_at + timeout {
+                    count_health_transition(*health, expiring_to);
                     *health = expiring_to;
                     *health_updated_at = now;
                     Some(id.clone())
@@ -784,6 +795,7 @@ impl MemberList {
 
         if !expired.is_empty() {
             self.increment_update_counter();
+            self.calculate_peer_health_metrics();
         }
 
         expired
@@ -794,6 +806,14 @@ impl MemberList {
     }
 }
 
+fn count_health_transition(from: Health, to: Health) {
+    if from != to {
+        PEER_HEALTH_TRANSITIONS
+            .with_label_values(&[&from.to_string(), &to.to_string()])
+            .inc();
+    }
+}
+
 /// This proxy wraps a MemberList so that we can customize its serialization logic.
 pub struct MemberListProxy<'a>(&'a MemberList);
 
@@ -902,7 +922,7 @@ mod tests {
     }
 
     mod member_list {
-        use crate::member::{Health, Member, MemberList, PINGREQ_TARGETS};
+        use crate::member::{Health, Member, MemberList, PEER_HEALTH_TRANSITIONS, PINGREQ_TARGETS};
 
         fn populated_member_list(size: u64) -> MemberList {
             let ml = MemberList::new();
@@ -933,6 +953,29 @@ mod tests {
             assert!(list_a != list_b);
         }
 
+        #[test]
+        fn health_changes_are_counted() {
+            fn transitions(from: Health, to: Health) -> i64 {
+                PEER_HEALTH_TRANSITIONS
+                    .with_label_values(&[&from.to_string(), &to.to_string()])
+                    .get()
+            }
+
+            let ml = MemberList::new();
+            let member = Member::default();
+            let suspected = transitions(Health::Alive, Health::Suspect);
+            let departed = transitions(Health::Suspect, Health::Departed);
+
+            ml.insert(member.clone(), Health::Alive);
+            ml.insert(member.clone(), Health::Suspect);
+            ml.set_departed(&member.id);
+
+            // Other tests may change the health of their own members at the same time, so the
+            // counters only have to have gone up.
+            assert!(transitions(Health::Alive, Health::Suspect) > suspected);
+            assert!(transitions(Health::Suspect, Health::Departed) > departed);
+        }
+
         #[test]
         fn health_of() {
             let ml = populated_member_list(1);
@@ -165,6 +165,15 @@ impl RumorHeat {
         }
     }
 
+    /// The number of rumors being tracked, by type.
+    pub fn len_by_type(&self) -> HashMap<RumorType, usize> {
+        let mut counts = HashMap::new();
+        for rk in self.0.read().expect("RumorHeat lock poisoned").keys() {
+            *counts.entry(rk.kind).or_insert(0) += 1;
+        }
+        counts
+    }
+
     /// When a member is considered "gone" (e.g., once it is
     /// considered Departed), we can get rid of all the "cooling"
     /// information, since we're not going to be sending anything
@@ -327,6 +336,21 @@ mod tests {
         assert!(hot_rumors.is_empty());
     }
 
+    #[test]
+    fn tracked_rumors_are_counted_by_type() {
+        let heat = RumorHeat::default();
+        assert!(heat.len_by_type().is_empty());
+
+        heat.start_hot_rumor(&FakeRumor::default());
+        heat.start_hot_rumor(&FakeRumor::default());
+        heat.start_hot_rumor(RumorKey::new(RumorType::Member, "member-1", ""));
+
+        let counts = heat.len_by_type();
+        assert_eq!(counts.get(&RumorType::Fake), Some(&2));
+        assert_eq!(counts.get(&RumorType::Member), Some(&1));
+        assert_eq!(counts.get(&RumorType::Service), None);
+    }
+
     #[test]
     fn a_hot_rumor_is_returned_as_such() {
         let l = lock_rumor_limit();
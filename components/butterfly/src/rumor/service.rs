@@ -41,6 +41,9 @@ pub struct Service {
     pub pkg: String,
     pub cfg: Vec<u8>,
     pub sys: SysInfo,
+    /// Whether this member is a canary whose service group holds back its update until it has
+    /// soaked.
+    pub soaking: bool,
 }
 
 // Ensures that `cfg` is rendered as a map, and not an array of bytes
@@ -49,7 +52,7 @@ impl Serialize for Service {
     where
         S: Serializer,
     {
-        let mut strukt = serializer.serialize_struct("service", 7)?;
+        let mut strukt = serializer.serialize_struct("service", 8)?;
         let cfg: toml::value::Table = toml::from_slice(&self.cfg).unwrap_or_default();
         strukt.serialize_field("member_id", &self.member_id)?;
         strukt.serialize_field("service_group", &self.service_group)?;
@@ -58,6 +61,7 @@ impl Serialize for Service {
         strukt.serialize_field("cfg", &cfg)?;
         strukt.serialize_field("sys", &self.sys)?;
         strukt.serialize_field("initialized", &self.initialized)?;
+        strukt.serialize_field("soaking", &self.soaking)?;
         strukt.end()
     }
 }
@@ -110,6 +114,7 @@ impl Service {
             initialized: false,
             pkg: package.to_string(),
             sys: sys,
+            soaking: false,
             cfg: cfg
                 .map(|v| {
                     // Directly serializing a toml::value::Table can lead to an error
@@ -147,6 +152,7 @@ impl FromProto<newscast::Rumor> for Service {
                 .sys
                 .ok_or(Error::ProtocolMismatch("sys"))
                 .and_then(SysInfo::from_proto)?,
+            soaking: payload.soaking.unwrap_or(false),
         })
     }
 }
@@ -161,6 +167,7 @@ impl From<Service> for newscast::Service {
             pkg: Some(value.pkg),
             cfg: Some(value.cfg),
             sys: Some(value.sys.into()),
+            soaking: Some(value.soaking),
         }
     }
 }
@@ -63,6 +63,8 @@ impl Expire {
                     .start_hot_rumor(RumorKey::new(RumorType::Member, id, ""));
             }
 
+            self.server.update_rumor_metrics();
+
             thread::sleep(Duration::from_millis(LOOP_DELAY_MS));
         }
     }
@@ -42,6 +42,12 @@ lazy_static! {
         &["type", "mode"]
     )
     .unwrap();
+    static ref SWIM_BYTES_RECEIVED_TOTAL: IntCounterVec = register_int_counter_vec!(
+        "hab_butterfly_swim_received_bytes_total",
+        "Total number of bytes of SWIM messages received",
+        &["type", "mode"]
+    )
+    .unwrap();
 }
 
 /// Takes the Server and a channel to send received Acks to the outbound thread.
@@ -83,6 +89,9 @@ impl Inbound {
                             SWIM_BYTES_RECEIVED
                                 .with_label_values(label_values)
                                 .set(length.to_i64());
+                            SWIM_BYTES_RECEIVED_TOTAL
+                                .with_label_values(label_values)
+                                .inc_by(length.to_i64());
                             SWIM_MESSAGES_RECEIVED.with_label_values(label_values).inc();
                             continue;
                         }
@@ -99,6 +108,9 @@ impl Inbound {
                             SWIM_BYTES_RECEIVED
                                 .with_label_values(label_values)
                                 .set(bytes_received.to_i64());
+                            SWIM_BYTES_RECEIVED_TOTAL
+                                .with_label_values(label_values)
+                                .inc_by(bytes_received.to_i64());
                             SWIM_MESSAGES_RECEIVED.with_label_values(label_values).inc();
                             continue;
                         }
@@ -110,6 +122,9 @@ impl Inbound {
                     SWIM_BYTES_RECEIVED
                         .with_label_values(&[msg.kind.as_str(), "success"])
                         .set(bytes_received.to_i64());
+                    SWIM_BYTES_RECEIVED_TOTAL
+                        .with_label_values(&[msg.kind.as_str(), "success"])
+                        .inc_by(bytes_received.to_i64());
                     SWIM_MESSAGES_RECEIVED
                         .with_label_values(&[msg.kind.as_str(), "success"])
                         .inc();
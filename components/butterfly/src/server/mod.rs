@@ -42,7 +42,7 @@ use std::thread;
 use std::time::{Duration, Instant};
 
 use habitat_core::crypto::SymKey;
-use prometheus::{HistogramTimer, HistogramVec, IntGauge};
+use prometheus::{HistogramTimer, HistogramVec, IntGauge, IntGaugeVec};
 use serde::ser::SerializeStruct;
 use serde::{Serialize, Serializer};
 
@@ -77,8 +77,31 @@ lazy_static! {
         &["service_group"]
     )
     .unwrap();
+    static ref RUMORS: IntGaugeVec = register_int_gauge_vec!(
+        "hab_butterfly_rumors",
+        "Number of rumors in the rumor stores",
+        &["type"]
+    )
+    .unwrap();
+    static ref RUMOR_HEAT_QUEUE: IntGaugeVec = register_int_gauge_vec!(
+        "hab_butterfly_rumor_heat_queue",
+        "Number of rumors whose heat is tracked for gossiping",
+        &["type"]
+    )
+    .unwrap();
 }
 
+/// The rumor types kept by a Server.
+const RUMOR_TYPES: [RumorType; 7] = [
+    RumorType::Member,
+    RumorType::Service,
+    RumorType::ServiceConfig,
+    RumorType::ServiceFile,
+    RumorType::Election,
+    RumorType::ElectionUpdate,
+    RumorType::Departure,
+];
+
 // We need this here to track how long it takes to complete an election. We need to store the timer
 // somehow so we can reference it between separate function invocations, and storing it directly in
 // the Server struct isn't an option, since HistogramTimer doesn't implement Debug.
@@ -1214,6 +1237,28 @@ impl Server {
     pub fn is_departed(&self) -> bool {
         self.departed.load(Ordering::Relaxed)
     }
+
+    /// Update the metrics describing the sizes of the rumor stores and of the heat queue.
+    pub fn update_rumor_metrics(&self) {
+        let heat = self.rumor_heat.len_by_type();
+        for kind in RUMOR_TYPES.iter() {
+            let size = match *kind {
+                RumorType::Member => self.member_list.len(),
+                RumorType::Service => self.service_store.len(),
+                RumorType::ServiceConfig => self.service_config_store.len(),
+                RumorType::ServiceFile => self.service_file_store.len(),
+                RumorType::Election => self.election_store.len(),
+                RumorType::ElectionUpdate => self.update_store.len(),
+                RumorType::Departure => self.departure_store.len(),
+                RumorType::Fake | RumorType::Fake2 => continue,
+            };
+            let label = kind.to_string();
+            RUMORS.with_label_values(&[&label]).set(size as i64);
+            RUMOR_HEAT_QUEUE
+                .with_label_values(&[&label])
+                .set(*heat.get(kind).unwrap_or(&0) as i64);
+        }
+    }
 }
 
 impl Serialize for Server {
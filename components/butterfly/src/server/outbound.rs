@@ -62,6 +62,12 @@ lazy_static! {
         &["type"]
     )
     .unwrap();
+    static ref SWIM_BYTES_SENT_TOTAL: IntCounterVec = register_int_counter_vec!(
+        "hab_butterfly_swim_sent_bytes_total",
+        "Total number of bytes of SWIM messages sent",
+        &["type"]
+    )
+    .unwrap();
 }
 
 /// Where an Ack came from; either Ping or PingReq.
@@ -361,6 +367,9 @@ pub fn pingreq(server: &Server, socket: &UdpSocket, pingreq_target: &Member, tar
             SWIM_BYTES_SENT
                 .with_label_values(label_values)
                 .set(payload.len().to_i64());
+            SWIM_BYTES_SENT_TOTAL
+                .with_label_values(label_values)
+                .inc_by(payload.len().to_i64());
             trace!(
                 "Sent PingReq to {}@{} for {}@{}",
                 &pingreq_target.id,
@@ -423,6 +432,9 @@ pub fn ping(
             SWIM_BYTES_SENT
                 .with_label_values(label_values)
                 .set(payload.len().to_i64());
+            SWIM_BYTES_SENT_TOTAL
+                .with_label_values(label_values)
+                .inc_by(payload.len().to_i64());
             let on_behalf_of = match forward_to {
                 Some(x) => format!(" on behalf of {}@{}", x.id, x.address),
                 None => "".into(),
@@ -502,6 +514,9 @@ pub fn ack(
             SWIM_BYTES_SENT
                 .with_label_values(label_values)
                 .set(payload.len().to_i64());
+            SWIM_BYTES_SENT_TOTAL
+                .with_label_values(label_values)
+                .inc_by(payload.len().to_i64());
             trace!("Sent ack to {}@{}", member_id, addr);
         }
         Err(e) => error!("Failed ack to {}@{}: {}", member_id, addr, e),
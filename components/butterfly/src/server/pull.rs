@@ -41,6 +41,12 @@ lazy_static! {
         &["type", "mode", "blocked"]
     )
     .unwrap();
+    static ref GOSSIP_BYTES_RECEIVED_TOTAL: IntCounterVec = register_int_counter_vec!(
+        "hab_butterfly_gossip_received_bytes_total",
+        "Total number of bytes of gossip messages received",
+        &["type", "mode", "blocked"]
+    )
+    .unwrap();
 }
 
 /// Takes a reference to the server itself
@@ -94,6 +100,9 @@ impl Pull {
                     GOSSIP_BYTES_RECEIVED
                         .with_label_values(label_values)
                         .set(msg.len().to_i64());
+                    GOSSIP_BYTES_RECEIVED_TOTAL
+                        .with_label_values(label_values)
+                        .inc_by(msg.len().to_i64());
                     GOSSIP_MESSAGES_RECEIVED
                         .with_label_values(label_values)
                         .inc();
@@ -109,6 +118,9 @@ impl Pull {
                     GOSSIP_BYTES_RECEIVED
                         .with_label_values(label_values)
                         .set(payload.len().to_i64());
+                    GOSSIP_BYTES_RECEIVED_TOTAL
+                        .with_label_values(label_values)
+                        .inc_by(payload.len().to_i64());
                     GOSSIP_MESSAGES_RECEIVED
                         .with_label_values(label_values)
                         .inc();
@@ -126,6 +138,9 @@ impl Pull {
             GOSSIP_BYTES_RECEIVED
                 .with_label_values(label_values)
                 .set(payload.len().to_i64());
+            GOSSIP_BYTES_RECEIVED_TOTAL
+                .with_label_values(label_values)
+                .inc_by(payload.len().to_i64());
 
             if blocked {
                 warn!(
@@ -47,6 +47,12 @@ lazy_static! {
         &["type", "mode"]
     )
     .unwrap();
+    static ref GOSSIP_BYTES_SENT_TOTAL: IntCounterVec = register_int_counter_vec!(
+        "hab_butterfly_gossip_sent_bytes_total",
+        "Total number of bytes of gossip messages sent",
+        &["type", "mode"]
+    )
+    .unwrap();
 }
 
 /// The Push server
@@ -365,6 +371,9 @@ impl PushWorker {
                     GOSSIP_BYTES_SENT
                         .with_label_values(label_values)
                         .set(rumor_len);
+                    GOSSIP_BYTES_SENT_TOTAL
+                        .with_label_values(label_values)
+                        .inc_by(rumor_len);
                     continue 'rumorlist;
                 }
             };
@@ -376,6 +385,9 @@ impl PushWorker {
                     GOSSIP_BYTES_SENT
                         .with_label_values(&[&rumor_key.kind.to_string(), "success"])
                         .set(payload.len().to_i64());
+                    GOSSIP_BYTES_SENT_TOTAL
+                        .with_label_values(&[&rumor_key.kind.to_string(), "success"])
+                        .inc_by(payload.len().to_i64());
                     debug!("Sent rumor {:?} to {:?}", rumor_key, member);
                 }
                 Err(e) => warn!(
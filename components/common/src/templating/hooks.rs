@@ -23,6 +23,8 @@ use std::path::{Path, PathBuf};
 #[cfg(not(windows))]
 use std::process::{Child, Command, ExitStatus, Stdio};
 use std::result;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
 
 #[cfg(windows)]
 use crate::hcore::os::process::windows_child::{Child, ExitStatus};
@@ -37,6 +39,9 @@ use crate::error::{Error, Result};
 pub const HOOK_PERMISSIONS: u32 = 0o755;
 static LOGKEY: &'static str = "HK";
 
+/// How often a hook run with a timeout is checked for completion.
+const HOOK_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
 pub fn stdout_log_path<T>(package_name: &str) -> PathBuf
 where
     T: Hook,
@@ -218,6 +223,59 @@ pub trait Hook: fmt::Debug + Sized {
         }
     }
 
+    /// Run a compiled hook, killing it if it hasn't exited within `timeout`.
+    ///
+    /// Returns `None` if the hook had to be killed.
+    fn run_with_timeout<T>(
+        &self,
+        service_group: &str,
+        pkg: &Pkg,
+        svc_encrypted_password: Option<T>,
+        timeout: Duration,
+    ) -> Option<Self::ExitValue>
+    where
+        T: ToString,
+    {
+        let mut child = match Self::exec(self.path(), &pkg, svc_encrypted_password) {
+            Ok(child) => child,
+            Err(err) => {
+                outputln!(preamble service_group,
+                    "Hook failed to run, {}, {}", Self::file_name(), err);
+                return Some(Self::ExitValue::default());
+            }
+        };
+        let hook_output = HookOutput::new(self.stdout_log_path(), self.stderr_log_path());
+        let streams = hook_output.stream_output_in_background::<Self>(service_group, &mut child);
+        let deadline = Instant::now() + timeout;
+        loop {
+            match child.try_wait() {
+                Ok(Some(status)) => {
+                    for stream in streams {
+                        stream.join().ok();
+                    }
+                    return Some(self.handle_exit(pkg, &hook_output, &status));
+                }
+                Ok(None) if Instant::now() >= deadline => {
+                    outputln!(preamble service_group,
+                        "{} hook did not finish within {}s; killing it",
+                        Self::file_name(),
+                        timeout.as_secs());
+                    if let Err(err) = child.kill().and_then(|_| child.wait().map(|_| ())) {
+                        outputln!(preamble service_group,
+                            "Unable to kill {} hook, {}", Self::file_name(), err);
+                    }
+                    return None;
+                }
+                Ok(None) => thread::sleep(HOOK_POLL_INTERVAL),
+                Err(err) => {
+                    outputln!(preamble service_group,
+                        "Hook failed to run, {}, {}", Self::file_name(), err);
+                    return Some(Self::ExitValue::default());
+                }
+            }
+        }
+    }
+
     #[cfg(windows)]
     fn exec<T, S>(path: S, pkg: &Pkg, svc_encrypted_password: Option<T>) -> Result<Child>
     where
@@ -506,11 +564,55 @@ impl<'a> HookOutput<'a> {
         }
     }
 
+    /// Like `stream_output`, but on background threads, so that the caller is free to wait on
+    /// the process in the meantime.
+    fn stream_output_in_background<H: Hook>(
+        &self,
+        service_group: &str,
+        process: &mut Child,
+    ) -> Vec<JoinHandle<()>> {
+        let preamble_str = self.stream_preamble::<H>(service_group);
+        let mut streams = Vec::new();
+        if let Some(stdout) = process.stdout.take() {
+            streams.push(stream_lines(
+                stdout,
+                self.stdout_log_file.to_path_buf(),
+                preamble_str.clone(),
+            ));
+        }
+        if let Some(stderr) = process.stderr.take() {
+            streams.push(stream_lines(
+                stderr,
+                self.stderr_log_file.to_path_buf(),
+                preamble_str,
+            ));
+        }
+        streams
+    }
+
     fn stream_preamble<H: Hook>(&self, service_group: &str) -> String {
         format!("{} hook[{}]:", service_group, H::file_name())
     }
 }
 
+/// Copy the lines of a hook's output to its log file and to the Supervisor's output, on a
+/// thread of its own.
+fn stream_lines<R>(reader: R, log_file: PathBuf, preamble_str: String) -> JoinHandle<()>
+where
+    R: Read + Send + 'static,
+{
+    thread::spawn(move || {
+        let mut log = File::create(&log_file).expect("couldn't create log output file");
+        for line in BufReader::new(reader).lines() {
+            if let Ok(ref l) = line {
+                outputln!(preamble preamble_str, l);
+                log.write_fmt(format_args!("{}\n", l))
+                    .expect("couldn't write line");
+            }
+        }
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use crate::hcore::package::{PackageIdent, PackageInstall};
@@ -564,6 +666,78 @@ mod tests {
             .expect("couldn't create ServiceGroup")
     }
 
+    /// A hook which runs whatever script a test writes for it, and logs its output next to it.
+    #[derive(Debug)]
+    struct ScriptHook {
+        render_pair: RenderPair,
+        stdout_log_path: PathBuf,
+        stderr_log_path: PathBuf,
+    }
+
+    impl ScriptHook {
+        #[cfg(not(windows))]
+        fn create(dir: &Path, content: &str) -> Self {
+            let path = dir.join(Self::file_name());
+            create_with_content(&path, content);
+            Self::set_permissions(&path).expect("couldn't make script executable");
+            ScriptHook {
+                render_pair: RenderPair {
+                    path: path,
+                    renderer: TemplateRenderer::new(),
+                },
+                stdout_log_path: dir.join("script.stdout.log"),
+                stderr_log_path: dir.join("script.stderr.log"),
+            }
+        }
+    }
+
+    impl Hook for ScriptHook {
+        type ExitValue = ExitCode;
+
+        fn file_name() -> &'static str {
+            "script"
+        }
+
+        fn new(package_name: &str, pair: RenderPair) -> Self {
+            ScriptHook {
+                render_pair: pair,
+                stdout_log_path: stdout_log_path::<Self>(package_name),
+                stderr_log_path: stderr_log_path::<Self>(package_name),
+            }
+        }
+
+        fn handle_exit<'a>(&self, _: &Pkg, _: &'a HookOutput, status: &ExitStatus) -> ExitCode {
+            ExitCode(status.code().unwrap_or(-1))
+        }
+
+        fn path(&self) -> &Path {
+            &self.render_pair.path
+        }
+
+        fn renderer(&self) -> &TemplateRenderer {
+            &self.render_pair.renderer
+        }
+
+        fn stdout_log_path(&self) -> &Path {
+            &self.stdout_log_path
+        }
+
+        fn stderr_log_path(&self) -> &Path {
+            &self.stderr_log_path
+        }
+    }
+
+    #[cfg(not(windows))]
+    fn script_pkg() -> Pkg {
+        let pkg_install = PackageInstall::new_from_parts(
+            PackageIdent::new("testing", "script", Some("1.0.0"), Some("20170712000000")),
+            PathBuf::from("/tmp"),
+            PathBuf::from("/tmp"),
+            PathBuf::from("/tmp"),
+        );
+        Pkg::from_install(&pkg_install).expect("Could not create package!")
+    }
+
     ////////////////////////////////////////////////////////////////////////
 
     #[test]
@@ -770,6 +944,61 @@ echo "The message is Hello"
 
     ////////////////////////////////////////////////////////////////////////
 
+    #[test]
+    #[cfg(not(windows))]
+    fn a_hook_finishing_within_its_timeout_returns_its_exit_value() {
+        let tmp_dir = TempDir::new().expect("create temp dir");
+        let hook = ScriptHook::create(tmp_dir.path(), "#!/bin/sh\necho done\nexit 3\n");
+
+        let exit = hook.run_with_timeout(
+            "script.default",
+            &script_pkg(),
+            None::<String>,
+            Duration::from_secs(30),
+        );
+
+        assert_eq!(exit.map(|code| code.0), Some(3));
+        assert_eq!(
+            file_content(tmp_dir.path().join("script.stdout.log")),
+            "done\n"
+        );
+    }
+
+    #[test]
+    #[cfg(not(windows))]
+    fn a_hook_running_past_its_timeout_is_killed() {
+        let tmp_dir = TempDir::new().expect("create temp dir");
+        let hook = ScriptHook::create(tmp_dir.path(), "#!/bin/sh\nexec sleep 30\n");
+
+        let start = Instant::now();
+        let exit = hook.run_with_timeout(
+            "script.default",
+            &script_pkg(),
+            None::<String>,
+            Duration::from_millis(200),
+        );
+
+        assert!(exit.is_none());
+        assert!(start.elapsed() < Duration::from_secs(10));
+    }
+
+    #[test]
+    #[cfg(not(windows))]
+    fn a_hook_failing_to_run_returns_the_default_exit_value() {
+        let tmp_dir = TempDir::new().expect("create temp dir");
+        let hook = ScriptHook::create(tmp_dir.path(), "#!/bin/sh\nexit 0\n");
+        std::fs::remove_file(hook.path()).expect("remove script");
+
+        let exit = hook.run_with_timeout(
+            "script.default",
+            &script_pkg(),
+            None::<String>,
+            Duration::from_secs(30),
+        );
+
+        assert_eq!(exit.map(|code| code.0), Some(-1));
+    }
+
     #[test]
     #[cfg(not(windows))]
     fn hook_output() {
@@ -14,6 +14,8 @@
 
 use std::collections::HashMap;
 use std::env;
+use std::fs::File;
+use std::io::Read;
 use std::ops::Deref;
 use std::path::PathBuf;
 use std::result;
@@ -33,6 +35,9 @@ const DEFAULT_GROUP: &str = "hab";
 
 const PATH_KEY: &str = "PATH";
 
+const SHUTDOWN_SIGNAL_METAFILE: &str = "SHUTDOWN_SIGNAL";
+const SHUTDOWN_TIMEOUT_METAFILE: &str = "SHUTDOWN_TIMEOUT";
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct Env(HashMap<String, String>);
 
@@ -101,6 +106,10 @@ pub struct Pkg {
     pub svc_run: PathBuf,
     pub svc_user: String,
     pub svc_group: String,
+    /// The signal the package asks to be stopped with, from its `SHUTDOWN_SIGNAL` metadata.
+    pub shutdown_signal: Option<String>,
+    /// Seconds the package asks to be given to stop, from its `SHUTDOWN_TIMEOUT` metadata.
+    pub shutdown_timeout: Option<u32>,
 }
 
 impl Pkg {
@@ -118,6 +127,19 @@ impl Pkg {
             svc_pid_file: fs::svc_pid_file(&package.ident.name),
             svc_user: svc_user,
             svc_group: svc_group,
+            shutdown_signal: read_metadata(&package, SHUTDOWN_SIGNAL_METAFILE),
+            shutdown_timeout: read_metadata(&package, SHUTDOWN_TIMEOUT_METAFILE).and_then(
+                |timeout| match timeout.parse() {
+                    Ok(timeout) => Some(timeout),
+                    Err(_) => {
+                        warn!(
+                            "Ignoring invalid {} in {}: {}",
+                            SHUTDOWN_TIMEOUT_METAFILE, package.ident, timeout
+                        );
+                        None
+                    }
+                },
+            ),
             env: Env::new(&package)?,
             deps: package.tdeps()?,
             exposes: package.exposes()?,
@@ -190,6 +212,21 @@ impl<'a> Serialize for PkgProxy<'a> {
     }
 }
 
+/// Read an optional metadata file which the core package metadata doesn't know about, returning
+/// its trimmed contents. A missing or empty file is `None`.
+fn read_metadata(package: &PackageInstall, name: &str) -> Option<String> {
+    let mut contents = String::new();
+    File::open(package.installed_path.join(name))
+        .and_then(|mut file| file.read_to_string(&mut contents))
+        .ok()?;
+    let contents = contents.trim();
+    if contents.is_empty() {
+        None
+    } else {
+        Some(contents.to_string())
+    }
+}
+
 /// check and see if a user/group is specified in package metadata.
 /// if not, we'll try and use hab/hab.
 /// If hab/hab doesn't exist, try to use (current username, current group).
@@ -259,3 +296,58 @@ fn default_user_and_group() -> Result<(String, String)> {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::io::Write;
+
+    use tempfile::TempDir;
+
+    fn pkg_with_metadata(metadata: &[(&str, &str)]) -> Pkg {
+        let tmpdir = TempDir::new().expect("create temp dir");
+        for (name, content) in metadata {
+            File::create(tmpdir.path().join(name))
+                .and_then(|mut file| file.write_all(content.as_bytes()))
+                .expect("write metadata file");
+        }
+        let pkg_install = PackageInstall::new_from_parts(
+            PackageIdent::new("testing", "test", Some("1.0.0"), Some("20170712000000")),
+            tmpdir.path().to_path_buf(),
+            tmpdir.path().to_path_buf(),
+            tmpdir.path().to_path_buf(),
+        );
+        Pkg::from_install(&pkg_install).expect("Could not create package!")
+    }
+
+    #[test]
+    fn pkg_reads_shutdown_metadata() {
+        let pkg = pkg_with_metadata(&[
+            (SHUTDOWN_SIGNAL_METAFILE, "INT\n"),
+            (SHUTDOWN_TIMEOUT_METAFILE, "60\n"),
+        ]);
+
+        assert_eq!(pkg.shutdown_signal, Some("INT".to_string()));
+        assert_eq!(pkg.shutdown_timeout, Some(60));
+    }
+
+    #[test]
+    fn pkg_without_shutdown_metadata_leaves_it_to_the_launcher() {
+        let pkg = pkg_with_metadata(&[]);
+
+        assert_eq!(pkg.shutdown_signal, None);
+        assert_eq!(pkg.shutdown_timeout, None);
+    }
+
+    #[test]
+    fn pkg_ignores_an_invalid_shutdown_timeout() {
+        let pkg = pkg_with_metadata(&[
+            (SHUTDOWN_SIGNAL_METAFILE, "   \n"),
+            (SHUTDOWN_TIMEOUT_METAFILE, "a minute"),
+        ]);
+
+        assert_eq!(pkg.shutdown_signal, None);
+        assert_eq!(pkg.shutdown_timeout, None);
+    }
+}
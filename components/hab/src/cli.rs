@@ -611,10 +611,14 @@ pub fn get() -> App<'static, 'static> {
                     (@arg ORG: "The service organization")
                 )
             )
+            (subcommand: sub_svc_apply().aliases(&["a", "ap", "app", "appl"]))
             (subcommand: sub_svc_load().aliases(&["l", "lo", "loa"]))
+            (subcommand: sub_svc_logs().aliases(&["log"]))
+            (subcommand: sub_svc_secret().aliases(&["sec", "secr", "secre"]))
             (subcommand: sub_svc_start().aliases(&["star"]))
             (subcommand: sub_svc_status().aliases(&["stat", "statu"]))
             (subcommand: sub_svc_stop().aliases(&["sto"]))
+            (subcommand: sub_svc_update().aliases(&["up", "upd", "upda", "updat"]))
             (@subcommand unload =>
                 (about: "Unload a service loaded by the Habitat Supervisor. If the service is \
                     running it will additionally be stopped.")
@@ -705,7 +709,9 @@ pub fn sup_commands() -> App<'static, 'static> {
     (usage: "hab sup <SUBCOMMAND>")
     (@setting VersionlessSubcommands)
     (@setting SubcommandRequiredElseHelp)
+    (subcommand: sub_sup_audit().aliases(&["a", "au", "aud", "audi"]))
     (subcommand: sub_sup_bash().aliases(&["b", "ba", "bas"]))
+    (subcommand: sub_sup_config().aliases(&["c", "co", "con", "conf", "confi"]))
     (subcommand: sub_sup_depart().aliases(&["d", "de", "dep", "depa", "depart"]))
     (subcommand: sub_sup_run().aliases(&["r", "ru"]))
     (subcommand: sub_sup_secret().aliases(&["sec", "secr"]))
@@ -838,6 +844,24 @@ fn sub_config_apply() -> App<'static, 'static> {
 // the following sup related functions are
 // public due to their utilization in `hab-sup`
 // for consistency, all supervisor related clap subcommands are defined in this module
+pub fn sub_sup_audit() -> App<'static, 'static> {
+    clap_app!(@subcommand audit =>
+        (about: "Show the audit log of requests which changed a Supervisor")
+        (@arg TAIL: --tail +takes_value {valid_numeric::<u64>}
+            "Only show this many of the most recent entries")
+        (@arg SINCE: --since +takes_value {valid_numeric::<u64>}
+            "Only show entries recorded in the last SINCE seconds")
+        (@arg ACTOR: --actor +takes_value
+            "Only show requests made with this credential (ex: ctl-secret, http-gateway)")
+        (@arg REQUEST: --request +takes_value
+            "Only show this type of request (ex: SvcLoad, SvcSetCfg, SupDepart)")
+        (@arg VERIFY: --verify
+            "Check that no entry of the audit log has been modified or removed")
+        (@arg REMOTE_SUP: --("remote-sup") -r +takes_value
+            "Address to a remote Supervisor's Control Gateway [default: 127.0.0.1:9632]")
+    )
+}
+
 pub fn sub_sup_depart() -> App<'static, 'static> {
     clap_app!(@subcommand depart =>
         (about: "Depart a Supervisor from the gossip ring; kicking and banning the target \
@@ -854,6 +878,24 @@ pub fn sub_sup_secret() -> App<'static, 'static> {
         (@setting ArgRequiredElseHelp)
         (@subcommand generate =>
             (about: "Generate a secret key to use as a Supervisor's Control Gateway secret")
+            (@arg SCOPE: --scope +takes_value {valid_ctl_scope} requires[NAME]
+                "Generate a named credential for the Supervisor's CTL_CREDENTIALS file, which \
+                may only make the requests allowed by this scope [values: read-only, operator, \
+                admin]")
+            (@arg NAME: --name +takes_value {valid_ctl_credential_name} requires[SCOPE]
+                "The name of the credential, used to identify it in the Supervisor's output")
+        )
+    )
+}
+
+pub fn sub_sup_config() -> App<'static, 'static> {
+    clap_app!(@subcommand config =>
+        (about: "Commands relating to a Habitat Supervisor's config file")
+        (@setting ArgRequiredElseHelp)
+        (@subcommand check =>
+            (about: "Check that a config file for `hab sup run --config` is valid")
+            (@arg FILE: +required +takes_value {file_exists}
+                "The config file to check (ex: /hab/sup/default/config.toml)")
         )
     )
 }
@@ -875,6 +917,9 @@ pub fn sub_sup_run() -> App<'static, 'static> {
     // is displayed confusingly as `hab-sup`
     // see: https://github.com/kbknapp/clap-rs/blob/2724ec5399c500b12a1a24d356f4090f4816f5e2/src/app/mod.rs#L373-L394
     (usage: "hab sup run [FLAGS] [OPTIONS] [--] [PKG_IDENT_OR_ARTIFACT]")
+    (@arg CONFIG_FILE: --config +takes_value {file_exists}
+        "Read the Supervisor's settings from this TOML file (ex: /hab/sup/default/config.toml). \
+         Flags and environment variables take precedence over the settings in the file.")
           (@arg LISTEN_GOSSIP: --("listen-gossip") env(GOSSIP_LISTEN_ADDRESS_ENVVAR) default_value(&GOSSIP_DEFAULT_ADDR) {valid_socket_addr}
         "The listen address for the Gossip System Gateway.")
     (@arg LISTEN_HTTP: --("listen-http") env(LISTEN_HTTP_ADDRESS_ENVVAR) default_value(&LISTEN_HTTP_DEFAULT_ADDR) {valid_socket_addr}
@@ -913,6 +958,14 @@ pub fn sub_sup_run() -> App<'static, 'static> {
         "Use package config from this path, rather than the package itself")
     (@arg AUTO_UPDATE: --("auto-update") -A "Enable automatic updates for the Supervisor \
         itself")
+    (@arg AUTO_UPDATE_WINDOW: --("auto-update-window") +takes_value +multiple
+        {valid_update_window} requires[AUTO_UPDATE]
+        "A period during which the Supervisor may update itself, in UTC (ex: \
+         'sat,sun 01:00-05:00'). May be given more than once. [default: any time]")
+    (@arg AUTO_UPDATE_SPLAY: --("auto-update-splay") +takes_value {valid_numeric::<u64>}
+        requires[AUTO_UPDATE]
+        "The maximum random delay (seconds) before the Supervisor polls for and applies \
+         updates to itself [default: 0]")
     (@arg KEY_FILE: --key +takes_value {file_exists} requires[CERT_FILE]
         "Used for enabling TLS for the HTTP gateway. Read private key from KEY_FILE. \
          This should be a RSA private key or PKCS8-encoded private key, in PEM format.")
@@ -920,6 +973,23 @@ pub fn sub_sup_run() -> App<'static, 'static> {
         "Used for enabling TLS for the HTTP gateway. Read server certificates from CERT_FILE. \
          This should contain PEM-format certificates in the right order (the first certificate \
          should certify KEY_FILE, the last should be a root CA).")
+    (@arg CTL_SERVER_KEY: --("ctl-server-key") +takes_value {file_exists}
+        requires[CTL_SERVER_CERTIFICATE CTL_CLIENT_CA_CERTIFICATE]
+        "Used for enabling TLS for the ctl gateway. Read the ctl gateway's private key from \
+         this file. This should be a RSA private key or PKCS8-encoded private key, in PEM format.")
+    (@arg CTL_SERVER_CERTIFICATE: --("ctl-server-certificate") +takes_value {file_exists}
+        requires[CTL_SERVER_KEY]
+        "Used for enabling TLS for the ctl gateway. Read the ctl gateway's certificates from \
+         this file, in PEM format.")
+    (@arg CTL_CLIENT_CA_CERTIFICATE: --("ctl-client-ca-certificate") +takes_value {file_exists}
+        requires[CTL_SERVER_KEY]
+        "Used for enabling TLS for the ctl gateway. Only accept clients presenting a \
+         certificate issued by the CA whose PEM-format certificate is in this file.")
+    (@arg EVENT_STREAM_FILE: --("event-stream-file") +takes_value
+        "Append structured lifecycle events to this file, one JSON document per line")
+    (@arg EVENT_STREAM_SOCKET: --("event-stream-socket") +takes_value
+        "Publish structured lifecycle events, one JSON document per line, to any client \
+         connected to a Unix domain socket created at this path")
     // === Optional arguments to additionally load an initial service for the Supervisor
     (@arg PKG_IDENT_OR_ARTIFACT: +takes_value "Load the given Habitat package as part of \
         the Supervisor startup specified by a package identifier \
@@ -934,7 +1004,22 @@ pub fn sub_sup_run() -> App<'static, 'static> {
     (@arg TOPOLOGY: --topology -t +takes_value possible_value[standalone leader]
         "Service topology; [default: none]")
     (@arg STRATEGY: --strategy -s +takes_value {valid_update_strategy}
-        "The update strategy; [default: none] [values: none, at-once, rolling]")
+        "The update strategy; [default: none] [values: none, at-once, rolling, batched, canary]")
+    (@arg UPDATE_BATCH_SIZE: --("update-batch-size") +takes_value {valid_update_batch_size}
+        "How many members of the service group update at a time with the batched and canary \
+         strategies: a count or a percentage of the group (ex: 3, 20%) [default: 1]")
+    (@arg CANARY_SOAK: --("canary-soak") +takes_value {valid_numeric::<u64>}
+        "The time (seconds) the canary must stay healthy on a new package before the rest of \
+         the service group updates [default: 300]")
+    (@arg UPDATE_WINDOW: --("update-window") +takes_value +multiple {valid_update_window}
+        "A period during which updates may be applied, in UTC (ex: 'sat,sun 01:00-05:00', \
+         'mon-fri 22:00-02:00'). May be given more than once. [default: any time]")
+    (@arg UPDATE_SPLAY: --("update-splay") +takes_value {valid_numeric::<u64>}
+        "The maximum random delay (seconds) before polling for and applying updates, to \
+         spread them out across Supervisors [default: 0]")
+    (@arg ROLLBACK_PERIOD: --("rollback-period") +takes_value {valid_numeric::<u64>}
+        "Roll the service back to its previous package if it crash-loops within this many \
+         seconds of an update, or isn't healthy after them. 0 disables rollbacks [default: 0]")
     (@arg BIND: --bind +takes_value +multiple
         "One or more service groups to bind to a configuration")
     (@arg BINDING_MODE: --("binding-mode") +takes_value {valid_binding_mode}
@@ -946,6 +1031,78 @@ pub fn sub_sup_run() -> App<'static, 'static> {
         Implies NO_COLOR")
     (@arg HEALTH_CHECK_INTERVAL: --("health-check-interval") -i +takes_value {valid_health_check_interval}
         "The interval (seconds) on which to run health checks [default: 30]")
+    (@arg HEALTH_CHECK_FAILURES: --("health-check-failures") +takes_value {valid_numeric::<u32>}
+        "The number of consecutive failed health checks before a healthy service is reported \
+         as degraded [default: 1]")
+    (@arg HEALTH_CHECK_SUCCESSES: --("health-check-successes") +takes_value {valid_numeric::<u32>}
+        "The number of consecutive passed health checks before a degraded service is reported \
+         as healthy again [default: 1]")
+    (@arg HEALTH_CHECK_GRACE_PERIOD: --("health-check-grace-period") +takes_value {valid_numeric::<u64>}
+        "The time (seconds) after the service starts during which failed health checks are \
+         ignored [default: 0]")
+    (@arg HEALTH_CHECK_RESTART_AFTER: --("health-check-restart-after") +takes_value {valid_numeric::<u32>}
+        "Restart the service after this many consecutive critical health checks; 0 never \
+         restarts it [default: 0]")
+    (@arg HEALTH_PROBE: --("health-probe") +takes_value {valid_health_probe}
+        "A health check for the Supervisor to perform itself, alongside any health-check hook: \
+         http://<HOST>[:<PORT>]/<PATH>, tcp://<HOST>:<PORT> or exec:<COMMAND>")
+    (@arg HEALTH_PROBE_TIMEOUT: --("health-probe-timeout") +takes_value {valid_numeric::<u64>} requires[HEALTH_PROBE]
+        "The time (seconds) to wait for the health probe to complete [default: 5]")
+    (@arg AFTER: --after +takes_value +multiple {valid_ident}
+        "Start the service after the services of these packages are up, if they are loaded \
+         (ex: --after core/consul)")
+    (@arg REQUIRES: --requires +takes_value +multiple {valid_ident}
+        "Only start the service once the services of these packages are loaded and up \
+         (ex: --requires core/postgresql)")
+    (@arg REQUIRE_HEALTHY: --("require-healthy")
+        "Wait for the services named by --after and --requires to be healthy, not just up")
+    (@arg SHUTDOWN_SIGNAL: --("shutdown-signal") +takes_value {valid_shutdown_signal}
+        "The signal sent to the service's process to ask it to stop: TERM, INT, QUIT, HUP, USR1 \
+         or USR2 [default: the package's pkg_shutdown_signal, or TERM]")
+    (@arg SHUTDOWN_TIMEOUT: --("shutdown-timeout") +takes_value {valid_numeric::<u32>}
+        "The time (seconds) the service's process is given to stop before it is killed \
+         [default: the package's pkg_shutdown_timeout_sec, or 8]")
+    (@arg PRE_START_TIMEOUT: --("pre-start-timeout") +takes_value {valid_numeric::<u32>}
+        "The time (seconds) the pre-start hook may run before it is killed; the Supervisor \
+         waits for it [default: 30]")
+    (@arg PRE_STOP_TIMEOUT: --("pre-stop-timeout") +takes_value {valid_numeric::<u32>}
+        "The time (seconds) the pre-stop hook may run before the service's process is stopped \
+         regardless [default: 30]")
+    (@arg MAX_RESTARTS: --("max-restarts") +takes_value {valid_numeric::<u32>}
+        "The number of restarts allowed within the restart window before the service is \
+         considered crash-looping and left down; 0 means no limit [default: 0]")
+    (@arg RESTART_WINDOW: --("restart-window") +takes_value {valid_numeric::<u64>}
+        "The window (seconds) in which restarts are counted [default: 300]")
+    (@arg RESTART_BACKOFF: --("restart-backoff") +takes_value {valid_numeric::<u64>}
+        "The initial delay (seconds) between consecutive restarts; doubled for each further \
+         restart [default: 1]")
+    (@arg RESTART_BACKOFF_MAX: --("restart-backoff-max") +takes_value {valid_numeric::<u64>}
+        "The maximum delay (seconds) between consecutive restarts [default: 60]")
+    (@arg MEMORY_MAX: --("memory-max") +takes_value {valid_memory_size}
+        "The maximum memory the service may use, in bytes or with a K, M, G or T suffix \
+         (Linux only, requires cgroup v2) [default: unlimited]")
+    (@arg CPU_QUOTA: --("cpu-quota") +takes_value {valid_cpu_quota}
+        "The CPU time the service may use, as a percentage of a single CPU, e.g. 150% \
+         (Linux only, requires cgroup v2) [default: unlimited]")
+    (@arg CPU_WEIGHT: --("cpu-weight") +takes_value {valid_cpu_weight}
+        "The relative share of CPU time the service gets under contention, from 1 to 10000 \
+         (Linux only, requires cgroup v2) [default: 100]")
+    (@arg PIDS_MAX: --("pids-max") +takes_value {valid_numeric::<u64>}
+        "The maximum number of processes and threads the service may run \
+         (Linux only, requires cgroup v2) [default: unlimited]")
+    (@arg LOG_FILE: --("log-file")
+        "Write the service's output to a rotated log file in its logs directory instead of the \
+         Supervisor's output")
+    (@arg LOG_MAX_SIZE: --("log-max-size") +takes_value {valid_memory_size}
+        "The size at which the service's log file is rotated, in bytes or with a K, M, G or T \
+         suffix; 0 means no limit [default: 10M]")
+    (@arg LOG_MAX_AGE: --("log-max-age") +takes_value {valid_numeric::<u64>}
+        "The age (seconds) at which the service's log file is rotated; 0 means no limit \
+         [default: 86400]")
+    (@arg LOG_RETAIN: --("log-retain") +takes_value {valid_numeric::<u32>}
+        "The number of rotated log files to keep; 0 keeps them all [default: 7]")
+    (@arg NO_LOG_COMPRESS: --("no-log-compress")
+        "Don't gzip rotated log files")
     )
 }
 
@@ -969,6 +1126,58 @@ pub fn sub_sup_term() -> App<'static, 'static> {
     )
 }
 
+fn sub_svc_apply() -> App<'static, 'static> {
+    clap_app!(@subcommand apply =>
+        (about: "Load, update, and optionally unload services so that the Supervisor runs the \
+            services listed in a manifest")
+        (@arg FILE: -f --file +required +takes_value {file_exists_or_stdin}
+            "A TOML manifest with a [[services]] table holding the spec of each service, or '-' \
+            to read it from standard input (ex: services.toml)")
+        (@arg PRUNE: --prune "Unload the loaded services which aren't listed in the manifest")
+        (@arg DRY_RUN: --("dry-run")
+            "Only show the operations which applying the manifest would perform")
+        (@arg REMOTE_SUP: --("remote-sup") -r +takes_value
+            "Address to a remote Supervisor's Control Gateway [default: 127.0.0.1:9632]")
+    )
+}
+
+fn sub_svc_logs() -> App<'static, 'static> {
+    clap_app!(@subcommand logs =>
+        (about: "Show the output of a Habitat service that writes to a log file.")
+        (@arg PKG_IDENT: +required +takes_value {valid_ident}
+            "A Habitat package identifier (ex: core/redis)")
+        (@arg FOLLOW: -f --follow "Keep showing new output as it is written")
+        (@arg TAIL: --tail +takes_value {valid_numeric::<u64>}
+            "Only show this many of the most recent lines")
+        (@arg SINCE: --since +takes_value {valid_numeric::<u64>}
+            "Only show lines written in the last SINCE seconds")
+        (@arg REMOTE_SUP: --("remote-sup") -r +takes_value
+            "Address to a remote Supervisor's Control Gateway [default: 127.0.0.1:9632]")
+    )
+}
+
+fn sub_svc_secret() -> App<'static, 'static> {
+    clap_app!(@subcommand secret =>
+        (about: "Commands relating to the secrets a Supervisor keeps for its services")
+        (@setting ArgRequiredElseHelp)
+        (@subcommand set =>
+            (about: "Set a secret of a service group. The secret is encrypted with the service \
+                key and kept on the Supervisor only; it isn't shared with the rest of the ring. \
+                Templates of the service read it as {{secret.NAME}}.")
+            (aliases: &["s", "se"])
+            (@arg SERVICE_GROUP: +required {valid_service_group}
+                "Target service group service.group@organization (ex: redis.default@bazcorp)")
+            (@arg NAME: +required +takes_value
+                "Name of the secret, made of letters, digits and underscores (ex: db_password)")
+            (@arg FILE: {file_exists_or_stdin}
+                "Path to a local file holding the value of the secret; a trailing newline is \
+                removed (ex: /tmp/password, default: <stdin>)")
+            (@arg REMOTE_SUP: --("remote-sup") -r +takes_value
+                "Address to a remote Supervisor's Control Gateway [default: 127.0.0.1:9632]")
+        )
+    )
+}
+
 fn sub_svc_start() -> App<'static, 'static> {
     clap_app!(@subcommand start =>
         (about: "Start a loaded, but stopped, Habitat service.")
@@ -1000,6 +1209,36 @@ fn sub_svc_stop() -> App<'static, 'static> {
     )
 }
 
+fn sub_svc_update() -> App<'static, 'static> {
+    clap_app!(@subcommand update =>
+        (about: "Control the automatic updates of a loaded Habitat service.")
+        (@setting ArgRequiredElseHelp)
+        (@subcommand pause =>
+            (about: "Stop updating a service until its updates are resumed. The service keeps \
+                running, and stays paused across Supervisor restarts.")
+            (@arg PKG_IDENT: +required +takes_value {valid_ident}
+                "A Habitat package identifier (ex: core/redis)")
+            (@arg REMOTE_SUP: --("remote-sup") -r +takes_value
+                "Address to a remote Supervisor's Control Gateway [default: 127.0.0.1:9632]")
+        )
+        (@subcommand resume =>
+            (about: "Resume the updates of a service whose updates were paused.")
+            (@arg PKG_IDENT: +required +takes_value {valid_ident}
+                "A Habitat package identifier (ex: core/redis)")
+            (@arg REMOTE_SUP: --("remote-sup") -r +takes_value
+                "Address to a remote Supervisor's Control Gateway [default: 127.0.0.1:9632]")
+        )
+        (@subcommand now =>
+            (about: "Check for an update to a service right away, rather than at the next \
+                scheduled check. Update windows still apply.")
+            (@arg PKG_IDENT: +required +takes_value {valid_ident}
+                "A Habitat package identifier (ex: core/redis)")
+            (@arg REMOTE_SUP: --("remote-sup") -r +takes_value
+                "Address to a remote Supervisor's Control Gateway [default: 127.0.0.1:9632]")
+        )
+    )
+}
+
 #[cfg(any(target_os = "linux", target_os = "macos"))]
 fn sub_svc_load() -> App<'static, 'static> {
     clap_app!(@subcommand load =>
@@ -1023,7 +1262,22 @@ fn sub_svc_load() -> App<'static, 'static> {
         (@arg TOPOLOGY: --topology -t +takes_value possible_value[standalone leader]
             "Service topology; [default: none]")
         (@arg STRATEGY: --strategy -s +takes_value {valid_update_strategy}
-            "The update strategy; [default: none] [values: none, at-once, rolling]")
+            "The update strategy; [default: none] [values: none, at-once, rolling, batched, canary]")
+        (@arg UPDATE_BATCH_SIZE: --("update-batch-size") +takes_value {valid_update_batch_size}
+            "How many members of the service group update at a time with the batched and canary \
+             strategies: a count or a percentage of the group (ex: 3, 20%) [default: 1]")
+        (@arg CANARY_SOAK: --("canary-soak") +takes_value {valid_numeric::<u64>}
+            "The time (seconds) the canary must stay healthy on a new package before the rest of \
+             the service group updates [default: 300]")
+        (@arg UPDATE_WINDOW: --("update-window") +takes_value +multiple {valid_update_window}
+            "A period during which updates may be applied, in UTC (ex: 'sat,sun 01:00-05:00', \
+             'mon-fri 22:00-02:00'). May be given more than once. [default: any time]")
+        (@arg UPDATE_SPLAY: --("update-splay") +takes_value {valid_numeric::<u64>}
+            "The maximum random delay (seconds) before polling for and applying updates, to \
+             spread them out across Supervisors [default: 0]")
+        (@arg ROLLBACK_PERIOD: --("rollback-period") +takes_value {valid_numeric::<u64>}
+            "Roll the service back to its previous package if it crash-loops within this many \
+             seconds of an update, or isn't healthy after them. 0 disables rollbacks [default: 0]")
         (@arg BIND: --bind +takes_value +multiple
             "One or more service groups to bind to a configuration")
         (@arg BINDING_MODE: --("binding-mode") +takes_value {valid_binding_mode}
@@ -1035,6 +1289,78 @@ fn sub_svc_load() -> App<'static, 'static> {
             "Address to a remote Supervisor's Control Gateway [default: 127.0.0.1:9632]")
         (@arg HEALTH_CHECK_INTERVAL: --("health-check-interval") -i +takes_value {valid_health_check_interval}
             "The interval (seconds) on which to run health checks [default: 30]")
+        (@arg HEALTH_CHECK_FAILURES: --("health-check-failures") +takes_value {valid_numeric::<u32>}
+            "The number of consecutive failed health checks before a healthy service is reported \
+             as degraded [default: 1]")
+        (@arg HEALTH_CHECK_SUCCESSES: --("health-check-successes") +takes_value {valid_numeric::<u32>}
+            "The number of consecutive passed health checks before a degraded service is reported \
+             as healthy again [default: 1]")
+        (@arg HEALTH_CHECK_GRACE_PERIOD: --("health-check-grace-period") +takes_value {valid_numeric::<u64>}
+            "The time (seconds) after the service starts during which failed health checks are \
+             ignored [default: 0]")
+        (@arg HEALTH_CHECK_RESTART_AFTER: --("health-check-restart-after") +takes_value {valid_numeric::<u32>}
+            "Restart the service after this many consecutive critical health checks; 0 never \
+             restarts it [default: 0]")
+        (@arg HEALTH_PROBE: --("health-probe") +takes_value {valid_health_probe}
+            "A health check for the Supervisor to perform itself, alongside any health-check hook: \
+             http://<HOST>[:<PORT>]/<PATH>, tcp://<HOST>:<PORT> or exec:<COMMAND>")
+        (@arg HEALTH_PROBE_TIMEOUT: --("health-probe-timeout") +takes_value {valid_numeric::<u64>} requires[HEALTH_PROBE]
+            "The time (seconds) to wait for the health probe to complete [default: 5]")
+        (@arg AFTER: --after +takes_value +multiple {valid_ident}
+            "Start the service after the services of these packages are up, if they are loaded \
+             (ex: --after core/consul)")
+        (@arg REQUIRES: --requires +takes_value +multiple {valid_ident}
+            "Only start the service once the services of these packages are loaded and up \
+             (ex: --requires core/postgresql)")
+        (@arg REQUIRE_HEALTHY: --("require-healthy")
+            "Wait for the services named by --after and --requires to be healthy, not just up")
+        (@arg SHUTDOWN_SIGNAL: --("shutdown-signal") +takes_value {valid_shutdown_signal}
+            "The signal sent to the service's process to ask it to stop: TERM, INT, QUIT, HUP, USR1 \
+             or USR2 [default: the package's pkg_shutdown_signal, or TERM]")
+        (@arg SHUTDOWN_TIMEOUT: --("shutdown-timeout") +takes_value {valid_numeric::<u32>}
+            "The time (seconds) the service's process is given to stop before it is killed \
+             [default: the package's pkg_shutdown_timeout_sec, or 8]")
+        (@arg PRE_START_TIMEOUT: --("pre-start-timeout") +takes_value {valid_numeric::<u32>}
+            "The time (seconds) the pre-start hook may run before it is killed; the Supervisor \
+             waits for it [default: 30]")
+        (@arg PRE_STOP_TIMEOUT: --("pre-stop-timeout") +takes_value {valid_numeric::<u32>}
+            "The time (seconds) the pre-stop hook may run before the service's process is \
+             stopped regardless [default: 30]")
+        (@arg MAX_RESTARTS: --("max-restarts") +takes_value {valid_numeric::<u32>}
+            "The number of restarts allowed within the restart window before the service is \
+             considered crash-looping and left down; 0 means no limit [default: 0]")
+        (@arg RESTART_WINDOW: --("restart-window") +takes_value {valid_numeric::<u64>}
+            "The window (seconds) in which restarts are counted [default: 300]")
+        (@arg RESTART_BACKOFF: --("restart-backoff") +takes_value {valid_numeric::<u64>}
+            "The initial delay (seconds) between consecutive restarts; doubled for each further \
+             restart [default: 1]")
+        (@arg RESTART_BACKOFF_MAX: --("restart-backoff-max") +takes_value {valid_numeric::<u64>}
+            "The maximum delay (seconds) between consecutive restarts [default: 60]")
+        (@arg MEMORY_MAX: --("memory-max") +takes_value {valid_memory_size}
+            "The maximum memory the service may use, in bytes or with a K, M, G or T suffix \
+             (Linux only, requires cgroup v2) [default: unlimited]")
+        (@arg CPU_QUOTA: --("cpu-quota") +takes_value {valid_cpu_quota}
+            "The CPU time the service may use, as a percentage of a single CPU, e.g. 150% \
+             (Linux only, requires cgroup v2) [default: unlimited]")
+        (@arg CPU_WEIGHT: --("cpu-weight") +takes_value {valid_cpu_weight}
+            "The relative share of CPU time the service gets under contention, from 1 to 10000 \
+             (Linux only, requires cgroup v2) [default: 100]")
+        (@arg PIDS_MAX: --("pids-max") +takes_value {valid_numeric::<u64>}
+            "The maximum number of processes and threads the service may run \
+             (Linux only, requires cgroup v2) [default: unlimited]")
+        (@arg LOG_FILE: --("log-file")
+            "Write the service's output to a rotated log file in its logs directory instead of the \
+             Supervisor's output")
+        (@arg LOG_MAX_SIZE: --("log-max-size") +takes_value {valid_memory_size}
+            "The size at which the service's log file is rotated, in bytes or with a K, M, G or T \
+             suffix; 0 means no limit [default: 10M]")
+        (@arg LOG_MAX_AGE: --("log-max-age") +takes_value {valid_numeric::<u64>}
+            "The age (seconds) at which the service's log file is rotated; 0 means no limit \
+             [default: 86400]")
+        (@arg LOG_RETAIN: --("log-retain") +takes_value {valid_numeric::<u32>}
+            "The number of rotated log files to keep; 0 keeps them all [default: 7]")
+        (@arg NO_LOG_COMPRESS: --("no-log-compress")
+            "Don't gzip rotated log files")
     )
 }
 
@@ -1061,7 +1387,22 @@ fn sub_svc_load() -> App<'static, 'static> {
         (@arg TOPOLOGY: --topology -t +takes_value possible_value[standalone leader]
             "Service topology; [default: none]")
         (@arg STRATEGY: --strategy -s +takes_value {valid_update_strategy}
-            "The update strategy; [default: none] [values: none, at-once, rolling]")
+            "The update strategy; [default: none] [values: none, at-once, rolling, batched, canary]")
+        (@arg UPDATE_BATCH_SIZE: --("update-batch-size") +takes_value {valid_update_batch_size}
+            "How many members of the service group update at a time with the batched and canary \
+             strategies: a count or a percentage of the group (ex: 3, 20%) [default: 1]")
+        (@arg CANARY_SOAK: --("canary-soak") +takes_value {valid_numeric::<u64>}
+            "The time (seconds) the canary must stay healthy on a new package before the rest of \
+             the service group updates [default: 300]")
+        (@arg UPDATE_WINDOW: --("update-window") +takes_value +multiple {valid_update_window}
+            "A period during which updates may be applied, in UTC (ex: 'sat,sun 01:00-05:00', \
+             'mon-fri 22:00-02:00'). May be given more than once. [default: any time]")
+        (@arg UPDATE_SPLAY: --("update-splay") +takes_value {valid_numeric::<u64>}
+            "The maximum random delay (seconds) before polling for and applying updates, to \
+             spread them out across Supervisors [default: 0]")
+        (@arg ROLLBACK_PERIOD: --("rollback-period") +takes_value {valid_numeric::<u64>}
+            "Roll the service back to its previous package if it crash-loops within this many \
+             seconds of an update, or isn't healthy after them. 0 disables rollbacks [default: 0]")
         (@arg BIND: --bind +takes_value +multiple
             "One or more service groups to bind to a configuration")
         (@arg BINDING_MODE: --("binding-mode") +takes_value {valid_binding_mode}
@@ -1074,6 +1415,78 @@ fn sub_svc_load() -> App<'static, 'static> {
             "Address to a remote Supervisor's Control Gateway [default: 127.0.0.1:9632]")
         (@arg HEALTH_CHECK_INTERVAL: --("health-check-interval") -i +takes_value {valid_health_check_interval}
             "The interval (seconds) on which to run health checks [default: 30]")
+        (@arg HEALTH_CHECK_FAILURES: --("health-check-failures") +takes_value {valid_numeric::<u32>}
+            "The number of consecutive failed health checks before a healthy service is reported \
+             as degraded [default: 1]")
+        (@arg HEALTH_CHECK_SUCCESSES: --("health-check-successes") +takes_value {valid_numeric::<u32>}
+            "The number of consecutive passed health checks before a degraded service is reported \
+             as healthy again [default: 1]")
+        (@arg HEALTH_CHECK_GRACE_PERIOD: --("health-check-grace-period") +takes_value {valid_numeric::<u64>}
+            "The time (seconds) after the service starts during which failed health checks are \
+             ignored [default: 0]")
+        (@arg HEALTH_CHECK_RESTART_AFTER: --("health-check-restart-after") +takes_value {valid_numeric::<u32>}
+            "Restart the service after this many consecutive critical health checks; 0 never \
+             restarts it [default: 0]")
+        (@arg HEALTH_PROBE: --("health-probe") +takes_value {valid_health_probe}
+            "A health check for the Supervisor to perform itself, alongside any health-check hook: \
+             http://<HOST>[:<PORT>]/<PATH>, tcp://<HOST>:<PORT> or exec:<COMMAND>")
+        (@arg HEALTH_PROBE_TIMEOUT: --("health-probe-timeout") +takes_value {valid_numeric::<u64>} requires[HEALTH_PROBE]
+            "The time (seconds) to wait for the health probe to complete [default: 5]")
+        (@arg AFTER: --after +takes_value +multiple {valid_ident}
+            "Start the service after the services of these packages are up, if they are loaded \
+             (ex: --after core/consul)")
+        (@arg REQUIRES: --requires +takes_value +multiple {valid_ident}
+            "Only start the service once the services of these packages are loaded and up \
+             (ex: --requires core/postgresql)")
+        (@arg REQUIRE_HEALTHY: --("require-healthy")
+            "Wait for the services named by --after and --requires to be healthy, not just up")
+        (@arg SHUTDOWN_SIGNAL: --("shutdown-signal") +takes_value {valid_shutdown_signal}
+            "The signal sent to the service's process to ask it to stop: TERM, INT, QUIT, HUP, USR1 \
+             or USR2 [default: the package's pkg_shutdown_signal, or TERM]")
+        (@arg SHUTDOWN_TIMEOUT: --("shutdown-timeout") +takes_value {valid_numeric::<u32>}
+            "The time (seconds) the service's process is given to stop before it is killed \
+             [default: the package's pkg_shutdown_timeout_sec, or 8]")
+        (@arg PRE_START_TIMEOUT: --("pre-start-timeout") +takes_value {valid_numeric::<u32>}
+            "The time (seconds) the pre-start hook may run before it is killed; the Supervisor \
+             waits for it [default: 30]")
+        (@arg PRE_STOP_TIMEOUT: --("pre-stop-timeout") +takes_value {valid_numeric::<u32>}
+            "The time (seconds) the pre-stop hook may run before the service's process is \
+             stopped regardless [default: 30]")
+        (@arg MAX_RESTARTS: --("max-restarts") +takes_value {valid_numeric::<u32>}
+            "The number of restarts allowed within the restart window before the service is \
+             considered crash-looping and left down; 0 means no limit [default: 0]")
+        (@arg RESTART_WINDOW: --("restart-window") +takes_value {valid_numeric::<u64>}
+            "The window (seconds) in which restarts are counted [default: 300]")
+        (@arg RESTART_BACKOFF: --("restart-backoff") +takes_value {valid_numeric::<u64>}
+            "The initial delay (seconds) between consecutive restarts; doubled for each further \
+             restart [default: 1]")
+        (@arg RESTART_BACKOFF_MAX: --("restart-backoff-max") +takes_value {valid_numeric::<u64>}
+            "The maximum delay (seconds) between consecutive restarts [default: 60]")
+        (@arg MEMORY_MAX: --("memory-max") +takes_value {valid_memory_size}
+            "The maximum memory the service may use, in bytes or with a K, M, G or T suffix \
+             (Linux only, requires cgroup v2) [default: unlimited]")
+        (@arg CPU_QUOTA: --("cpu-quota") +takes_value {valid_cpu_quota}
+            "The CPU time the service may use, as a percentage of a single CPU, e.g. 150% \
+             (Linux only, requires cgroup v2) [default: unlimited]")
+        (@arg CPU_WEIGHT: --("cpu-weight") +takes_value {valid_cpu_weight}
+            "The relative share of CPU time the service gets under contention, from 1 to 10000 \
+             (Linux only, requires cgroup v2) [default: 100]")
+        (@arg PIDS_MAX: --("pids-max") +takes_value {valid_numeric::<u64>}
+            "The maximum number of processes and threads the service may run \
+             (Linux only, requires cgroup v2) [default: unlimited]")
+        (@arg LOG_FILE: --("log-file")
+            "Write the service's output to a rotated log file in its logs directory instead of the \
+             Supervisor's output")
+        (@arg LOG_MAX_SIZE: --("log-max-size") +takes_value {valid_memory_size}
+            "The size at which the service's log file is rotated, in bytes or with a K, M, G or T \
+             suffix; 0 means no limit [default: 10M]")
+        (@arg LOG_MAX_AGE: --("log-max-age") +takes_value {valid_numeric::<u64>}
+            "The age (seconds) at which the service's log file is rotated; 0 means no limit \
+             [default: 86400]")
+        (@arg LOG_RETAIN: --("log-retain") +takes_value {valid_numeric::<u32>}
+            "The number of rotated log files to keep; 0 keeps them all [default: 7]")
+        (@arg NO_LOG_COMPRESS: --("no-log-compress")
+            "Don't gzip rotated log files")
     )
 }
 
@@ -1147,6 +1560,62 @@ fn valid_numeric<T: FromStr>(val: String) -> result::Result<(), String> {
     }
 }
 
+fn valid_memory_size(val: String) -> result::Result<(), String> {
+    match protocol::types::ResourceLimits::parse_memory(&val) {
+        Ok(_) => Ok(()),
+        Err(e) => Err(e.msg),
+    }
+}
+
+fn valid_ctl_scope(val: String) -> result::Result<(), String> {
+    match protocol::ctl::CtlScope::from_str(&val) {
+        Ok(_) => Ok(()),
+        Err(e) => Err(e.msg),
+    }
+}
+
+fn valid_ctl_credential_name(val: String) -> result::Result<(), String> {
+    if !val.is_empty() && !val.contains(char::is_whitespace) {
+        Ok(())
+    } else {
+        Err(format!(
+            "Credential name: '{}' is not valid, it must not be empty or contain whitespace",
+            &val
+        ))
+    }
+}
+
+fn valid_health_probe(val: String) -> result::Result<(), String> {
+    match protocol::types::HealthProbe::from_str(&val) {
+        Ok(_) => Ok(()),
+        Err(e) => Err(e.msg),
+    }
+}
+
+fn valid_shutdown_signal(val: String) -> result::Result<(), String> {
+    match protocol::types::ShutdownSignal::from_str(&val) {
+        Ok(_) => Ok(()),
+        Err(e) => Err(e.msg),
+    }
+}
+
+fn valid_cpu_quota(val: String) -> result::Result<(), String> {
+    match protocol::types::ResourceLimits::parse_cpu_quota(&val) {
+        Ok(_) => Ok(()),
+        Err(e) => Err(e.msg),
+    }
+}
+
+fn valid_cpu_weight(val: String) -> result::Result<(), String> {
+    match val.parse::<u64>() {
+        Ok(weight) if weight >= 1 && weight <= 10_000 => Ok(()),
+        _ => Err(format!(
+            "'{}' is not a valid CPU weight, must be between 1 and 10000",
+            &val
+        )),
+    }
+}
+
 fn valid_health_check_interval(val: String) -> result::Result<(), String> {
     match HealthCheckInterval::from_str(&val) {
         Ok(_) => Ok(()),
@@ -1164,6 +1633,36 @@ fn valid_update_strategy(val: String) -> result::Result<(), String> {
     }
 }
 
+fn valid_update_window(val: String) -> result::Result<(), String> {
+    match protocol::types::UpdateWindow::from_str(&val) {
+        Ok(_) => Ok(()),
+        Err(e) => Err(format!("Update window: '{}' is not valid: {}", &val, e)),
+    }
+}
+
+fn valid_update_batch_size(val: String) -> result::Result<(), String> {
+    let valid = if val.ends_with('%') {
+        match val.trim_end_matches('%').parse::<u8>() {
+            Ok(percent) => percent > 0 && percent <= 100,
+            Err(_) => false,
+        }
+    } else {
+        match val.parse::<u32>() {
+            Ok(count) => count > 0,
+            Err(_) => false,
+        }
+    };
+    if valid {
+        Ok(())
+    } else {
+        Err(format!(
+            "Update batch size: '{}' is not valid; expected a number of members or a \
+             percentage, like 3 or 20%",
+            &val
+        ))
+    }
+}
+
 fn valid_ident(val: String) -> result::Result<(), String> {
     match PackageIdent::from_str(&val) {
         Ok(_) => Ok(()),
@@ -14,7 +14,7 @@
 
 #[cfg(windows)]
 use std::env;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::result;
 
 #[cfg(windows)]
@@ -201,6 +201,18 @@ pub fn start(
     } else {
         ui.para("Okay, maybe another time.")?;
     }
+    if ask_default_ctl_tls(ui)? {
+        ui.br()?;
+        ui.para(
+            "Enter the PEM-encoded files used to connect to Supervisors whose CtlGateway \
+             requires TLS: the client certificate and its private key, and the certificate of \
+             the CA which issues the Supervisors' certificates.",
+        )?;
+        let (cert, key, ca_cert, server_name) = prompt_ctl_tls(ui)?;
+        write_cli_config_ctl_tls(cert, key, ca_cert, server_name)?;
+    } else {
+        ui.para("Okay, maybe another time.")?;
+    }
     if cfg!(windows) {
         ui.heading("Habitat Binlink Path")?;
         ui.para(
@@ -299,6 +311,20 @@ where
     config::save(&config)
 }
 
+fn write_cli_config_ctl_tls(
+    cert: PathBuf,
+    key: PathBuf,
+    ca_cert: PathBuf,
+    server_name: String,
+) -> Result<()> {
+    let mut config = config::load()?;
+    config.ctl_client_certificate = Some(cert);
+    config.ctl_client_key = Some(key);
+    config.ctl_server_ca_certificate = Some(ca_cert);
+    config.ctl_server_name = Some(server_name);
+    config::save(&config)
+}
+
 fn is_origin_in_cache(origin: &str, cache_path: &Path) -> bool {
     match SigKeyPair::get_latest_pair_for(origin, cache_path, None) {
         Ok(pair) => match pair.secret() {
@@ -345,6 +371,13 @@ fn ask_default_ctl_secret(ui: &mut UI) -> Result<bool> {
     )?)
 }
 
+fn ask_default_ctl_tls(ui: &mut UI) -> Result<bool> {
+    Ok(ui.prompt_yes_no(
+        "Set up a client certificate for Habitat Supervisor CtlGateways which require TLS?",
+        Some(false),
+    )?)
+}
+
 fn prompt_url(ui: &mut UI) -> Result<String> {
     let config = config::load()?;
     let default = match config.bldr_url {
@@ -396,6 +429,42 @@ fn prompt_ctl_secret(ui: &mut UI) -> Result<String> {
     )?)
 }
 
+fn prompt_ctl_tls(ui: &mut UI) -> Result<(PathBuf, PathBuf, PathBuf, String)> {
+    let config = config::load()?;
+    let display = |path: &Option<PathBuf>| path.as_ref().map(|p| p.display().to_string());
+    let cert = ui.prompt_ask(
+        "Client certificate file",
+        display(&config.ctl_client_certificate)
+            .as_ref()
+            .map(|x| &**x),
+    )?;
+    let key = ui.prompt_ask(
+        "Client private key file",
+        display(&config.ctl_client_key).as_ref().map(|x| &**x),
+    )?;
+    let ca_cert = ui.prompt_ask(
+        "Supervisor CA certificate file",
+        display(&config.ctl_server_ca_certificate)
+            .as_ref()
+            .map(|x| &**x),
+    )?;
+    let server_name = ui.prompt_ask(
+        "Name the Supervisors' certificates are issued for",
+        Some(
+            config
+                .ctl_server_name
+                .as_ref()
+                .map_or("localhost", |x| &**x),
+        ),
+    )?;
+    Ok((
+        PathBuf::from(cert),
+        PathBuf::from(key),
+        PathBuf::from(ca_cert),
+        server_name,
+    ))
+}
+
 fn ask_enable_analytics(ui: &mut UI, analytics_path: &Path) -> Result<bool> {
     let default = match analytics::is_opted_in(analytics_path) {
         Some(val) => Some(val),
@@ -31,6 +31,15 @@ pub struct Config {
     pub auth_token: Option<String>,
     pub origin: Option<String>,
     pub ctl_secret: Option<String>,
+    /// The certificate presented to Supervisors whose ctl gateway requires TLS.
+    pub ctl_client_certificate: Option<PathBuf>,
+    /// The private key of `ctl_client_certificate`.
+    pub ctl_client_key: Option<PathBuf>,
+    /// The certificate of the CA which issues the certificates of Supervisors' ctl gateways.
+    pub ctl_server_ca_certificate: Option<PathBuf>,
+    /// The name the certificates of Supervisors' ctl gateways are issued for; defaults to
+    /// `localhost`.
+    pub ctl_server_name: Option<String>,
     pub bldr_url: Option<String>,
 }
 
@@ -44,6 +53,10 @@ impl Default for Config {
             auth_token: None,
             origin: None,
             ctl_secret: None,
+            ctl_client_certificate: None,
+            ctl_client_key: None,
+            ctl_server_ca_certificate: None,
+            ctl_server_name: None,
             bldr_url: None,
         }
     }
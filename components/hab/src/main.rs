@@ -40,6 +40,7 @@ use std::process;
 use std::result;
 use std::str::FromStr;
 use std::thread;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use crate::common::command::package::install::{
     InstallHookMode, InstallMode, InstallSource, LocalPackageUsage,
@@ -63,10 +64,10 @@ use futures::prelude::*;
 use crate::hcore::service::{HealthCheckInterval, ServiceGroup};
 use crate::hcore::url::{bldr_url_from_env, default_bldr_url};
 use crate::protocol::codec::*;
-use crate::protocol::ctl::ServiceBindList;
+use crate::protocol::ctl::{CtlCredential, ServiceBindList};
 use crate::protocol::net::ErrCode;
 use crate::protocol::types::*;
-use crate::sup_client::{SrvClient, SrvClientError};
+use crate::sup_client::{ClientTls, SrvClient, SrvClientError};
 use tabwriter::TabWriter;
 
 use hab::analytics;
@@ -84,6 +85,9 @@ const HABITAT_ORG_ENVVAR: &str = "HAB_ORG";
 /// Makes the --user CLI param optional when this env var is set
 const HABITAT_USER_ENVVAR: &str = "HAB_USER";
 const SYSTEMDRIVE_ENVVAR: &str = "SYSTEMDRIVE";
+/// The name Supervisors' ctl gateway certificates are checked against, unless the CLI config sets
+/// one
+const DEFAULT_CTL_SERVER_NAME: &str = "localhost";
 
 lazy_static! {
     static ref STATUS_HEADER: Vec<&'static str> = {
@@ -251,17 +255,30 @@ fn start(ui: &mut UI) -> Result<()> {
                 ("generate", Some(sc)) => sub_service_key_generate(ui, sc)?,
                 _ => unreachable!(),
             },
+            ("apply", Some(m)) => sub_svc_apply(m)?,
             ("load", Some(m)) => sub_svc_load(m)?,
+            ("logs", Some(m)) => sub_svc_logs(m)?,
+            ("secret", Some(m)) => match m.subcommand() {
+                ("set", Some(sc)) => sub_svc_secret_set(sc)?,
+                _ => unreachable!(),
+            },
             ("unload", Some(m)) => sub_svc_unload(m)?,
             ("start", Some(m)) => sub_svc_start(m)?,
             ("stop", Some(m)) => sub_svc_stop(m)?,
             ("status", Some(m)) => sub_svc_status(m)?,
+            ("update", Some(m)) => match m.subcommand() {
+                ("pause", Some(sc)) => sub_svc_update_pause(sc)?,
+                ("resume", Some(sc)) => sub_svc_update_resume(sc)?,
+                ("now", Some(sc)) => sub_svc_update_now(sc)?,
+                _ => unreachable!(),
+            },
             _ => unreachable!(),
         },
         ("sup", Some(m)) => match m.subcommand() {
+            ("audit", Some(m)) => sub_sup_audit(m)?,
             ("depart", Some(m)) => sub_sup_depart(m)?,
             ("secret", Some(m)) => match m.subcommand() {
-                ("generate", _) => sub_sup_secret_generate()?,
+                ("generate", Some(sc)) => sub_sup_secret_generate(sc)?,
                 _ => unreachable!(),
             },
             // this is effectively an alias of `hab svc status`
@@ -880,7 +897,7 @@ fn sub_svc_set(m: &ArgMatches<'_>) -> Result<()> {
             .unwrap_or_else(|| "UNKNOWN".to_string()),
     ))?;
     ui.status(Status::Creating, "service configuration")?;
-    SrvClient::connect(&listen_ctl_addr, &secret_key)
+    ctl_connect(&cfg, &listen_ctl_addr, &secret_key)?
         .and_then(|conn| {
             conn.call(validate)
                 .for_each(|reply| match reply.message_id() {
@@ -907,7 +924,7 @@ fn sub_svc_set(m: &ArgMatches<'_>) -> Result<()> {
     // JW: We should not need to make two connections here. I need a way to return the
     // SrvClient from a for_each iterator so we can chain upon a successful stream but I don't
     // know if it's possible with this version of futures.
-    SrvClient::connect(&listen_ctl_addr, secret_key)
+    ctl_connect(&cfg, &listen_ctl_addr, secret_key)?
         .and_then(|conn| {
             conn.call(set).for_each(|reply| match reply.message_id() {
                 "NetOk" => Ok(()),
@@ -934,7 +951,7 @@ fn sub_svc_config(m: &ArgMatches<'_>) -> Result<()> {
     let secret_key = ctl_secret_key(&cfg)?;
     let mut msg = protocol::ctl::SvcGetDefaultCfg::default();
     msg.ident = Some(ident.into());
-    SrvClient::connect(&listen_ctl_addr, secret_key)
+    ctl_connect(&cfg, &listen_ctl_addr, secret_key)?
         .and_then(|conn| {
             conn.call(msg).for_each(|reply| match reply.message_id() {
                 "ServiceCfg" => {
@@ -967,7 +984,26 @@ fn sub_svc_load(m: &ArgMatches<'_>) -> Result<()> {
     update_svc_load_from_input(m, &mut msg)?;
     let ident: PackageIdent = m.value_of("PKG_IDENT").unwrap().parse()?;
     msg.ident = Some(ident.into());
-    SrvClient::connect(&listen_ctl_addr, secret_key)
+    ctl_connect(&cfg, &listen_ctl_addr, secret_key)?
+        .and_then(|conn| conn.call(msg).for_each(handle_ctl_reply))
+        .wait()?;
+    Ok(())
+}
+
+fn sub_svc_apply(m: &ArgMatches<'_>) -> Result<()> {
+    let mut manifest = String::new();
+    match m.value_of("FILE") {
+        Some("-") | None => io::stdin().read_to_string(&mut manifest)?,
+        Some(f) => File::open(f)?.read_to_string(&mut manifest)?,
+    };
+    let cfg = config::load()?;
+    let listen_ctl_addr = listen_ctl_addr_from_input(m)?;
+    let secret_key = ctl_secret_key(&cfg)?;
+    let mut msg = protocol::ctl::SvcApply::default();
+    msg.manifest = Some(manifest);
+    msg.prune = Some(m.is_present("PRUNE"));
+    msg.dry_run = Some(m.is_present("DRY_RUN"));
+    ctl_connect(&cfg, &listen_ctl_addr, secret_key)?
         .and_then(|conn| conn.call(msg).for_each(handle_ctl_reply))
         .wait()?;
     Ok(())
@@ -980,7 +1016,50 @@ fn sub_svc_unload(m: &ArgMatches<'_>) -> Result<()> {
     let secret_key = ctl_secret_key(&cfg)?;
     let mut msg = protocol::ctl::SvcUnload::default();
     msg.ident = Some(ident.into());
-    SrvClient::connect(&listen_ctl_addr, secret_key)
+    ctl_connect(&cfg, &listen_ctl_addr, secret_key)?
+        .and_then(|conn| conn.call(msg).for_each(handle_ctl_reply))
+        .wait()?;
+    Ok(())
+}
+
+fn sub_svc_logs(m: &ArgMatches<'_>) -> Result<()> {
+    let ident = PackageIdent::from_str(m.value_of("PKG_IDENT").unwrap())?;
+    let cfg = config::load()?;
+    let listen_ctl_addr = listen_ctl_addr_from_input(m)?;
+    let secret_key = ctl_secret_key(&cfg)?;
+    let mut msg = protocol::ctl::SvcLogs::default();
+    msg.ident = Some(ident.into());
+    msg.follow = Some(m.is_present("FOLLOW"));
+    // Values will have already been validated by `cli::valid_numeric`
+    msg.tail = m.value_of("TAIL").and_then(|s| s.parse().ok());
+    if let Some(secs) = m.value_of("SINCE").and_then(|s| s.parse::<u64>().ok()) {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        msg.since = Some(now.saturating_sub(secs) as i64);
+    }
+    ctl_connect(&cfg, &listen_ctl_addr, secret_key)?
+        .and_then(|conn| conn.call(msg).for_each(handle_ctl_reply))
+        .wait()?;
+    Ok(())
+}
+
+fn sub_svc_secret_set(m: &ArgMatches<'_>) -> Result<()> {
+    let service_group = ServiceGroup::from_str(m.value_of("SERVICE_GROUP").unwrap())?;
+    let mut value = String::new();
+    match m.value_of("FILE") {
+        Some("-") | None => io::stdin().read_to_string(&mut value)?,
+        Some(f) => File::open(f)?.read_to_string(&mut value)?,
+    };
+    let cfg = config::load()?;
+    let listen_ctl_addr = listen_ctl_addr_from_input(m)?;
+    let secret_key = ctl_secret_key(&cfg)?;
+    let mut msg = protocol::ctl::SvcSecretSet::default();
+    msg.service_group = Some(service_group.into());
+    msg.name = Some(m.value_of("NAME").unwrap().to_string());
+    msg.value = Some(value.trim_end_matches(&['\r', '\n'][..]).to_string());
+    ctl_connect(&cfg, &listen_ctl_addr, secret_key)?
         .and_then(|conn| conn.call(msg).for_each(handle_ctl_reply))
         .wait()?;
     Ok(())
@@ -993,7 +1072,7 @@ fn sub_svc_start(m: &ArgMatches<'_>) -> Result<()> {
     let secret_key = ctl_secret_key(&cfg)?;
     let mut msg = protocol::ctl::SvcStart::default();
     msg.ident = Some(ident.into());
-    SrvClient::connect(&listen_ctl_addr, secret_key)
+    ctl_connect(&cfg, &listen_ctl_addr, secret_key)?
         .and_then(|conn| conn.call(msg).for_each(handle_ctl_reply))
         .wait()?;
     Ok(())
@@ -1008,7 +1087,7 @@ fn sub_svc_status(m: &ArgMatches<'_>) -> Result<()> {
         msg.ident = Some(PackageIdent::from_str(pkg)?.into());
     }
 
-    SrvClient::connect(&listen_ctl_addr, secret_key)
+    ctl_connect(&cfg, &listen_ctl_addr, secret_key)?
         .and_then(|conn| {
             let mut out = TabWriter::new(io::stdout());
             conn.call(msg)
@@ -1047,7 +1126,46 @@ fn sub_svc_stop(m: &ArgMatches<'_>) -> Result<()> {
     let secret_key = ctl_secret_key(&cfg)?;
     let mut msg = protocol::ctl::SvcStop::default();
     msg.ident = Some(ident.into());
-    SrvClient::connect(&listen_ctl_addr, secret_key)
+    ctl_connect(&cfg, &listen_ctl_addr, secret_key)?
+        .and_then(|conn| conn.call(msg).for_each(handle_ctl_reply))
+        .wait()?;
+    Ok(())
+}
+
+fn sub_svc_update_pause(m: &ArgMatches<'_>) -> Result<()> {
+    let ident = PackageIdent::from_str(m.value_of("PKG_IDENT").unwrap())?;
+    let cfg = config::load()?;
+    let listen_ctl_addr = listen_ctl_addr_from_input(m)?;
+    let secret_key = ctl_secret_key(&cfg)?;
+    let mut msg = protocol::ctl::SvcUpdatePause::default();
+    msg.ident = Some(ident.into());
+    ctl_connect(&cfg, &listen_ctl_addr, secret_key)?
+        .and_then(|conn| conn.call(msg).for_each(handle_ctl_reply))
+        .wait()?;
+    Ok(())
+}
+
+fn sub_svc_update_resume(m: &ArgMatches<'_>) -> Result<()> {
+    let ident = PackageIdent::from_str(m.value_of("PKG_IDENT").unwrap())?;
+    let cfg = config::load()?;
+    let listen_ctl_addr = listen_ctl_addr_from_input(m)?;
+    let secret_key = ctl_secret_key(&cfg)?;
+    let mut msg = protocol::ctl::SvcUpdateResume::default();
+    msg.ident = Some(ident.into());
+    ctl_connect(&cfg, &listen_ctl_addr, secret_key)?
+        .and_then(|conn| conn.call(msg).for_each(handle_ctl_reply))
+        .wait()?;
+    Ok(())
+}
+
+fn sub_svc_update_now(m: &ArgMatches<'_>) -> Result<()> {
+    let ident = PackageIdent::from_str(m.value_of("PKG_IDENT").unwrap())?;
+    let cfg = config::load()?;
+    let listen_ctl_addr = listen_ctl_addr_from_input(m)?;
+    let secret_key = ctl_secret_key(&cfg)?;
+    let mut msg = protocol::ctl::SvcUpdateNow::default();
+    msg.ident = Some(ident.into());
+    ctl_connect(&cfg, &listen_ctl_addr, secret_key)?
         .and_then(|conn| conn.call(msg).for_each(handle_ctl_reply))
         .wait()?;
     Ok(())
@@ -1104,7 +1222,7 @@ fn sub_file_put(m: &ArgMatches<'_>) -> Result<()> {
         }
         _ => msg.content = Some(buf.to_vec()),
     }
-    SrvClient::connect(&listen_ctl_addr, secret_key)
+    ctl_connect(&cfg, &listen_ctl_addr, secret_key)?
         .and_then(|conn| {
             ui.status(Status::Applying, format!("via peer {}", listen_ctl_addr))
                 .unwrap();
@@ -1132,6 +1250,29 @@ fn sub_file_put(m: &ArgMatches<'_>) -> Result<()> {
     Ok(())
 }
 
+fn sub_sup_audit(m: &ArgMatches<'_>) -> Result<()> {
+    let cfg = config::load()?;
+    let listen_ctl_addr = listen_ctl_addr_from_input(m)?;
+    let secret_key = ctl_secret_key(&cfg)?;
+    let mut msg = protocol::ctl::SupAudit::default();
+    // Values will have already been validated by `cli::valid_numeric`
+    msg.tail = m.value_of("TAIL").and_then(|s| s.parse().ok());
+    if let Some(secs) = m.value_of("SINCE").and_then(|s| s.parse::<u64>().ok()) {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        msg.since = Some(now.saturating_sub(secs) as i64);
+    }
+    msg.actor = m.value_of("ACTOR").map(str::to_string);
+    msg.request = m.value_of("REQUEST").map(str::to_string);
+    msg.verify = Some(m.is_present("VERIFY"));
+    ctl_connect(&cfg, &listen_ctl_addr, secret_key)?
+        .and_then(|conn| conn.call(msg).for_each(handle_ctl_reply))
+        .wait()?;
+    Ok(())
+}
+
 fn sub_sup_depart(m: &ArgMatches<'_>) -> Result<()> {
     let cfg = config::load()?;
     let listen_ctl_addr = listen_ctl_addr_from_input(m)?;
@@ -1139,7 +1280,7 @@ fn sub_sup_depart(m: &ArgMatches<'_>) -> Result<()> {
     let mut ui = ui();
     let mut msg = protocol::ctl::SupDepart::default();
     msg.member_id = Some(m.value_of("MEMBER_ID").unwrap().to_string());
-    SrvClient::connect(&listen_ctl_addr, secret_key)
+    ctl_connect(&cfg, &listen_ctl_addr, secret_key)?
         .and_then(|conn| {
             ui.begin(format!(
                 "Permanently marking {} as departed",
@@ -1169,11 +1310,21 @@ fn sub_sup_depart(m: &ArgMatches<'_>) -> Result<()> {
     Ok(())
 }
 
-fn sub_sup_secret_generate() -> Result<()> {
+fn sub_sup_secret_generate(m: &ArgMatches<'_>) -> Result<()> {
     let mut ui = ui();
     let mut buf = String::new();
     protocol::generate_secret_key(&mut buf);
-    ui.info(buf)?;
+    match m.value_of("SCOPE") {
+        Some(scope) => {
+            let credential = CtlCredential {
+                name: m.value_of("NAME").unwrap().to_string(), // Required via clap
+                scope: scope.parse()?,
+                secret_key: buf,
+            };
+            ui.info(credential.to_string())?;
+        }
+        None => ui.info(buf)?,
+    }
     Ok(())
 }
 
@@ -1265,6 +1416,7 @@ fn exec_subcommand_if_called(ui: &mut UI) -> Result<()> {
         ("sup", "", "")
         | ("sup", "term", _)
         | ("sup", "bash", _)
+        | ("sup", "config", _)
         | ("sup", "sh", _)
         | ("sup", "-V", _)
         | ("sup", "--version", _) => command::sup::start(ui, env::args_os().skip(2).collect()),
@@ -1318,6 +1470,34 @@ fn auth_token_param_or_env(m: &ArgMatches<'_>) -> Result<String> {
     }
 }
 
+/// Connect to a Supervisor's ctl gateway, over TLS if the CLI config has a client certificate.
+fn ctl_connect<S>(
+    config: &Config,
+    listen_ctl_addr: &ListenCtlAddr,
+    secret_key: S,
+) -> Result<Box<dyn Future<Item = SrvClient, Error = SrvClientError>>>
+where
+    S: ToString,
+{
+    let tls = match (
+        &config.ctl_client_certificate,
+        &config.ctl_client_key,
+        &config.ctl_server_ca_certificate,
+    ) {
+        (Some(cert), Some(key), Some(ca_cert)) => Some(ClientTls::new(
+            cert,
+            key,
+            ca_cert,
+            config
+                .ctl_server_name
+                .as_ref()
+                .map_or(DEFAULT_CTL_SERVER_NAME, String::as_str),
+        )?),
+        _ => None,
+    };
+    Ok(SrvClient::connect_with(listen_ctl_addr, secret_key, tls))
+}
+
 /// Check if the HAB_CTL_SECRET env var. If not, check the CLI config to see if there is a ctl
 /// secret set and return a copy of that value.
 fn ctl_secret_key(config: &Config) -> Result<String> {
@@ -1551,6 +1731,16 @@ where
             ),
         }
     };
+    // A crash-looping service is down for good, and a service waiting on one it depends on is
+    // down for now, both of which are worth calling out over a plain "down".
+    let svc_state = if status.crash_looping.unwrap_or(false) {
+        "crash-looping".to_string()
+    } else if let Some(ref dep) = status.waiting_on {
+        // One word, so that the columns can still be split on whitespace.
+        format!("waiting-on:{}", dep)
+    } else {
+        ProcessState::from_str(&svc_state)?.to_string()
+    };
     if print_header {
         writeln!(out, "{}", STATUS_HEADER.join("\t")).unwrap();
     }
@@ -1567,7 +1757,7 @@ where
         "{}\tstandalone\t{}\t{}\t{}\t{}\t{}",
         status.ident,
         DesiredState::from_str(&svc_desired_state)?,
-        ProcessState::from_str(&svc_state)?,
+        svc_state,
         svc_elapsed,
         svc_pid,
         status.service_group,
@@ -1595,7 +1785,7 @@ fn supervisor_services() -> Result<Vec<PackageIdent>> {
     let msg = protocol::ctl::SvcStatus::default();
 
     let mut out: Vec<PackageIdent> = vec![];
-    SrvClient::connect(&listen_ctl_addr, &secret_key)
+    ctl_connect(&cfg, &listen_ctl_addr, &secret_key)?
         .and_then(|conn| {
             conn.call(msg).for_each(|reply| match reply.message_id() {
                 "ServiceStatus" => {
@@ -1666,6 +1856,123 @@ fn get_group_from_input(m: &ArgMatches<'_>) -> Option<String> {
     m.value_of("GROUP").map(ToString::to_string)
 }
 
+fn get_update_settings_from_input(m: &ArgMatches<'_>) -> Option<protocol::types::UpdateSettings> {
+    // Values will have already been validated
+    let settings = protocol::types::UpdateSettings {
+        batch_size: m.value_of("UPDATE_BATCH_SIZE").map(ToString::to_string),
+        canary_soak: m.value_of("CANARY_SOAK").and_then(|s| s.parse().ok()),
+        windows: m
+            .values_of("UPDATE_WINDOW")
+            .map(|w| w.map(ToString::to_string).collect())
+            .unwrap_or_default(),
+        splay: m.value_of("UPDATE_SPLAY").and_then(|s| s.parse().ok()),
+        rollback_period: m.value_of("ROLLBACK_PERIOD").and_then(|s| s.parse().ok()),
+    };
+    if settings == protocol::types::UpdateSettings::default() {
+        None
+    } else {
+        Some(settings)
+    }
+}
+
+fn get_restart_policy_from_input(m: &ArgMatches<'_>) -> Option<protocol::types::RestartPolicy> {
+    // Values will have already been validated by `cli::valid_numeric`
+    let policy = protocol::types::RestartPolicy {
+        max_restarts: m.value_of("MAX_RESTARTS").and_then(|s| s.parse().ok()),
+        window: m.value_of("RESTART_WINDOW").and_then(|s| s.parse().ok()),
+        backoff: m.value_of("RESTART_BACKOFF").and_then(|s| s.parse().ok()),
+        backoff_max: m
+            .value_of("RESTART_BACKOFF_MAX")
+            .and_then(|s| s.parse().ok()),
+    };
+    if policy == protocol::types::RestartPolicy::default() {
+        None
+    } else {
+        Some(policy)
+    }
+}
+
+fn get_health_check_thresholds_from_input(
+    m: &ArgMatches<'_>,
+) -> Option<protocol::types::HealthCheckThresholds> {
+    // Values will have already been validated by `cli::valid_numeric`
+    let thresholds = protocol::types::HealthCheckThresholds {
+        failures: m
+            .value_of("HEALTH_CHECK_FAILURES")
+            .and_then(|s| s.parse().ok()),
+        successes: m
+            .value_of("HEALTH_CHECK_SUCCESSES")
+            .and_then(|s| s.parse().ok()),
+        grace_period: m
+            .value_of("HEALTH_CHECK_GRACE_PERIOD")
+            .and_then(|s| s.parse().ok()),
+        restart_after: m
+            .value_of("HEALTH_CHECK_RESTART_AFTER")
+            .and_then(|s| s.parse().ok()),
+    };
+    if thresholds == protocol::types::HealthCheckThresholds::default() {
+        None
+    } else {
+        Some(thresholds)
+    }
+}
+
+fn get_health_probe_from_input(m: &ArgMatches<'_>) -> Option<protocol::types::HealthProbe> {
+    // Values will have already been validated by `cli::valid_health_probe`
+    m.value_of("HEALTH_PROBE")
+        .map(|target| protocol::types::HealthProbe {
+            target: target.to_string(),
+            timeout: m
+                .value_of("HEALTH_PROBE_TIMEOUT")
+                .and_then(|s| s.parse().ok()),
+        })
+}
+
+fn get_log_settings_from_input(m: &ArgMatches<'_>) -> Option<protocol::types::LogSettings> {
+    // Values will have already been validated by the cli
+    let settings = protocol::types::LogSettings {
+        enabled: if m.is_present("LOG_FILE") {
+            Some(true)
+        } else {
+            None
+        },
+        max_size: m
+            .value_of("LOG_MAX_SIZE")
+            .and_then(|s| protocol::types::ResourceLimits::parse_memory(s).ok()),
+        max_age: m.value_of("LOG_MAX_AGE").and_then(|s| s.parse().ok()),
+        retain: m.value_of("LOG_RETAIN").and_then(|s| s.parse().ok()),
+        compress: if m.is_present("NO_LOG_COMPRESS") {
+            Some(false)
+        } else {
+            None
+        },
+    };
+    if settings == protocol::types::LogSettings::default() {
+        None
+    } else {
+        Some(settings)
+    }
+}
+
+fn get_resource_limits_from_input(m: &ArgMatches<'_>) -> Option<protocol::types::ResourceLimits> {
+    // Values will have already been validated by the cli
+    let limits = protocol::types::ResourceLimits {
+        memory_max: m
+            .value_of("MEMORY_MAX")
+            .and_then(|s| protocol::types::ResourceLimits::parse_memory(s).ok()),
+        cpu_weight: m.value_of("CPU_WEIGHT").and_then(|s| s.parse().ok()),
+        cpu_quota: m
+            .value_of("CPU_QUOTA")
+            .and_then(|s| protocol::types::ResourceLimits::parse_cpu_quota(s).ok()),
+        pids_max: m.value_of("PIDS_MAX").and_then(|s| s.parse().ok()),
+    };
+    if limits == protocol::types::ResourceLimits::default() {
+        None
+    } else {
+        Some(limits)
+    }
+}
+
 fn get_health_check_interval_from_input(
     m: &ArgMatches<'_>,
 ) -> Option<protocol::types::HealthCheckInterval> {
@@ -1699,6 +2006,22 @@ fn get_strategy_from_input(m: &ArgMatches<'_>) -> Option<UpdateStrategy> {
         .and_then(|f| UpdateStrategy::from_str(f).ok())
 }
 
+fn get_idents_from_input(
+    m: &ArgMatches<'_>,
+    name: &str,
+) -> Result<Vec<protocol::types::PackageIdent>> {
+    m.values_of(name)
+        .into_iter()
+        .flatten()
+        .map(|ident| Ok(PackageIdent::from_str(ident)?.into()))
+        .collect()
+}
+
+fn get_shutdown_signal_from_input(m: &ArgMatches<'_>) -> Option<ShutdownSignal> {
+    m.value_of("SHUTDOWN_SIGNAL")
+        .and_then(|s| ShutdownSignal::from_str(s).ok())
+}
+
 fn listen_ctl_addr_from_input(m: &ArgMatches<'_>) -> Result<ListenCtlAddr> {
     m.value_of("REMOTE_SUP")
         .map_or(Ok(ListenCtlAddr::default()), resolve_listen_ctl_addr)
@@ -1777,9 +2100,24 @@ fn update_svc_load_from_input(m: &ArgMatches<'_>, msg: &mut protocol::ctl::SvcLo
     msg.group = get_group_from_input(m);
     msg.svc_encrypted_password = get_password_from_input(m)?;
     msg.health_check_interval = get_health_check_interval_from_input(m);
+    msg.restart_policy = get_restart_policy_from_input(m);
+    msg.resource_limits = get_resource_limits_from_input(m);
+    msg.log_settings = get_log_settings_from_input(m);
+    msg.health_check_thresholds = get_health_check_thresholds_from_input(m);
+    msg.health_probe = get_health_probe_from_input(m);
     msg.binding_mode = get_binding_mode_from_input(m).map(|v| v as i32);
     msg.topology = get_topology_from_input(m).map(|v| v as i32);
     msg.update_strategy = get_strategy_from_input(m).map(|v| v as i32);
+    msg.update_settings = get_update_settings_from_input(m);
+    msg.shutdown_signal = get_shutdown_signal_from_input(m).map(|v| v as i32);
+    msg.shutdown_timeout = m.value_of("SHUTDOWN_TIMEOUT").and_then(|s| s.parse().ok());
+    msg.pre_start_timeout = m.value_of("PRE_START_TIMEOUT").and_then(|s| s.parse().ok());
+    msg.pre_stop_timeout = m.value_of("PRE_STOP_TIMEOUT").and_then(|s| s.parse().ok());
+    msg.after = get_idents_from_input(m, "AFTER")?;
+    msg.requires = get_idents_from_input(m, "REQUIRES")?;
+    if m.is_present("REQUIRE_HEALTHY") {
+        msg.require_healthy = Some(true);
+    }
     Ok(())
 }
 
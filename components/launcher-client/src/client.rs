@@ -115,9 +115,12 @@ impl LauncherCli {
         }
     }
 
-    /// Restart a running process with the same arguments
-    pub fn restart(&self, pid: Pid) -> Result<Pid> {
-        let msg = protocol::Restart { pid: pid.into() };
+    /// Restart a running process with the same arguments, stopping it as `shutdown` describes
+    pub fn restart(&self, pid: Pid, shutdown: protocol::ShutdownSpec) -> Result<Pid> {
+        let msg = protocol::Restart {
+            pid: pid.into(),
+            shutdown: shutdown,
+        };
         Self::send(&self.tx, &msg)?;
         let reply = Self::recv::<protocol::SpawnOk>(&self.rx)?;
         Ok(reply.pid as Pid)
@@ -128,6 +131,12 @@ impl LauncherCli {
     /// `user` and `group` are string names, while `user_id` and
     /// `group_id` are numeric IDs. Newer versions of the Launcher can
     /// accept either, but prefer numeric IDs.
+    ///
+    /// `limits` are enforced by placing the process in its own cgroup;
+    /// they are ignored on platforms without cgroups.
+    ///
+    /// When `log` is given, the process's output is written to a
+    /// rotated log file rather than the Launcher's own stdout/stderr.
     pub fn spawn<I, B, U, G, P>(
         &self,
         id: &I,
@@ -138,6 +147,9 @@ impl LauncherCli {
         group_id: Option<u32>,
         password: Option<P>,
         env: Env,
+        limits: Option<protocol::ResourceLimits>,
+        log: Option<protocol::LogConfig>,
+        shutdown: protocol::ShutdownSpec,
     ) -> Result<Pid>
     where
         I: ToString,
@@ -161,6 +173,9 @@ impl LauncherCli {
             svc_password: password.map(|p| p.to_string()),
             env: env,
             id: id.to_string(),
+            limits: limits,
+            log: log,
+            shutdown: shutdown,
             ..Default::default()
         };
 
@@ -169,10 +184,17 @@ impl LauncherCli {
         Ok(reply.pid as Pid)
     }
 
-    pub fn terminate(&self, pid: Pid) -> Result<i32> {
-        let msg = protocol::Terminate { pid: pid.into() };
+    /// Terminate a running process as `shutdown` describes, returning how it was stopped
+    pub fn terminate(
+        &self,
+        pid: Pid,
+        shutdown: protocol::ShutdownSpec,
+    ) -> Result<protocol::TerminateOk> {
+        let msg = protocol::Terminate {
+            pid: pid.into(),
+            shutdown: shutdown,
+        };
         Self::send(&self.tx, &msg)?;
-        let reply = Self::recv::<protocol::TerminateOk>(&self.rx)?;
-        Ok(reply.exit_code)
+        Self::recv::<protocol::TerminateOk>(&self.rx)
     }
 }
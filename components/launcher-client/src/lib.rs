@@ -19,6 +19,7 @@ mod client;
 pub mod error;
 
 pub use habitat_launcher_protocol::{
+    service_cgroup_path, LogConfig, ResourceLimits, ShutdownMethod, ShutdownSpec, TerminateOk,
     ERR_NO_RETRY_EXCODE, LAUNCHER_LOCK_CLEAN_ENV, LAUNCHER_PID_ENV, OK_NO_RETRY_EXCODE,
 };
 
@@ -24,6 +24,7 @@ mod types;
 
 use crate::error::Result;
 pub use crate::{error::Error, types::*};
+use std::{env, path::PathBuf};
 
 pub const LAUNCHER_PIPE_ENV: &str = "HAB_LAUNCHER_PIPE";
 pub const LAUNCHER_PID_ENV: &str = "HAB_LAUNCHER_PID";
@@ -37,6 +38,10 @@ pub const OK_NO_RETRY_EXCODE: i32 = 84;
 /// Same as `OK_NO_RETRY_EXCODE` except the Supervisor ran to completion with an unsuccessful
 /// exit code. The Launcher should exit immediately with a non-zero exit code.
 pub const ERR_NO_RETRY_EXCODE: i32 = 86;
+/// Directory under which the Launcher creates a cgroup for each service with resource limits.
+/// The parent of this directory must be part of a mounted cgroup v2 hierarchy.
+pub const CGROUP_ROOT_ENV: &str = "HAB_LAUNCHER_CGROUP_ROOT";
+pub const DEFAULT_CGROUP_ROOT: &str = "/sys/fs/cgroup/habitat";
 
 #[derive(Debug)]
 pub struct NetTxn(Envelope);
@@ -74,6 +79,14 @@ impl NetTxn {
     }
 }
 
+/// Path of the cgroup the Launcher places the service with the given spawn ID into.
+pub fn service_cgroup_path(id: &str) -> PathBuf {
+    env::var(CGROUP_ROOT_ENV)
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from(DEFAULT_CGROUP_ROOT))
+        .join(id)
+}
+
 pub fn error<T>(err: T) -> NetErr
 where
     T: ToString + Into<ErrCode>,
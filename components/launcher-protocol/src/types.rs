@@ -139,6 +139,7 @@ impl From<Register> for generated::Register {
 #[derive(Clone, Debug, Default, PartialEq)]
 pub struct Restart {
     pub pid: i64,
+    pub shutdown: ShutdownSpec,
 }
 
 impl LauncherMessage for Restart {
@@ -148,6 +149,7 @@ impl LauncherMessage for Restart {
     fn from_proto(proto: generated::Restart) -> Result<Self> {
         Ok(Restart {
             pid: proto.pid.ok_or(Error::ProtocolMismatch("pid"))?,
+            shutdown: ShutdownSpec::from_parts(proto.shutdown_signal, proto.shutdown_timeout),
         })
     }
 }
@@ -156,6 +158,8 @@ impl From<Restart> for generated::Restart {
     fn from(value: Restart) -> Self {
         generated::Restart {
             pid: Some(value.pid),
+            shutdown_signal: Some(value.shutdown.signal),
+            shutdown_timeout: Some(value.shutdown.timeout),
         }
     }
 }
@@ -170,6 +174,10 @@ pub struct Spawn {
     pub env: HashMap<String, String>,
     pub svc_user_id: Option<u32>,
     pub svc_group_id: Option<u32>,
+    pub limits: Option<ResourceLimits>,
+    pub log: Option<LogConfig>,
+    /// How to stop the process when the Launcher stops it without being asked to.
+    pub shutdown: ShutdownSpec,
 }
 
 impl LauncherMessage for Spawn {
@@ -186,6 +194,12 @@ impl LauncherMessage for Spawn {
             env: proto.env,
             svc_user_id: proto.svc_user_id,
             svc_group_id: proto.svc_group_id,
+            limits: proto.limits.map(Into::into),
+            log: match proto.log {
+                Some(log) => Some(LogConfig::from_proto(log)?),
+                None => None,
+            },
+            shutdown: ShutdownSpec::from_parts(proto.shutdown_signal, proto.shutdown_timeout),
         })
     }
 }
@@ -201,6 +215,88 @@ impl From<Spawn> for generated::Spawn {
             env: value.env,
             svc_user_id: value.svc_user_id,
             svc_group_id: value.svc_group_id,
+            limits: value.limits.map(Into::into),
+            log: value.log.map(Into::into),
+            shutdown_signal: Some(value.shutdown.signal),
+            shutdown_timeout: Some(value.shutdown.timeout),
+        }
+    }
+}
+
+/// Resource limits for a spawned process. `None` means unlimited.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ResourceLimits {
+    /// Maximum memory, in bytes.
+    pub memory_max: Option<u64>,
+    /// Relative CPU weight, from 1 to 10000.
+    pub cpu_weight: Option<u64>,
+    /// CPU time, as a percentage of a single CPU.
+    pub cpu_quota: Option<u64>,
+    /// Maximum number of processes and threads.
+    pub pids_max: Option<u64>,
+}
+
+impl ResourceLimits {
+    pub fn is_unlimited(&self) -> bool {
+        *self == ResourceLimits::default()
+    }
+}
+
+impl From<generated::ResourceLimits> for ResourceLimits {
+    fn from(proto: generated::ResourceLimits) -> Self {
+        ResourceLimits {
+            memory_max: proto.memory_max,
+            cpu_weight: proto.cpu_weight,
+            cpu_quota: proto.cpu_quota,
+            pids_max: proto.pids_max,
+        }
+    }
+}
+
+impl From<ResourceLimits> for generated::ResourceLimits {
+    fn from(value: ResourceLimits) -> Self {
+        generated::ResourceLimits {
+            memory_max: value.memory_max,
+            cpu_weight: value.cpu_weight,
+            cpu_quota: value.cpu_quota,
+            pids_max: value.pids_max,
+        }
+    }
+}
+
+/// Settings for writing a spawned process's output to a rotated log file.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LogConfig {
+    pub path: String,
+    /// Rotate once the file reaches this many bytes.
+    pub max_size: Option<u64>,
+    /// Rotate once the file is this many seconds old.
+    pub max_age: Option<u64>,
+    /// Number of rotated files to keep.
+    pub retain: Option<u32>,
+    pub compress: bool,
+}
+
+impl LogConfig {
+    fn from_proto(proto: generated::LogConfig) -> Result<Self> {
+        Ok(LogConfig {
+            path: proto.path.ok_or(Error::ProtocolMismatch("path"))?,
+            max_size: proto.max_size,
+            max_age: proto.max_age,
+            retain: proto.retain,
+            compress: proto.compress.unwrap_or(false),
+        })
+    }
+}
+
+impl From<LogConfig> for generated::LogConfig {
+    fn from(value: LogConfig) -> Self {
+        generated::LogConfig {
+            path: Some(value.path),
+            max_size: value.max_size,
+            max_age: value.max_age,
+            retain: value.retain,
+            compress: Some(value.compress),
         }
     }
 }
@@ -229,9 +325,38 @@ impl From<SpawnOk> for generated::SpawnOk {
     }
 }
 
+/// How the Launcher asks a process to stop, and how long it waits before killing it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ShutdownSpec {
+    /// Name of the signal to send, without the `SIG` prefix.
+    pub signal: String,
+    /// Seconds to wait for the process to exit.
+    pub timeout: u32,
+}
+
+impl ShutdownSpec {
+    pub const DEFAULT_SIGNAL: &'static str = "TERM";
+    pub const DEFAULT_TIMEOUT: u32 = 8;
+
+    /// Older Supervisors send neither field, so each falls back to its default on its own.
+    fn from_parts(signal: Option<String>, timeout: Option<u32>) -> Self {
+        ShutdownSpec {
+            signal: signal.unwrap_or_else(|| Self::DEFAULT_SIGNAL.to_string()),
+            timeout: timeout.unwrap_or(Self::DEFAULT_TIMEOUT),
+        }
+    }
+}
+
+impl Default for ShutdownSpec {
+    fn default() -> Self {
+        ShutdownSpec::from_parts(None, None)
+    }
+}
+
 #[derive(Clone, Debug, Default, PartialEq)]
 pub struct Terminate {
     pub pid: i64,
+    pub shutdown: ShutdownSpec,
 }
 
 impl LauncherMessage for Terminate {
@@ -241,6 +366,7 @@ impl LauncherMessage for Terminate {
     fn from_proto(proto: generated::Terminate) -> Result<Self> {
         Ok(Terminate {
             pid: proto.pid.ok_or(Error::ProtocolMismatch("pid"))?,
+            shutdown: ShutdownSpec::from_parts(proto.shutdown_signal, proto.shutdown_timeout),
         })
     }
 }
@@ -249,6 +375,8 @@ impl From<Terminate> for generated::Terminate {
     fn from(value: Terminate) -> Self {
         generated::Terminate {
             pid: Some(value.pid),
+            shutdown_signal: Some(value.shutdown.signal),
+            shutdown_timeout: Some(value.shutdown.timeout),
         }
     }
 }
@@ -257,6 +385,8 @@ impl From<Terminate> for generated::Terminate {
 pub struct TerminateOk {
     pub exit_code: i32,
     pub shutdown_method: generated::ShutdownMethod,
+    /// The signal sent to ask the process to stop, if one was sent.
+    pub shutdown_signal: Option<String>,
 }
 
 impl LauncherMessage for TerminateOk {
@@ -274,6 +404,7 @@ impl LauncherMessage for TerminateOk {
                     .ok_or(Error::ProtocolMismatch("shutdown_method"))?,
             )
             .ok_or(Error::ProtocolMismatch("shutdown_method"))?,
+            shutdown_signal: proto.shutdown_signal,
         })
     }
 }
@@ -283,6 +414,7 @@ impl From<TerminateOk> for generated::TerminateOk {
         generated::TerminateOk {
             exit_code: Some(value.exit_code),
             shutdown_method: Some(value.shutdown_method as i32),
+            shutdown_signal: value.shutdown_signal,
         }
     }
 }
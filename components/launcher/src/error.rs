@@ -21,6 +21,7 @@ use crate::{SUP_CMD, SUP_PACKAGE_IDENT};
 #[derive(Debug)]
 pub enum Error {
     AcceptConn,
+    Cgroup(String, io::Error),
     Connect(io::Error),
     ExecWait(io::Error),
     GroupNotFound(String),
@@ -42,6 +43,9 @@ impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let msg = match *self {
             Error::AcceptConn => "Unable to accept connection from Supervisor".to_string(),
+            Error::Cgroup(ref id, ref e) => {
+                format!("Unable to set up resource limits for {}, {}", id, e)
+            }
             Error::Connect(ref e) => {
                 format!("Unable to connect to Supervisor's comm channel, {}", e)
             }
@@ -70,6 +74,7 @@ impl error::Error for Error {
     fn description(&self) -> &str {
         match *self {
             Error::AcceptConn => "Unable to accept connection from Supervisor",
+            Error::Cgroup(..) => "Unable to set up cgroup for service",
             Error::Connect(_) => "Unable to connect to Supervisor's pipe",
             Error::GroupNotFound(_) => "No matching GID for group found",
             Error::ExecWait(_) => "OS Error while waiting on PID",
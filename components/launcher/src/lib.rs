@@ -22,6 +22,7 @@ extern crate log;
 extern crate winapi;
 
 pub mod error;
+mod log_file;
 pub mod server;
 pub mod service;
 mod sys;
@@ -0,0 +1,357 @@
+// Copyright (c) 2019 Chef Software Inc. and/or applicable contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A service's log file, rotated by size and age.
+//!
+//! Rotated files are renamed to `<file>.<UTC timestamp>`, optionally gzipped, and pruned down
+//! to the configured number of files. Rotation is only considered when a line is written, so an
+//! idle service's file may outlive its maximum age. Only the rename holds up the writer; the
+//! rotated file is compressed and the old ones pruned on a background thread.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, SystemTime};
+
+use crate::protocol::LogConfig;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use time;
+
+const ROTATED_SUFFIX_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+
+pub struct LogFile {
+    config: LogConfig,
+    path: PathBuf,
+    file: File,
+    size: u64,
+    opened: SystemTime,
+    /// Compresses and prunes the files rotated out last time.
+    upkeep: Option<JoinHandle<()>>,
+}
+
+impl LogFile {
+    /// Open (or append to) the log file described by `config`, creating its directory if need
+    /// be.
+    pub fn open(config: LogConfig) -> io::Result<Self> {
+        let path = PathBuf::from(&config.path);
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let file = open_append(&path)?;
+        let metadata = file.metadata()?;
+        let opened = metadata
+            .created()
+            .or_else(|_| metadata.modified())
+            .unwrap_or_else(|_| SystemTime::now());
+        Ok(LogFile {
+            size: metadata.len(),
+            config: config,
+            path: path,
+            file: file,
+            opened: opened,
+            upkeep: None,
+        })
+    }
+
+    /// Append a line to the log, rotating the file first if it is due.
+    pub fn write_line(&mut self, line: &str) -> io::Result<()> {
+        let len = line.len() as u64 + 1;
+        if self.needs_rotation(len) {
+            self.rotate()?;
+        }
+        writeln!(self.file, "{}", line)?;
+        self.size += len;
+        Ok(())
+    }
+
+    fn needs_rotation(&self, incoming: u64) -> bool {
+        if self.size == 0 {
+            return false;
+        }
+        let too_big = self
+            .config
+            .max_size
+            .map_or(false, |max| self.size + incoming > max);
+        let too_old = self.config.max_age.map_or(false, |max| {
+            self.opened
+                .elapsed()
+                .map(|age| age >= Duration::from_secs(max))
+                .unwrap_or(false)
+        });
+        too_big || too_old
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        // Waits on the previous upkeep, which is only still running if the file filled up again
+        // before its rotated file was compressed, so that two never prune at once.
+        self.finish_upkeep();
+        let rotated = self.rotated_path();
+        debug!("Rotating {} to {}", self.path.display(), rotated.display());
+        fs::rename(&self.path, &rotated)?;
+        self.file = open_append(&self.path)?;
+        self.size = 0;
+        self.opened = SystemTime::now();
+
+        let path = self.path.clone();
+        let compress_rotated = self.config.compress;
+        let retain = self.config.retain;
+        let upkeep = thread::Builder::new()
+            .name(format!("log-upkeep-{}", path.display()))
+            .spawn(move || {
+                if compress_rotated {
+                    if let Err(err) = compress(&rotated) {
+                        error!("Unable to compress {}: {}", rotated.display(), err);
+                    }
+                }
+                if let Err(err) = prune(&path, retain) {
+                    error!("Unable to remove old logs of {}: {}", path.display(), err);
+                }
+            })?;
+        self.upkeep = Some(upkeep);
+        Ok(())
+    }
+
+    /// Block until the files rotated out last have been compressed and pruned.
+    fn finish_upkeep(&mut self) {
+        if let Some(upkeep) = self.upkeep.take() {
+            if upkeep.join().is_err() {
+                error!("Log upkeep of {} panicked", self.path.display());
+            }
+        }
+    }
+
+    /// A name for the file being rotated out that no other rotated file has.
+    fn rotated_path(&self) -> PathBuf {
+        let stamp = time::strftime(ROTATED_SUFFIX_FORMAT, &time::now_utc())
+            .expect("rotated log suffix format is valid");
+        let base = format!("{}.{}", self.path.display(), stamp);
+        let mut rotated = PathBuf::from(&base);
+        let mut n = 1;
+        while rotated.exists() || gz_path(&rotated).exists() {
+            rotated = PathBuf::from(format!("{}-{}", base, n));
+            n += 1;
+        }
+        rotated
+    }
+}
+
+/// Remove the oldest files rotated out of the log at `path` beyond the number to retain.
+fn prune(path: &Path, retain: Option<u32>) -> io::Result<()> {
+    let retain = match retain {
+        Some(retain) => retain as usize,
+        None => return Ok(()),
+    };
+    let dir = match path.parent() {
+        Some(dir) => dir,
+        None => return Ok(()),
+    };
+    let prefix = format!(
+        "{}.",
+        path.file_name()
+            .map(|f| f.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    );
+    let mut rotated = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if name.starts_with(&prefix) {
+                Some((rotation_order(&name[prefix.len()..]), entry.path()))
+            } else {
+                None
+            }
+        })
+        .collect::<Vec<_>>();
+    rotated.sort();
+    let excess = rotated.len().saturating_sub(retain);
+    for (_, path) in rotated.into_iter().take(excess) {
+        debug!("Removing old log {}", path.display());
+        fs::remove_file(path)?;
+    }
+    Ok(())
+}
+
+/// Sorts the suffixes of rotated files oldest first: by timestamp, then by the number added to
+/// tell apart files rotated out within the same second, whether or not they were compressed.
+fn rotation_order(suffix: &str) -> (String, u64) {
+    let suffix = suffix.trim_end_matches(".gz");
+    let mut parts = suffix.splitn(2, '-');
+    let stamp = parts.next().unwrap_or_default().to_string();
+    let n = parts.next().and_then(|n| n.parse().ok()).unwrap_or(0);
+    (stamp, n)
+}
+
+fn open_append(path: &Path) -> io::Result<File> {
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+fn gz_path(path: &Path) -> PathBuf {
+    PathBuf::from(format!("{}.gz", path.display()))
+}
+
+/// Replace the file at `path` with a gzipped copy.
+fn compress(path: &Path) -> io::Result<()> {
+    let mut input = File::open(path)?;
+    let mut encoder = GzEncoder::new(File::create(gz_path(path))?, Compression::default());
+    io::copy(&mut input, &mut encoder)?;
+    encoder.finish()?;
+    fs::remove_file(path)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::io::Read;
+
+    use flate2::read::GzDecoder;
+    use tempfile::TempDir;
+
+    fn config(dir: &TempDir) -> LogConfig {
+        LogConfig {
+            path: dir.path().join("redis.log").to_string_lossy().into_owned(),
+            // Every line after the first rotates the file.
+            max_size: Some(1),
+            ..LogConfig::default()
+        }
+    }
+
+    fn write_lines(config: LogConfig, lines: &[&str]) {
+        let mut log = LogFile::open(config).unwrap();
+        for line in lines {
+            log.write_line(line).unwrap();
+        }
+        log.finish_upkeep();
+    }
+
+    /// The contents of the log and of each file rotated out of it, oldest first.
+    fn contents(dir: &TempDir) -> Vec<String> {
+        let mut files = fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .filter(|name| name != "redis.log")
+            .collect::<Vec<_>>();
+        files.sort_by_key(|name| rotation_order(&name["redis.log.".len()..]));
+        files.push("redis.log".to_string());
+        files
+            .iter()
+            .map(|name| {
+                let file = File::open(dir.path().join(name)).unwrap();
+                let mut content = String::new();
+                if name.ends_with(".gz") {
+                    GzDecoder::new(file).read_to_string(&mut content).unwrap();
+                } else {
+                    io::BufReader::new(file)
+                        .read_to_string(&mut content)
+                        .unwrap();
+                }
+                content
+            })
+            .collect()
+    }
+
+    #[test]
+    fn rotates_once_the_file_is_too_big() {
+        let dir = TempDir::new().unwrap();
+        write_lines(config(&dir), &["one", "two", "three"]);
+
+        assert_eq!(contents(&dir), vec!["one\n", "two\n", "three\n"]);
+    }
+
+    #[test]
+    fn rotates_once_the_file_is_too_old() {
+        let dir = TempDir::new().unwrap();
+        let config = LogConfig {
+            max_size: None,
+            max_age: Some(0),
+            ..config(&dir)
+        };
+        write_lines(config, &["one", "two"]);
+
+        assert_eq!(contents(&dir), vec!["one\n", "two\n"]);
+    }
+
+    #[test]
+    fn appends_to_an_existing_file() {
+        let dir = TempDir::new().unwrap();
+        let config = LogConfig {
+            max_size: Some(1024),
+            ..config(&dir)
+        };
+        write_lines(config.clone(), &["one"]);
+        write_lines(config, &["two"]);
+
+        assert_eq!(contents(&dir), vec!["one\ntwo\n"]);
+    }
+
+    #[test]
+    fn compresses_rotated_files() {
+        let dir = TempDir::new().unwrap();
+        let config = LogConfig {
+            compress: true,
+            ..config(&dir)
+        };
+        write_lines(config, &["one", "two", "three"]);
+
+        let compressed = fs::read_dir(dir.path())
+            .unwrap()
+            .filter(|entry| {
+                entry
+                    .as_ref()
+                    .unwrap()
+                    .file_name()
+                    .to_string_lossy()
+                    .ends_with(".gz")
+            })
+            .count();
+        assert_eq!(compressed, 2);
+        assert_eq!(contents(&dir), vec!["one\n", "two\n", "three\n"]);
+    }
+
+    #[test]
+    fn prunes_the_oldest_rotated_files() {
+        let dir = TempDir::new().unwrap();
+        let config = LogConfig {
+            retain: Some(2),
+            compress: true,
+            ..config(&dir)
+        };
+        write_lines(config, &["one", "two", "three", "four", "five"]);
+
+        assert_eq!(contents(&dir), vec!["three\n", "four\n", "five\n"]);
+    }
+
+    #[test]
+    fn rotated_files_sort_oldest_first() {
+        let mut suffixes = vec![
+            "20190102T000000Z",
+            "20190101T000000Z-10.gz",
+            "20190101T000000Z-2",
+            "20190101T000000Z.gz",
+        ];
+        suffixes.sort_by_key(|suffix| rotation_order(suffix));
+
+        assert_eq!(
+            suffixes,
+            vec![
+                "20190101T000000Z.gz",
+                "20190101T000000Z-2",
+                "20190101T000000Z-10.gz",
+                "20190102T000000Z",
+            ]
+        );
+    }
+}
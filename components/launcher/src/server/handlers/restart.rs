@@ -32,17 +32,22 @@ impl Handler for RestartHandler {
                 return Err(reply);
             }
         };
-        service.kill();
+        service.kill(&msg.shutdown);
         match service.wait() {
-            Ok(_status) => match service::run(service.take_args()) {
-                Ok(new_service) => {
-                    let mut reply = protocol::SpawnOk::default();
-                    reply.pid = new_service.id().into();
-                    services.insert(new_service);
-                    Ok(reply)
+            Ok(_status) => {
+                // The new process is stopped the way the Supervisor now asks for.
+                let mut args = service.take_args();
+                args.shutdown = msg.shutdown;
+                match service::run(args) {
+                    Ok(new_service) => {
+                        let mut reply = protocol::SpawnOk::default();
+                        reply.pid = new_service.id().into();
+                        services.insert(new_service);
+                        Ok(reply)
+                    }
+                    Err(err) => Err(protocol::error(err)),
                 }
-                Err(err) => Err(protocol::error(err)),
-            },
+            }
             Err(_) => {
                 let mut reply = protocol::NetErr::default();
                 reply.code = protocol::ErrCode::ExecWait;
@@ -26,12 +26,13 @@ impl Handler for TerminateHandler {
         match services.get_mut(msg.pid as u32) {
             Some(service) => {
                 debug!("Terminating: {}", service.id());
-                let shutdown_method = service.kill();
+                let (shutdown_method, shutdown_signal) = service.kill(&msg.shutdown);
                 match service.wait() {
                     Ok(status) => {
                         let mut reply = protocol::TerminateOk::default();
                         reply.exit_code = status.code().unwrap_or(0);
                         reply.shutdown_method = shutdown_method;
+                        reply.shutdown_signal = shutdown_signal;
                         Ok(reply)
                     }
                     Err(_) => {
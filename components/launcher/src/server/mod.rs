@@ -377,10 +377,12 @@ impl ServiceTable {
         self.0.remove(&pid)
     }
 
+    /// Stop every service as the Supervisor asked for when it spawned them.
     fn kill_all(&mut self) {
         for service in self.0.values_mut() {
             outputln!(preamble service.name(), "Stopping...");
-            let shutdown_method = service.kill();
+            let shutdown = service.args().shutdown.clone();
+            let (shutdown_method, _) = service.kill(&shutdown);
             outputln!(preamble service.name(), "Shutdown OK: {}", shutdown_method);
         }
     }
@@ -620,3 +622,81 @@ fn supervisor_cmd() -> Result<PathBuf> {
         Err(_) => Err(Error::SupPackageNotFound),
     }
 }
+
+#[cfg(all(test, unix))]
+mod test {
+    use super::*;
+
+    use std::{os::unix::fs::PermissionsExt, time::Instant};
+
+    use tempfile::TempDir;
+
+    use crate::service;
+
+    const RUN: &str = r#"#!/bin/sh
+trap 'exit 0' INT
+trap '' TERM
+touch "$READY"
+while true; do sleep 0.1; done
+"#;
+
+    /// Spawns a service which exits cleanly on INT and ignores TERM, once it is ready for either.
+    fn spawn(tmpdir: &TempDir, shutdown: protocol::ShutdownSpec) -> Service {
+        let run = tmpdir.path().join("run");
+        let ready = tmpdir.path().join("ready");
+        fs::write(&run, RUN).unwrap();
+        fs::set_permissions(&run, fs::Permissions::from_mode(0o755)).unwrap();
+        let mut env = HashMap::new();
+        env.insert("READY".to_string(), ready.to_string_lossy().into_owned());
+        let service = service::run(protocol::Spawn {
+            id: "test.default".to_string(),
+            binary: run.to_string_lossy().into_owned(),
+            svc_user_id: Some(unsafe { libc::getuid() }),
+            svc_group_id: Some(unsafe { libc::getgid() }),
+            env: env,
+            shutdown: shutdown,
+            ..Default::default()
+        })
+        .unwrap();
+        let started = Instant::now();
+        while !ready.exists() {
+            assert!(started.elapsed() < Duration::from_secs(10));
+            thread::sleep(Duration::from_millis(10));
+        }
+        service
+    }
+
+    #[test]
+    fn kill_all_stops_services_as_they_were_spawned_to_be() {
+        let tmpdir = TempDir::new().unwrap();
+        let shutdown = protocol::ShutdownSpec {
+            signal: "INT".to_string(),
+            timeout: 10,
+        };
+        let mut services = ServiceTable::default();
+        let service = spawn(&tmpdir, shutdown);
+        let pid = service.id();
+        services.insert(service);
+
+        services.kill_all();
+
+        let status = services.get_mut(pid).unwrap().wait().unwrap();
+        assert_eq!(status.code(), Some(0));
+    }
+
+    #[test]
+    fn kill_forcefully_kills_a_service_which_outlives_the_timeout() {
+        let tmpdir = TempDir::new().unwrap();
+        let mut service = spawn(&tmpdir, protocol::ShutdownSpec::default());
+        let shutdown = protocol::ShutdownSpec {
+            signal: "TERM".to_string(),
+            timeout: 0,
+        };
+
+        let (method, signal) = service.kill(&shutdown);
+
+        assert_eq!(method, protocol::ShutdownMethod::Killed);
+        assert_eq!(signal, Some("TERM".to_string()));
+        assert_eq!(service.wait().unwrap().signal(), Some(libc::SIGKILL));
+    }
+}
@@ -17,9 +17,11 @@ use std::process::{ChildStderr, ChildStdout, ExitStatus};
 use std::{
     fmt,
     io::{self, BufRead, BufReader, Read, Write},
+    sync::{Arc, Mutex},
     thread,
 };
 
+use crate::log_file::LogFile;
 use crate::protocol;
 #[cfg(windows)]
 use core::os::process::windows_child::{ChildStderr, ChildStdout, ExitStatus};
@@ -38,18 +40,32 @@ impl Service {
         stdout: Option<ChildStdout>,
         stderr: Option<ChildStderr>,
     ) -> Self {
+        let log = spawn.log.clone().and_then(|config| {
+            let path = config.path.clone();
+            match LogFile::open(config) {
+                Ok(log) => Some(Arc::new(Mutex::new(log))),
+                Err(err) => {
+                    error!(
+                        "Unable to open log file {} for {}, logging to stdout instead: {}",
+                        path, spawn.id, err
+                    );
+                    None
+                }
+            }
+        });
         if let Some(stdout) = stdout {
             let id = spawn.id.to_string();
+            let log = log.clone();
             thread::Builder::new()
                 .name(format!("{}-out", spawn.id))
-                .spawn(move || pipe_stdout(stdout, &id))
+                .spawn(move || pipe_stdout(stdout, &id, log))
                 .ok();
         }
         if let Some(stderr) = stderr {
             let id = spawn.id.to_string();
             thread::Builder::new()
                 .name(format!("{}-err", spawn.id))
-                .spawn(move || pipe_stderr(stderr, &id))
+                .spawn(move || pipe_stderr(stderr, &id, log))
                 .ok();
         }
         Service {
@@ -66,10 +82,13 @@ impl Service {
         self.process.id()
     }
 
-    /// Attempt to gracefully terminate a proccess and then forcefully kill it after
-    /// 8 seconds if it has not terminated.
-    pub fn kill(&mut self) -> protocol::ShutdownMethod {
-        self.process.kill()
+    /// Attempt to gracefully terminate a proccess and then forcefully kill it if it has not
+    /// terminated within the shutdown timeout.
+    pub fn kill(
+        &mut self,
+        shutdown: &protocol::ShutdownSpec,
+    ) -> (protocol::ShutdownMethod, Option<String>) {
+        self.process.kill(shutdown)
     }
 
     pub fn name(&self) -> &str {
@@ -96,29 +115,57 @@ impl fmt::Debug for Service {
 }
 
 /// Consume output from a child process until EOF, then finish
-fn pipe_stdout<T>(out: T, id: &str)
+fn pipe_stdout<T>(out: T, id: &str, log: Option<Arc<Mutex<LogFile>>>)
 where
     T: Read,
 {
     let mut reader = BufReader::new(out);
     let mut buffer = String::new();
     while reader.read_line(&mut buffer).unwrap() > 0 {
-        let line = output_format!(preamble &id, logkey "O", buffer);
-        writeln!(&mut io::stdout(), "{}", line).expect("unable to write to stdout");
+        match log {
+            Some(ref log) => write_log(log, id, "O", &buffer),
+            None => {
+                let line = output_format!(preamble &id, logkey "O", buffer);
+                writeln!(&mut io::stdout(), "{}", line).expect("unable to write to stdout");
+            }
+        }
         buffer.clear();
     }
 }
 
 /// Consume standard error from a child process until EOF, then finish
-fn pipe_stderr<T>(err: T, id: &str)
+fn pipe_stderr<T>(err: T, id: &str, log: Option<Arc<Mutex<LogFile>>>)
 where
     T: Read,
 {
     let mut reader = BufReader::new(err);
     let mut buffer = String::new();
     while reader.read_line(&mut buffer).unwrap() > 0 {
-        let line = output_format!(preamble &id, logkey "E", buffer);
-        writeln!(&mut io::stderr(), "{}", line).expect("unable to write to stderr");
+        match log {
+            Some(ref log) => write_log(log, id, "E", &buffer),
+            None => {
+                let line = output_format!(preamble &id, logkey "E", buffer);
+                writeln!(&mut io::stderr(), "{}", line).expect("unable to write to stderr");
+            }
+        }
         buffer.clear();
     }
 }
+
+/// Write a line of output to a service's log file, stamped with the time and the stream it
+/// came from.
+fn write_log(log: &Mutex<LogFile>, id: &str, stream: &str, line: &str) {
+    let line = format!(
+        "{} {}: {}",
+        time::now_utc().rfc3339(),
+        stream,
+        line.trim_end_matches(|c| c == '\r' || c == '\n')
+    );
+    let result = log
+        .lock()
+        .expect("Log file lock poisoned")
+        .write_line(&line);
+    if let Err(err) = result {
+        error!("Unable to write to log file for {}: {}", id, err);
+    }
+}
@@ -0,0 +1,284 @@
+// Copyright (c) 2019 Chef Software Inc. and/or applicable contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Confines a service to its own cgroup (v2) so that its memory, CPU and process count can be
+//! limited.
+//!
+//! Each service gets a cgroup named after its spawn ID, under the directory given by
+//! `protocol::service_cgroup_path`. The Supervisor reads the usage counters of the same cgroup
+//! to report on the service.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::os::unix::io::RawFd;
+use std::path::{Path, PathBuf};
+
+use crate::protocol::{self, ResourceLimits};
+use libc;
+
+/// The period, in microseconds, over which `cpu.max` quotas are enforced.
+const CPU_PERIOD_USEC: u64 = 100_000;
+/// The kernel's default `cpu.weight`.
+const CPU_WEIGHT_DEFAULT: &str = "100";
+
+pub struct Cgroup {
+    path: PathBuf,
+}
+
+impl Cgroup {
+    /// Create (or reuse) the cgroup for the service with the given spawn ID, and apply `limits`
+    /// to it.
+    pub fn create(id: &str, limits: &ResourceLimits) -> io::Result<Self> {
+        Self::create_at(protocol::service_cgroup_path(id), limits)
+    }
+
+    fn create_at(path: PathBuf, limits: &ResourceLimits) -> io::Result<Self> {
+        let parent = path
+            .parent()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "cgroup has no parent"))?;
+        fs::create_dir_all(parent)?;
+        // Controllers have to be enabled all the way down from the root of the hierarchy. The
+        // grandparent is usually the root itself, which systemd has already taken care of.
+        if let Some(grandparent) = parent.parent() {
+            if let Err(err) = enable_controllers(grandparent, limits) {
+                debug!(
+                    "Unable to enable cgroup controllers in {}: {}",
+                    grandparent.display(),
+                    err
+                );
+            }
+        }
+        enable_controllers(parent, limits)?;
+        fs::create_dir_all(&path)?;
+
+        let cgroup = Cgroup { path: path };
+        cgroup.write(
+            "memory.max",
+            &limits
+                .memory_max
+                .map_or_else(|| "max".to_string(), |m| m.to_string()),
+        )?;
+        // A reused cgroup may still have the CPU limits of an earlier spawn, so they're reset
+        // when unset. The cpu controller is only enabled for services with CPU limits, so their
+        // files may not be there to reset.
+        match limits.cpu_weight {
+            Some(weight) => cgroup.write("cpu.weight", &weight.to_string())?,
+            None => cgroup.reset("cpu.weight", CPU_WEIGHT_DEFAULT)?,
+        }
+        match limits.cpu_quota {
+            Some(percent) => {
+                let quota = percent
+                    .checked_mul(CPU_PERIOD_USEC)
+                    .map(|q| q / 100)
+                    .ok_or_else(|| {
+                        io::Error::new(
+                            io::ErrorKind::InvalidInput,
+                            format!("CPU quota of {}% is too large", percent),
+                        )
+                    })?;
+                cgroup.write("cpu.max", &format!("{} {}", quota, CPU_PERIOD_USEC))?;
+            }
+            None => cgroup.reset("cpu.max", &format!("max {}", CPU_PERIOD_USEC))?,
+        }
+        cgroup.write(
+            "pids.max",
+            &limits
+                .pids_max
+                .map_or_else(|| "max".to_string(), |p| p.to_string()),
+        )?;
+        Ok(cgroup)
+    }
+
+    /// Open the file through which a process moves itself into this cgroup. The returned file
+    /// is closed on exec, so it doesn't leak into the service.
+    pub fn procs_file(&self) -> io::Result<File> {
+        OpenOptions::new()
+            .write(true)
+            .open(self.path.join("cgroup.procs"))
+    }
+
+    fn write(&self, file: &str, value: &str) -> io::Result<()> {
+        debug!("Setting {}/{} to {}", self.path.display(), file, value);
+        let mut f = OpenOptions::new().write(true).open(self.path.join(file))?;
+        f.write_all(value.as_bytes())
+    }
+
+    /// Write `value` to `file` if the cgroup has it.
+    fn reset(&self, file: &str, value: &str) -> io::Result<()> {
+        match self.write(file, value) {
+            Err(ref err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+            result => result,
+        }
+    }
+}
+
+impl Drop for Cgroup {
+    fn drop(&mut self) {
+        // This only succeeds once every process of the service is gone. If the service was
+        // restarted, the new process is already in here and the cgroup is simply reused.
+        if let Err(err) = fs::remove_dir(&self.path) {
+            debug!("Not removing cgroup {}: {}", self.path.display(), err);
+        }
+    }
+}
+
+/// Move the calling process into the cgroup whose `cgroup.procs` file is open as `fd`.
+///
+/// This runs between `fork` and `exec`, so it sticks to async-signal-safe calls.
+pub fn join(fd: RawFd) -> io::Result<()> {
+    let pid = b"0";
+    let written = unsafe { libc::write(fd, pid.as_ptr() as *const libc::c_void, pid.len()) };
+    if written < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+fn enable_controllers(dir: &Path, limits: &ResourceLimits) -> io::Result<()> {
+    // Memory and pids are always enabled, as the Supervisor reports on them.
+    let mut controllers = vec!["+memory", "+pids"];
+    if limits.cpu_weight.is_some() || limits.cpu_quota.is_some() {
+        controllers.push("+cpu");
+    }
+    let mut file = OpenOptions::new()
+        .write(true)
+        .open(dir.join("cgroup.subtree_control"))?;
+    file.write_all(controllers.join(" ").as_bytes())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use tempfile::TempDir;
+
+    const CGROUP_FILES: &[&str] = &[
+        "cgroup.procs",
+        "cgroup.subtree_control",
+        "cpu.max",
+        "cpu.weight",
+        "memory.max",
+        "pids.max",
+    ];
+
+    /// Lays out a cgroup hierarchy under `root` the way the kernel would: every cgroup already
+    /// has its interface files, which the Launcher only ever writes to.
+    fn hierarchy(root: &Path, id: &str) -> PathBuf {
+        let path = root.join("habitat").join(id);
+        for dir in &[root.to_path_buf(), root.join("habitat"), path.clone()] {
+            fs::create_dir_all(dir).unwrap();
+            for file in CGROUP_FILES {
+                File::create(dir.join(file)).unwrap();
+            }
+        }
+        path
+    }
+
+    fn read(path: &Path) -> String {
+        fs::read_to_string(path).unwrap()
+    }
+
+    #[test]
+    fn create_writes_limits() {
+        let tmpdir = TempDir::new().unwrap();
+        let path = hierarchy(tmpdir.path(), "redis.default");
+        let limits = ResourceLimits {
+            memory_max: Some(512 * 1024 * 1024),
+            cpu_weight: Some(200),
+            cpu_quota: Some(150),
+            pids_max: Some(64),
+        };
+
+        let _cgroup = Cgroup::create_at(path.clone(), &limits).unwrap();
+
+        assert_eq!(read(&path.join("memory.max")), "536870912");
+        assert_eq!(read(&path.join("cpu.weight")), "200");
+        assert_eq!(read(&path.join("cpu.max")), "150000 100000");
+        assert_eq!(read(&path.join("pids.max")), "64");
+        for dir in &[tmpdir.path().to_path_buf(), tmpdir.path().join("habitat")] {
+            assert_eq!(
+                read(&dir.join("cgroup.subtree_control")),
+                "+memory +pids +cpu"
+            );
+        }
+    }
+
+    #[test]
+    fn create_leaves_what_is_not_limited_unlimited() {
+        let tmpdir = TempDir::new().unwrap();
+        let path = hierarchy(tmpdir.path(), "redis.default");
+        let limits = ResourceLimits {
+            pids_max: Some(64),
+            ..ResourceLimits::default()
+        };
+
+        let _cgroup = Cgroup::create_at(path.clone(), &limits).unwrap();
+
+        assert_eq!(read(&path.join("memory.max")), "max");
+        assert_eq!(read(&path.join("cpu.weight")), "100");
+        assert_eq!(read(&path.join("cpu.max")), "max 100000");
+        assert_eq!(read(&path.join("pids.max")), "64");
+        assert_eq!(
+            read(&tmpdir.path().join("habitat").join("cgroup.subtree_control")),
+            "+memory +pids"
+        );
+    }
+
+    #[test]
+    fn create_rejects_a_cpu_quota_too_large_to_write() {
+        let tmpdir = TempDir::new().unwrap();
+        let path = hierarchy(tmpdir.path(), "redis.default");
+        let limits = ResourceLimits {
+            cpu_quota: Some(u64::max_value() / 1000),
+            ..ResourceLimits::default()
+        };
+
+        let err = Cgroup::create_at(path, &limits).err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn create_fails_without_cgroup_controllers() {
+        let tmpdir = TempDir::new().unwrap();
+        let path = tmpdir.path().join("habitat").join("redis.default");
+
+        assert!(Cgroup::create_at(path, &ResourceLimits::default()).is_err());
+    }
+
+    #[test]
+    fn procs_file_is_the_cgroups() {
+        let tmpdir = TempDir::new().unwrap();
+        let path = hierarchy(tmpdir.path(), "redis.default");
+        let cgroup = Cgroup::create_at(path.clone(), &ResourceLimits::default()).unwrap();
+
+        cgroup.procs_file().unwrap().write_all(b"42").unwrap();
+
+        assert_eq!(read(&path.join("cgroup.procs")), "42");
+    }
+
+    #[test]
+    fn dropping_removes_the_cgroup_once_it_is_empty() {
+        let tmpdir = TempDir::new().unwrap();
+        let path = tmpdir.path().join("redis.default");
+        fs::create_dir(&path).unwrap();
+        drop(Cgroup { path: path.clone() });
+        assert!(!path.exists());
+
+        // A cgroup which still has processes in it can't be removed, and is left for the next
+        // process of the service to reuse.
+        let path = hierarchy(tmpdir.path(), "redis.default");
+        drop(Cgroup { path: path.clone() });
+        assert!(path.exists());
+    }
+}
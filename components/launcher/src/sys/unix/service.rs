@@ -14,30 +14,57 @@
 
 use std::io;
 use std::ops::Neg;
+#[cfg(target_os = "linux")]
+use std::os::unix::io::AsRawFd;
 use std::os::unix::process::CommandExt;
 use std::process::{Child, Command, ExitStatus, Stdio};
 use std::result;
+use std::thread;
 
 use crate::core::os;
 use crate::core::os::process::{signal, Signal};
-use crate::protocol::{self, ShutdownMethod};
+use crate::protocol::{self, ShutdownMethod, ShutdownSpec};
 use libc;
 use time::{Duration, SteadyTime};
 
+/// How often a process being stopped is checked on until it exits or its shutdown timeout runs
+/// out.
+const KILL_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(10);
+
 use crate::error::{Error, Result};
 use crate::service::Service;
+#[cfg(target_os = "linux")]
+use crate::sys::cgroup::{self, Cgroup};
 
-pub struct Process(Child);
+pub struct Process {
+    child: Child,
+    /// The cgroup enforcing the process's resource limits, if it has any. Dropped (and removed)
+    /// along with the process.
+    #[cfg(target_os = "linux")]
+    cgroup: Option<Cgroup>,
+}
 
 impl Process {
     pub fn id(&self) -> u32 {
-        self.0.id()
+        self.child.id()
     }
 
-    /// Attempt to gracefully terminate a process and then forcefully kill it after
-    /// 8 seconds if it has not terminated.
-    pub fn kill(&mut self) -> ShutdownMethod {
-        let mut pid_to_kill = self.0.id() as i32;
+    /// Attempt to gracefully terminate a process with the shutdown signal and then forcefully
+    /// kill it if it has not terminated within the shutdown timeout. Returns how the process was
+    /// stopped and the name of the signal sent to ask it to stop, if any.
+    pub fn kill(&mut self, shutdown: &ShutdownSpec) -> (ShutdownMethod, Option<String>) {
+        let (signal_name, shutdown_signal) = match shutdown_signal(&shutdown.signal) {
+            Some(signal) => (shutdown.signal.as_str(), signal),
+            None => {
+                warn!(
+                    "Unsupported shutdown signal {}, sending {} instead",
+                    shutdown.signal,
+                    ShutdownSpec::DEFAULT_SIGNAL
+                );
+                (ShutdownSpec::DEFAULT_SIGNAL, Signal::TERM)
+            }
+        };
+        let mut pid_to_kill = self.child.id() as i32;
         // check the group of the process being killed
         // if it is the root process of the process group
         // we send our signals to the entire process group
@@ -55,32 +82,47 @@ impl Process {
 
         // JW TODO: Determine if the error represents a case where the process was already
         // exited before we return out and assume so.
-        if signal(pid_to_kill, Signal::TERM).is_err() {
-            return ShutdownMethod::AlreadyExited;
+        if signal(pid_to_kill, shutdown_signal).is_err() {
+            return (ShutdownMethod::AlreadyExited, None);
         }
-        let stop_time = SteadyTime::now() + Duration::seconds(8);
+        let signal_name = Some(signal_name.to_string());
+        let stop_time = SteadyTime::now() + Duration::seconds(i64::from(shutdown.timeout));
         loop {
             if let Ok(Some(_status)) = self.try_wait() {
-                return ShutdownMethod::GracefulTermination;
+                return (ShutdownMethod::GracefulTermination, signal_name);
             }
             if SteadyTime::now() < stop_time {
+                thread::sleep(KILL_POLL_INTERVAL);
                 continue;
             }
             // JW TODO: Determine if the error represents a case where the process was already
             // exited before we return out and assume so.
             if signal(pid_to_kill, Signal::KILL).is_err() {
-                return ShutdownMethod::GracefulTermination;
+                return (ShutdownMethod::GracefulTermination, signal_name);
             }
-            return ShutdownMethod::Killed;
+            return (ShutdownMethod::Killed, signal_name);
         }
     }
 
     pub fn try_wait(&mut self) -> io::Result<Option<ExitStatus>> {
-        self.0.try_wait()
+        self.child.try_wait()
     }
 
     pub fn wait(&mut self) -> io::Result<ExitStatus> {
-        self.0.wait()
+        self.child.wait()
+    }
+}
+
+/// The signals a service may ask to be stopped with, by name.
+fn shutdown_signal(name: &str) -> Option<Signal> {
+    match name {
+        "TERM" => Some(Signal::TERM),
+        "INT" => Some(Signal::INT),
+        "QUIT" => Some(Signal::QUIT),
+        "HUP" => Some(Signal::HUP),
+        "USR1" => Some(Signal::USR1),
+        "USR2" => Some(Signal::USR2),
+        _ => None,
     }
 }
 
@@ -115,10 +157,46 @@ pub fn run(msg: protocol::Spawn) -> Result<Service> {
     for (key, val) in msg.env.iter() {
         cmd.env(key, val);
     }
+    #[cfg(target_os = "linux")]
+    let cgroup = match msg.limits {
+        Some(ref limits) if !limits.is_unlimited() => {
+            Some(Cgroup::create(&msg.id, limits).map_err(|e| Error::Cgroup(msg.id.clone(), e))?)
+        }
+        _ => None,
+    };
+    // The child moves itself into its cgroup before exec, so that nothing it does escapes the
+    // limits. The file stays open in the Launcher until the child has been spawned.
+    #[cfg(target_os = "linux")]
+    let procs_file = match cgroup {
+        Some(ref cgroup) => {
+            let file = cgroup
+                .procs_file()
+                .map_err(|e| Error::Cgroup(msg.id.clone(), e))?;
+            let fd = file.as_raw_fd();
+            cmd.before_exec(move || cgroup::join(fd));
+            Some(file)
+        }
+        None => None,
+    };
+    #[cfg(not(target_os = "linux"))]
+    {
+        if msg.limits.map_or(false, |l| !l.is_unlimited()) {
+            warn!(
+                "Resource limits are only supported on Linux; ignoring them for {}",
+                msg.id
+            );
+        }
+    }
     let mut child = cmd.spawn().map_err(Error::Spawn)?;
+    #[cfg(target_os = "linux")]
+    drop(procs_file);
     let stdout = child.stdout.take();
     let stderr = child.stderr.take();
-    let process = Process(child);
+    let process = Process {
+        child: child,
+        #[cfg(target_os = "linux")]
+        cgroup: cgroup,
+    };
     Ok(Service::new(msg, process, stdout, stderr))
 }
 
@@ -131,3 +209,49 @@ fn owned_pgid() -> result::Result<(), io::Error> {
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::time::Instant;
+
+    fn spawn(command: &str) -> Process {
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c").arg(command).before_exec(owned_pgid);
+        Process {
+            child: cmd.spawn().unwrap(),
+            #[cfg(target_os = "linux")]
+            cgroup: None,
+        }
+    }
+
+    #[test]
+    fn kill_returns_once_the_process_exits() {
+        let mut process = spawn("sleep 30");
+        let shutdown = ShutdownSpec {
+            signal: "TERM".to_string(),
+            timeout: 60,
+        };
+
+        let started = Instant::now();
+        let (method, signal_name) = process.kill(&shutdown);
+
+        assert_eq!(method, ShutdownMethod::GracefulTermination);
+        assert_eq!(signal_name, Some("TERM".to_string()));
+        assert!(started.elapsed() < std::time::Duration::from_secs(5));
+    }
+
+    #[test]
+    fn kill_kills_a_process_that_outlives_its_timeout() {
+        let mut process = spawn("trap '' TERM; sleep 30");
+        let shutdown = ShutdownSpec {
+            signal: "TERM".to_string(),
+            timeout: 1,
+        };
+
+        let (method, _) = process.kill(&shutdown);
+
+        assert_eq!(method, ShutdownMethod::Killed);
+    }
+}
@@ -16,7 +16,7 @@ use std::collections::HashMap;
 use std::io;
 use std::mem;
 
-use crate::protocol::{self, ShutdownMethod};
+use crate::protocol::{self, ShutdownMethod, ShutdownSpec};
 use core::os::process::handle_from_pid;
 use core::os::process::windows_child::{Child, ExitStatus, Handle};
 use time::{Duration, SteadyTime};
@@ -52,11 +52,12 @@ impl Process {
         unsafe { processthreadsapi::GetProcessId(self.handle.raw()) as u32 }
     }
 
-    /// Attempt to gracefully terminate a process and then forcefully kill it after
-    /// 8 seconds if it has not terminated.
-    pub fn kill(&mut self) -> ShutdownMethod {
+    /// Attempt to gracefully terminate a process with a Ctrl-Break event and then forcefully
+    /// kill it if it has not terminated within the shutdown timeout. The shutdown signal does not
+    /// apply on Windows, so none is ever reported as sent.
+    pub fn kill(&mut self, shutdown: &ShutdownSpec) -> (ShutdownMethod, Option<String>) {
         if self.status().is_some() {
-            return ShutdownMethod::AlreadyExited;
+            return (ShutdownMethod::AlreadyExited, None);
         }
         let ret = unsafe { wincon::GenerateConsoleCtrlEvent(1, self.id()) };
         if ret == 0 {
@@ -67,16 +68,16 @@ impl Process {
             );
         }
 
-        let stop_time = SteadyTime::now() + Duration::seconds(8);
+        let stop_time = SteadyTime::now() + Duration::seconds(i64::from(shutdown.timeout));
         loop {
             if ret == 0 || SteadyTime::now() > stop_time {
                 let proc_table = build_proc_table();
                 terminate_process_descendants(&proc_table, self.id());
-                return ShutdownMethod::Killed;
+                return (ShutdownMethod::Killed, None);
             }
 
             if self.status().is_some() {
-                return ShutdownMethod::GracefulTermination;
+                return (ShutdownMethod::GracefulTermination, None);
             }
         }
     }
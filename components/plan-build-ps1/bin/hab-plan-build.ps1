@@ -122,6 +122,8 @@ $script:pkg_exports = @{}
 $script:pkg_svc_user = "hab"
 # The group to run the service as
 $script:pkg_svc_group = "$pkg_svc_user"
+# The number of seconds the service is given to stop before it is killed
+$script:pkg_shutdown_timeout_sec = ""
 
 # Initially set $pkg_svc_* variables. This happens before the Plan is sourced,
 # meaning that `$pkg_name` is not yet set. However, `$pkg_svc_run` wants
@@ -1969,6 +1971,10 @@ function _Write-Metadata {
         "$pkg_svc_group" |
             Out-File "$pkg_prefix\SVC_GROUP" -Encoding ascii
     }
+    if (-Not ([string]::IsNullOrEmpty($pkg_shutdown_timeout_sec))) {
+        "$pkg_shutdown_timeout_sec" |
+            Out-File "$pkg_prefix\SHUTDOWN_TIMEOUT" -Encoding ascii
+    }
 
     # Generate the blake2b hashes of all the files in the package. This
     # is not in the resulting MANIFEST because MANIFEST is included!
@@ -173,6 +173,19 @@
 # pkg_svc_run="haproxy -f $pkg_svc_config_path/haproxy.conf"
 # ```
 #
+# ### pkg_shutdown_signal
+# The signal the Supervisor sends to ask the service to stop, one of `TERM`, `INT`, `QUIT`, `HUP`,
+# `USR1` or `USR2`. Defaults to `TERM`.
+# ```
+# pkg_shutdown_signal=INT
+# ```
+#
+# ### pkg_shutdown_timeout_sec
+# The number of seconds the service is given to stop before it is killed. Defaults to 8.
+# ```
+# pkg_shutdown_timeout_sec=60
+# ```
+#
 # ### pkg_exports
 # An associative array representing configuration data which should be gossiped to peers. The keys
 # in this array represent the name the value will be assigned and the values represent the toml path
@@ -412,6 +425,10 @@ declare -A pkg_binds_optional
 pkg_svc_user=hab
 # The group to run the service as
 pkg_svc_group=$pkg_svc_user
+# The signal sent to ask the service to stop
+pkg_shutdown_signal=''
+# The number of seconds the service is given to stop before it is killed
+pkg_shutdown_timeout_sec=''
 
 # Initially set $pkg_svc_* variables. This happens before the Plan is sourced,
 # meaning that `$pkg_name` is not yet set. However, `$pkg_svc_run` wants
@@ -1937,6 +1954,8 @@ _build_metadata() {
   if [[ -f "$PLAN_CONTEXT/hooks/run" || -n "${pkg_svc_run:-}" ]]; then
     _render_metadata_SVC_USER
     _render_metadata_SVC_GROUP
+    _render_metadata_SHUTDOWN_SIGNAL
+    _render_metadata_SHUTDOWN_TIMEOUT
   fi
 
   return 0
@@ -232,6 +232,24 @@ _render_metadata_RUNTIME_PATH(){
   fi
 }
 
+_render_metadata_SHUTDOWN_SIGNAL() {
+  if [[ -n "${pkg_shutdown_signal:-}" ]]; then
+    debug "Rendering SHUTDOWN_SIGNAL metadata file"
+    echo "$pkg_shutdown_signal" > "$pkg_prefix"/SHUTDOWN_SIGNAL
+  else
+    debug "Would have rendered SHUTDOWN_SIGNAL, but there was no data for it"
+  fi
+}
+
+_render_metadata_SHUTDOWN_TIMEOUT() {
+  if [[ -n "${pkg_shutdown_timeout_sec:-}" ]]; then
+    debug "Rendering SHUTDOWN_TIMEOUT metadata file"
+    echo "$pkg_shutdown_timeout_sec" > "$pkg_prefix"/SHUTDOWN_TIMEOUT
+  else
+    debug "Would have rendered SHUTDOWN_TIMEOUT, but there was no data for it"
+  fi
+}
+
 _render_metadata_SVC_GROUP() {
   debug "Rendering SVC_GROUP metadata file"
   # shellcheck disable=2154
@@ -47,15 +47,22 @@ use habitat_common as common;
 
 use std::error;
 use std::fmt;
-use std::io;
-use std::path::PathBuf;
+use std::fs::File;
+use std::io::{self, BufReader};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 use crate::protocol::codec::*;
 use crate::protocol::net::NetErr;
+use futures::future::{self, Either};
 use futures::prelude::*;
 use futures::sink;
+use rustls::internal::pemfile;
+use rustls::ClientConfig;
 use tokio::net::TcpStream;
 use tokio_codec::Framed;
+use tokio_rustls::TlsConnector;
+use webpki::{DNSName, DNSNameRef};
 
 use crate::common::types::ListenCtlAddr;
 
@@ -70,6 +77,12 @@ pub enum SrvClientError {
     CtlSecretNotFound(PathBuf),
     /// Decoding a message from the remote failed.
     Decode(prost::DecodeError),
+    /// A file given for TLS does not contain any PEM-encoded certificates.
+    InvalidCertFile(PathBuf),
+    /// A file given for TLS does not contain a PEM-encoded private key.
+    InvalidKeyFile(PathBuf),
+    /// The name the server's certificate is checked against is not a valid DNS name.
+    InvalidServerName(String),
     /// An Os level IO error occurred.
     Io(io::Error),
     /// An RPC call to the remote was received but failed.
@@ -82,6 +95,9 @@ impl error::Error for SrvClientError {
             SrvClientError::ConnectionClosed => "Connection closed",
             SrvClientError::CtlSecretNotFound(_) => "Ctl secret key not found",
             SrvClientError::Decode(ref err) => err.description(),
+            SrvClientError::InvalidCertFile(_) => "Invalid certificate file",
+            SrvClientError::InvalidKeyFile(_) => "Invalid private key file",
+            SrvClientError::InvalidServerName(_) => "Invalid server name",
             SrvClientError::Io(ref err) => err.description(),
             SrvClientError::NetErr(ref err) => err.description(),
         }
@@ -99,6 +115,19 @@ impl fmt::Display for SrvClientError {
                 path.display()
             ),
             SrvClientError::Decode(ref err) => format!("{}", err),
+            SrvClientError::InvalidCertFile(ref path) => format!(
+                "No PEM-encoded certificates found in {}",
+                path.display()
+            ),
+            SrvClientError::InvalidKeyFile(ref path) => format!(
+                "No PEM-encoded RSA or PKCS8 private key found in {}",
+                path.display()
+            ),
+            SrvClientError::InvalidServerName(ref name) => format!(
+                "The Supervisor's certificate can't be checked against '{}', which is not a \
+                 valid DNS name",
+                name
+            ),
             SrvClientError::Io(ref err) => format!(
                 "Unable to contact the Supervisor.\n\n\
                 If the Supervisor you are contacting is local, this probably means it is not running. You can run a Supervisor in the foreground with:\n\n\
@@ -132,6 +161,57 @@ impl From<prost::DecodeError> for SrvClientError {
     }
 }
 
+/// TLS settings for connecting to a CtlGateway which requires clients to present a certificate.
+#[derive(Clone)]
+pub struct ClientTls {
+    config: Arc<ClientConfig>,
+    server_name: DNSName,
+}
+
+impl ClientTls {
+    /// Read the client's certificate chain and private key, and the certificate of the CA the
+    /// server's certificate is checked with, from PEM-encoded files. The server's certificate must
+    /// be issued for `server_name`.
+    pub fn new<A, B, C>(
+        cert_path: A,
+        key_path: B,
+        ca_cert_path: C,
+        server_name: &str,
+    ) -> Result<Self, SrvClientError>
+    where
+        A: AsRef<Path>,
+        B: AsRef<Path>,
+        C: AsRef<Path>,
+    {
+        let server_name = DNSNameRef::try_from_ascii_str(server_name)
+            .map_err(|_| SrvClientError::InvalidServerName(server_name.to_string()))?
+            .to_owned();
+        let cert_file = &mut BufReader::new(File::open(&cert_path)?);
+        let cert_chain = pemfile::certs(cert_file)
+            .ok()
+            .filter(|certs| !certs.is_empty())
+            .ok_or_else(|| SrvClientError::InvalidCertFile(cert_path.as_ref().to_path_buf()))?;
+        let key = protocol::tls::read_private_key(key_path.as_ref())?
+            .ok_or_else(|| SrvClientError::InvalidKeyFile(key_path.as_ref().to_path_buf()))?;
+
+        let mut config = ClientConfig::new();
+        let ca_cert_file = &mut BufReader::new(File::open(&ca_cert_path)?);
+        match config.root_store.add_pem_file(ca_cert_file) {
+            Ok((added, _)) if added > 0 => (),
+            _ => {
+                return Err(SrvClientError::InvalidCertFile(
+                    ca_cert_path.as_ref().to_path_buf(),
+                ));
+            }
+        }
+        config.set_single_client_cert(cert_chain, key);
+        Ok(ClientTls {
+            config: Arc::new(config),
+            server_name: server_name,
+        })
+    }
+}
+
 /// Client for connecting and communicating with a server listener which speaks SrvProtocol.
 ///
 /// See module doc for usage.
@@ -152,11 +232,32 @@ impl SrvClient {
         addr: &ListenCtlAddr,
         secret_key: S,
     ) -> Box<dyn Future<Item = SrvClient, Error = SrvClientError> + 'static>
+    where
+        S: ToString,
+    {
+        Self::connect_with(addr, secret_key, None)
+    }
+
+    /// Connect to the given remote server, over TLS if `tls` is given, and authenticate with the
+    /// given secret_key.
+    pub fn connect_with<S>(
+        addr: &ListenCtlAddr,
+        secret_key: S,
+        tls: Option<ClientTls>,
+    ) -> Box<dyn Future<Item = SrvClient, Error = SrvClientError> + 'static>
     where
         S: ToString,
     {
         let secret_key = secret_key.to_string();
         let conn = TcpStream::connect(addr.as_ref())
+            .and_then(move |socket| match tls {
+                Some(tls) => Either::A(
+                    TlsConnector::from(tls.config)
+                        .connect(tls.server_name.as_ref(), socket)
+                        .map(|socket| Box::new(socket) as Box<dyn SrvIo>),
+                ),
+                None => Either::B(future::ok(Box::new(socket) as Box<dyn SrvIo>)),
+            })
             .map_err(SrvClientError::from)
             .and_then(move |socket| {
                 let client = Self::new(socket, None);
@@ -187,7 +288,7 @@ impl SrvClient {
         Ok(buf)
     }
 
-    fn new(socket: TcpStream, current_txn: Option<SrvTxn>) -> Self {
+    fn new(socket: Box<dyn SrvIo>, current_txn: Option<SrvTxn>) -> Self {
         SrvClient {
             socket: Framed::new(socket, SrvCodec::new()),
             current_txn: current_txn.unwrap_or_default(),
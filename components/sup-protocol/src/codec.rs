@@ -61,7 +61,7 @@ use std::str;
 use bytes::{Buf, BufMut, Bytes, BytesMut};
 use futures;
 use prost::{self, Message};
-use tokio::net::TcpStream;
+use tokio::io::{AsyncRead, AsyncWrite};
 use tokio_codec::{Decoder, Encoder, Framed};
 
 use crate::message::MessageStatic;
@@ -80,9 +80,15 @@ const RESPONSE_MASK: u32 = 0x1;
 const COMPLETE_OFFSET: u32 = 30;
 const COMPLETE_MASK: u32 = 0x1;
 
-/// A `TcpStream` framed with `SrvCodec`. This is the base socket connection that the CtlGateway
+/// A connection between a CtlGateway client and server: either a plain `TcpStream`, or a TLS
+/// session on top of one.
+pub trait SrvIo: AsyncRead + AsyncWrite + Send {}
+
+impl<T> SrvIo for T where T: AsyncRead + AsyncWrite + Send {}
+
+/// A connection framed with `SrvCodec`. This is the base socket connection that the CtlGateway
 /// client and server speak.
-pub type SrvStream = Framed<TcpStream, SrvCodec>;
+pub type SrvStream = Framed<Box<dyn SrvIo>, SrvCodec>;
 
 /// Sending half of `SrvStream`.
 pub type SrvSink = futures::stream::SplitSink<SrvStream>;
@@ -21,9 +21,163 @@ include!("generated/sup.ctl.rs");
 include!("generated/sup.ctl.impl.rs");
 
 use std::fmt;
+use std::str::FromStr;
+
+use crate::net::{self, ErrCode, NetErr};
 
 impl fmt::Display for ConsoleLine {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}", self.line)
     }
 }
+
+/// The requests a Control Gateway credential is allowed to make. Each scope also allows every
+/// request allowed by the scopes before it.
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub enum CtlScope {
+    /// Inspect services and the audit log: status, logs, and configuration.
+    ReadOnly,
+    /// Load, unload, start, stop, and update services.
+    Operator,
+    /// Apply configuration, upload service files, and depart Supervisors.
+    Admin,
+}
+
+impl CtlScope {
+    /// The scope required to make the request with the given message id. Requests which aren't
+    /// listed here require `Admin` so new requests aren't accidentally allowed to everyone.
+    pub fn required_for(message_id: &str) -> Self {
+        match message_id {
+            "SvcGetDefaultCfg" | "SvcValidateCfg" | "SvcStatus" | "SvcLogs" | "SupAudit" => {
+                CtlScope::ReadOnly
+            }
+            "SvcLoad" | "SvcApply" | "SvcUnload" | "SvcStart" | "SvcStop" | "SvcUpdatePause"
+            | "SvcUpdateResume" | "SvcUpdateNow" => CtlScope::Operator,
+            _ => CtlScope::Admin,
+        }
+    }
+
+    /// Returns true if this scope allows the request with the given message id.
+    pub fn allows(self, message_id: &str) -> bool {
+        self >= Self::required_for(message_id)
+    }
+}
+
+impl fmt::Display for CtlScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = match *self {
+            CtlScope::ReadOnly => "read-only",
+            CtlScope::Operator => "operator",
+            CtlScope::Admin => "admin",
+        };
+        write!(f, "{}", value)
+    }
+}
+
+impl FromStr for CtlScope {
+    type Err = NetErr;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_ref() {
+            "read-only" => Ok(CtlScope::ReadOnly),
+            "operator" => Ok(CtlScope::Operator),
+            "admin" => Ok(CtlScope::Admin),
+            _ => Err(net::err(
+                ErrCode::InvalidPayload,
+                format!(
+                    "Invalid ctl scope \"{}\", must be `read-only`, `operator` or `admin`.",
+                    value
+                ),
+            )),
+        }
+    }
+}
+
+/// A named secret key which authenticates clients of the Control Gateway with a scope.
+///
+/// Credentials are written one per line as `<name> <scope> <secret key>`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CtlCredential {
+    pub name: String,
+    pub scope: CtlScope,
+    pub secret_key: String,
+}
+
+impl fmt::Display for CtlCredential {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} {}", self.name, self.scope, self.secret_key)
+    }
+}
+
+impl FromStr for CtlCredential {
+    type Err = NetErr;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let fields: Vec<&str> = value.split_whitespace().collect();
+        match fields.as_slice() {
+            [name, scope, secret_key] => Ok(CtlCredential {
+                name: name.to_string(),
+                scope: scope.parse()?,
+                secret_key: secret_key.to_string(),
+            }),
+            _ => Err(net::err(
+                ErrCode::InvalidPayload,
+                format!(
+                    "Invalid ctl credential \"{}\", must be `<name> <scope> <secret key>`.",
+                    value
+                ),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn scopes_include_the_scopes_before_them() {
+        assert!(CtlScope::ReadOnly.allows("SvcStatus"));
+        assert!(!CtlScope::ReadOnly.allows("SvcStart"));
+        assert!(!CtlScope::ReadOnly.allows("SupDepart"));
+
+        assert!(CtlScope::Operator.allows("SvcStatus"));
+        assert!(CtlScope::Operator.allows("SvcStart"));
+        assert!(CtlScope::Operator.allows("SvcApply"));
+        assert!(!CtlScope::Operator.allows("SvcSetCfg"));
+        assert!(!CtlScope::Operator.allows("SvcSecretSet"));
+        assert!(!CtlScope::Operator.allows("SupDepart"));
+
+        assert!(CtlScope::Admin.allows("SvcStatus"));
+        assert!(CtlScope::Admin.allows("SvcStart"));
+        assert!(CtlScope::Admin.allows("SupDepart"));
+    }
+
+    #[test]
+    fn unknown_requests_require_admin() {
+        assert_eq!(CtlScope::required_for("SvcSomethingNew"), CtlScope::Admin);
+    }
+
+    #[test]
+    fn credential_round_trips_through_its_line_format() {
+        let credential = CtlCredential {
+            name: "deploy-bot".to_string(),
+            scope: CtlScope::Operator,
+            secret_key: "w9TuoqTk4Ixaht8ZpJpHQlmPRbvpgz13GaGnvxunJy8iOhZcS7qGqE==".to_string(),
+        };
+        let line = credential.to_string();
+        assert_eq!(
+            line,
+            "deploy-bot operator w9TuoqTk4Ixaht8ZpJpHQlmPRbvpgz13GaGnvxunJy8iOhZcS7qGqE=="
+        );
+        assert_eq!(line.parse::<CtlCredential>().unwrap(), credential);
+    }
+
+    #[test]
+    fn malformed_credentials_are_rejected() {
+        assert!("deploy-bot operator".parse::<CtlCredential>().is_err());
+        assert!("deploy-bot superuser secret"
+            .parse::<CtlCredential>()
+            .is_err());
+    }
+}
@@ -12,6 +12,9 @@ impl message::MessageStatic for ServiceBindList {
 impl message::MessageStatic for SupDepart {
     const MESSAGE_ID: &'static str = "SupDepart";
 }
+impl message::MessageStatic for SupAudit {
+    const MESSAGE_ID: &'static str = "SupAudit";
+}
 impl message::MessageStatic for SvcFilePut {
     const MESSAGE_ID: &'static str = "SvcFilePut";
 }
@@ -24,9 +27,15 @@ impl message::MessageStatic for SvcValidateCfg {
 impl message::MessageStatic for SvcSetCfg {
     const MESSAGE_ID: &'static str = "SvcSetCfg";
 }
+impl message::MessageStatic for SvcSecretSet {
+    const MESSAGE_ID: &'static str = "SvcSecretSet";
+}
 impl message::MessageStatic for SvcLoad {
     const MESSAGE_ID: &'static str = "SvcLoad";
 }
+impl message::MessageStatic for SvcApply {
+    const MESSAGE_ID: &'static str = "SvcApply";
+}
 impl message::MessageStatic for SvcUnload {
     const MESSAGE_ID: &'static str = "SvcUnload";
 }
@@ -36,9 +45,21 @@ impl message::MessageStatic for SvcStart {
 impl message::MessageStatic for SvcStop {
     const MESSAGE_ID: &'static str = "SvcStop";
 }
+impl message::MessageStatic for SvcUpdatePause {
+    const MESSAGE_ID: &'static str = "SvcUpdatePause";
+}
+impl message::MessageStatic for SvcUpdateResume {
+    const MESSAGE_ID: &'static str = "SvcUpdateResume";
+}
+impl message::MessageStatic for SvcUpdateNow {
+    const MESSAGE_ID: &'static str = "SvcUpdateNow";
+}
 impl message::MessageStatic for SvcStatus {
     const MESSAGE_ID: &'static str = "SvcStatus";
 }
+impl message::MessageStatic for SvcLogs {
+    const MESSAGE_ID: &'static str = "SvcLogs";
+}
 impl message::MessageStatic for ConsoleLine {
     const MESSAGE_ID: &'static str = "ConsoleLine";
 }
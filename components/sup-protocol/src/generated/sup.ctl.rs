@@ -38,6 +38,28 @@ pub struct SupDepart {
     #[prost(string, optional, tag="1")]
     pub member_id: ::std::option::Option<String>,
 }
+/// Request to read the Supervisor's audit log. Each matching entry is replied as a `ConsoleLine`
+/// holding its JSON.
+#[derive(Clone, PartialEq, Message)]
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct SupAudit {
+    /// Only send this many of the most recent matching entries. If unset, every entry is sent.
+    #[prost(uint64, optional, tag="1")]
+    pub tail: ::std::option::Option<u64>,
+    /// Only send entries recorded at or after this time, in seconds since the Unix epoch.
+    #[prost(int64, optional, tag="2")]
+    pub since: ::std::option::Option<i64>,
+    /// Only send entries for requests made with this credential.
+    #[prost(string, optional, tag="3")]
+    pub actor: ::std::option::Option<String>,
+    /// Only send entries for this type of request, ex: `SvcLoad`.
+    #[prost(string, optional, tag="4")]
+    pub request: ::std::option::Option<String>,
+    /// Check the hash chain of the whole audit log first, failing the request if it is broken.
+    #[prost(bool, optional, tag="5", default="false")]
+    pub verify: ::std::option::Option<bool>,
+}
 #[derive(Clone, PartialEq, Message)]
 #[derive(Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case")]
@@ -97,6 +119,22 @@ pub struct SvcSetCfg {
     #[prost(bool, optional, tag="4", default="false")]
     pub is_encrypted: ::std::option::Option<bool>,
 }
+/// Request to set a secret of a service group in the Supervisor's local secret store. Secrets are
+/// never gossiped and are only exposed to the templates of the service as `{{secret.<name>}}`.
+#[derive(Clone, PartialEq, Message)]
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct SvcSecretSet {
+    /// Service group the secret belongs to. The Supervisor encrypts the secret with its key.
+    #[prost(message, optional, tag="1")]
+    pub service_group: ::std::option::Option<super::types::ServiceGroup>,
+    /// Name of the secret. Only letters, digits and underscores are allowed.
+    #[prost(string, optional, tag="2")]
+    pub name: ::std::option::Option<String>,
+    /// Value of the secret.
+    #[prost(string, optional, tag="3")]
+    pub value: ::std::option::Option<String>,
+}
 /// Request to load a new service.
 #[derive(Clone, PartialEq, Message)]
 #[derive(Serialize, Deserialize)]
@@ -148,6 +186,61 @@ pub struct SvcLoad {
     /// Health Check interval for the service
     #[prost(message, optional, tag="15")]
     pub health_check_interval: ::std::option::Option<super::types::HealthCheckInterval>,
+    /// How eagerly the service is restarted after its process dies.
+    #[prost(message, optional, tag="16")]
+    pub restart_policy: ::std::option::Option<super::types::RestartPolicy>,
+    /// Memory, CPU and process limits for the service.
+    #[prost(message, optional, tag="17")]
+    pub resource_limits: ::std::option::Option<super::types::ResourceLimits>,
+    /// Whether, and how, the service's output is written to rotated log files.
+    #[prost(message, optional, tag="18")]
+    pub log_settings: ::std::option::Option<super::types::LogSettings>,
+    /// How many health check results it takes to change the service's reported health.
+    #[prost(message, optional, tag="19")]
+    pub health_check_thresholds: ::std::option::Option<super::types::HealthCheckThresholds>,
+    /// A health check for the Supervisor to perform itself.
+    #[prost(message, optional, tag="20")]
+    pub health_probe: ::std::option::Option<super::types::HealthProbe>,
+    /// The signal sent to the service's process to ask it to stop.
+    #[prost(enumeration="super::types::ShutdownSignal", optional, tag="21")]
+    pub shutdown_signal: ::std::option::Option<i32>,
+    /// Seconds the service's process is given to stop before it is killed.
+    #[prost(uint32, optional, tag="22")]
+    pub shutdown_timeout: ::std::option::Option<u32>,
+    /// Packages whose services, if loaded on this Supervisor, start before this one and stop after it.
+    #[prost(message, repeated, tag="23")]
+    pub after: ::std::vec::Vec<super::types::PackageIdent>,
+    /// Packages whose services must be loaded and up on this Supervisor before this one starts. They
+    /// also stop after it.
+    #[prost(message, repeated, tag="24")]
+    pub requires: ::std::vec::Vec<super::types::PackageIdent>,
+    /// Whether the services this one starts after must also be healthy before it starts.
+    #[prost(bool, optional, tag="25")]
+    pub require_healthy: ::std::option::Option<bool>,
+    /// How and when updates are rolled out.
+    #[prost(message, optional, tag="26")]
+    pub update_settings: ::std::option::Option<super::types::UpdateSettings>,
+    /// Seconds the pre-start hook may run before it is killed.
+    #[prost(uint32, optional, tag="27")]
+    pub pre_start_timeout: ::std::option::Option<u32>,
+    /// Seconds the pre-stop hook may run before the service's process is stopped regardless.
+    #[prost(uint32, optional, tag="28")]
+    pub pre_stop_timeout: ::std::option::Option<u32>,
+}
+/// Request to bring the loaded services in line with a manifest listing the specs of many services.
+#[derive(Clone, PartialEq, Message)]
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct SvcApply {
+    /// The TOML manifest, with a `[[services]]` table for the spec of each service.
+    #[prost(string, optional, tag="1")]
+    pub manifest: ::std::option::Option<String>,
+    /// Unload the loaded services which aren't in the manifest.
+    #[prost(bool, optional, tag="2", default="false")]
+    pub prune: ::std::option::Option<bool>,
+    /// Only reply with the operations which applying the manifest would perform.
+    #[prost(bool, optional, tag="3", default="false")]
+    pub dry_run: ::std::option::Option<bool>,
 }
 /// Request to unload a loaded service.
 #[derive(Clone, PartialEq, Message)]
@@ -173,6 +266,30 @@ pub struct SvcStop {
     #[prost(message, optional, tag="1")]
     pub ident: ::std::option::Option<super::types::PackageIdent>,
 }
+/// Request to pause the automatic updates of a loaded service, without restarting it.
+#[derive(Clone, PartialEq, Message)]
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct SvcUpdatePause {
+    #[prost(message, optional, tag="1")]
+    pub ident: ::std::option::Option<super::types::PackageIdent>,
+}
+/// Request to resume the automatic updates of a loaded service.
+#[derive(Clone, PartialEq, Message)]
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct SvcUpdateResume {
+    #[prost(message, optional, tag="1")]
+    pub ident: ::std::option::Option<super::types::PackageIdent>,
+}
+/// Request to check for an update to a loaded service right away.
+#[derive(Clone, PartialEq, Message)]
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct SvcUpdateNow {
+    #[prost(message, optional, tag="1")]
+    pub ident: ::std::option::Option<super::types::PackageIdent>,
+}
 /// Request to retrieve the service status of one or all services.
 #[derive(Clone, PartialEq, Message)]
 #[derive(Serialize, Deserialize)]
@@ -183,6 +300,24 @@ pub struct SvcStatus {
     #[prost(message, optional, tag="1")]
     pub ident: ::std::option::Option<super::types::PackageIdent>,
 }
+/// Request to stream the output a service has written to its log file. Each line is replied as a
+/// `ConsoleLine`.
+#[derive(Clone, PartialEq, Message)]
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct SvcLogs {
+    #[prost(message, optional, tag="1")]
+    pub ident: ::std::option::Option<super::types::PackageIdent>,
+    /// Keep the transaction open and send lines as they are written.
+    #[prost(bool, optional, tag="2", default="false")]
+    pub follow: ::std::option::Option<bool>,
+    /// Only send this many of the most recent lines. If unset, the whole log file is sent.
+    #[prost(uint64, optional, tag="3")]
+    pub tail: ::std::option::Option<u64>,
+    /// Only send lines written at or after this time, in seconds since the Unix epoch.
+    #[prost(int64, optional, tag="4")]
+    pub since: ::std::option::Option<i64>,
+}
 /// A reply to various requests which contains a pre-formatted console line.
 #[derive(Clone, PartialEq, Message)]
 #[derive(Serialize, Deserialize)]
@@ -24,3 +24,21 @@ impl message::MessageStatic for ServiceStatus {
 impl message::MessageStatic for HealthCheckInterval {
     const MESSAGE_ID: &'static str = "HealthCheckInterval";
 }
+impl message::MessageStatic for HealthCheckThresholds {
+    const MESSAGE_ID: &'static str = "HealthCheckThresholds";
+}
+impl message::MessageStatic for HealthProbe {
+    const MESSAGE_ID: &'static str = "HealthProbe";
+}
+impl message::MessageStatic for LogSettings {
+    const MESSAGE_ID: &'static str = "LogSettings";
+}
+impl message::MessageStatic for ResourceLimits {
+    const MESSAGE_ID: &'static str = "ResourceLimits";
+}
+impl message::MessageStatic for RestartPolicy {
+    const MESSAGE_ID: &'static str = "RestartPolicy";
+}
+impl message::MessageStatic for UpdateSettings {
+    const MESSAGE_ID: &'static str = "UpdateSettings";
+}
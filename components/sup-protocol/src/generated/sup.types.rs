@@ -85,6 +85,15 @@ pub struct ServiceStatus {
     pub service_group: ServiceGroup,
     #[prost(enumeration="DesiredState", optional, tag="5")]
     pub desired_state: ::std::option::Option<i32>,
+    /// Number of times the Supervisor has restarted the service's process after it died.
+    #[prost(uint64, optional, tag="6")]
+    pub restarts: ::std::option::Option<u64>,
+    /// Set once the service has exceeded its restart policy and will no longer be restarted.
+    #[prost(bool, optional, tag="7")]
+    pub crash_looping: ::std::option::Option<bool>,
+    /// Set while the service's start is waiting on a service it depends on.
+    #[prost(message, optional, tag="8")]
+    pub waiting_on: ::std::option::Option<PackageIdent>,
 }
 #[derive(Clone, PartialEq, Message)]
 #[derive(Serialize, Deserialize)]
@@ -93,6 +102,117 @@ pub struct HealthCheckInterval {
     #[prost(uint64, required, tag="1")]
     pub seconds: u64,
 }
+/// How many health check results it takes to change a service's reported health.
+#[derive(Clone, PartialEq, Message)]
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct HealthCheckThresholds {
+    /// Consecutive failing checks before a healthy service is reported as degraded.
+    #[prost(uint32, optional, tag="1")]
+    pub failures: ::std::option::Option<u32>,
+    /// Consecutive passing checks before a degraded service is reported as healthy again.
+    #[prost(uint32, optional, tag="2")]
+    pub successes: ::std::option::Option<u32>,
+    /// Seconds after the service starts during which failing checks are ignored.
+    #[prost(uint64, optional, tag="3")]
+    pub grace_period: ::std::option::Option<u64>,
+    /// Consecutive critical checks after which the service's process is restarted. Zero never
+    /// restarts it.
+    #[prost(uint32, optional, tag="4")]
+    pub restart_after: ::std::option::Option<u32>,
+}
+/// A health check the Supervisor performs itself, instead of or alongside a `health-check` hook.
+#[derive(Clone, PartialEq, Message)]
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct HealthProbe {
+    /// What to probe: `http://<host>[:<port>]/<path>`, `tcp://<host>:<port>` or `exec:<command>`.
+    #[prost(string, required, tag="1")]
+    pub target: String,
+    /// Seconds to wait for the probe to complete before considering it failed.
+    #[prost(uint64, optional, tag="2")]
+    pub timeout: ::std::option::Option<u64>,
+}
+/// Settings for writing a service's output to rotated log files under its `logs` directory.
+#[derive(Clone, PartialEq, Message)]
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct LogSettings {
+    /// Whether the service's output is written to a log file at all.
+    #[prost(bool, optional, tag="1")]
+    pub enabled: ::std::option::Option<bool>,
+    /// Rotate once the file reaches this many bytes. Zero means no size limit.
+    #[prost(uint64, optional, tag="2")]
+    pub max_size: ::std::option::Option<u64>,
+    /// Rotate once the file is this many seconds old. Zero means no age limit.
+    #[prost(uint64, optional, tag="3")]
+    pub max_age: ::std::option::Option<u64>,
+    /// Number of rotated files to keep. Zero keeps them all.
+    #[prost(uint32, optional, tag="4")]
+    pub retain: ::std::option::Option<u32>,
+    /// Whether rotated files are gzipped.
+    #[prost(bool, optional, tag="5")]
+    pub compress: ::std::option::Option<bool>,
+}
+/// Limits enforced on a service's process through a dedicated cgroup. Unset fields are unlimited.
+#[derive(Clone, PartialEq, Message)]
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ResourceLimits {
+    /// Maximum memory, in bytes.
+    #[prost(uint64, optional, tag="1")]
+    pub memory_max: ::std::option::Option<u64>,
+    /// Relative CPU weight, from 1 to 10000.
+    #[prost(uint64, optional, tag="2")]
+    pub cpu_weight: ::std::option::Option<u64>,
+    /// CPU time, as a percentage of a single CPU (e.g. 150 for one and a half CPUs).
+    #[prost(uint64, optional, tag="3")]
+    pub cpu_quota: ::std::option::Option<u64>,
+    /// Maximum number of processes and threads.
+    #[prost(uint64, optional, tag="4")]
+    pub pids_max: ::std::option::Option<u64>,
+}
+#[derive(Clone, PartialEq, Message)]
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct RestartPolicy {
+    /// Restarts allowed within `window` before the service is considered crash-looping. Zero
+    /// means there is no limit.
+    #[prost(uint32, optional, tag="1")]
+    pub max_restarts: ::std::option::Option<u32>,
+    /// Length, in seconds, of the window in which restarts are counted.
+    #[prost(uint64, optional, tag="2")]
+    pub window: ::std::option::Option<u64>,
+    /// Initial delay, in seconds, between consecutive restarts. Doubles with each restart.
+    #[prost(uint64, optional, tag="3")]
+    pub backoff: ::std::option::Option<u64>,
+    /// Upper bound, in seconds, of the delay between consecutive restarts.
+    #[prost(uint64, optional, tag="4")]
+    pub backoff_max: ::std::option::Option<u64>,
+}
+/// How and when updates are rolled out to a service group.
+#[derive(Clone, PartialEq, Message)]
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct UpdateSettings {
+    /// How many members update at a time: a count (`3`) or a percentage of the group (`20%`).
+    #[prost(string, optional, tag="1")]
+    pub batch_size: ::std::option::Option<String>,
+    /// Seconds the canary must stay healthy on the new package before the rest of the group updates.
+    #[prost(uint64, optional, tag="2")]
+    pub canary_soak: ::std::option::Option<u64>,
+    /// Periods, in UTC, during which updates may be applied, as `[DAYS ]HH:MM-HH:MM`. Updates found
+    /// outside of them are held until one opens.
+    #[prost(string, repeated, tag="3")]
+    pub windows: ::std::vec::Vec<String>,
+    /// Upper bound, in seconds, of a random delay before polling for and applying updates.
+    #[prost(uint64, optional, tag="4")]
+    pub splay: ::std::option::Option<u64>,
+    /// Seconds after an update during which a service that crash-loops, or isn't healthy at the
+    /// end, is rolled back to the package it ran before. Zero disables rollbacks.
+    #[prost(uint64, optional, tag="5")]
+    pub rollback_period: ::std::option::Option<u64>,
+}
 /// Encapsulate all possible sources we can install packages from.
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Enumeration)]
 #[derive(Serialize, Deserialize)]
@@ -135,6 +255,11 @@ pub enum UpdateStrategy {
     None = 0,
     AtOnce = 1,
     Rolling = 2,
+    /// Members update in batches of `UpdateSettings.batch_size` once the update leader has updated.
+    Batched = 3,
+    /// The update leader updates first and must stay healthy for `UpdateSettings.canary_soak`
+    /// seconds before the other members update in batches.
+    Canary = 4,
 }
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Enumeration)]
 #[derive(Serialize, Deserialize)]
@@ -145,3 +270,15 @@ pub enum BindingMode {
     /// Service start-up is blocked until all binds are available
     Strict = 1,
 }
+/// The signal sent to a service's process to ask it to stop.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Enumeration)]
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ShutdownSignal {
+    Term = 0,
+    Int = 1,
+    Quit = 2,
+    Hup = 3,
+    Usr1 = 4,
+    Usr2 = 5,
+}
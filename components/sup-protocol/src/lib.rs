@@ -59,9 +59,11 @@ pub mod codec;
 pub mod ctl;
 pub mod message;
 pub mod net;
+pub mod tls;
 pub mod types;
 
 use crate::core::env as henv;
+use crate::ctl::CtlCredential;
 use crate::net::{ErrCode, NetResult};
 use rand::RngCore;
 use std::fs::File;
@@ -71,6 +73,8 @@ use std::path::{Path, PathBuf};
 
 // Name of file containing the CtlGateway secret key.
 const CTL_SECRET_FILENAME: &str = "CTL_SECRET";
+// Name of file containing additional, scoped CtlGateway credentials.
+const CTL_CREDENTIALS_FILENAME: &str = "CTL_CREDENTIALS";
 /// Length of characters in CtlGateway secret key.
 const CTL_SECRET_LEN: usize = 64;
 
@@ -138,6 +142,47 @@ where
     sup_root.as_ref().join(CTL_SECRET_FILENAME)
 }
 
+/// Read the scoped credentials used to authenticate connections to the `CtlGateway` from disk.
+/// The file holds one credential per line; blank lines and lines starting with `#` are ignored.
+/// A missing file is the same as a file with no credentials.
+pub fn read_credentials<T>(sup_root: T) -> NetResult<Vec<CtlCredential>>
+where
+    T: AsRef<Path>,
+{
+    let credentials_path = credentials_path(sup_root);
+    if !credentials_path.exists() {
+        return Ok(vec![]);
+    }
+    let mut content = String::new();
+    File::open(&credentials_path)
+        .and_then(|mut f| f.read_to_string(&mut content))
+        .map_err(|e| {
+            net::err(
+                ErrCode::Io,
+                format!(
+                    "IoError while reading ctl credentials, {}, {}",
+                    credentials_path.display(),
+                    e
+                ),
+            )
+        })?;
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::parse::<CtlCredential>)
+        .collect()
+}
+
+/// Returns the location of the scoped CtlGateway credentials on disk for the given Supervisor
+/// root.
+pub fn credentials_path<T>(sup_root: T) -> PathBuf
+where
+    T: AsRef<Path>,
+{
+    sup_root.as_ref().join(CTL_CREDENTIALS_FILENAME)
+}
+
 pub fn sup_root<U>(custom_state_path: Option<U>) -> PathBuf
 where
     U: AsRef<Path>,
@@ -165,6 +210,7 @@ pub fn socket_addr_env_or_default(env_var: &str, default: SocketAddr) -> SocketA
 #[cfg(test)]
 mod ctl_secret {
     use super::*;
+    use crate::ctl::CtlScope;
     use std::fs::File;
     use std::io::Write;
     use tempfile::TempDir;
@@ -210,4 +256,37 @@ mod ctl_secret {
         let empty = String::new();
         assert_eq!(empty, out);
     }
+
+    #[test]
+    fn read_credentials_skips_comments_and_blank_lines() {
+        let tmpdir = TempDir::new().unwrap();
+        let file_path = tmpdir.path().to_owned().join("CTL_CREDENTIALS");
+        let mut credentials_file = File::create(file_path).unwrap();
+        writeln!(credentials_file, "# name scope secret").unwrap();
+        writeln!(credentials_file, "dashboard read-only c2VjcmV0MQ==").unwrap();
+        writeln!(credentials_file).unwrap();
+        writeln!(credentials_file, "deploy-bot operator c2VjcmV0Mg==\r").unwrap();
+        let credentials = read_credentials(&tmpdir).unwrap();
+        assert_eq!(
+            credentials,
+            vec![
+                CtlCredential {
+                    name: "dashboard".to_string(),
+                    scope: CtlScope::ReadOnly,
+                    secret_key: "c2VjcmV0MQ==".to_string(),
+                },
+                CtlCredential {
+                    name: "deploy-bot".to_string(),
+                    scope: CtlScope::Operator,
+                    secret_key: "c2VjcmV0Mg==".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn read_credentials_file_nonexistent() {
+        let tmpdir = TempDir::new().unwrap();
+        assert_eq!(read_credentials(tmpdir), Ok(vec![]));
+    }
 }
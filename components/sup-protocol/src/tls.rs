@@ -0,0 +1,34 @@
+// Copyright (c) 2018 Chef Software Inc. and/or applicable contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Helpers for loading the TLS material of the CtlGateway, shared by its server and its clients.
+
+use std::fs::File;
+use std::io::{self, BufReader};
+use std::path::Path;
+
+use rustls::internal::pemfile;
+use rustls::PrivateKey;
+
+/// Read the first RSA or PKCS8 private key from a PEM-encoded file. Returns `None` if the file
+/// holds neither.
+pub fn read_private_key(path: &Path) -> io::Result<Option<PrivateKey>> {
+    let mut keys =
+        pemfile::rsa_private_keys(&mut BufReader::new(File::open(path)?)).unwrap_or_default();
+    if keys.is_empty() {
+        keys =
+            pemfile::pkcs8_private_keys(&mut BufReader::new(File::open(path)?)).unwrap_or_default();
+    }
+    Ok(keys.pop())
+}
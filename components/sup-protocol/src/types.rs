@@ -179,6 +179,47 @@ impl FromStr for ServiceBind {
     }
 }
 
+impl FromStr for HealthProbe {
+    type Err = NetErr;
+
+    fn from_str(target: &str) -> Result<Self, Self::Err> {
+        let valid = if target.starts_with("http://") {
+            let authority = target["http://".len()..].split('/').next().unwrap_or("");
+            !authority.is_empty() && valid_port(authority, true)
+        } else if target.starts_with("tcp://") {
+            let address = &target["tcp://".len()..];
+            !address.starts_with(':') && valid_port(address, false)
+        } else if target.starts_with("exec:") {
+            !target["exec:".len()..].trim().is_empty()
+        } else {
+            false
+        };
+        if valid {
+            Ok(HealthProbe {
+                target: target.to_string(),
+                timeout: None,
+            })
+        } else {
+            Err(net::err(
+                ErrCode::InvalidPayload,
+                format!(
+                    "Invalid health probe \"{}\", must be of the form http://<HOST>[:<PORT>]/<PATH>, \
+                     tcp://<HOST>:<PORT> or exec:<COMMAND>.",
+                    target
+                ),
+            ))
+        }
+    }
+}
+
+/// Whether the port at the end of a `<host>:<port>` address is valid, or absent when optional.
+fn valid_port(address: &str, optional: bool) -> bool {
+    match address.rfind(':') {
+        Some(i) => address[i + 1..].parse::<u16>().is_ok(),
+        None => optional,
+    }
+}
+
 impl FromStr for ServiceGroup {
     type Err = NetErr;
 
@@ -363,6 +404,8 @@ impl UpdateStrategy {
             UpdateStrategy::None => "none",
             UpdateStrategy::AtOnce => "at-once",
             UpdateStrategy::Rolling => "rolling",
+            UpdateStrategy::Batched => "batched",
+            UpdateStrategy::Canary => "canary",
         }
     }
 }
@@ -375,6 +418,8 @@ impl FromStr for UpdateStrategy {
             "none" => Ok(UpdateStrategy::None),
             "at-once" => Ok(UpdateStrategy::AtOnce),
             "rolling" => Ok(UpdateStrategy::Rolling),
+            "batched" => Ok(UpdateStrategy::Batched),
+            "canary" => Ok(UpdateStrategy::Canary),
             _ => Err(net::err(
                 ErrCode::InvalidPayload,
                 "Invalid update strategy.",
@@ -389,6 +434,254 @@ impl fmt::Display for UpdateStrategy {
     }
 }
 
+impl ShutdownSignal {
+    /// The signal's name, without the `SIG` prefix, as the Launcher expects it.
+    pub fn as_str(&self) -> &str {
+        match *self {
+            ShutdownSignal::Term => "TERM",
+            ShutdownSignal::Int => "INT",
+            ShutdownSignal::Quit => "QUIT",
+            ShutdownSignal::Hup => "HUP",
+            ShutdownSignal::Usr1 => "USR1",
+            ShutdownSignal::Usr2 => "USR2",
+        }
+    }
+}
+
+impl FromStr for ShutdownSignal {
+    type Err = NetErr;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let name = value.to_uppercase();
+        match name.trim_start_matches("SIG") {
+            "TERM" => Ok(ShutdownSignal::Term),
+            "INT" => Ok(ShutdownSignal::Int),
+            "QUIT" => Ok(ShutdownSignal::Quit),
+            "HUP" => Ok(ShutdownSignal::Hup),
+            "USR1" => Ok(ShutdownSignal::Usr1),
+            "USR2" => Ok(ShutdownSignal::Usr2),
+            _ => Err(net::err(
+                ErrCode::InvalidPayload,
+                format!(
+                    "Invalid shutdown signal \"{}\", must be one of TERM, INT, QUIT, HUP, USR1 \
+                     or USR2.",
+                    value
+                ),
+            )),
+        }
+    }
+}
+
+impl fmt::Display for ShutdownSignal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl ResourceLimits {
+    /// Parse a memory size in bytes, optionally followed by a `K`, `M`, `G` or `T` suffix
+    /// (powers of 1024).
+    pub fn parse_memory(value: &str) -> Result<u64, NetErr> {
+        let value = value.trim();
+        let (digits, multiplier) = match value.chars().last().map(|c| c.to_ascii_uppercase()) {
+            Some('K') => (&value[..value.len() - 1], 1 << 10),
+            Some('M') => (&value[..value.len() - 1], 1 << 20),
+            Some('G') => (&value[..value.len() - 1], 1 << 30),
+            Some('T') => (&value[..value.len() - 1], 1 << 40),
+            _ => (value, 1),
+        };
+        digits
+            .parse::<u64>()
+            .ok()
+            .and_then(|n| n.checked_mul(multiplier))
+            .ok_or_else(|| {
+                net::err(
+                    ErrCode::InvalidPayload,
+                    format!(
+                        "Invalid memory size \"{}\", must be a number of bytes optionally \
+                         followed by K, M, G or T.",
+                        value
+                    ),
+                )
+            })
+    }
+
+    /// Parse a CPU quota, given as a percentage of a single CPU with an optional trailing `%`.
+    pub fn parse_cpu_quota(value: &str) -> Result<u64, NetErr> {
+        let value = value.trim();
+        value
+            .trim_end_matches('%')
+            .parse::<u64>()
+            .ok()
+            .filter(|q| *q > 0)
+            .ok_or_else(|| {
+                net::err(
+                    ErrCode::InvalidPayload,
+                    format!(
+                        "Invalid CPU quota \"{}\", must be a positive percentage of a single \
+                         CPU (e.g. 150%).",
+                        value
+                    ),
+                )
+            })
+    }
+}
+
+const DAY_NAMES: [&str; 7] = [
+    "monday",
+    "tuesday",
+    "wednesday",
+    "thursday",
+    "friday",
+    "saturday",
+    "sunday",
+];
+const ALL_DAYS: u8 = 0b111_1111;
+const MINUTES_PER_DAY: u16 = 24 * 60;
+
+/// A recurring period, in UTC, during which automatic updates may be applied.
+///
+/// Written as `[DAYS ]HH:MM-HH:MM`, where the optional `DAYS` is a comma-separated list of days
+/// or day ranges, such as `mon-fri` or `sat,sun`. A window that ends earlier than it starts runs
+/// past midnight into the next day, and one that starts and ends at the same time lasts the
+/// whole day.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct UpdateWindow {
+    /// The days the window opens on, as a bit mask with Monday as the lowest bit.
+    days: u8,
+    /// Minutes after midnight at which the window opens.
+    start: u16,
+    /// Minutes after midnight at which the window closes.
+    end: u16,
+}
+
+impl UpdateWindow {
+    /// Whether the window is open at the given time, where `weekday` counts the days from
+    /// Monday, starting at 0, and `minute` the minutes since midnight.
+    pub fn contains(&self, weekday: u8, minute: u16) -> bool {
+        let opens_on = |day: u8| self.days & (1 << (day % 7)) != 0;
+        if self.start < self.end {
+            opens_on(weekday) && minute >= self.start && minute < self.end
+        } else if self.start > self.end {
+            (opens_on(weekday) && minute >= self.start)
+                || (opens_on(weekday + 6) && minute < self.end)
+        } else {
+            opens_on(weekday)
+        }
+    }
+
+    fn parse_days(value: &str) -> Option<u8> {
+        let day = |name: &str| {
+            let name = name.to_lowercase();
+            DAY_NAMES
+                .iter()
+                .position(|day| name.len() >= 3 && day.starts_with(&name))
+        };
+        let mut days = 0;
+        for item in value.split(',') {
+            let mut range = item.splitn(2, '-');
+            let first = day(range.next()?)?;
+            let last = match range.next() {
+                Some(last) => day(last)?,
+                None => first,
+            };
+            let mut current = first;
+            loop {
+                days |= 1 << current;
+                if current == last {
+                    break;
+                }
+                current = (current + 1) % 7;
+            }
+        }
+        Some(days)
+    }
+
+    fn parse_time(value: &str) -> Option<u16> {
+        let mut parts = value.splitn(2, ':');
+        let hours = parts.next()?.parse::<u16>().ok()?;
+        let minutes = parts.next()?.parse::<u16>().ok()?;
+        if (hours < 24 && minutes < 60) || (hours == 24 && minutes == 0) {
+            Some(hours * 60 + minutes)
+        } else {
+            None
+        }
+    }
+}
+
+impl FromStr for UpdateWindow {
+    type Err = NetErr;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            net::err(
+                ErrCode::InvalidPayload,
+                format!(
+                    "Invalid update window \"{}\", must be of the form [DAYS ]HH:MM-HH:MM in \
+                     UTC (e.g. \"sat,sun 01:00-05:00\").",
+                    value
+                ),
+            )
+        };
+        let mut parts = value.split_whitespace();
+        let (days, times) = match (parts.next(), parts.next(), parts.next()) {
+            (Some(times), None, None) => (ALL_DAYS, times),
+            (Some(days), Some(times), None) => (Self::parse_days(days).ok_or_else(invalid)?, times),
+            _ => return Err(invalid()),
+        };
+        let mut times = times.splitn(2, '-');
+        let start = times.next().and_then(Self::parse_time);
+        let end = times.next().and_then(Self::parse_time);
+        match (start, end) {
+            (Some(start), Some(end)) if start < MINUTES_PER_DAY => {
+                Ok(UpdateWindow { days, start, end })
+            }
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl fmt::Display for UpdateWindow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.days != ALL_DAYS {
+            let days: Vec<&str> = DAY_NAMES
+                .iter()
+                .enumerate()
+                .filter(|&(i, _)| self.days & (1 << i) != 0)
+                .map(|(_, name)| &name[..3])
+                .collect();
+            write!(f, "{} ", days.join(","))?;
+        }
+        write!(
+            f,
+            "{:02}:{:02}-{:02}:{:02}",
+            self.start / 60,
+            self.start % 60,
+            self.end / 60,
+            self.end % 60
+        )
+    }
+}
+
+impl serde::Serialize for UpdateWindow {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for UpdateWindow {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let value = <String as serde::Deserialize>::deserialize(deserializer)?;
+        value.parse().map_err(serde::de::Error::custom)
+    }
+}
+
 #[cfg(test)]
 mod test {
     use toml;
@@ -469,6 +762,22 @@ mod test {
         assert_eq!(strategy, UpdateStrategy::AtOnce);
     }
 
+    #[test]
+    fn update_strategy_round_trips_through_strings() {
+        for strategy in &[
+            UpdateStrategy::None,
+            UpdateStrategy::AtOnce,
+            UpdateStrategy::Rolling,
+            UpdateStrategy::Batched,
+            UpdateStrategy::Canary,
+        ] {
+            assert_eq!(
+                UpdateStrategy::from_str(&strategy.to_string()).unwrap(),
+                *strategy
+            );
+        }
+    }
+
     #[test]
     fn update_strategy_from_str_invalid() {
         let strategy_str = "dope";
@@ -510,4 +819,140 @@ mod test {
 
         assert!(toml.starts_with(r#"key = "at-once""#));
     }
+
+    #[test]
+    fn shutdown_signal_from_str() {
+        assert_eq!(
+            ShutdownSignal::from_str("INT").unwrap(),
+            ShutdownSignal::Int
+        );
+        assert_eq!(
+            ShutdownSignal::from_str("sigquit").unwrap(),
+            ShutdownSignal::Quit
+        );
+        assert!(ShutdownSignal::from_str("KILL").is_err());
+    }
+
+    #[test]
+    fn shutdown_signal_to_string() {
+        assert_eq!("USR1", ShutdownSignal::Usr1.to_string());
+    }
+
+    #[test]
+    fn resource_limits_parse_memory() {
+        assert_eq!(ResourceLimits::parse_memory("1024").unwrap(), 1024);
+        assert_eq!(
+            ResourceLimits::parse_memory("512M").unwrap(),
+            512 * 1024 * 1024
+        );
+        assert_eq!(
+            ResourceLimits::parse_memory("2g").unwrap(),
+            2 * 1024 * 1024 * 1024
+        );
+        assert!(ResourceLimits::parse_memory("lots").is_err());
+        assert!(ResourceLimits::parse_memory("M").is_err());
+    }
+
+    #[test]
+    fn update_window_from_str() {
+        let window = UpdateWindow::from_str("22:00-04:30").unwrap();
+        assert_eq!(window.to_string(), "22:00-04:30");
+
+        let window = UpdateWindow::from_str("Mon-Wed,saturday 01:00-05:00").unwrap();
+        assert_eq!(window.to_string(), "mon,tue,wed,sat 01:00-05:00");
+
+        let window = UpdateWindow::from_str("fri-mon 00:00-24:00").unwrap();
+        assert_eq!(window.to_string(), "mon,fri,sat,sun 00:00-24:00");
+    }
+
+    #[test]
+    fn update_window_from_str_invalid() {
+        for value in &[
+            "",
+            "22:00",
+            "22:00-",
+            "25:00-01:00",
+            "01:60-02:00",
+            "24:00-01:00",
+            "mo 01:00-02:00",
+            "mon,,tue 01:00-02:00",
+            "mon 01:00-02:00 extra",
+        ] {
+            assert!(
+                UpdateWindow::from_str(value).is_err(),
+                "{} should not parse",
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn update_window_contains() {
+        let window = UpdateWindow::from_str("sat,sun 01:00-05:00").unwrap();
+        assert!(window.contains(5, 60));
+        assert!(window.contains(6, 4 * 60 + 59));
+        assert!(!window.contains(6, 5 * 60));
+        assert!(!window.contains(0, 2 * 60));
+
+        // Runs past midnight, into the next day
+        let window = UpdateWindow::from_str("fri 22:00-02:00").unwrap();
+        assert!(window.contains(4, 23 * 60));
+        assert!(window.contains(5, 60));
+        assert!(!window.contains(4, 60));
+        assert!(!window.contains(5, 23 * 60));
+
+        let window = UpdateWindow::from_str("sun 00:00-00:00").unwrap();
+        assert!(window.contains(6, 0));
+        assert!(window.contains(6, 23 * 60 + 59));
+        assert!(!window.contains(0, 0));
+    }
+
+    #[test]
+    fn update_window_toml_round_trip() {
+        #[derive(Deserialize, Serialize)]
+        struct Data {
+            key: UpdateWindow,
+        }
+        let data: Data = toml::from_str(r#"key = "tue 03:15-04:00""#).unwrap();
+        assert_eq!(data.key, UpdateWindow::from_str("tue 03:15-04:00").unwrap());
+        assert!(toml::to_string(&data)
+            .unwrap()
+            .starts_with(r#"key = "tue 03:15-04:00""#));
+    }
+
+    #[test]
+    fn resource_limits_parse_cpu_quota() {
+        assert_eq!(ResourceLimits::parse_cpu_quota("150%").unwrap(), 150);
+        assert_eq!(ResourceLimits::parse_cpu_quota("50").unwrap(), 50);
+        assert!(ResourceLimits::parse_cpu_quota("0%").is_err());
+        assert!(ResourceLimits::parse_cpu_quota("half").is_err());
+    }
+
+    #[test]
+    fn health_probe_from_str() {
+        for target in &[
+            "http://localhost/healthz",
+            "http://127.0.0.1:8080/status",
+            "http://localhost",
+            "tcp://localhost:5432",
+            "exec:pg_isready -q",
+        ] {
+            assert_eq!(HealthProbe::from_str(target).unwrap().target, *target);
+        }
+    }
+
+    #[test]
+    fn health_probe_from_str_invalid() {
+        for target in &[
+            "localhost:5432",
+            "https://localhost/healthz",
+            "http:///healthz",
+            "http://localhost:http/",
+            "tcp://localhost",
+            "tcp://:5432",
+            "exec: ",
+        ] {
+            assert!(HealthProbe::from_str(target).is_err(), "{}", target);
+        }
+    }
 }
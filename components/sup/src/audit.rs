@@ -0,0 +1,971 @@
+// Copyright (c) 2018 Chef Software Inc. and/or applicable contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A tamper-evident, append-only record of the requests which change the Supervisor.
+//!
+//! Every mutating request the Manager handles for the CtlGateway or the HTTP gateway is written
+//! to the audit log as a line of JSON, recording who made it, what it was, when, and how it
+//! turned out. Payloads are only summarized, so configuration and encrypted passwords never end
+//! up in the log.
+//!
+//! Each `AuditEntry` carries the MAC of the entry before it along with a MAC of its own content,
+//! keyed with the Supervisor's audit key, so editing, removing, or reordering entries breaks the
+//! chain from that point on, and the chain can't be recomputed without the key. The sequence
+//! number and MAC of the latest entry are also kept in an anchor file outside the log, so that
+//! entries removed from its end are noticed too; `verify` checks both. The log is rotated once it
+//! grows past `MAX_SIZE`, and the chain carries on into the new file. Only the newest
+//! `MAX_ROTATED_FILES` rotated files are kept; the chain is verified from the oldest entry left.
+//!
+//! An anchor which can't be read doesn't stop the Supervisor from recording requests. It is left
+//! as it is, rather than replaced, so that `verify` keeps reporting the log as unanchored until an
+//! operator removes it.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::result;
+use std::sync::Mutex;
+
+use prost::Message;
+use serde_json;
+use sodiumoxide::crypto::auth::hmacsha256::{self, Key};
+use time;
+
+use crate::hcore;
+use crate::hcore::crypto;
+use crate::protocol;
+use crate::protocol::codec::SrvMessage;
+use crate::protocol::message::MessageStatic;
+use crate::protocol::net::NetResult;
+
+static LOGKEY: &'static str = "AU";
+
+/// Name of the audit log file, in the `audit` directory of the Supervisor's root.
+pub const AUDIT_LOG_FILENAME: &str = "audit.log";
+/// Name of the file, next to the audit log, holding the key its entries are authenticated with.
+pub const AUDIT_KEY_FILENAME: &str = "AUDIT_KEY";
+/// Name of the file, next to the audit log, recording its latest entry.
+pub const AUDIT_ANCHOR_FILENAME: &str = "AUDIT_ANCHOR";
+/// Size in bytes past which the audit log is rotated.
+pub const MAX_SIZE: u64 = 10 * 1024 * 1024;
+/// Number of rotated audit log files kept; older ones are removed when the log is rotated.
+pub const MAX_ROTATED_FILES: usize = 10;
+
+const ROTATED_SUFFIX_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+
+#[cfg(not(windows))]
+const AUDIT_KEY_PERMISSIONS: u32 = 0o600;
+
+lazy_static! {
+    static ref AUDIT_LOG: Mutex<Option<AuditLog>> = Mutex::new(None);
+}
+
+/// A short description of a request's payload for the audit log. Anything which may be secret,
+/// such as configuration or file contents, is left out.
+pub trait AuditSummary {
+    fn audit_summary(&self) -> String;
+}
+
+impl AuditSummary for protocol::ctl::SvcLoad {
+    fn audit_summary(&self) -> String {
+        format!(
+            "ident={} group={} force={}",
+            display_or_none(&self.ident),
+            display_or_none(&self.group),
+            self.force.unwrap_or(false)
+        )
+    }
+}
+
+impl AuditSummary for protocol::ctl::SvcApply {
+    fn audit_summary(&self) -> String {
+        format!(
+            "manifest=<{} bytes> prune={} dry_run={}",
+            self.manifest.as_ref().map_or(0, String::len),
+            self.prune.unwrap_or(false),
+            self.dry_run.unwrap_or(false)
+        )
+    }
+}
+
+impl AuditSummary for protocol::ctl::SvcSetCfg {
+    fn audit_summary(&self) -> String {
+        format!(
+            "service_group={} version={} cfg=<{} bytes redacted>",
+            display_or_none(&self.service_group),
+            display_or_none(&self.version),
+            self.cfg.as_ref().map_or(0, Vec::len)
+        )
+    }
+}
+
+impl AuditSummary for protocol::ctl::SvcSecretSet {
+    fn audit_summary(&self) -> String {
+        format!(
+            "service_group={} name={} value=<{} bytes redacted>",
+            display_or_none(&self.service_group),
+            display_or_none(&self.name),
+            self.value.as_ref().map_or(0, String::len)
+        )
+    }
+}
+
+impl AuditSummary for protocol::ctl::SvcFilePut {
+    fn audit_summary(&self) -> String {
+        format!(
+            "service_group={} filename={} version={} content=<{} bytes redacted>",
+            display_or_none(&self.service_group),
+            display_or_none(&self.filename),
+            display_or_none(&self.version),
+            self.content.as_ref().map_or(0, Vec::len)
+        )
+    }
+}
+
+impl AuditSummary for protocol::ctl::SupDepart {
+    fn audit_summary(&self) -> String {
+        format!("member_id={}", display_or_none(&self.member_id))
+    }
+}
+
+macro_rules! ident_summary {
+    ($($msg:ty),*) => {
+        $(
+            impl AuditSummary for $msg {
+                fn audit_summary(&self) -> String {
+                    format!("ident={}", display_or_none(&self.ident))
+                }
+            }
+        )*
+    };
+}
+
+ident_summary!(
+    protocol::ctl::SvcUnload,
+    protocol::ctl::SvcStart,
+    protocol::ctl::SvcStop,
+    protocol::ctl::SvcUpdatePause,
+    protocol::ctl::SvcUpdateResume,
+    protocol::ctl::SvcUpdateNow
+);
+
+fn display_or_none<T: ToString>(value: &Option<T>) -> String {
+    value
+        .as_ref()
+        .map_or_else(|| "<none>".to_string(), ToString::to_string)
+}
+
+/// Who made a request which changes the Supervisor, and what it was.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AuditRequest {
+    /// The name of the credential the request was authenticated with.
+    pub actor: String,
+    pub peer: Option<SocketAddr>,
+    /// The message id of the request, ex: `SvcLoad`.
+    pub request: String,
+    pub summary: String,
+}
+
+impl AuditRequest {
+    pub fn new<T>(actor: &str, peer: Option<SocketAddr>, msg: &T) -> Self
+    where
+        T: MessageStatic + AuditSummary,
+    {
+        AuditRequest {
+            actor: actor.to_string(),
+            peer: peer,
+            request: T::MESSAGE_ID.to_string(),
+            summary: msg.audit_summary(),
+        }
+    }
+
+    /// Describe a message received by the CtlGateway, if it is one which changes the Supervisor.
+    pub fn from_msg(actor: &str, peer: Option<SocketAddr>, msg: &SrvMessage) -> Option<Self> {
+        match msg.message_id() {
+            "SvcLoad" => Self::parse::<protocol::ctl::SvcLoad>(actor, peer, msg),
+            "SvcApply" => Self::parse::<protocol::ctl::SvcApply>(actor, peer, msg),
+            "SvcUnload" => Self::parse::<protocol::ctl::SvcUnload>(actor, peer, msg),
+            "SvcStart" => Self::parse::<protocol::ctl::SvcStart>(actor, peer, msg),
+            "SvcStop" => Self::parse::<protocol::ctl::SvcStop>(actor, peer, msg),
+            "SvcSetCfg" => Self::parse::<protocol::ctl::SvcSetCfg>(actor, peer, msg),
+            "SvcSecretSet" => Self::parse::<protocol::ctl::SvcSecretSet>(actor, peer, msg),
+            "SvcFilePut" => Self::parse::<protocol::ctl::SvcFilePut>(actor, peer, msg),
+            "SvcUpdatePause" => Self::parse::<protocol::ctl::SvcUpdatePause>(actor, peer, msg),
+            "SvcUpdateResume" => Self::parse::<protocol::ctl::SvcUpdateResume>(actor, peer, msg),
+            "SvcUpdateNow" => Self::parse::<protocol::ctl::SvcUpdateNow>(actor, peer, msg),
+            "SupDepart" => Self::parse::<protocol::ctl::SupDepart>(actor, peer, msg),
+            _ => None,
+        }
+    }
+
+    fn parse<T>(actor: &str, peer: Option<SocketAddr>, msg: &SrvMessage) -> Option<Self>
+    where
+        T: MessageStatic + AuditSummary + Message + Default,
+    {
+        msg.parse::<T>().ok().map(|m| Self::new(actor, peer, &m))
+    }
+}
+
+/// A single line of the audit log.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct AuditEntry {
+    /// Position of the entry in the log, counting from 1.
+    pub seq: u64,
+    pub timestamp: String,
+    pub actor: String,
+    pub peer: Option<String>,
+    pub request: String,
+    pub summary: String,
+    /// `ok` if the request succeeded, or `error` if it failed.
+    pub outcome: String,
+    pub error: Option<String>,
+    /// The `mac` of the previous entry, or an empty string for the first entry.
+    pub prev_mac: String,
+    /// An HMAC-SHA256 of the entry's content and `prev_mac`, keyed with the audit key.
+    pub mac: String,
+}
+
+impl AuditEntry {
+    fn new(
+        seq: u64,
+        prev_mac: String,
+        request: &AuditRequest,
+        result: &NetResult<()>,
+        key: &Key,
+    ) -> Self {
+        let mut entry = AuditEntry {
+            seq: seq,
+            timestamp: time::now_utc().rfc3339().to_string(),
+            actor: request.actor.clone(),
+            peer: request.peer.map(|p| p.to_string()),
+            request: request.request.clone(),
+            summary: request.summary.clone(),
+            outcome: if result.is_ok() { "ok" } else { "error" }.to_string(),
+            error: result.as_ref().err().map(|e| e.to_string()),
+            prev_mac: prev_mac,
+            mac: String::new(),
+        };
+        entry.mac = entry.content_mac(key);
+        entry
+    }
+
+    /// Time the entry was recorded, in seconds since the Unix epoch.
+    pub fn recorded_at(&self) -> Option<i64> {
+        time::strptime(&self.timestamp, "%Y-%m-%dT%H:%M:%SZ")
+            .ok()
+            .map(|tm| tm.to_timespec().sec)
+    }
+
+    fn content_mac(&self, key: &Key) -> String {
+        let content = serde_json::to_string(&(
+            self.seq,
+            &self.timestamp,
+            &self.actor,
+            &self.peer,
+            &self.request,
+            &self.summary,
+            &self.outcome,
+            &self.error,
+            &self.prev_mac,
+        ))
+        .expect("audit entry content is serializable");
+        hmacsha256::authenticate(content.as_bytes(), key)
+            .0
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect()
+    }
+}
+
+/// The sequence number and MAC of the latest entry of the audit log, kept outside of it.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct AuditAnchor {
+    pub seq: u64,
+    pub mac: String,
+}
+
+/// The audit log file, rotated by size.
+pub struct AuditLog {
+    path: PathBuf,
+    max_size: u64,
+    retain: usize,
+    file: File,
+    size: u64,
+    seq: u64,
+    last_mac: String,
+    /// Whether the anchor could be read when the log was opened, and so is kept up to date.
+    anchored: bool,
+    key: Key,
+}
+
+impl AuditLog {
+    /// Open (or append to) the audit log at `path`, keeping `retain` rotated files, and pick up
+    /// the chain where the last entry left off. If entries were removed from the end of the log,
+    /// the chain picks up from its anchor instead, so that the gap stays visible.
+    pub fn open<P>(path: P, max_size: u64, retain: usize) -> io::Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref().to_path_buf();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let key = load_key(&path)?;
+        let latest = last_entry(&path)?.map(|e| (e.seq, e.mac));
+        let (anchored, anchor) = match read_anchor(&path) {
+            Ok(anchor) => (true, anchor.map(|a| (a.seq, a.mac))),
+            Err(err) => {
+                warn!(
+                    "Unable to read the anchor of {}, leaving it unanchored: {}",
+                    path.display(),
+                    err
+                );
+                (false, None)
+            }
+        };
+        let (seq, last_mac) = match (latest, anchor) {
+            (Some(latest), Some(anchor)) => {
+                if anchor.0 > latest.0 {
+                    anchor
+                } else {
+                    latest
+                }
+            }
+            (latest, anchor) => latest.or(anchor).unwrap_or((0, String::new())),
+        };
+        let file = open_append(&path)?;
+        Ok(AuditLog {
+            size: file.metadata()?.len(),
+            path: path,
+            max_size: max_size,
+            retain: retain,
+            file: file,
+            seq: seq,
+            last_mac: last_mac,
+            anchored: anchored,
+            key: key,
+        })
+    }
+
+    /// Append an entry for a request and its outcome, rotating the file first if it is due.
+    pub fn append(
+        &mut self,
+        request: &AuditRequest,
+        result: &NetResult<()>,
+    ) -> io::Result<AuditEntry> {
+        let entry = AuditEntry::new(
+            self.seq + 1,
+            self.last_mac.clone(),
+            request,
+            result,
+            &self.key,
+        );
+        let mut line = serde_json::to_vec(&entry)?;
+        line.push(b'\n');
+        if self.size > 0 && self.size + line.len() as u64 > self.max_size {
+            self.rotate()?;
+        }
+        self.file.write_all(&line)?;
+        self.file.sync_data()?;
+        self.size += line.len() as u64;
+        self.seq = entry.seq;
+        self.last_mac = entry.mac.clone();
+        if self.anchored {
+            write_anchor(
+                &self.path,
+                &AuditAnchor {
+                    seq: entry.seq,
+                    mac: entry.mac.clone(),
+                },
+            )?;
+        }
+        Ok(entry)
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        let stamp = time::strftime(ROTATED_SUFFIX_FORMAT, &time::now_utc())
+            .expect("rotated audit log suffix format is valid");
+        // Numbered after the latest file rotated out within the same second, rather than after
+        // the first free name, so that it still sorts last once older ones have been pruned.
+        let rotated = match rotations(&self.path)?.pop() {
+            Some(((ref latest, n), _)) if *latest == stamp => {
+                PathBuf::from(format!("{}.{}-{}", self.path.display(), stamp, n + 1))
+            }
+            _ => PathBuf::from(format!("{}.{}", self.path.display(), stamp)),
+        };
+        debug!("Rotating {} to {}", self.path.display(), rotated.display());
+        fs::rename(&self.path, &rotated)?;
+        self.file = open_append(&self.path)?;
+        self.size = 0;
+        if let Err(err) = prune(&self.path, self.retain) {
+            error!(
+                "Unable to remove old audit logs of {}: {}",
+                self.path.display(),
+                err
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Returns the location of the audit log for the given Supervisor root.
+pub fn path<T>(sup_root: T) -> PathBuf
+where
+    T: AsRef<Path>,
+{
+    sup_root.as_ref().join("audit").join(AUDIT_LOG_FILENAME)
+}
+
+/// Start recording audited requests to the audit log in the given Supervisor root.
+pub fn init<T>(sup_root: T) -> io::Result<()>
+where
+    T: AsRef<Path>,
+{
+    let log = AuditLog::open(path(sup_root), MAX_SIZE, MAX_ROTATED_FILES)?;
+    outputln!("Recording audited requests to {}", log.path.display());
+    *AUDIT_LOG.lock().expect("Audit log lock is poisoned") = Some(log);
+    Ok(())
+}
+
+/// Record a request and its outcome in the audit log. This is a no-op until `init` is called.
+pub fn record(request: &AuditRequest, result: &NetResult<()>) {
+    let mut log = AUDIT_LOG.lock().expect("Audit log lock is poisoned");
+    if let Some(ref mut log) = *log {
+        if let Err(err) = log.append(request, result) {
+            error!(
+                "Unable to record {} request from {} in {}: {}",
+                request.request,
+                request.actor,
+                log.path.display(),
+                err
+            );
+        }
+    }
+}
+
+/// Read every entry of the audit log at `path`, including its rotated files, oldest first.
+pub fn read<P>(path: P) -> io::Result<Vec<AuditEntry>>
+where
+    P: AsRef<Path>,
+{
+    let mut entries = vec![];
+    for file in files(path.as_ref())? {
+        entries.extend(read_file(&file)?);
+    }
+    Ok(entries)
+}
+
+/// Read the latest entries of the audit log at `path` which `matches` accepts, oldest first.
+///
+/// Files are read newest first, and reading stops once `limit` matching entries have been found,
+/// or once a file reaches back before `since`, as older files can't hold entries recorded since.
+pub fn read_latest<P, F>(
+    path: P,
+    limit: Option<usize>,
+    since: Option<i64>,
+    mut matches: F,
+) -> io::Result<Vec<AuditEntry>>
+where
+    P: AsRef<Path>,
+    F: FnMut(&AuditEntry) -> bool,
+{
+    let mut found = vec![];
+    if limit == Some(0) {
+        return Ok(found);
+    }
+    for file in files(path.as_ref())?.iter().rev() {
+        let entries = read_file(file)?;
+        let reaches_since = since.map_or(false, |since| {
+            entries
+                .first()
+                .and_then(AuditEntry::recorded_at)
+                .map_or(false, |t| t < since)
+        });
+        for entry in entries.into_iter().rev() {
+            if matches(&entry) {
+                found.push(entry);
+                if limit.map_or(false, |limit| found.len() >= limit) {
+                    found.reverse();
+                    return Ok(found);
+                }
+            }
+        }
+        if reaches_since {
+            break;
+        }
+    }
+    found.reverse();
+    Ok(found)
+}
+
+/// Check that each entry matches its MAC under `key` and follows on from the entry before it,
+/// and that the log reaches at least as far as `anchor`. The first entry is trusted to follow on
+/// from older ones, since older rotated files may have been archived elsewhere.
+pub fn verify(
+    entries: &[AuditEntry],
+    key: &Key,
+    anchor: Option<&AuditAnchor>,
+) -> result::Result<(), String> {
+    let mut previous: Option<&AuditEntry> = None;
+    for entry in entries {
+        if !crypto::secure_eq(&entry.mac, &entry.content_mac(key)) {
+            return Err(format!(
+                "audit log entry {} does not match its MAC",
+                entry.seq
+            ));
+        }
+        if let Some(previous) = previous {
+            if entry.seq != previous.seq + 1 || entry.prev_mac != previous.mac {
+                return Err(format!(
+                    "audit log entry {} does not follow entry {}",
+                    entry.seq, previous.seq
+                ));
+            }
+        }
+        previous = Some(entry);
+    }
+    if let Some(anchor) = anchor {
+        // Entries past the anchor may have been written just before the Supervisor stopped,
+        // without their anchor; their MACs vouch for them.
+        match entries.iter().find(|e| e.seq == anchor.seq) {
+            Some(entry) if entry.mac == anchor.mac => (),
+            Some(_) => {
+                return Err(format!(
+                    "audit log entry {} does not match the anchor",
+                    anchor.seq
+                ));
+            }
+            None if entries.last().map_or(true, |e| e.seq < anchor.seq) => {
+                return Err(format!(
+                    "audit log ends before entry {}, its latest recorded entry; it was truncated",
+                    anchor.seq
+                ));
+            }
+            None => (),
+        }
+    }
+    Ok(())
+}
+
+/// Read the key which authenticates the entries of the audit log at `path`, generating it if
+/// there isn't one yet.
+pub fn load_key(path: &Path) -> io::Result<Key> {
+    let key_path = sibling(path, AUDIT_KEY_FILENAME);
+    match fs::read(&key_path) {
+        Ok(bytes) => Key::from_slice(&bytes).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{} is not a valid audit key", key_path.display()),
+            )
+        }),
+        Err(ref err) if err.kind() == io::ErrorKind::NotFound => {
+            let key = hmacsha256::gen_key();
+            let mut file = File::create(&key_path)?;
+            set_permissions(&key_path)
+                .map_err(|e| io::Error::new(io::ErrorKind::PermissionDenied, e.to_string()))?;
+            file.write_all(&key.0)?;
+            file.sync_all()?;
+            Ok(key)
+        }
+        Err(err) => Err(err),
+    }
+}
+
+/// Read the anchor of the audit log at `path`, if it has one yet.
+pub fn read_anchor(path: &Path) -> io::Result<Option<AuditAnchor>> {
+    let anchor_path = sibling(path, AUDIT_ANCHOR_FILENAME);
+    match fs::read(&anchor_path) {
+        Ok(bytes) => serde_json::from_slice(&bytes).map(Some).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{}: {}", anchor_path.display(), e),
+            )
+        }),
+        Err(ref err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+fn write_anchor(path: &Path, anchor: &AuditAnchor) -> io::Result<()> {
+    let anchor_path = sibling(path, AUDIT_ANCHOR_FILENAME);
+    let tmp_path = anchor_path.with_extension("tmp");
+    {
+        let mut file = File::create(&tmp_path)?;
+        serde_json::to_writer(&mut file, anchor)?;
+        file.sync_all()?;
+    }
+    fs::rename(&tmp_path, &anchor_path)
+}
+
+/// The file named `name` in the directory of the audit log at `path`.
+fn sibling(path: &Path, name: &str) -> PathBuf {
+    path.parent()
+        .map_or_else(|| PathBuf::from(name), |dir| dir.join(name))
+}
+
+#[cfg(not(windows))]
+fn set_permissions(path: &Path) -> hcore::error::Result<()> {
+    use crate::hcore::util::posix_perm;
+
+    posix_perm::set_permissions(path, AUDIT_KEY_PERMISSIONS)
+}
+
+#[cfg(windows)]
+fn set_permissions(path: &Path) -> hcore::error::Result<()> {
+    use crate::hcore::util::win_perm;
+
+    win_perm::harden_path(path)
+}
+
+/// The rotated files of the audit log at `path`, oldest first, followed by `path` itself if it
+/// exists.
+fn files(path: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut files = rotated_files(path)?;
+    if path.exists() {
+        files.push(path.to_path_buf());
+    }
+    Ok(files)
+}
+
+/// The rotated files of the audit log at `path`, oldest first.
+fn rotated_files(path: &Path) -> io::Result<Vec<PathBuf>> {
+    Ok(rotations(path)?.into_iter().map(|(_, path)| path).collect())
+}
+
+/// The rotated files of the audit log at `path` along with their `rotation_order`, oldest first.
+fn rotations(path: &Path) -> io::Result<Vec<((String, u64), PathBuf)>> {
+    let dir = match path.parent() {
+        Some(dir) if dir.exists() => dir,
+        _ => return Ok(vec![]),
+    };
+    let prefix = format!(
+        "{}.",
+        path.file_name()
+            .map(|f| f.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    );
+    let mut rotated = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if name.starts_with(&prefix) {
+                Some((rotation_order(&name[prefix.len()..]), entry.path()))
+            } else {
+                None
+            }
+        })
+        .collect::<Vec<_>>();
+    rotated.sort();
+    Ok(rotated)
+}
+
+/// Sorts the suffixes of rotated files oldest first: by timestamp, then by the number added to
+/// tell apart files rotated out within the same second.
+fn rotation_order(suffix: &str) -> (String, u64) {
+    let mut parts = suffix.splitn(2, '-');
+    let stamp = parts.next().unwrap_or_default().to_string();
+    let n = parts.next().and_then(|n| n.parse().ok()).unwrap_or(0);
+    (stamp, n)
+}
+
+/// Remove the oldest files rotated out of the audit log at `path` beyond the number to retain.
+fn prune(path: &Path, retain: usize) -> io::Result<()> {
+    let rotated = rotated_files(path)?;
+    let excess = rotated.len().saturating_sub(retain);
+    for path in rotated.into_iter().take(excess) {
+        debug!("Removing old audit log {}", path.display());
+        fs::remove_file(path)?;
+    }
+    Ok(())
+}
+
+fn read_file(path: &Path) -> io::Result<Vec<AuditEntry>> {
+    let mut entries = vec![];
+    for (n, line) in BufReader::new(File::open(path)?).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entry = serde_json::from_str(&line).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{}, line {}: {}", path.display(), n + 1, e),
+            )
+        })?;
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+fn last_entry(path: &Path) -> io::Result<Option<AuditEntry>> {
+    for file in files(path)?.iter().rev() {
+        if let Some(entry) = read_file(file)?.pop() {
+            return Ok(Some(entry));
+        }
+    }
+    Ok(None)
+}
+
+fn open_append(path: &Path) -> io::Result<File> {
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tempfile::TempDir;
+
+    use crate::protocol::net::{self, ErrCode};
+
+    fn request(n: u64) -> AuditRequest {
+        let mut msg = protocol::ctl::SupDepart::default();
+        msg.member_id = Some(format!("member-{}", n));
+        AuditRequest::new("deploy-bot", "127.0.0.1:9632".parse().ok(), &msg)
+    }
+
+    fn check(path: &Path) -> result::Result<(), String> {
+        verify(
+            &read(path).unwrap(),
+            &load_key(path).unwrap(),
+            read_anchor(path).unwrap().as_ref(),
+        )
+    }
+
+    /// Drop the last line of the audit log at `path`.
+    fn truncate(path: &Path) {
+        let content = fs::read_to_string(path).unwrap();
+        let mut lines: Vec<&str> = content.lines().collect();
+        lines.pop();
+        fs::write(path, format!("{}\n", lines.join("\n"))).unwrap();
+    }
+
+    #[test]
+    fn entries_are_chained() {
+        let tmpdir = TempDir::new().expect("Could not create tempdir");
+        let path = tmpdir.path().join(AUDIT_LOG_FILENAME);
+        let mut log = AuditLog::open(&path, MAX_SIZE, MAX_ROTATED_FILES).unwrap();
+        log.append(&request(1), &Ok(())).unwrap();
+        log.append(
+            &request(2),
+            &Err(net::err(ErrCode::Unauthorized, "not allowed")),
+        )
+        .unwrap();
+
+        let entries = read(&path).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].seq, 1);
+        assert_eq!(entries[0].prev_mac, "");
+        assert_eq!(entries[0].request, "SupDepart");
+        assert_eq!(entries[0].summary, "member_id=member-1");
+        assert_eq!(entries[0].outcome, "ok");
+        assert_eq!(entries[1].prev_mac, entries[0].mac);
+        assert_eq!(entries[1].outcome, "error");
+        assert!(check(&path).is_ok());
+    }
+
+    #[test]
+    fn tampering_breaks_the_chain() {
+        let tmpdir = TempDir::new().expect("Could not create tempdir");
+        let path = tmpdir.path().join(AUDIT_LOG_FILENAME);
+        let mut log = AuditLog::open(&path, MAX_SIZE, MAX_ROTATED_FILES).unwrap();
+        for n in 1..4 {
+            log.append(&request(n), &Ok(())).unwrap();
+        }
+        let entries = read(&path).unwrap();
+        let key = load_key(&path).unwrap();
+
+        let mut edited = entries.clone();
+        edited[1].actor = "someone-else".to_string();
+        assert!(verify(&edited, &key, None).is_err());
+
+        let mut removed = entries.clone();
+        removed.remove(1);
+        assert!(verify(&removed, &key, None).is_err());
+    }
+
+    #[test]
+    fn the_chain_cannot_be_recomputed_without_the_key() {
+        let tmpdir = TempDir::new().expect("Could not create tempdir");
+        let path = tmpdir.path().join(AUDIT_LOG_FILENAME);
+        let mut log = AuditLog::open(&path, MAX_SIZE, MAX_ROTATED_FILES).unwrap();
+        log.append(&request(1), &Ok(())).unwrap();
+
+        let mut forged = read(&path).unwrap();
+        forged[0].actor = "someone-else".to_string();
+        forged[0].mac = forged[0].content_mac(&hmacsha256::gen_key());
+        assert!(verify(&forged, &load_key(&path).unwrap(), None).is_err());
+    }
+
+    #[test]
+    fn the_key_is_kept_between_runs() {
+        let tmpdir = TempDir::new().expect("Could not create tempdir");
+        let path = tmpdir.path().join(AUDIT_LOG_FILENAME);
+        AuditLog::open(&path, MAX_SIZE, MAX_ROTATED_FILES)
+            .unwrap()
+            .append(&request(1), &Ok(()))
+            .unwrap();
+
+        AuditLog::open(&path, MAX_SIZE, MAX_ROTATED_FILES)
+            .unwrap()
+            .append(&request(2), &Ok(()))
+            .unwrap();
+        assert!(check(&path).is_ok());
+    }
+
+    #[test]
+    fn truncation_is_detected_by_the_anchor() {
+        let tmpdir = TempDir::new().expect("Could not create tempdir");
+        let path = tmpdir.path().join(AUDIT_LOG_FILENAME);
+        let mut log = AuditLog::open(&path, MAX_SIZE, MAX_ROTATED_FILES).unwrap();
+        for n in 1..4 {
+            log.append(&request(n), &Ok(())).unwrap();
+        }
+        drop(log);
+        assert_eq!(read_anchor(&path).unwrap().map(|a| a.seq), Some(3));
+
+        truncate(&path);
+        assert!(verify(&read(&path).unwrap(), &load_key(&path).unwrap(), None).is_ok());
+        assert!(check(&path).is_err());
+
+        // The gap is still there once the Supervisor carries on appending.
+        AuditLog::open(&path, MAX_SIZE, MAX_ROTATED_FILES)
+            .unwrap()
+            .append(&request(4), &Ok(()))
+            .unwrap();
+        assert_eq!(read(&path).unwrap().last().map(|e| e.seq), Some(4));
+        assert!(check(&path).is_err());
+    }
+
+    #[test]
+    fn entries_past_the_anchor_are_accepted() {
+        let tmpdir = TempDir::new().expect("Could not create tempdir");
+        let path = tmpdir.path().join(AUDIT_LOG_FILENAME);
+        let mut log = AuditLog::open(&path, MAX_SIZE, MAX_ROTATED_FILES).unwrap();
+        log.append(&request(1), &Ok(())).unwrap();
+        let anchor = read_anchor(&path).unwrap();
+        log.append(&request(2), &Ok(())).unwrap();
+
+        let entries = read(&path).unwrap();
+        let key = load_key(&path).unwrap();
+        assert!(verify(&entries, &key, anchor.as_ref()).is_ok());
+    }
+
+    #[test]
+    fn chain_continues_across_rotation_and_reopening() {
+        let tmpdir = TempDir::new().expect("Could not create tempdir");
+        let path = tmpdir.path().join(AUDIT_LOG_FILENAME);
+        // Every entry is bigger than this, so each append rotates the previous one out.
+        let mut log = AuditLog::open(&path, 1, MAX_ROTATED_FILES).unwrap();
+        for n in 1..4 {
+            log.append(&request(n), &Ok(())).unwrap();
+        }
+        drop(log);
+
+        let mut log = AuditLog::open(&path, MAX_SIZE, MAX_ROTATED_FILES).unwrap();
+        log.append(&request(4), &Ok(())).unwrap();
+
+        assert_eq!(files(&path).unwrap().len(), 3);
+        let entries = read(&path).unwrap();
+        assert_eq!(
+            entries.iter().map(|e| e.seq).collect::<Vec<_>>(),
+            vec![1, 2, 3, 4]
+        );
+        assert!(check(&path).is_ok());
+    }
+
+    #[test]
+    fn rotated_files_are_pruned() {
+        let tmpdir = TempDir::new().expect("Could not create tempdir");
+        let path = tmpdir.path().join(AUDIT_LOG_FILENAME);
+        let mut log = AuditLog::open(&path, 1, 2).unwrap();
+        for n in 1..6 {
+            log.append(&request(n), &Ok(())).unwrap();
+        }
+
+        assert_eq!(rotated_files(&path).unwrap().len(), 2);
+        assert_eq!(
+            read(&path)
+                .unwrap()
+                .iter()
+                .map(|e| e.seq)
+                .collect::<Vec<_>>(),
+            vec![3, 4, 5]
+        );
+        assert!(check(&path).is_ok());
+    }
+
+    #[test]
+    fn read_latest_stops_at_the_limit() {
+        let tmpdir = TempDir::new().expect("Could not create tempdir");
+        let path = tmpdir.path().join(AUDIT_LOG_FILENAME);
+        let mut log = AuditLog::open(&path, 1, MAX_ROTATED_FILES).unwrap();
+        for n in 1..5 {
+            log.append(&request(n), &Ok(())).unwrap();
+        }
+        // An unreadable older file shows that it was never read.
+        fs::write(&rotated_files(&path).unwrap()[0], "not json\n").unwrap();
+
+        let entries = read_latest(&path, Some(2), None, |_| true).unwrap();
+        assert_eq!(
+            entries.iter().map(|e| e.seq).collect::<Vec<_>>(),
+            vec![3, 4]
+        );
+        let entries = read_latest(&path, Some(1), None, |e| e.seq == 3).unwrap();
+        assert_eq!(entries.iter().map(|e| e.seq).collect::<Vec<_>>(), vec![3]);
+        assert!(read_latest(&path, None, None, |_| true).is_err());
+    }
+
+    #[test]
+    fn an_unreadable_anchor_is_left_for_verify_to_report() {
+        let tmpdir = TempDir::new().expect("Could not create tempdir");
+        let path = tmpdir.path().join(AUDIT_LOG_FILENAME);
+        AuditLog::open(&path, MAX_SIZE, MAX_ROTATED_FILES)
+            .unwrap()
+            .append(&request(1), &Ok(()))
+            .unwrap();
+        let anchor_path = sibling(&path, AUDIT_ANCHOR_FILENAME);
+        fs::write(&anchor_path, "not json").unwrap();
+
+        let mut log = AuditLog::open(&path, MAX_SIZE, MAX_ROTATED_FILES).unwrap();
+        log.append(&request(2), &Ok(())).unwrap();
+        assert_eq!(read(&path).unwrap().last().map(|e| e.seq), Some(2));
+        assert!(read_anchor(&path).is_err());
+        assert_eq!(fs::read_to_string(&anchor_path).unwrap(), "not json");
+    }
+
+    #[test]
+    fn summaries_leave_out_secrets() {
+        let mut msg = protocol::ctl::SvcSetCfg::default();
+        msg.cfg = Some(b"password = \"hunter2\"".to_vec());
+        msg.version = Some(3);
+        let summary = msg.audit_summary();
+        assert!(!summary.contains("hunter2"));
+        assert_eq!(
+            summary,
+            "service_group=<none> version=3 cfg=<20 bytes redacted>"
+        );
+
+        let mut msg = protocol::ctl::SvcSecretSet::default();
+        msg.name = Some("db_password".to_string());
+        msg.value = Some("hunter2".to_string());
+        let summary = msg.audit_summary();
+        assert!(!summary.contains("hunter2"));
+        assert_eq!(
+            summary,
+            "service_group=<none> name=db_password value=<7 bytes redacted>"
+        );
+    }
+}
@@ -13,7 +13,7 @@
 // limitations under the License.
 
 use std::borrow::Cow;
-use std::collections::{BTreeMap, HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
 use std::fmt;
 use std::result;
 use std::str::FromStr;
@@ -609,6 +609,7 @@ pub struct CensusMember {
     suspect: bool,
     confirmed: bool,
     departed: bool,
+    soaking: bool,
     // Maps must be represented last in a serializable struct for the current version of the toml
     // crate. Additionally, this deserialization method is required to correct any ordering issues
     // with the table being serialized - https://docs.rs/toml/0.4.0/toml/ser/fn.tables_last.html
@@ -633,6 +634,7 @@ impl CensusMember {
             Err(err) => warn!("Received a bad package ident from gossip data, err={}", err),
         };
         self.sys = rumor.sys.clone();
+        self.soaking = rumor.soaking;
         self.cfg = toml::from_slice(&rumor.cfg).unwrap_or_default();
     }
 
@@ -703,6 +705,27 @@ impl CensusMember {
     pub fn departed(&self) -> bool {
         self.departed
     }
+
+    /// Whether this member is a canary that runs an update the rest of its service group must
+    /// not follow yet.
+    pub fn soaking(&self) -> bool {
+        self.soaking
+    }
+
+    /// The member's health, if it is known yet.
+    fn health(&self) -> Option<Health> {
+        if self.alive {
+            Some(Health::Alive)
+        } else if self.suspect {
+            Some(Health::Suspect)
+        } else if self.confirmed {
+            Some(Health::Confirmed)
+        } else if self.departed {
+            Some(Health::Departed)
+        } else {
+            None
+        }
+    }
 }
 
 /// This data structure just wraps the CensusMember and allows us to tweak the serialization logic.
@@ -764,6 +787,186 @@ impl<'a> Serialize for CensusMemberProxy<'a> {
     }
 }
 
+/// A single change to a service group in the census, as streamed by the HTTP gateway's
+/// `/census/watch` endpoint.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "event", rename_all = "kebab-case")]
+pub enum CensusChange {
+    MemberJoined {
+        service_group: String,
+        member_id: MemberId,
+        health: Option<String>,
+    },
+    MemberLeft {
+        service_group: String,
+        member_id: MemberId,
+    },
+    HealthChanged {
+        service_group: String,
+        member_id: MemberId,
+        health: Option<String>,
+    },
+    LeaderChanged {
+        service_group: String,
+        leader_id: Option<MemberId>,
+    },
+    ConfigChanged {
+        service_group: String,
+        incarnation: u64,
+    },
+}
+
+impl CensusChange {
+    /// The name of the event, as used in the serialized change.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            CensusChange::MemberJoined { .. } => "member-joined",
+            CensusChange::MemberLeft { .. } => "member-left",
+            CensusChange::HealthChanged { .. } => "health-changed",
+            CensusChange::LeaderChanged { .. } => "leader-changed",
+            CensusChange::ConfigChanged { .. } => "config-changed",
+        }
+    }
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct GroupSnapshot {
+    leader_id: Option<MemberId>,
+    config_incarnation: Option<u64>,
+    members: BTreeMap<MemberId, Option<Health>>,
+}
+
+/// The parts of the census that changes are reported for, so that the census can be compared
+/// with itself over time.
+#[derive(Debug, Default)]
+pub struct CensusSnapshot(BTreeMap<String, GroupSnapshot>);
+
+impl CensusSnapshot {
+    pub fn new(ring: &CensusRing) -> Self {
+        let groups = ring
+            .census_groups
+            .iter()
+            .map(|(sg, group)| {
+                let snapshot = GroupSnapshot {
+                    leader_id: group.leader_id.clone(),
+                    config_incarnation: group.service_config.as_ref().map(|c| c.incarnation),
+                    members: group
+                        .population
+                        .iter()
+                        .map(|(id, member)| (id.clone(), member.health()))
+                        .collect(),
+                };
+                (sg.to_string(), snapshot)
+            })
+            .collect();
+        CensusSnapshot(groups)
+    }
+
+    /// The changes that lead from `previous` to this snapshot, grouped by service group.
+    pub fn changes_since(&self, previous: &CensusSnapshot) -> Vec<CensusChange> {
+        let empty = GroupSnapshot::default();
+        let mut changes = Vec::new();
+        let service_groups: HashSet<&String> = self.0.keys().chain(previous.0.keys()).collect();
+        let mut service_groups: Vec<&String> = service_groups.into_iter().collect();
+        service_groups.sort();
+
+        for sg in service_groups {
+            let old = previous.0.get(sg).unwrap_or(&empty);
+            let new = self.0.get(sg).unwrap_or(&empty);
+            for (id, health) in new.members.iter() {
+                match old.members.get(id) {
+                    None => changes.push(CensusChange::MemberJoined {
+                        service_group: sg.clone(),
+                        member_id: id.clone(),
+                        health: health.map(|h| h.to_string()),
+                    }),
+                    Some(old_health) if old_health != health => {
+                        changes.push(CensusChange::HealthChanged {
+                            service_group: sg.clone(),
+                            member_id: id.clone(),
+                            health: health.map(|h| h.to_string()),
+                        })
+                    }
+                    Some(_) => {}
+                }
+            }
+            for id in old
+                .members
+                .keys()
+                .filter(|id| !new.members.contains_key(*id))
+            {
+                changes.push(CensusChange::MemberLeft {
+                    service_group: sg.clone(),
+                    member_id: id.clone(),
+                });
+            }
+            if new.leader_id != old.leader_id {
+                changes.push(CensusChange::LeaderChanged {
+                    service_group: sg.clone(),
+                    leader_id: new.leader_id.clone(),
+                });
+            }
+            if let Some(incarnation) = new.config_incarnation {
+                if old.config_incarnation != Some(incarnation) {
+                    changes.push(CensusChange::ConfigChanged {
+                        service_group: sg.clone(),
+                        incarnation: incarnation,
+                    });
+                }
+            }
+        }
+        changes
+    }
+}
+
+/// The number of changes kept for watchers that fall behind.
+const CHANGE_LOG_CAPACITY: usize = 1024;
+
+/// The most recent census changes. Each change is numbered by a cursor, one greater than the
+/// change before it, which watchers use to pick up where they left off.
+#[derive(Debug, Default)]
+pub struct CensusChangeLog {
+    latest: u64,
+    changes: VecDeque<CensusChange>,
+}
+
+impl CensusChangeLog {
+    /// The cursor of the latest change, or 0 if nothing has changed yet.
+    pub fn latest(&self) -> u64 {
+        self.latest
+    }
+
+    pub fn extend<I>(&mut self, changes: I)
+    where
+        I: IntoIterator<Item = CensusChange>,
+    {
+        for change in changes {
+            if self.changes.len() == CHANGE_LOG_CAPACITY {
+                self.changes.pop_front();
+            }
+            self.changes.push_back(change);
+            self.latest += 1;
+        }
+    }
+
+    /// The changes made after the given cursor, along with their cursors. Returns `None` if some
+    /// of those changes are no longer kept, or if the cursor is ahead of the latest change (it
+    /// was handed out by a previous Supervisor), in which case the census has to be read again.
+    pub fn since(&self, cursor: u64) -> Option<Vec<(u64, CensusChange)>> {
+        let first = self.latest + 1 - self.changes.len() as u64;
+        if cursor > self.latest || cursor.saturating_add(1) < first {
+            return None;
+        }
+        Some(
+            (first..)
+                .zip(self.changes.iter())
+                .filter(|&(c, _)| c > cursor)
+                .map(|(c, change)| (c, change.clone()))
+                .collect(),
+        )
+    }
+}
+
 fn service_group_from_str(sg: &str) -> Result<ServiceGroup, hcore::Error> {
     ServiceGroup::from_str(sg).map_err(|e| {
         outputln!(
@@ -908,6 +1111,118 @@ mod tests {
         (ring, sg_one, sg_two)
     }
 
+    #[test]
+    fn census_snapshot_changes() {
+        let (ring, _, _) = test_census_ring();
+        let snapshot = CensusSnapshot::new(&ring);
+        assert!(snapshot
+            .changes_since(&CensusSnapshot::new(&ring))
+            .is_empty());
+
+        let changes = snapshot.changes_since(&CensusSnapshot::default());
+        assert_eq!(
+            changes,
+            vec![
+                CensusChange::MemberJoined {
+                    service_group: "shield.one".to_string(),
+                    member_id: "member-a".to_string(),
+                    health: None,
+                },
+                CensusChange::LeaderChanged {
+                    service_group: "shield.one".to_string(),
+                    leader_id: Some("member-a".to_string()),
+                },
+                CensusChange::MemberJoined {
+                    service_group: "shield.two".to_string(),
+                    member_id: "member-a".to_string(),
+                    health: None,
+                },
+                CensusChange::MemberJoined {
+                    service_group: "shield.two".to_string(),
+                    member_id: "member-b".to_string(),
+                    health: None,
+                },
+            ]
+        );
+
+        let mut changed = CensusSnapshot::new(&ring);
+        {
+            let group = changed.0.get_mut("shield.two").unwrap();
+            group.members.remove("member-a");
+            group
+                .members
+                .insert("member-b".to_string(), Some(Health::Suspect));
+            group.config_incarnation = Some(2);
+        }
+        assert_eq!(
+            changed.changes_since(&snapshot),
+            vec![
+                CensusChange::HealthChanged {
+                    service_group: "shield.two".to_string(),
+                    member_id: "member-b".to_string(),
+                    health: Some("suspect".to_string()),
+                },
+                CensusChange::MemberLeft {
+                    service_group: "shield.two".to_string(),
+                    member_id: "member-a".to_string(),
+                },
+                CensusChange::ConfigChanged {
+                    service_group: "shield.two".to_string(),
+                    incarnation: 2,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn census_change_serialization() {
+        let change = CensusChange::LeaderChanged {
+            service_group: "shield.one".to_string(),
+            leader_id: None,
+        };
+        assert_eq!(
+            serde_json::to_string(&change).unwrap(),
+            r#"{"event":"leader-changed","service_group":"shield.one","leader_id":null}"#
+        );
+        assert_eq!(change.name(), "leader-changed");
+    }
+
+    #[test]
+    fn census_change_log_cursors() {
+        let change = |n: u64| CensusChange::ConfigChanged {
+            service_group: "shield.one".to_string(),
+            incarnation: n,
+        };
+        let mut log = CensusChangeLog::default();
+        assert_eq!(log.latest(), 0);
+        assert_eq!(log.since(0), Some(vec![]));
+
+        log.extend(vec![change(1), change(2)]);
+        assert_eq!(log.latest(), 2);
+        assert_eq!(log.since(0), Some(vec![(1, change(1)), (2, change(2))]));
+        assert_eq!(log.since(1), Some(vec![(2, change(2))]));
+        assert_eq!(log.since(2), Some(vec![]));
+
+        log.extend((3..=CHANGE_LOG_CAPACITY as u64 + 2).map(change));
+        assert_eq!(log.since(0), None);
+        assert_eq!(log.since(1).unwrap().len(), CHANGE_LOG_CAPACITY);
+    }
+
+    #[test]
+    fn census_change_log_cursors_ahead_of_the_latest_change() {
+        let change = |n: u64| CensusChange::ConfigChanged {
+            service_group: "shield.one".to_string(),
+            incarnation: n,
+        };
+        let mut log = CensusChangeLog::default();
+        assert_eq!(log.since(1), None);
+
+        log.extend(vec![change(1), change(2)]);
+        assert_eq!(log.since(2), Some(vec![]));
+        assert_eq!(log.since(3), None);
+        assert_eq!(log.since(u64::max_value()), None);
+    }
+
     /// Create a bare-minimum CensusMember with the given Health
     fn test_census_member(id: &str, health: Health) -> CensusMember {
         CensusMember {
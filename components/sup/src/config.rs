@@ -18,21 +18,32 @@
 //! [command](../command) modules. Check out the `config_from_args(..)` function there for more
 //! details.
 //!
-//! See the [Config](struct.Config.html) struct for the specific options available.
+//! See the [Config](struct.Config.html) struct for the specific options available, and the
+//! [ConfigFile](struct.ConfigFile.html) struct for the ones which may also be given in a file.
 
 use std::fmt;
+use std::fs;
 use std::io;
 use std::net::{IpAddr, Ipv4Addr, SocketAddr, SocketAddrV4, ToSocketAddrs};
 use std::ops::{Deref, DerefMut};
 use std::option;
+use std::path::{Path, PathBuf};
 use std::result;
 use std::str::FromStr;
 
+use serde::{de, Deserialize, Deserializer};
+use toml;
+use url::Url;
+
 use crate::common::{
     cli_defaults::{GOSSIP_DEFAULT_IP, GOSSIP_DEFAULT_PORT, GOSSIP_LISTEN_ADDRESS_ENVVAR},
-    types::EnvConfig,
+    types::{EnvConfig, ListenCtlAddr},
 };
-use crate::error::{Result, SupError};
+use crate::error::{Error, Result, SupError};
+use crate::http_gateway;
+use crate::protocol::types::UpdateWindow;
+
+static LOGKEY: &'static str = "CF";
 
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub struct GossipListenAddr(SocketAddr);
@@ -106,6 +117,121 @@ impl fmt::Display for GossipListenAddr {
     }
 }
 
+/// The settings for `hab sup run` which may be read from a TOML file with `--config`. Each key
+/// is named after the corresponding flag. Any setting given as a flag or in the environment takes
+/// precedence over the file.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ConfigFile {
+    #[serde(deserialize_with = "deserialize_option_using_from_str")]
+    pub listen_gossip: Option<GossipListenAddr>,
+    #[serde(deserialize_with = "deserialize_option_using_from_str")]
+    pub listen_http: Option<http_gateway::ListenAddr>,
+    pub http_disable: Option<bool>,
+    #[serde(deserialize_with = "deserialize_option_using_from_str")]
+    pub listen_ctl: Option<ListenCtlAddr>,
+    pub organization: Option<String>,
+    pub peers: Vec<String>,
+    pub permanent_peer: Option<bool>,
+    pub peer_watch_file: Option<String>,
+    pub ring: Option<String>,
+    pub channel: Option<String>,
+    pub bldr_url: Option<String>,
+    pub auto_update: Option<bool>,
+    #[serde(deserialize_with = "deserialize_vec_using_from_str")]
+    pub auto_update_windows: Vec<UpdateWindow>,
+    pub auto_update_splay: Option<u64>,
+    pub key_file: Option<PathBuf>,
+    pub cert_file: Option<PathBuf>,
+    pub ctl_server_key: Option<PathBuf>,
+    pub ctl_server_certificate: Option<PathBuf>,
+    pub ctl_client_ca_certificate: Option<PathBuf>,
+    pub event_stream_file: Option<PathBuf>,
+    pub event_stream_socket: Option<PathBuf>,
+}
+
+impl ConfigFile {
+    /// Read the config file at `path`, failing if it can't be parsed or its settings are invalid.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let contents = fs::read_to_string(path)
+            .map_err(|e| sup_error!(Error::ConfigFileIO(path.to_path_buf(), e)))?;
+        let cfg = Self::from_toml(&contents)
+            .map_err(|e| sup_error!(Error::ConfigFileParse(path.to_path_buf(), e)))?;
+        cfg.validate()
+            .map_err(|e| sup_error!(Error::InvalidConfigFile(path.to_path_buf(), e)))?;
+        Ok(cfg)
+    }
+
+    fn from_toml(contents: &str) -> result::Result<Self, toml::de::Error> {
+        toml::from_str(contents)
+    }
+
+    /// Apply the checks which `hab sup run` makes of the corresponding flags.
+    fn validate(&self) -> result::Result<(), String> {
+        if let Some(ref url) = self.bldr_url {
+            if Url::parse(url).is_err() {
+                return Err(format!("bldr_url: '{}' is not a valid URL", url));
+            }
+        }
+        if !self.peers.is_empty() && self.peer_watch_file.is_some() {
+            return Err("peers and peer_watch_file can't both be set".to_string());
+        }
+        if self.key_file.is_some() != self.cert_file.is_some() {
+            return Err("key_file and cert_file must be set together".to_string());
+        }
+        let ctl_tls_files = [
+            &self.ctl_server_key,
+            &self.ctl_server_certificate,
+            &self.ctl_client_ca_certificate,
+        ];
+        if ctl_tls_files.iter().any(|f| f.is_some()) && ctl_tls_files.iter().any(|f| f.is_none()) {
+            return Err("ctl_server_key, ctl_server_certificate, and \
+                        ctl_client_ca_certificate must be set together"
+                .to_string());
+        }
+        let files = [
+            ("key_file", &self.key_file),
+            ("cert_file", &self.cert_file),
+            ("ctl_server_key", &self.ctl_server_key),
+            ("ctl_server_certificate", &self.ctl_server_certificate),
+            ("ctl_client_ca_certificate", &self.ctl_client_ca_certificate),
+        ];
+        for (key, file) in files.iter() {
+            if let Some(file) = file {
+                if !file.is_file() {
+                    return Err(format!("{}: '{}' cannot be found", key, file.display()));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn deserialize_option_using_from_str<'de, T, D>(d: D) -> result::Result<Option<T>, D::Error>
+where
+    T: FromStr,
+    T::Err: fmt::Display,
+    D: Deserializer<'de>,
+{
+    match Option::<String>::deserialize(d)? {
+        Some(s) => s.parse().map(Some).map_err(de::Error::custom),
+        None => Ok(None),
+    }
+}
+
+fn deserialize_vec_using_from_str<'de, T, D>(d: D) -> result::Result<Vec<T>, D::Error>
+where
+    T: FromStr,
+    T::Err: fmt::Display,
+    D: Deserializer<'de>,
+{
+    Vec::<String>::deserialize(d)?
+        .iter()
+        .map(|s| s.parse().map_err(de::Error::custom))
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -188,4 +314,62 @@ mod tests {
             assert_eq!(Thingie::configured_value(), Thingie::default());
         }
     }
+
+    mod config_file {
+        use super::*;
+
+        #[test]
+        fn settings_are_parsed() {
+            let cfg = ConfigFile::from_toml(
+                r#"
+                listen_gossip = "0.0.0.0:9638"
+                listen_ctl = "0.0.0.0:9632"
+                organization = "acme"
+                peers = ["10.0.0.1", "10.0.0.2:9638"]
+                auto_update = true
+                auto_update_windows = ["sat,sun 01:00-05:00"]
+                "#,
+            )
+            .expect("config file should parse");
+            assert_eq!(
+                cfg.listen_gossip,
+                Some("0.0.0.0:9638".parse::<GossipListenAddr>().unwrap())
+            );
+            assert_eq!(cfg.listen_ctl, Some("0.0.0.0:9632".parse().unwrap()));
+            assert_eq!(cfg.organization, Some("acme".to_string()));
+            assert_eq!(cfg.peers, vec!["10.0.0.1", "10.0.0.2:9638"]);
+            assert_eq!(cfg.auto_update, Some(true));
+            assert_eq!(
+                cfg.auto_update_windows,
+                vec!["sat,sun 01:00-05:00".parse().unwrap()]
+            );
+            assert!(cfg.listen_http.is_none());
+            assert!(cfg.validate().is_ok());
+        }
+
+        #[test]
+        fn unknown_settings_are_rejected() {
+            assert!(ConfigFile::from_toml("listen_gossipp = \"0.0.0.0:9638\"").is_err());
+        }
+
+        #[test]
+        fn invalid_values_are_rejected() {
+            assert!(ConfigFile::from_toml("listen_gossip = \"not an address\"").is_err());
+            assert!(ConfigFile::from_toml("auto_update_windows = [\"someday\"]").is_err());
+        }
+
+        #[test]
+        fn key_file_requires_cert_file() {
+            let cfg = ConfigFile::from_toml("key_file = \"/nonexistent/key.pem\"").unwrap();
+            assert!(cfg.validate().is_err());
+        }
+
+        #[test]
+        fn peers_conflict_with_peer_watch_file() {
+            let cfg =
+                ConfigFile::from_toml("peers = [\"10.0.0.1\"]\npeer_watch_file = \"/hab/peers\"")
+                    .unwrap();
+            assert!(cfg.validate().is_err());
+        }
+    }
 }
@@ -26,7 +26,7 @@ use std::borrow::Cow;
 use std::fmt;
 use std::fs::{self, File};
 use std::io::{self, Write};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use regex::Regex;
 
@@ -34,6 +34,7 @@ use crate::api_client::DisplayProgress;
 use crate::common::ui::UIWriter;
 use crate::hcore::{self, output};
 use crate::protocol;
+use crate::protocol::ctl::{CtlCredential, CtlScope};
 use futures::prelude::*;
 
 use crate::error::{Error, Result};
@@ -48,12 +49,25 @@ lazy_static! {
 /// Time to wait in milliseconds for a client connection to timeout.
 pub const REQ_TIMEOUT: u64 = 10_000;
 static LOGKEY: &'static str = "AG";
+/// Name of the credential holding the Supervisor's own secret key.
+const CTL_SECRET_CREDENTIAL_NAME: &str = "ctl-secret";
 
 /// The control gateway secret should only be readable by the
 /// Supervisor process
 #[cfg(not(windows))]
 pub const CTL_SECRET_PERMISSIONS: u32 = 0o600;
 
+/// The PEM-encoded files the CtlGateway is configured with to accept clients over TLS.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CtlTlsFiles {
+    /// The private key of the CtlGateway's certificate.
+    pub key: PathBuf,
+    /// The CtlGateway's certificate chain.
+    pub cert: PathBuf,
+    /// The certificate of the CA which issues client certificates.
+    pub ca_cert: PathBuf,
+}
+
 /// Used by modules outside of the CtlGateway for seamlessly replying to transactional messages.
 /// This type is used in functions which can be called by the CtlGateway such as
 /// [`Manager::service_load`] and [`Manager::service_unload`].
@@ -94,6 +108,15 @@ impl CtlRequest {
         self.send_msg(msg, false);
     }
 
+    /// Like `reply_partial`, but returns false if the client has gone away and will never see
+    /// the message. Long-running replies use this to know when to stop.
+    pub fn try_reply_partial<T>(&mut self, msg: T) -> bool
+    where
+        T: Into<protocol::codec::SrvMessage> + fmt::Debug,
+    {
+        self.send_msg(msg, false)
+    }
+
     /// Reply to the transaction with the given message and indicate to the receiver that this is
     /// the final message for the transaction.
     pub fn reply_complete<T>(&mut self, msg: T)
@@ -108,7 +131,7 @@ impl CtlRequest {
         self.transaction.is_some() && self.tx.is_some()
     }
 
-    fn send_msg<T>(&mut self, msg: T, complete: bool)
+    fn send_msg<T>(&mut self, msg: T, complete: bool) -> bool
     where
         T: Into<protocol::codec::SrvMessage> + fmt::Debug,
     {
@@ -117,11 +140,12 @@ impl CtlRequest {
                 "Attempted to reply to a non-transactional message with {:?}",
                 msg
             );
-            return;
+            return false;
         }
         let mut wire: protocol::codec::SrvMessage = msg.into();
         wire.reply_for(self.transaction.unwrap(), complete);
-        self.tx.as_ref().unwrap().start_send(wire).ok(); // ignore Err return
+        // The send only fails once the client's connection has been dropped.
+        self.tx.as_ref().unwrap().start_send(wire).is_ok()
     }
 }
 
@@ -199,7 +223,7 @@ impl io::Write for CtlRequest {
         // that CtlRequest is sending output to two destinations with
         // different formatting requirements complicates things a bit.
         let maybe_stripped = if output::is_json() || !output::is_color() {
-            STRIP_ANSI_CODES.replace_all(&line, "")
+            strip_ansi_codes(&line)
         } else {
             Cow::Owned(line)
         };
@@ -215,6 +239,11 @@ impl io::Write for CtlRequest {
     }
 }
 
+/// Remove any ANSI color codes from a line of console output.
+pub fn strip_ansi_codes(line: &str) -> Cow<'_, str> {
+    STRIP_ANSI_CODES.replace_all(line, "")
+}
+
 /// A wrapper around a [`protocol.ctl.NetProgress`] and [`CtlRequest`]. This type implements
 /// traits for writing it's progress to the console.
 pub struct NetProgressBar {
@@ -279,6 +308,22 @@ where
     }
 }
 
+/// Reads every credential clients may authenticate with the `CtlGateway` with. The secret key,
+/// which is read or generated as in [`readgen_secret_key`], is allowed to make any request and is
+/// followed by the scoped credentials in the Supervisor's `CTL_CREDENTIALS` file.
+pub fn read_credentials<T>(sup_root: T) -> Result<Vec<CtlCredential>>
+where
+    T: AsRef<Path>,
+{
+    let mut credentials = vec![CtlCredential {
+        name: CTL_SECRET_CREDENTIAL_NAME.to_string(),
+        scope: CtlScope::Admin,
+        secret_key: readgen_secret_key(&sup_root)?,
+    }];
+    credentials.extend(protocol::read_credentials(&sup_root)?);
+    Ok(credentials)
+}
+
 #[cfg(not(windows))]
 fn set_permissions<T: AsRef<Path>>(path: T) -> hcore::error::Result<()> {
     use crate::hcore::util::posix_perm;
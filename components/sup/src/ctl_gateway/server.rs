@@ -28,23 +28,28 @@ use std::fmt;
 use std::io;
 use std::net::SocketAddr;
 use std::rc::Rc;
+use std::sync::Arc;
 use std::thread;
 use std::time::Duration;
 
 use crate::hcore::crypto;
 use crate::protocol;
 use crate::protocol::codec::*;
+use crate::protocol::ctl::{CtlCredential, CtlScope};
 use crate::protocol::net::{self, ErrCode, NetErr, NetResult};
 use futures::future::{self, Either};
 use futures::prelude::*;
 use futures::sync::mpsc;
 use prometheus::{HistogramTimer, HistogramVec, IntCounterVec};
 use prost;
-use tokio::net::TcpListener;
+use rustls::ServerConfig;
+use tokio::net::{TcpListener, TcpStream};
 use tokio_codec::Framed;
 use tokio_core::reactor;
+use tokio_rustls::TlsAcceptor;
 
 use super::{CtlRequest, REQ_TIMEOUT};
+use crate::audit::{self, AuditRequest};
 use crate::manager::{commands, ManagerState};
 
 lazy_static! {
@@ -133,6 +138,9 @@ impl From<mpsc::SendError<CtlCommand>> for HandlerError {
 /// A wrapper around a [`ctl_gateway.CtlRequest`] and a closure for the main thread to execute.
 pub struct CtlCommand {
     pub req: CtlRequest,
+    /// Set for requests which change the Supervisor, to be recorded in the audit log along with
+    /// their outcome.
+    pub audit: Option<AuditRequest>,
     // JW: This needs to be an `FnOnce<Box>` and not an `Fn<Box>` but right now there is no support
     // for boxing an FnOnce in stable Rust. There is a new type called `FnBox` which exists only on
     // nightly right now which accomplishes this but it won't stabilize because the Rust core team
@@ -152,9 +160,16 @@ impl CtlCommand {
         CtlCommand {
             fun: Box::new(fun),
             req: CtlRequest::new(tx, txn),
+            audit: None,
         }
     }
 
+    /// Record this command in the audit log once it has run.
+    pub fn audited(mut self, audit: Option<AuditRequest>) -> Self {
+        self.audit = audit;
+        self
+    }
+
     /// Run the contained closure with the given [`manager.ManagerState`].
     pub fn run(&mut self, state: &ManagerState) -> NetResult<()> {
         (self.fun)(state, &mut self.req)
@@ -164,6 +179,7 @@ impl CtlCommand {
 /// Server's client representation. Each new connection will allocate a new Client.
 struct Client {
     handle: reactor::Handle,
+    addr: SocketAddr,
     state: Rc<RefCell<SrvState>>,
 }
 
@@ -171,19 +187,23 @@ impl Client {
     /// Serve the client from the given framed socket stream.
     pub fn serve(self, socket: SrvStream) -> Box<dyn Future<Item = (), Error = HandlerError>> {
         let mgr_tx = self.state.borrow().mgr_tx.clone();
+        let addr = self.addr;
         Box::new(
             self.handshake(socket)
-                .and_then(|socket| SrvHandler::new(socket, mgr_tx)),
+                .and_then(move |(socket, credential)| {
+                    SrvHandler::new(socket, credential, addr, mgr_tx)
+                }),
         )
     }
 
     /// Initiate a handshake with the connected client before allowing future requests. A failed
-    /// handshake will close the connection.
+    /// handshake will close the connection, while a successful one resolves to the credential the
+    /// client authenticated with.
     fn handshake(
         &self,
         socket: SrvStream,
-    ) -> Box<dyn Future<Item = SrvStream, Error = HandlerError>> {
-        let secret_key = self.state.borrow().secret_key.to_string();
+    ) -> Box<dyn Future<Item = (SrvStream, CtlCredential), Error = HandlerError>> {
+        let credentials = self.state.borrow().credentials.clone();
         let handshake = socket
             .into_future()
             .map_err(|(err, _)| HandlerError::from(err))
@@ -210,7 +230,7 @@ impl Client {
                             Ok(decoded) => {
                                 trace!("Received handshake, {:?}", decoded);
                                 let decoded_key = decoded.secret_key.unwrap_or_default();
-                                Ok((m, crypto::secure_eq(decoded_key, secret_key), io))
+                                Ok((m, authenticate(&credentials, &decoded_key), io))
                             }
                             Err(err) => {
                                 warn!("Handshake error, {:?}", err);
@@ -222,8 +242,8 @@ impl Client {
                     },
                 )
             })
-            .and_then(|(msg, success, socket)| {
-                let mut reply = if success {
+            .and_then(|(msg, credential, socket)| {
+                let mut reply = if credential.is_some() {
                     SrvMessage::from(net::ok())
                 } else {
                     SrvMessage::from(net::err(ErrCode::Unauthorized, "secret key mismatch"))
@@ -232,14 +252,14 @@ impl Client {
                 socket
                     .send(reply)
                     .map_err(HandlerError::from)
-                    .and_then(move |io| Ok((io, success)))
+                    .and_then(move |io| Ok((io, credential)))
             });
         Box::new(
             handshake
                 .select2(self.timeout(REQ_TIMEOUT))
                 .then(|res| match res {
-                    Ok(Either::A(((io, true), _to))) => future::ok(io),
-                    Ok(Either::A(((_, false), _to))) => future::err(HandlerError::from(
+                    Ok(Either::A(((io, Some(credential)), _to))) => future::ok((io, credential)),
+                    Ok(Either::A(((_, None), _to))) => future::err(HandlerError::from(
                         io::Error::new(io::ErrorKind::ConnectionAborted, "handshake failed"),
                     )),
                     Ok(Either::B((_to, _hs))) => future::err(HandlerError::from(io::Error::new(
@@ -263,6 +283,8 @@ impl Client {
 #[must_use = "futures do nothing unless polled"]
 struct SrvHandler {
     io: SrvStream,
+    credential: CtlCredential,
+    addr: SocketAddr,
     state: SrvHandlerState,
     mgr_tx: MgrSender,
     rx: CtlReceiver,
@@ -271,10 +293,12 @@ struct SrvHandler {
 }
 
 impl SrvHandler {
-    fn new(io: SrvStream, mgr_tx: MgrSender) -> Self {
+    fn new(io: SrvStream, credential: CtlCredential, addr: SocketAddr, mgr_tx: MgrSender) -> Self {
         let (tx, rx) = mpsc::unbounded();
         SrvHandler {
             io: io,
+            credential: credential,
+            addr: addr,
             state: SrvHandlerState::Receiving,
             mgr_tx: mgr_tx,
             rx: rx,
@@ -301,6 +325,37 @@ impl Future for SrvHandler {
                         self.timer = Some(timer);
 
                         trace!("OnMessage, {}", msg.message_id());
+                        let audit =
+                            AuditRequest::from_msg(&self.credential.name, Some(self.addr), &msg);
+                        if !self.credential.scope.allows(msg.message_id()) {
+                            warn!(
+                                "Rejected {} request from ctl credential {} with scope {}",
+                                msg.message_id(),
+                                self.credential.name,
+                                self.credential.scope
+                            );
+                            let err = net::err(
+                                ErrCode::Unauthorized,
+                                format!(
+                                    "{} requests require the {} scope, credential {} has the {} \
+                                     scope",
+                                    msg.message_id(),
+                                    CtlScope::required_for(msg.message_id()),
+                                    self.credential.name,
+                                    self.credential.scope
+                                ),
+                            );
+                            if let Some(ref audit) = audit {
+                                audit::record(audit, &Err(err.clone()));
+                            }
+                            let mut req = CtlRequest::new(Some(self.tx.clone()), msg.transaction());
+                            if !req.transactional() {
+                                break;
+                            }
+                            req.reply_complete(err);
+                            self.state = SrvHandlerState::Sending;
+                            continue;
+                        }
                         let cmd = match msg.message_id() {
                             "SvcGetDefaultCfg" => {
                                 let m = msg
@@ -336,6 +391,18 @@ impl Future for SrvHandler {
                                     },
                                 )
                             }
+                            "SvcSecretSet" => {
+                                let m = msg
+                                    .parse::<protocol::ctl::SvcSecretSet>()
+                                    .map_err(HandlerError::from)?;
+                                CtlCommand::new(
+                                    Some(self.tx.clone()),
+                                    msg.transaction(),
+                                    move |state, req| {
+                                        commands::service_secret_set(state, req, m.clone())
+                                    },
+                                )
+                            }
                             "SvcValidateCfg" => {
                                 let m = msg
                                     .parse::<protocol::ctl::SvcValidateCfg>()
@@ -358,6 +425,18 @@ impl Future for SrvHandler {
                                     move |state, req| commands::service_load(state, req, m.clone()),
                                 )
                             }
+                            "SvcApply" => {
+                                let m = msg
+                                    .parse::<protocol::ctl::SvcApply>()
+                                    .map_err(HandlerError::from)?;
+                                CtlCommand::new(
+                                    Some(self.tx.clone()),
+                                    msg.transaction(),
+                                    move |state, req| {
+                                        commands::service_apply(state, req, m.clone())
+                                    },
+                                )
+                            }
                             "SvcUnload" => {
                                 let m = msg
                                     .parse::<protocol::ctl::SvcUnload>()
@@ -392,6 +471,42 @@ impl Future for SrvHandler {
                                     move |state, req| commands::service_stop(state, req, m.clone()),
                                 )
                             }
+                            "SvcUpdatePause" => {
+                                let m = msg
+                                    .parse::<protocol::ctl::SvcUpdatePause>()
+                                    .map_err(HandlerError::from)?;
+                                CtlCommand::new(
+                                    Some(self.tx.clone()),
+                                    msg.transaction(),
+                                    move |state, req| {
+                                        commands::service_update_pause(state, req, m.clone())
+                                    },
+                                )
+                            }
+                            "SvcUpdateResume" => {
+                                let m = msg
+                                    .parse::<protocol::ctl::SvcUpdateResume>()
+                                    .map_err(HandlerError::from)?;
+                                CtlCommand::new(
+                                    Some(self.tx.clone()),
+                                    msg.transaction(),
+                                    move |state, req| {
+                                        commands::service_update_resume(state, req, m.clone())
+                                    },
+                                )
+                            }
+                            "SvcUpdateNow" => {
+                                let m = msg
+                                    .parse::<protocol::ctl::SvcUpdateNow>()
+                                    .map_err(HandlerError::from)?;
+                                CtlCommand::new(
+                                    Some(self.tx.clone()),
+                                    msg.transaction(),
+                                    move |state, req| {
+                                        commands::service_update_now(state, req, m.clone())
+                                    },
+                                )
+                            }
                             "SvcStatus" => {
                                 let m = msg
                                     .parse::<protocol::ctl::SvcStatus>()
@@ -404,6 +519,16 @@ impl Future for SrvHandler {
                                     },
                                 )
                             }
+                            "SvcLogs" => {
+                                let m = msg
+                                    .parse::<protocol::ctl::SvcLogs>()
+                                    .map_err(HandlerError::from)?;
+                                CtlCommand::new(
+                                    Some(self.tx.clone()),
+                                    msg.transaction(),
+                                    move |state, req| commands::service_logs(state, req, m.clone()),
+                                )
+                            }
                             "SupDepart" => {
                                 let m = msg
                                     .parse::<protocol::ctl::SupDepart>()
@@ -416,11 +541,24 @@ impl Future for SrvHandler {
                                     },
                                 )
                             }
+                            "SupAudit" => {
+                                let m = msg
+                                    .parse::<protocol::ctl::SupAudit>()
+                                    .map_err(HandlerError::from)?;
+                                CtlCommand::new(
+                                    Some(self.tx.clone()),
+                                    msg.transaction(),
+                                    move |state, req| {
+                                        commands::supervisor_audit(state, req, m.clone())
+                                    },
+                                )
+                            }
                             _ => {
                                 warn!("Unhandled message, {}", msg.message_id());
                                 break;
                             }
-                        };
+                        }
+                        .audited(audit);
                         match self.mgr_tx.start_send(cmd) {
                             Ok(AsyncSink::Ready) => {
                                 self.state = SrvHandlerState::Sending;
@@ -473,15 +611,29 @@ enum SrvHandlerState {
 }
 
 struct SrvState {
-    secret_key: String,
+    credentials: Vec<CtlCredential>,
     mgr_tx: MgrSender,
 }
 
+/// Returns the credential whose secret key matches the one a client presented, if any.
+fn authenticate(credentials: &[CtlCredential], secret_key: &str) -> Option<CtlCredential> {
+    credentials
+        .iter()
+        .find(|c| crypto::secure_eq(secret_key, &c.secret_key))
+        .cloned()
+}
+
 /// Start a new thread which will run the CtlGateway server.
 ///
-/// New connections will be authenticated using `secret_key`. Messages from the main thread
-/// will be sent over the channel `mgr_tx`.
-pub fn run(listen_addr: SocketAddr, secret_key: String, mgr_tx: MgrSender) {
+/// New connections will be authenticated using one of `credentials`, after completing a TLS
+/// handshake if `tls_config` is given, and may only make the requests allowed by the scope of
+/// that credential. Messages from the main thread will be sent over the channel `mgr_tx`.
+pub fn run(
+    listen_addr: SocketAddr,
+    credentials: Vec<CtlCredential>,
+    tls_config: Option<ServerConfig>,
+    mgr_tx: MgrSender,
+) {
     thread::Builder::new()
         .name("ctl-gateway".to_string())
         .spawn(move || {
@@ -489,21 +641,19 @@ pub fn run(listen_addr: SocketAddr, secret_key: String, mgr_tx: MgrSender) {
             let handle = core.handle();
             let listener = TcpListener::bind(&listen_addr).unwrap();
             let state = SrvState {
-                secret_key: secret_key,
+                credentials: credentials,
                 mgr_tx: mgr_tx,
             };
             let state = Rc::new(RefCell::new(state));
+            let tls_acceptor = tls_config.map(|c| TlsAcceptor::from(Arc::new(c)));
             let clients = listener.incoming().map(|socket| {
                 let addr = socket.peer_addr().unwrap();
-                let io = Framed::new(socket, SrvCodec::new());
-                (
-                    Client {
-                        handle: handle.clone(),
-                        state: state.clone(),
-                    }
-                    .serve(io),
-                    addr,
-                )
+                let client = Client {
+                    handle: handle.clone(),
+                    addr: addr,
+                    state: state.clone(),
+                };
+                (accept(socket, tls_acceptor.as_ref(), client), addr)
             });
             let server = clients.for_each(|(client, addr)| {
                 handle.spawn(client.then(move |res| {
@@ -516,3 +666,37 @@ pub fn run(listen_addr: SocketAddr, secret_key: String, mgr_tx: MgrSender) {
         })
         .expect("ctl-gateway thread start failure");
 }
+
+/// Serve a newly connected client, once the TLS handshake is complete if there is one. Like the
+/// secret key handshake, the TLS handshake must complete within `REQ_TIMEOUT`.
+fn accept(
+    socket: TcpStream,
+    tls_acceptor: Option<&TlsAcceptor>,
+    client: Client,
+) -> Box<dyn Future<Item = (), Error = HandlerError>> {
+    match tls_acceptor {
+        Some(acceptor) => Box::new(
+            acceptor
+                .accept(socket)
+                .map_err(HandlerError::from)
+                .select2(client.timeout(REQ_TIMEOUT))
+                .then(|res| match res {
+                    Ok(Either::A((socket, _to))) => future::ok(socket),
+                    Ok(Either::B((_to, _tls))) => future::err(HandlerError::from(io::Error::new(
+                        io::ErrorKind::TimedOut,
+                        "TLS handshake timed out",
+                    ))),
+                    Err(Either::A((err, _))) => future::err(err),
+                    Err(Either::B((err, _))) => future::err(HandlerError::from(err)),
+                })
+                .and_then(move |socket| {
+                    let io: Box<dyn SrvIo> = Box::new(socket);
+                    client.serve(Framed::new(io, SrvCodec::new()))
+                }),
+        ),
+        None => {
+            let io: Box<dyn SrvIo> = Box::new(socket);
+            client.serve(Framed::new(io, SrvCodec::new()))
+        }
+    }
+}
@@ -116,10 +116,14 @@ pub enum Error {
     BindTimeout(String),
     LockPoisoned,
     TestBootFail,
+    AuditLogIO(PathBuf, io::Error),
     ButterflyError(butterfly::error::Error),
+    ConfigFileIO(PathBuf, io::Error),
+    ConfigFileParse(PathBuf, toml::de::Error),
     CtlSecretIo(PathBuf, io::Error),
     APIClient(api_client::Error),
     EnvJoinPathsError(env::JoinPathsError),
+    EventSinkIO(PathBuf, io::Error),
     ExecCommandNotFound(String),
     FileNotFound(String),
     FileWatcherFileIsRoot,
@@ -129,11 +133,16 @@ pub enum Error {
     InvalidBinding(String),
     InvalidBinds(Vec<String>),
     InvalidCertFile(PathBuf),
+    InvalidConfigFile(PathBuf, String),
+    InvalidHealthProbe(String),
     InvalidKeyFile(PathBuf),
     InvalidKeyParameter(String),
     InvalidPidFile,
+    InvalidSecretName(String),
+    InvalidServiceManifest(String),
     InvalidTokioThreadCount,
     InvalidTopology(String),
+    InvalidUpdateBatchSize(String),
     InvalidUpdateStrategy(String),
     Io(io::Error),
     IPFailed,
@@ -158,9 +167,11 @@ pub enum Error {
     ProcessLocked(Pid),
     ProcessLockIO(PathBuf, io::Error),
     RecvError(mpsc::RecvError),
+    SecretStoreIO(PathBuf, io::Error),
     ServiceDeserializationError(serde_json::Error),
     ServiceNotLoaded(package::PackageIdent),
     ServiceSerializationError(serde_json::Error),
+    ServiceManifestParse(toml::de::Error),
     ServiceSpecFileIO(PathBuf, io::Error),
     ServiceSpecParse(toml::de::Error),
     ServiceSpecRender(toml::ser::Error),
@@ -219,12 +230,30 @@ impl fmt::Display for SupError {
             Error::BindTimeout(ref err) => format!("Timeout waiting to bind to {}", err),
             Error::LockPoisoned => "A mutex or read/write lock has failed.".to_string(),
             Error::TestBootFail => "Simulated boot failure".to_string(),
+            Error::AuditLogIO(ref path, ref err) => {
+                format!("Unable to open audit log, {}, {}", path.display(), err)
+            }
             Error::ButterflyError(ref err) => format!("Butterfly error: {}", err),
+            Error::ConfigFileIO(ref path, ref err) => format!(
+                "Unable to read Supervisor config file, {}, {}",
+                path.display(),
+                err
+            ),
+            Error::ConfigFileParse(ref path, ref err) => format!(
+                "Unable to parse Supervisor config file, {}, {}",
+                path.display(),
+                err
+            ),
             Error::CtlSecretIo(ref path, ref err) => format!(
                 "IoError while reading or writing ctl secret, {}, {}",
                 path.display(),
                 err
             ),
+            Error::EventSinkIO(ref path, ref err) => format!(
+                "Unable to open event stream sink, {}, {}",
+                path.display(),
+                err
+            ),
             Error::ExecCommandNotFound(ref c) => {
                 format!("`{}' was not found on the filesystem or in PATH", c)
             }
@@ -242,15 +271,25 @@ impl fmt::Display for SupError {
             ),
             Error::InvalidBinds(ref e) => format!("Invalid bind(s), {}", e.join(", ")),
             Error::InvalidCertFile(ref path) => format!("Invalid cert file: {}", path.display()),
+            Error::InvalidConfigFile(ref path, ref e) => {
+                format!("Invalid Supervisor config file, {}, {}", path.display(), e)
+            }
+            Error::InvalidHealthProbe(ref e) => e.to_string(),
             Error::InvalidKeyFile(ref path) => format!("Invalid key file: {}", path.display()),
             Error::InvalidKeyParameter(ref e) => {
                 format!("Invalid parameter for key generation: {:?}", e)
             }
             Error::InvalidPidFile => "Invalid child process PID file".to_string(),
+            Error::InvalidSecretName(ref name) => format!(
+                "Invalid secret name '{}', names may only contain letters, digits and underscores",
+                name
+            ),
+            Error::InvalidServiceManifest(ref e) => format!("Invalid service manifest: {}", e),
             Error::InvalidTokioThreadCount => {
                 "Tokio thread count should be a positive integer".to_string()
             }
             Error::InvalidTopology(ref t) => format!("Invalid topology: {}", t),
+            Error::InvalidUpdateBatchSize(ref s) => format!("Invalid update batch size: {}", s),
             Error::InvalidUpdateStrategy(ref s) => format!("Invalid update strategy: {}", s),
             Error::Io(ref err) => err.to_string(),
             Error::IPFailed => "Failed to discover this hosts outbound IP address".to_string(),
@@ -297,6 +336,11 @@ impl fmt::Display for SupError {
                 err
             ),
             Error::RecvError(ref err) => err.to_string(),
+            Error::SecretStoreIO(ref path, ref err) => format!(
+                "Unable to write or read to the secret store at {}, {}",
+                path.display(),
+                err
+            ),
             Error::ServiceDeserializationError(ref e) => {
                 format!("Can't deserialize service status: {}", e)
             }
@@ -304,6 +348,9 @@ impl fmt::Display for SupError {
             Error::ServiceSerializationError(ref e) => {
                 format!("Can't serialize service to file: {}", e)
             }
+            Error::ServiceManifestParse(ref err) => {
+                format!("Unable to parse contents of service manifest, {}", err)
+            }
             Error::ServiceSpecFileIO(ref path, ref err) => format!(
                 "Unable to write or read to a service spec file at {}, {}",
                 path.display(),
@@ -360,8 +407,12 @@ impl error::Error for SupError {
             Error::BindTimeout(_) => "Timeout waiting to bind to an address",
             Error::LockPoisoned => "A mutex or read/write lock has failed",
             Error::TestBootFail => "Simulated boot failure",
+            Error::AuditLogIO(_, _) => "Unable to open the audit log",
             Error::ButterflyError(ref err) => err.description(),
+            Error::ConfigFileIO(_, _) => "Unable to read the Supervisor config file",
+            Error::ConfigFileParse(_, _) => "Unable to parse the Supervisor config file",
             Error::CtlSecretIo(_, _) => "IoError while reading ctl secret",
+            Error::EventSinkIO(_, _) => "Unable to open an event stream sink",
             Error::ExecCommandNotFound(_) => "Exec command was not found on filesystem or in PATH",
             Error::GroupNotFound(_) => "No matching GID for group found",
             Error::HabitatCommon(ref err) => err.description(),
@@ -374,11 +425,16 @@ impl error::Error for SupError {
                 "Service binds detected that are neither required nor optional package binds"
             }
             Error::InvalidCertFile(_) => "Invalid cert file",
+            Error::InvalidConfigFile(_, _) => "Invalid Supervisor config file",
+            Error::InvalidHealthProbe(_) => "Invalid health probe",
             Error::InvalidKeyFile(_) => "Invalid key file",
             Error::InvalidKeyParameter(_) => "Key parameter error",
             Error::InvalidPidFile => "Invalid child process PID file",
+            Error::InvalidSecretName(_) => "Invalid secret name",
+            Error::InvalidServiceManifest(_) => "Invalid service manifest",
             Error::InvalidTokioThreadCount => "Invalid Tokio thread count",
             Error::InvalidTopology(_) => "Invalid topology",
+            Error::InvalidUpdateBatchSize(_) => "Invalid update batch size",
             Error::InvalidUpdateStrategy(_) => "Invalid update strategy",
             Error::Io(ref err) => err.description(),
             Error::IPFailed => "Failed to discover the outbound IP address",
@@ -411,9 +467,11 @@ impl error::Error for SupError {
             }
             Error::ProcessLockIO(_, _) => "Unable to read or write to a process lock",
             Error::RecvError(_) => "A channel failed to receive a response",
+            Error::SecretStoreIO(_, _) => "Unable to write or read to the secret store",
             Error::ServiceDeserializationError(_) => "Can't deserialize service status",
             Error::ServiceNotLoaded(_) => "Service status called when service not loaded",
             Error::ServiceSerializationError(_) => "Can't serialize service to file",
+            Error::ServiceManifestParse(_) => "Service manifest could not be parsed successfully",
             Error::ServiceSpecFileIO(_, _) => "Unable to write or read to a service spec file",
             Error::ServiceSpecParse(_) => "Service spec could not be parsed successfully",
             Error::ServiceSpecRender(_) => "Service spec TOML could not be rendered successfully",
@@ -0,0 +1,426 @@
+// Copyright (c) 2018 Chef Software Inc. and/or applicable contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A structured stream of Supervisor lifecycle events.
+//!
+//! As the Manager and its services go about their business, they `publish` typed `Event`s
+//! describing what happened: a service was loaded or started, its health changed, it was updated
+//! to a new package, and so on. Each event is wrapped in an `EventEnvelope`, which records the
+//! version of the event feed, when the event happened, and which Supervisor it came from.
+//!
+//! Envelopes are rendered as newline-delimited JSON and handed to every configured `EventSink`
+//! on a dedicated thread, so slow consumers never hold up the main loop. If no sinks are
+//! configured, publishing an event is a no-op.
+//!
+//! The shape of an envelope is described by `doc/event_stream_schema.json`. Any change to an
+//! existing event must bump `EVENT_STREAM_VERSION`; adding a new event type does not.
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+#[cfg(unix)]
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::Mutex;
+use std::thread;
+#[cfg(unix)]
+use std::time::Duration;
+
+use serde_json;
+use time;
+
+use crate::hcore::os::process::Pid;
+use crate::manager::service::HealthCheck;
+
+static LOGKEY: &'static str = "EV";
+
+/// The version of the event feed. Consumers should check this before interpreting an envelope.
+pub const EVENT_STREAM_VERSION: u32 = 1;
+
+#[cfg(unix)]
+const CLIENT_WRITE_TIMEOUT: Duration = Duration::from_secs(1);
+
+lazy_static! {
+    static ref PUBLISHER: Mutex<Option<Publisher>> = Mutex::new(None);
+}
+
+/// Everything that can be reported on the event stream.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Event {
+    ServiceLoaded {
+        service_group: String,
+        pkg_ident: String,
+    },
+    ServiceUnloaded {
+        service_group: String,
+        pkg_ident: String,
+        reason: String,
+    },
+    ServiceStarted {
+        service_group: String,
+        pkg_ident: String,
+        pid: Option<Pid>,
+    },
+    ServiceStopped {
+        service_group: String,
+        pkg_ident: String,
+        reason: String,
+    },
+    ServiceRestarted {
+        service_group: String,
+        pkg_ident: String,
+        pid: Option<Pid>,
+    },
+    ServiceCrashLooping {
+        service_group: String,
+        pkg_ident: String,
+        restarts: u64,
+    },
+    ServiceOomKilled {
+        service_group: String,
+        pkg_ident: String,
+        oom_kills: u64,
+    },
+    ServiceUnhealthy {
+        service_group: String,
+        pkg_ident: String,
+        critical_checks: u32,
+    },
+    HealthChanged {
+        service_group: String,
+        previous: HealthCheck,
+        current: HealthCheck,
+    },
+    PackageUpdated {
+        service_group: String,
+        previous: String,
+        current: String,
+    },
+    UpdateRolledBack {
+        service_group: String,
+        previous: String,
+        current: String,
+    },
+    ElectionFinished {
+        service_group: String,
+        leader_id: String,
+    },
+    ConfigApplied {
+        service_group: String,
+        incarnation: u64,
+    },
+    FileApplied {
+        service_group: String,
+        filename: String,
+        incarnation: u64,
+    },
+}
+
+/// An `Event` along with the metadata that every consumer needs to interpret it.
+#[derive(Clone, Debug, Serialize)]
+pub struct EventEnvelope {
+    pub version: u32,
+    pub timestamp: String,
+    pub member_id: String,
+    pub event: Event,
+}
+
+impl EventEnvelope {
+    fn new(member_id: &str, event: Event) -> Self {
+        EventEnvelope {
+            version: EVENT_STREAM_VERSION,
+            timestamp: time::now_utc().rfc3339().to_string(),
+            member_id: member_id.to_string(),
+            event: event,
+        }
+    }
+}
+
+/// Where the event stream should be written, as given on the command line.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EventSinkConfig {
+    /// Append newline-delimited JSON to the file at this path.
+    File(PathBuf),
+    /// Listen on a Unix domain socket at this path and write newline-delimited JSON to every
+    /// connected client.
+    UnixSocket(PathBuf),
+}
+
+impl EventSinkConfig {
+    pub fn path(&self) -> &Path {
+        match *self {
+            EventSinkConfig::File(ref path) | EventSinkConfig::UnixSocket(ref path) => path,
+        }
+    }
+
+    pub fn open(&self) -> io::Result<Box<dyn EventSink>> {
+        match *self {
+            EventSinkConfig::File(ref path) => Ok(Box::new(FileSink::open(path)?)),
+            #[cfg(unix)]
+            EventSinkConfig::UnixSocket(ref path) => Ok(Box::new(UnixSocketSink::bind(path)?)),
+            #[cfg(windows)]
+            EventSinkConfig::UnixSocket(_) => Err(io::Error::new(
+                io::ErrorKind::Other,
+                "Unix socket event sinks are not supported on this platform",
+            )),
+        }
+    }
+}
+
+/// A destination for rendered events. Each call to `write_line` receives exactly one
+/// newline-terminated JSON document.
+pub trait EventSink: Send {
+    fn describe(&self) -> String;
+    fn write_line(&mut self, line: &[u8]) -> io::Result<()>;
+}
+
+/// Appends events to a file on disk.
+pub struct FileSink {
+    path: PathBuf,
+    file: File,
+}
+
+impl FileSink {
+    pub fn open<P>(path: P) -> io::Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path.as_ref())?;
+        Ok(FileSink {
+            path: path.as_ref().to_path_buf(),
+            file: file,
+        })
+    }
+}
+
+impl EventSink for FileSink {
+    fn describe(&self) -> String {
+        format!("file:{}", self.path.display())
+    }
+
+    fn write_line(&mut self, line: &[u8]) -> io::Result<()> {
+        self.file.write_all(line)?;
+        self.file.flush()
+    }
+}
+
+/// Serves events to any local process connected to a Unix domain socket. Clients that go away
+/// are dropped; events published while nobody is connected are not buffered.
+#[cfg(unix)]
+pub struct UnixSocketSink {
+    path: PathBuf,
+    listener: UnixListener,
+    clients: Vec<UnixStream>,
+}
+
+#[cfg(unix)]
+impl UnixSocketSink {
+    pub fn bind<P>(path: P) -> io::Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        // A socket left behind by a previous Supervisor would prevent us from binding.
+        if path.as_ref().exists() {
+            std::fs::remove_file(path.as_ref())?;
+        }
+        let listener = UnixListener::bind(path.as_ref())?;
+        listener.set_nonblocking(true)?;
+        Ok(UnixSocketSink {
+            path: path.as_ref().to_path_buf(),
+            listener: listener,
+            clients: Vec::new(),
+        })
+    }
+
+    fn accept_pending(&mut self) {
+        loop {
+            match self.listener.accept() {
+                Ok((stream, _)) => {
+                    // Don't let a stalled reader hold up delivery to everyone else.
+                    if let Err(e) = stream.set_write_timeout(Some(CLIENT_WRITE_TIMEOUT)) {
+                        warn!("Unable to configure event stream client: {}", e);
+                        continue;
+                    }
+                    debug!("Event stream client connected to {}", self.path.display());
+                    self.clients.push(stream);
+                }
+                Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => break,
+                Err(e) => {
+                    warn!(
+                        "Error accepting event stream client on {}: {}",
+                        self.path.display(),
+                        e
+                    );
+                    break;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(unix)]
+impl EventSink for UnixSocketSink {
+    fn describe(&self) -> String {
+        format!("unix:{}", self.path.display())
+    }
+
+    fn write_line(&mut self, line: &[u8]) -> io::Result<()> {
+        self.accept_pending();
+        self.clients
+            .retain(|mut client| client.write_all(line).is_ok());
+        Ok(())
+    }
+}
+
+struct Publisher {
+    member_id: String,
+    tx: Sender<EventEnvelope>,
+}
+
+/// Start delivering published events to the given sinks. Calling this with no sinks leaves the
+/// event stream disabled.
+pub fn init(member_id: &str, sinks: Vec<Box<dyn EventSink>>) {
+    if sinks.is_empty() {
+        return;
+    }
+    for sink in &sinks {
+        outputln!("Publishing lifecycle events to {}", sink.describe());
+    }
+    let (tx, rx) = channel();
+    thread::Builder::new()
+        .name("event-stream".to_string())
+        .spawn(move || deliver(rx, sinks))
+        .expect("unable to start event-stream thread");
+    *PUBLISHER.lock().expect("Event publisher lock is poisoned") = Some(Publisher {
+        member_id: member_id.to_string(),
+        tx: tx,
+    });
+}
+
+/// Publish an event to all configured sinks.
+pub fn publish(event: Event) {
+    let publisher = PUBLISHER.lock().expect("Event publisher lock is poisoned");
+    if let Some(ref publisher) = *publisher {
+        let envelope = EventEnvelope::new(&publisher.member_id, event);
+        if publisher.tx.send(envelope).is_err() {
+            debug!("Event stream thread has gone away; dropping event");
+        }
+    }
+}
+
+fn deliver(rx: Receiver<EventEnvelope>, mut sinks: Vec<Box<dyn EventSink>>) {
+    for envelope in rx {
+        let mut line = match serde_json::to_vec(&envelope) {
+            Ok(line) => line,
+            Err(e) => {
+                warn!("Unable to serialize event {:?}: {}", envelope.event, e);
+                continue;
+            }
+        };
+        line.push(b'\n');
+        for sink in sinks.iter_mut() {
+            if let Err(e) = sink.write_line(&line) {
+                warn!("Unable to write event to {}: {}", sink.describe(), e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::fs;
+
+    use tempfile::TempDir;
+
+    use crate::test_helpers::*;
+
+    fn events() -> Vec<Event> {
+        vec![
+            Event::ServiceLoaded {
+                service_group: "redis.default".to_string(),
+                pkg_ident: "core/redis/4.0.10/20180801003001".to_string(),
+            },
+            Event::ServiceStarted {
+                service_group: "redis.default".to_string(),
+                pkg_ident: "core/redis/4.0.10/20180801003001".to_string(),
+                pid: Some(1234),
+            },
+            Event::ServiceCrashLooping {
+                service_group: "redis.default".to_string(),
+                pkg_ident: "core/redis/4.0.10/20180801003001".to_string(),
+                restarts: 5,
+            },
+            Event::ServiceOomKilled {
+                service_group: "redis.default".to_string(),
+                pkg_ident: "core/redis/4.0.10/20180801003001".to_string(),
+                oom_kills: 1,
+            },
+            Event::ServiceUnhealthy {
+                service_group: "redis.default".to_string(),
+                pkg_ident: "core/redis/4.0.10/20180801003001".to_string(),
+                critical_checks: 3,
+            },
+            Event::HealthChanged {
+                service_group: "redis.default".to_string(),
+                previous: HealthCheck::Unknown,
+                current: HealthCheck::Ok,
+            },
+            Event::UpdateRolledBack {
+                service_group: "redis.default".to_string(),
+                previous: "core/redis/4.0.11/20180901003001".to_string(),
+                current: "core/redis/4.0.10/20180801003001".to_string(),
+            },
+            Event::ElectionFinished {
+                service_group: "redis.default".to_string(),
+                leader_id: "8b6bc1bfe2c24fa4a8c0f1e7f2d3a3f2".to_string(),
+            },
+            Event::FileApplied {
+                service_group: "redis.default".to_string(),
+                filename: "ca.pem".to_string(),
+                incarnation: 2,
+            },
+        ]
+    }
+
+    #[test]
+    fn envelopes_conform_to_the_schema() {
+        for event in events() {
+            let envelope = EventEnvelope::new("8b6bc1bfe2c24fa4a8c0f1e7f2d3a3f2", event);
+            let json = serde_json::to_string(&envelope).expect("Could not serialize envelope");
+            assert_valid(&json, "event_stream_schema.json");
+        }
+    }
+
+    #[test]
+    fn file_sink_appends_one_line_per_event() {
+        let tmpdir = TempDir::new().expect("Could not create tempdir");
+        let path = tmpdir.path().join("events.ndjson");
+
+        let mut sink = FileSink::open(&path).expect("Could not open file sink");
+        sink.write_line(b"{\"one\":1}\n").unwrap();
+        sink.write_line(b"{\"two\":2}\n").unwrap();
+
+        let mut sink = FileSink::open(&path).expect("Could not reopen file sink");
+        sink.write_line(b"{\"three\":3}\n").unwrap();
+
+        let contents = fs::read_to_string(&path).expect("Could not read events file");
+        assert_eq!(contents.lines().count(), 3);
+    }
+}
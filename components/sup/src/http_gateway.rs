@@ -23,6 +23,7 @@ use std::{
     str::FromStr,
     sync::{Arc, Condvar, Mutex, RwLock},
     thread,
+    time::{Duration, Instant},
 };
 
 use crate::common::cli_defaults::{
@@ -30,22 +31,40 @@ use crate::common::cli_defaults::{
 };
 use crate::common::templating::hooks;
 use crate::common::types::EnvConfig;
-use crate::hcore::{crypto, env as henv, service::ServiceGroup};
+use crate::hcore::{crypto, env as henv, package::PackageIdent, service::ServiceGroup};
+use crate::protocol::{
+    self,
+    codec::SrvTxn,
+    message::MessageStatic,
+    net::{self, ErrCode, NetErr, NetResult},
+};
 use actix;
 use actix_web::{
-    http::{self, StatusCode},
+    error,
+    http::{self, Method, StatusCode},
     middleware::{Finished, Middleware, Started},
     pred::Predicate,
-    server, App, FromRequest, HttpRequest, HttpResponse, Path, Request,
+    server, App, AsyncResponder, FromRequest, FutureResponse, HttpMessage, HttpRequest,
+    HttpResponse, Path, Query, Request,
 };
+use bytes::Bytes;
+use futures::{future, sync::mpsc, Async, Future, Poll, Stream};
 use prometheus::{self, CounterVec, Encoder, HistogramTimer, HistogramVec, TextEncoder};
 use rustls::ServerConfig;
 use serde_json::{self, Value as Json};
-
+use tokio::timer::Interval;
+use toml;
+
+use crate::audit::AuditRequest;
+use crate::ctl_gateway::{
+    self,
+    server::{CtlCommand, MgrSender},
+    CtlRequest,
+};
 use crate::error::{Result, SupError};
-use crate::manager;
 use crate::manager::service::hooks::HealthCheckHook;
 use crate::manager::service::HealthCheck;
+use crate::manager::{self, commands, ManagerState};
 
 use crate::feat;
 
@@ -56,6 +75,17 @@ pub const HTTP_THREAD_COUNT: usize = 2;
 /// Default listening port for the HTTPGateway listener.
 pub const DEFAULT_PORT: u16 = 9631;
 
+/// How often watchers of the census are checked for changes to send them.
+const CENSUS_WATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Watchers are sent a comment after this many checks without changes, so that idle connections
+/// aren't closed along the way.
+const CENSUS_WATCH_KEEPALIVE: u32 = 15;
+
+/// Who the audit log says made the requests which were authenticated with the HTTP gateway's
+/// auth token.
+const HTTP_GATEWAY_ACTOR: &str = "http-gateway";
+
 lazy_static! {
     static ref HTTP_GATEWAY_REQUESTS: CounterVec = register_counter_vec!(
         "hab_sup_http_gateway_requests_total",
@@ -148,20 +178,322 @@ impl Into<StatusCode> for HealthCheck {
     }
 }
 
+/// Body of a request to load a service. Mirrors the options of `hab svc load`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct LoadRequest {
+    ident: String,
+    group: Option<String>,
+    bldr_url: Option<String>,
+    channel: Option<String>,
+    topology: Option<String>,
+    strategy: Option<String>,
+    binds: Option<Vec<String>>,
+    binding_mode: Option<String>,
+    force: Option<bool>,
+}
+
+impl LoadRequest {
+    fn into_msg(self) -> NetResult<protocol::ctl::SvcLoad> {
+        let ident = PackageIdent::from_str(&self.ident)
+            .map_err(|e| net::err(ErrCode::InvalidPayload, e))?;
+        let mut msg = protocol::ctl::SvcLoad::default();
+        msg.ident = Some(ident.into());
+        msg.group = self.group;
+        msg.bldr_url = self.bldr_url;
+        msg.bldr_channel = self.channel;
+        if let Some(topology) = self.topology {
+            msg.topology = Some(protocol::types::Topology::from_str(&topology)? as i32);
+        }
+        if let Some(strategy) = self.strategy {
+            msg.update_strategy =
+                Some(protocol::types::UpdateStrategy::from_str(&strategy)? as i32);
+        }
+        if let Some(binds) = self.binds {
+            let mut list = protocol::ctl::ServiceBindList::default();
+            for bind in binds {
+                list.binds
+                    .push(protocol::types::ServiceBind::from_str(&bind)?);
+            }
+            msg.binds = Some(list);
+            msg.specified_binds = Some(true);
+        }
+        if let Some(binding_mode) = self.binding_mode {
+            msg.binding_mode = Some(protocol::types::BindingMode::from_str(&binding_mode)? as i32);
+        }
+        msg.force = self.force;
+        Ok(msg)
+    }
+}
+
+/// Body of a request to apply a new configuration to a service group, like `hab config apply`.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConfigRequest {
+    version: u64,
+    config: toml::Value,
+}
+
+impl ConfigRequest {
+    fn into_msg(self, service_group: ServiceGroup) -> NetResult<protocol::ctl::SvcSetCfg> {
+        if !self.config.is_table() {
+            return Err(net::err(
+                ErrCode::InvalidPayload,
+                "The configuration must be an object.",
+            ));
+        }
+        let cfg =
+            toml::to_string(&self.config).map_err(|e| net::err(ErrCode::InvalidPayload, e))?;
+        let mut msg = protocol::ctl::SvcSetCfg::default();
+        msg.service_group = Some(service_group.into());
+        msg.cfg = Some(cfg.into_bytes());
+        msg.version = Some(self.version);
+        Ok(msg)
+    }
+}
+
+/// Body of the response to any request that changes the Supervisor's state. `output` holds the
+/// lines the Supervisor would have printed for the equivalent `hab` command.
+#[derive(Debug, Default, Serialize)]
+struct CommandResponse {
+    ok: bool,
+    output: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<CommandError>,
+}
+
+#[derive(Debug, Serialize)]
+struct CommandError {
+    code: ErrCode,
+    message: String,
+}
+
+impl CommandResponse {
+    /// Assemble the response from the replies the Manager sent for a command.
+    fn from_replies(replies: Vec<protocol::codec::SrvMessage>) -> Self {
+        let mut response = CommandResponse::default();
+        for reply in replies {
+            match reply.message_id() {
+                id if id == protocol::ctl::ConsoleLine::MESSAGE_ID => {
+                    if let Ok(msg) = reply.parse::<protocol::ctl::ConsoleLine>() {
+                        let line = ctl_gateway::strip_ansi_codes(&msg.line);
+                        response
+                            .output
+                            .push(line.trim_right_matches('\n').to_string());
+                    }
+                }
+                id if id == NetErr::MESSAGE_ID => {
+                    if let Ok(err) = reply.parse::<NetErr>() {
+                        response.error = Some(CommandError::from(err));
+                    }
+                }
+                _ => (),
+            }
+        }
+        response.ok = response.error.is_none();
+        response
+    }
+
+    fn status(&self) -> StatusCode {
+        let code = match self.error {
+            Some(ref err) => err.code,
+            None => return StatusCode::OK,
+        };
+        match code {
+            ErrCode::NotFound => StatusCode::NOT_FOUND,
+            ErrCode::Conflict => StatusCode::CONFLICT,
+            ErrCode::Unauthorized => StatusCode::FORBIDDEN,
+            ErrCode::BadPayload | ErrCode::UpdateClient => StatusCode::BAD_REQUEST,
+            ErrCode::NotSupported | ErrCode::InvalidPayload => StatusCode::UNPROCESSABLE_ENTITY,
+            ErrCode::EntityTooLarge => StatusCode::PAYLOAD_TOO_LARGE,
+            ErrCode::Internal | ErrCode::Io => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+impl From<NetErr> for CommandResponse {
+    fn from(err: NetErr) -> Self {
+        CommandResponse {
+            ok: false,
+            output: vec![],
+            error: Some(CommandError::from(err)),
+        }
+    }
+}
+
+impl Into<HttpResponse> for CommandResponse {
+    fn into(self) -> HttpResponse {
+        HttpResponse::build(self.status()).json(self)
+    }
+}
+
+impl From<NetErr> for CommandError {
+    fn from(err: NetErr) -> Self {
+        CommandError {
+            code: ErrCode::from_i32(err.code).unwrap_or_default(),
+            message: err.msg,
+        }
+    }
+}
+
+/// The commands that act on a single loaded service.
+#[derive(Clone, Copy, Debug)]
+enum ServiceCommand {
+    Unload,
+    Start,
+    Stop,
+}
+
+impl ServiceCommand {
+    fn run(
+        self,
+        state: &ManagerState,
+        req: &mut CtlRequest,
+        ident: protocol::types::PackageIdent,
+    ) -> NetResult<()> {
+        match self {
+            ServiceCommand::Unload => {
+                let mut msg = protocol::ctl::SvcUnload::default();
+                msg.ident = Some(ident);
+                commands::service_unload(state, req, msg)
+            }
+            ServiceCommand::Start => {
+                let mut msg = protocol::ctl::SvcStart::default();
+                msg.ident = Some(ident);
+                commands::service_start(state, req, msg)
+            }
+            ServiceCommand::Stop => {
+                let mut msg = protocol::ctl::SvcStop::default();
+                msg.ident = Some(ident);
+                commands::service_stop(state, req, msg)
+            }
+        }
+    }
+
+    fn audit(
+        self,
+        peer: Option<SocketAddr>,
+        ident: &protocol::types::PackageIdent,
+    ) -> AuditRequest {
+        let ident = Some(ident.clone());
+        match self {
+            ServiceCommand::Unload => AuditRequest::new(
+                HTTP_GATEWAY_ACTOR,
+                peer,
+                &protocol::ctl::SvcUnload { ident: ident },
+            ),
+            ServiceCommand::Start => AuditRequest::new(
+                HTTP_GATEWAY_ACTOR,
+                peer,
+                &protocol::ctl::SvcStart { ident: ident },
+            ),
+            ServiceCommand::Stop => AuditRequest::new(
+                HTTP_GATEWAY_ACTOR,
+                peer,
+                &protocol::ctl::SvcStop { ident: ident },
+            ),
+        }
+    }
+}
+
 struct AppState {
     gateway_state: Arc<RwLock<manager::GatewayState>>,
+    mgr_tx: MgrSender,
     timer: Cell<Option<HistogramTimer>>,
 }
 
 impl AppState {
-    fn new(gs: Arc<RwLock<manager::GatewayState>>) -> Self {
+    fn new(gs: Arc<RwLock<manager::GatewayState>>, mgr_tx: MgrSender) -> Self {
         AppState {
             gateway_state: gs,
+            mgr_tx: mgr_tx,
             timer: Cell::new(None),
         }
     }
 }
 
+#[derive(Deserialize)]
+struct WatchQuery {
+    since: Option<u64>,
+}
+
+/// Streams the changes to the census as Server-Sent Events, starting after the given cursor.
+/// Each event is named after the kind of change, and carries its cursor as the event's ID.
+struct CensusWatch {
+    gateway_state: Arc<RwLock<manager::GatewayState>>,
+    cursor: u64,
+    interval: Interval,
+    idle: u32,
+}
+
+impl CensusWatch {
+    fn new(gateway_state: Arc<RwLock<manager::GatewayState>>, cursor: u64) -> Self {
+        CensusWatch {
+            gateway_state: gateway_state,
+            cursor: cursor,
+            interval: Interval::new(Instant::now(), CENSUS_WATCH_INTERVAL),
+            idle: 0,
+        }
+    }
+
+    /// The events for the changes made since the last call.
+    fn events(&mut self) -> String {
+        let gateway_state = self
+            .gateway_state
+            .read()
+            .expect("GatewayState lock is poisoned");
+        let log = &gateway_state.census_changes;
+        let mut events = String::new();
+        match log.since(self.cursor) {
+            Some(changes) => {
+                for (cursor, change) in changes {
+                    let data = serde_json::to_string(&change).expect("Census change is valid JSON");
+                    events.push_str(&sse_event(cursor, change.name(), &data));
+                    self.cursor = cursor;
+                }
+            }
+            None => {
+                // We fell too far behind to know what changed, so the watcher has to read the
+                // whole census again.
+                self.cursor = log.latest();
+                let data = json!({ "cursor": self.cursor }).to_string();
+                events.push_str(&sse_event(self.cursor, "resync", &data));
+            }
+        }
+        events
+    }
+}
+
+impl Stream for CensusWatch {
+    type Item = Bytes;
+    type Error = actix_web::Error;
+
+    fn poll(&mut self) -> Poll<Option<Bytes>, actix_web::Error> {
+        loop {
+            match self.interval.poll() {
+                Ok(Async::Ready(Some(_))) => {}
+                Ok(Async::Ready(None)) => return Ok(Async::Ready(None)),
+                Ok(Async::NotReady) => return Ok(Async::NotReady),
+                Err(e) => return Err(error::ErrorInternalServerError(e)),
+            }
+            let events = self.events();
+            if !events.is_empty() {
+                self.idle = 0;
+                return Ok(Async::Ready(Some(Bytes::from(events))));
+            }
+            self.idle += 1;
+            if self.idle >= CENSUS_WATCH_KEEPALIVE {
+                self.idle = 0;
+                return Ok(Async::Ready(Some(Bytes::from_static(b": keepalive\n\n"))));
+            }
+        }
+    }
+}
+
+fn sse_event(id: u64, event: &str, data: &str) -> String {
+    format!("id: {}\nevent: {}\ndata: {}\n\n", id, event, data)
+}
+
 // Begin middleware
 struct Authentication;
 
@@ -176,6 +508,19 @@ impl Middleware<AppState> for Authentication {
 
         let current_token = match current_token.as_ref() {
             Some(t) => t,
+            // Requests that change the Supervisor's state are never allowed without an auth token.
+            None if changes_state(req) => {
+                debug!(
+                    "No auth token present. Refusing {} {}.",
+                    req.method(),
+                    req.path()
+                );
+                let response = CommandResponse::from(net::err(
+                    ErrCode::Unauthorized,
+                    "Set HAB_SUP_GATEWAY_AUTH_TOKEN to enable this endpoint.",
+                ));
+                return Ok(Started::Response(response.into()));
+            }
             // If there's no auth token in the state, just return. Everything will continue to function
             // unauthenticated.
             None => {
@@ -212,6 +557,13 @@ impl Middleware<AppState> for Authentication {
     }
 }
 
+fn changes_state<S>(req: &HttpRequest<S>) -> bool {
+    match *req.method() {
+        Method::GET | Method::HEAD | Method::OPTIONS => false,
+        _ => true,
+    }
+}
+
 struct Metrics;
 
 impl Middleware<AppState> for Metrics {
@@ -254,6 +606,7 @@ impl Server {
         listen_addr: ListenAddr,
         tls_config: Option<ServerConfig>,
         gateway_state: Arc<RwLock<manager::GatewayState>>,
+        mgr_tx: MgrSender,
         control: Arc<(Mutex<ServerStartup>, Condvar)>,
     ) {
         thread::spawn(move || {
@@ -268,7 +621,7 @@ impl Server {
             };
 
             let mut server = server::new(move || {
-                let app_state = AppState::new(gateway_state.clone());
+                let app_state = AppState::new(gateway_state.clone(), mgr_tx.clone());
                 App::with_state(app_state)
                     .middleware(Authentication)
                     .middleware(Metrics)
@@ -321,27 +674,49 @@ impl<S: 'static> Predicate<S> for RedactHTTP {
 
 fn routes(app: App<AppState>) -> App<AppState> {
     app.resource("/", |r| r.get().f(doc))
-        .resource("/services", |r| r.get().f(services))
+        .resource("/services", |r| {
+            r.get().f(services);
+            r.post().a(load);
+        })
         .resource("/services/{svc}/{group}", |r| {
-            r.get().f(service_without_org)
+            r.get().f(service_without_org);
+            r.delete().a(unload_without_org);
         })
         .resource("/services/{svc}/{group}/config", |r| {
-            r.get().f(config_without_org)
+            r.get().f(config_without_org);
+            r.put().a(apply_config_without_org);
         })
         .resource("/services/{svc}/{group}/health", |r| {
             r.get().f(health_without_org)
         })
+        .resource("/services/{svc}/{group}/start", |r| {
+            r.post().a(start_without_org)
+        })
+        .resource("/services/{svc}/{group}/stop", |r| {
+            r.post().a(stop_without_org)
+        })
         .resource("/services/{svc}/{group}/{org}", |r| {
-            r.get().f(service_with_org)
+            r.get().f(service_with_org);
+            r.delete().a(unload_with_org);
         })
         .resource("/services/{svc}/{group}/{org}/config", |r| {
-            r.get().f(config_with_org)
+            r.get().f(config_with_org);
+            r.put().a(apply_config_with_org);
         })
         .resource("/services/{svc}/{group}/{org}/health", |r| {
             r.get().f(health_with_org)
         })
+        .resource("/services/{svc}/{group}/{org}/start", |r| {
+            r.post().a(start_with_org)
+        })
+        .resource("/services/{svc}/{group}/{org}/stop", |r| {
+            r.post().a(stop_with_org)
+        })
         .resource("/butterfly", |r| r.get().filter(RedactHTTP).f(butterfly))
         .resource("/census", |r| r.get().filter(RedactHTTP).f(census))
+        .resource("/census/watch", |r| {
+            r.get().filter(RedactHTTP).f(census_watch)
+        })
         .resource("/metrics", |r| r.get().f(metrics))
 }
 
@@ -372,6 +747,32 @@ fn census(req: &HttpRequest<AppState>) -> HttpResponse {
     json_response(data.to_string())
 }
 
+fn census_watch(req: &HttpRequest<AppState>) -> HttpResponse {
+    let since = match Query::<WatchQuery>::extract(&req) {
+        Ok(query) => query.into_inner().since,
+        Err(_) => return HttpResponse::BadRequest().finish(),
+    };
+    // Clients reconnecting to an event stream send the cursor of the last change they saw.
+    let last_event_id = req
+        .headers()
+        .get("Last-Event-ID")
+        .and_then(|id| id.to_str().ok())
+        .and_then(|id| id.parse().ok());
+    let gateway_state = req.state().gateway_state.clone();
+    let cursor = match since.or(last_event_id) {
+        Some(cursor) => cursor,
+        None => gateway_state
+            .read()
+            .expect("GatewayState lock is poisoned")
+            .census_changes
+            .latest(),
+    };
+    HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .header(http::header::CACHE_CONTROL, "no-cache")
+        .streaming(CensusWatch::new(gateway_state, cursor))
+}
+
 fn services(req: &HttpRequest<AppState>) -> HttpResponse {
     let data = &req
         .state()
@@ -539,8 +940,178 @@ fn metrics(_req: &HttpRequest<AppState>) -> HttpResponse {
 fn doc(_req: &HttpRequest<AppState>) -> HttpResponse {
     HttpResponse::Ok().content_type("text/html").body(APIDOCS)
 }
+
+fn load(req: &HttpRequest<AppState>) -> FutureResponse<HttpResponse> {
+    let mgr_tx = req.state().mgr_tx.clone();
+    let peer = req.peer_addr();
+    req.json::<LoadRequest>()
+        .then(move |body| {
+            match body
+                .map_err(|e| net::err(ErrCode::BadPayload, e))
+                .and_then(LoadRequest::into_msg)
+            {
+                Ok(msg) => {
+                    let audit = AuditRequest::new(HTTP_GATEWAY_ACTOR, peer, &msg);
+                    dispatch(&mgr_tx, audit, move |state, req| {
+                        commands::service_load(state, req, msg.clone())
+                    })
+                }
+                Err(err) => Box::new(future::ok(CommandResponse::from(err).into())),
+            }
+        })
+        .responder()
+}
+
+fn unload_with_org(req: &HttpRequest<AppState>) -> FutureResponse<HttpResponse> {
+    let (svc, group, org) = Path::<(String, String, String)>::extract(&req)
+        .unwrap()
+        .into_inner();
+    service_command(req, svc, group, Some(&org), ServiceCommand::Unload)
+}
+
+fn unload_without_org(req: &HttpRequest<AppState>) -> FutureResponse<HttpResponse> {
+    let (svc, group) = Path::<(String, String)>::extract(&req)
+        .unwrap()
+        .into_inner();
+    service_command(req, svc, group, None, ServiceCommand::Unload)
+}
+
+fn start_with_org(req: &HttpRequest<AppState>) -> FutureResponse<HttpResponse> {
+    let (svc, group, org) = Path::<(String, String, String)>::extract(&req)
+        .unwrap()
+        .into_inner();
+    service_command(req, svc, group, Some(&org), ServiceCommand::Start)
+}
+
+fn start_without_org(req: &HttpRequest<AppState>) -> FutureResponse<HttpResponse> {
+    let (svc, group) = Path::<(String, String)>::extract(&req)
+        .unwrap()
+        .into_inner();
+    service_command(req, svc, group, None, ServiceCommand::Start)
+}
+
+fn stop_with_org(req: &HttpRequest<AppState>) -> FutureResponse<HttpResponse> {
+    let (svc, group, org) = Path::<(String, String, String)>::extract(&req)
+        .unwrap()
+        .into_inner();
+    service_command(req, svc, group, Some(&org), ServiceCommand::Stop)
+}
+
+fn stop_without_org(req: &HttpRequest<AppState>) -> FutureResponse<HttpResponse> {
+    let (svc, group) = Path::<(String, String)>::extract(&req)
+        .unwrap()
+        .into_inner();
+    service_command(req, svc, group, None, ServiceCommand::Stop)
+}
+
+fn service_command(
+    req: &HttpRequest<AppState>,
+    svc: String,
+    group: String,
+    org: Option<&str>,
+    command: ServiceCommand,
+) -> FutureResponse<HttpResponse> {
+    let ident = match loaded_ident(req, svc, group, org) {
+        Ok(ident) => ident,
+        Err(err) => return Box::new(future::ok(CommandResponse::from(err).into())),
+    };
+    let audit = command.audit(req.peer_addr(), &ident);
+    dispatch(&req.state().mgr_tx, audit, move |state, req| {
+        command.run(state, req, ident.clone())
+    })
+}
+
+fn apply_config_with_org(req: &HttpRequest<AppState>) -> FutureResponse<HttpResponse> {
+    let (svc, group, org) = Path::<(String, String, String)>::extract(&req)
+        .unwrap()
+        .into_inner();
+    apply_config(req, svc, group, Some(&org))
+}
+
+fn apply_config_without_org(req: &HttpRequest<AppState>) -> FutureResponse<HttpResponse> {
+    let (svc, group) = Path::<(String, String)>::extract(&req)
+        .unwrap()
+        .into_inner();
+    apply_config(req, svc, group, None)
+}
+
+fn apply_config(
+    req: &HttpRequest<AppState>,
+    svc: String,
+    group: String,
+    org: Option<&str>,
+) -> FutureResponse<HttpResponse> {
+    let service_group = match ServiceGroup::new(None, svc, group, org) {
+        Ok(sg) => sg,
+        Err(e) => {
+            let err = net::err(ErrCode::InvalidPayload, e);
+            return Box::new(future::ok(CommandResponse::from(err).into()));
+        }
+    };
+    let mgr_tx = req.state().mgr_tx.clone();
+    let peer = req.peer_addr();
+    req.json::<ConfigRequest>()
+        .then(move |body| {
+            match body
+                .map_err(|e| net::err(ErrCode::BadPayload, e))
+                .and_then(|body| body.into_msg(service_group))
+            {
+                Ok(msg) => {
+                    let audit = AuditRequest::new(HTTP_GATEWAY_ACTOR, peer, &msg);
+                    dispatch(&mgr_tx, audit, move |state, req| {
+                        commands::service_cfg_set(state, req, msg.clone())
+                    })
+                }
+                Err(err) => Box::new(future::ok(CommandResponse::from(err).into())),
+            }
+        })
+        .responder()
+}
 // End route handlers
 
+/// Hand a command over to the Manager, the same way the CtlGateway does, and respond with the
+/// replies it sends back. The command is recorded in the audit log as `audit`.
+fn dispatch<F>(mgr_tx: &MgrSender, audit: AuditRequest, fun: F) -> FutureResponse<HttpResponse>
+where
+    F: Fn(&ManagerState, &mut CtlRequest) -> NetResult<()> + Send + 'static,
+{
+    let (tx, rx) = mpsc::unbounded();
+    let cmd = CtlCommand::new(Some(tx), Some(SrvTxn::from(0)), fun).audited(Some(audit));
+    if let Err(err) = mgr_tx.unbounded_send(cmd) {
+        error!("Unable to send command to the Manager: {}", err);
+        return Box::new(future::ok(HttpResponse::ServiceUnavailable().finish()));
+    }
+    Box::new(
+        rx.collect()
+            .then(|replies| Ok(CommandResponse::from_replies(replies.unwrap_or_default()).into())),
+    )
+}
+
+/// The package identifier a loaded service was loaded with, which is what the Manager's commands
+/// expect.
+fn loaded_ident(
+    req: &HttpRequest<AppState>,
+    svc: String,
+    group: String,
+    org: Option<&str>,
+) -> NetResult<protocol::types::PackageIdent> {
+    let service_group = ServiceGroup::new(None, svc, group, org)
+        .map_err(|e| net::err(ErrCode::InvalidPayload, e))?;
+    let data = &req
+        .state()
+        .gateway_state
+        .read()
+        .expect("GatewayState lock is poisoned")
+        .services_data;
+    let mut service = service_from_services(&service_group, &data).ok_or_else(|| {
+        net::err(
+            ErrCode::NotFound,
+            format!("Service {} not loaded", service_group),
+        )
+    })?;
+    serde_json::from_value(service["spec_ident"].take()).map_err(|e| net::err(ErrCode::Internal, e))
+}
+
 fn service_from_services(service_group: &ServiceGroup, services_json: &str) -> Option<Json> {
     match serde_json::from_str(services_json) {
         Ok(Json::Array(services)) => services
@@ -552,18 +1123,21 @@ fn service_from_services(service_group: &ServiceGroup, services_json: &str) -> O
 
 #[cfg(test)]
 mod tests {
-    use std::{fs::File, io::Read, path::PathBuf, sync::Mutex};
+    use std::{fs::File, io::Read, path::PathBuf, str::FromStr, sync::Mutex};
 
+    use super::{CommandResponse, ConfigRequest, LoadRequest};
     use crate::butterfly::{
         member::Member,
         server::{Server, ServerProxy, Suitability},
         trace::Trace,
     };
+    use crate::hcore::service::ServiceGroup;
+    use crate::protocol::net::{self, ErrCode};
     use serde_json;
 
     use crate::test_helpers::*;
 
-    fn validate_sample_file_against_schema(name: &str, schema: &str) {
+    fn sample_file(name: &str) -> String {
         let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
             .join("tests")
             .join("fixtures")
@@ -574,8 +1148,11 @@ mod tests {
         let mut json = String::new();
         f.read_to_string(&mut json)
             .unwrap_or_else(|_| panic!("could not read {}", &name));
+        json
+    }
 
-        assert_valid(&json, schema);
+    fn validate_sample_file_against_schema(name: &str, schema: &str) {
+        assert_valid(&sample_file(name), schema);
     }
 
     #[test]
@@ -691,4 +1268,109 @@ mod tests {
             "Expected schema validation to fail, but it succeeded"
         );
     }
+
+    #[test]
+    fn sample_load_request_file_is_valid() {
+        validate_sample_file_against_schema(
+            "sample-load-request.json",
+            "http_gateway_load_request_schema.json",
+        );
+    }
+
+    #[test]
+    fn trivial_load_request_failure() {
+        let failure = validate_string(
+            r#"{"ident": "core/redis", "topology": "mesh"}"#,
+            "http_gateway_load_request_schema.json",
+        );
+        assert!(
+            !failure.is_valid(),
+            "Expected schema validation to fail, but it succeeded"
+        );
+    }
+
+    #[test]
+    fn sample_load_request_becomes_svc_load() {
+        let request: LoadRequest =
+            serde_json::from_str(&sample_file("sample-load-request.json")).unwrap();
+        let msg = request.into_msg().unwrap();
+
+        assert_eq!(msg.ident.unwrap().to_string(), "core/builder-api");
+        assert_eq!(msg.group, Some("prod".to_string()));
+        assert_eq!(msg.bldr_channel, Some("stable".to_string()));
+        assert_eq!(msg.binds.unwrap().binds.len(), 1);
+        assert_eq!(msg.specified_binds, Some(true));
+        assert_eq!(msg.force, None);
+    }
+
+    #[test]
+    fn invalid_load_request_is_rejected() {
+        let request: LoadRequest =
+            serde_json::from_str(r#"{"ident": "core/redis", "binds": ["nope"]}"#).unwrap();
+        let err = request.into_msg().unwrap_err();
+
+        assert_eq!(err.code, ErrCode::InvalidPayload as i32);
+    }
+
+    #[test]
+    fn sample_config_request_file_is_valid() {
+        validate_sample_file_against_schema(
+            "sample-config-request.json",
+            "http_gateway_config_request_schema.json",
+        );
+    }
+
+    #[test]
+    fn trivial_config_request_failure() {
+        let failure = validate_string(
+            r#"{"config": "port = 80"}"#,
+            "http_gateway_config_request_schema.json",
+        );
+        assert!(
+            !failure.is_valid(),
+            "Expected schema validation to fail, but it succeeded"
+        );
+    }
+
+    #[test]
+    fn sample_config_request_becomes_svc_set_cfg() {
+        let request: ConfigRequest =
+            serde_json::from_str(&sample_file("sample-config-request.json")).unwrap();
+        let msg = request
+            .into_msg(ServiceGroup::from_str("builder-api.prod").unwrap())
+            .unwrap();
+
+        assert_eq!(msg.version, Some(2));
+        assert_eq!(
+            String::from_utf8(msg.cfg.unwrap()).unwrap(),
+            "log_level = \"debug\"\n\n[http]\nport = 9636\n"
+        );
+    }
+
+    #[test]
+    fn sample_command_response_files_are_valid() {
+        validate_sample_file_against_schema(
+            "sample-command-response.json",
+            "http_gateway_command_response_schema.json",
+        );
+        validate_sample_file_against_schema(
+            "sample-command-error-response.json",
+            "http_gateway_command_response_schema.json",
+        );
+    }
+
+    #[test]
+    fn command_responses_are_valid() {
+        let ok = CommandResponse::from_replies(vec![]);
+        assert_valid(
+            &serde_json::to_string(&ok).unwrap(),
+            "http_gateway_command_response_schema.json",
+        );
+
+        let err = CommandResponse::from(net::err(ErrCode::NotFound, "Service not loaded"));
+        assert_valid(
+            &serde_json::to_string(&err).unwrap(),
+            "http_gateway_command_response_schema.json",
+        );
+    }
 }
@@ -101,12 +101,14 @@ macro_rules! sup_error {
 #[cfg(test)]
 #[macro_use]
 pub mod cli_test_helpers;
+pub mod audit;
 pub mod census;
 pub mod cli;
 pub mod command;
 pub mod config;
 pub mod ctl_gateway;
 pub mod error;
+pub mod event;
 pub mod http_gateway;
 pub mod manager;
 #[cfg(test)]
@@ -114,6 +116,7 @@ pub mod test_helpers;
 pub mod util;
 
 use std::env;
+use std::fmt;
 use std::path::PathBuf;
 
 lazy_static! {
@@ -150,6 +153,21 @@ pub enum ShutdownReason {
     Departed,
     LauncherStopping,
     PkgUpdating,
+    Restarting,
     Signal,
     SvcStopCmd,
 }
+
+impl fmt::Display for ShutdownReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = match *self {
+            ShutdownReason::Departed => "departed",
+            ShutdownReason::LauncherStopping => "launcher-stopping",
+            ShutdownReason::PkgUpdating => "pkg-updating",
+            ShutdownReason::Restarting => "restarting",
+            ShutdownReason::Signal => "signal",
+            ShutdownReason::SvcStopCmd => "svc-stop-cmd",
+        };
+        write!(f, "{}", value)
+    }
+}
@@ -40,7 +40,7 @@ extern crate tokio_core;
 extern crate url;
 
 use std::{
-    env,
+    env, fmt,
     io::{self, Write},
     net::{SocketAddr, ToSocketAddrs},
     path::PathBuf,
@@ -48,8 +48,11 @@ use std::{
     str::{self, FromStr},
 };
 
-use crate::common::cli_defaults::GOSSIP_DEFAULT_PORT;
+use crate::common::cli_defaults::{
+    GOSSIP_DEFAULT_PORT, GOSSIP_LISTEN_ADDRESS_ENVVAR, LISTEN_HTTP_ADDRESS_ENVVAR,
+};
 use crate::common::command::package::install::InstallSource;
+use crate::common::types::{EnvConfig, ListenCtlAddr};
 use crate::common::ui::{Coloring, NONINTERACTIVE_ENVVAR, UI};
 use crate::hcore::crypto::{self, default_cache_key_path, SymKey};
 use crate::hcore::env as henv;
@@ -58,7 +61,11 @@ use crate::hcore::ChannelIdent;
 use crate::launcher_client::{LauncherCli, ERR_NO_RETRY_EXCODE};
 use crate::protocol::{
     ctl::ServiceBindList,
-    types::{ApplicationEnvironment, BindingMode, ServiceBind, Topology, UpdateStrategy},
+    types::{
+        ApplicationEnvironment, BindingMode, HealthCheckThresholds, HealthProbe, LogSettings,
+        PackageIdent, ResourceLimits, RestartPolicy, ServiceBind, ShutdownSignal, Topology,
+        UpdateSettings, UpdateStrategy, UpdateWindow,
+    },
 };
 use clap::ArgMatches;
 use habitat_common as common;
@@ -67,7 +74,10 @@ use hcore::crypto::dpapi::encrypt;
 
 use crate::sup::cli::cli;
 use crate::sup::command;
+use crate::sup::config::ConfigFile;
+use crate::sup::ctl_gateway::CtlTlsFiles;
 use crate::sup::error::{Error, Result, SupError};
+use crate::sup::event::EventSinkConfig;
 use crate::sup::feat;
 use crate::sup::manager::{Manager, ManagerConfig};
 use crate::sup::util;
@@ -139,6 +149,10 @@ fn start() -> Result<()> {
     };
     match app_matches.subcommand() {
         ("bash", Some(_)) => sub_bash(),
+        ("config", Some(m)) => match m.subcommand() {
+            ("check", Some(m)) => sub_config_check(m),
+            _ => unreachable!(),
+        },
         ("run", Some(m)) => {
             let launcher = launcher.ok_or(sup_error!(Error::NoLauncher))?;
             sub_run(m, launcher)
@@ -153,6 +167,13 @@ fn sub_bash() -> Result<()> {
     command::shell::bash()
 }
 
+fn sub_config_check(m: &ArgMatches) -> Result<()> {
+    let path = m.value_of("FILE").expect("FILE is a required argument");
+    ConfigFile::load(path)?;
+    println!("{} is a valid Supervisor config file", path);
+    Ok(())
+}
+
 fn sub_run(m: &ArgMatches, launcher: LauncherCli) -> Result<()> {
     set_supervisor_logging_options(m);
 
@@ -220,59 +241,93 @@ fn sub_term() -> Result<()> {
 ////////////////////////////////////////////////////////////////////////
 
 fn mgrcfg_from_sup_run_matches(m: &ArgMatches) -> Result<ManagerConfig> {
+    // Settings given as flags or in the environment take precedence over the config file.
+    let file = match m.value_of("CONFIG_FILE") {
+        Some(path) => ConfigFile::load(path)?,
+        None => ConfigFile::default(),
+    };
     let cfg = ManagerConfig {
-        auto_update: m.is_present("AUTO_UPDATE"),
-        update_url: bldr_url(m),
-        update_channel: channel(m),
-        http_disable: m.is_present("HTTP_DISABLE"),
-        organization: m.value_of("ORGANIZATION").map(str::to_string),
-        gossip_permanent: m.is_present("PERMANENT_PEER"),
-        ring_key: get_ring_key(m)?,
-        gossip_peers: get_peers(m)?,
-        watch_peer_file: m.value_of("PEER_WATCH_FILE").map(str::to_string),
-        // TODO: Refactor this to remove the duplication
-        gossip_listen: m.value_of("LISTEN_GOSSIP").map_or_else(
-            || {
-                let default = sup::config::GossipListenAddr::default();
-                error!(
-                    "Value for LISTEN_GOSSIP has not been set. Using default: {}",
-                    default
-                );
-                Ok(default)
-            },
-            |v| v.parse(),
-        )?,
-        ctl_listen: m.value_of("LISTEN_CTL").map_or_else(
-            || {
-                let default = common::types::ListenCtlAddr::default();
-                error!(
-                    "Value for LISTEN_CTL has not been set. Using default: {}",
-                    default
-                );
-                Ok(default)
-            },
-            |v| v.parse(),
+        auto_update: m.is_present("AUTO_UPDATE") || file.auto_update.unwrap_or(false),
+        update_url: bldr_url_from_input(m)
+            .or(file.bldr_url)
+            .unwrap_or_else(default_bldr_url),
+        update_channel: channel_from_input(m)
+            .or(file.channel.map(ChannelIdent::from))
+            .unwrap_or_default(),
+        update_windows: if m.is_present("AUTO_UPDATE_WINDOW") {
+            get_update_windows(m)
+        } else {
+            file.auto_update_windows
+        },
+        update_splay: m
+            .value_of("AUTO_UPDATE_SPLAY")
+            .and_then(|s| s.parse().ok())
+            .or(file.auto_update_splay)
+            .unwrap_or(0),
+        http_disable: m.is_present("HTTP_DISABLE") || file.http_disable.unwrap_or(false),
+        organization: m
+            .value_of("ORGANIZATION")
+            .map(str::to_string)
+            .or(file.organization),
+        gossip_permanent: m.is_present("PERMANENT_PEER") || file.permanent_peer.unwrap_or(false),
+        ring_key: get_ring_key(m, file.ring.as_ref().map(String::as_str))?,
+        gossip_peers: get_peers(m, &file.peers)?,
+        watch_peer_file: m
+            .value_of("PEER_WATCH_FILE")
+            .map(str::to_string)
+            .or(file.peer_watch_file),
+        gossip_listen: get_listen_addr(
+            m,
+            "LISTEN_GOSSIP",
+            GOSSIP_LISTEN_ADDRESS_ENVVAR,
+            file.listen_gossip,
         )?,
-        http_listen: m.value_of("LISTEN_HTTP").map_or_else(
-            || {
-                let default = sup::http_gateway::ListenAddr::default();
-                error!(
-                    "Value for LISTEN_HTTP has not been set. Using default: {}",
-                    default
-                );
-                Ok(default)
-            },
-            |v| v.parse(),
+        ctl_listen: get_listen_addr(m, "LISTEN_CTL", ListenCtlAddr::ENVVAR, file.listen_ctl)?,
+        http_listen: get_listen_addr(
+            m,
+            "LISTEN_HTTP",
+            LISTEN_HTTP_ADDRESS_ENVVAR,
+            file.listen_http,
         )?,
-        tls_files: m.value_of("KEY_FILE").and_then(|kf| {
-            Some((
+        tls_files: match m.value_of("KEY_FILE") {
+            Some(kf) => Some((
                 PathBuf::from(kf),
                 PathBuf::from(
                     m.value_of("CERT_FILE")
                         .expect("CERT_FILE should always have a value if KEY_FILE has a value."),
                 ),
-            ))
-        }),
+            )),
+            None => match (file.key_file, file.cert_file) {
+                (Some(key_file), Some(cert_file)) => Some((key_file, cert_file)),
+                _ => None,
+            },
+        },
+        ctl_tls_files: match m.value_of("CTL_SERVER_KEY") {
+            Some(key) => Some(CtlTlsFiles {
+                key: PathBuf::from(key),
+                cert: PathBuf::from(m.value_of("CTL_SERVER_CERTIFICATE").expect(
+                    "CTL_SERVER_CERTIFICATE should always have a value if CTL_SERVER_KEY has a \
+                     value.",
+                )),
+                ca_cert: PathBuf::from(m.value_of("CTL_CLIENT_CA_CERTIFICATE").expect(
+                    "CTL_CLIENT_CA_CERTIFICATE should always have a value if CTL_SERVER_KEY has \
+                     a value.",
+                )),
+            }),
+            None => match (
+                file.ctl_server_key,
+                file.ctl_server_certificate,
+                file.ctl_client_ca_certificate,
+            ) {
+                (Some(key), Some(cert), Some(ca_cert)) => Some(CtlTlsFiles {
+                    key: key,
+                    cert: cert,
+                    ca_cert: ca_cert,
+                }),
+                _ => None,
+            },
+        },
+        event_sinks: get_event_sinks(m, file.event_stream_file, file.event_stream_socket),
         // default is only included here for the custom_state_path field which will ideally eventually
         // be removed, it only exists to manipulate test data.
         ..Default::default()
@@ -284,37 +339,87 @@ fn mgrcfg_from_sup_run_matches(m: &ArgMatches) -> Result<ManagerConfig> {
 // Various CLI Parsing Functions
 ////////////////////////////////////////////////////////////////////////
 
-fn get_peers(matches: &ArgMatches) -> Result<Vec<SocketAddr>> {
+fn get_peers(matches: &ArgMatches, from_file: &[String]) -> Result<Vec<SocketAddr>> {
     // TODO fn: Clean this up--using a for loop doesn't feel good however an iterator was
     // causing a lot of developer/compiler type confusion
     let mut gossip_peers = Vec::new();
-    if let Some(peers) = matches.values_of("PEER") {
-        for peer in peers {
-            let peer_addr = if peer.find(':').is_some() {
-                peer.to_string()
-            } else {
-                format!("{}:{}", peer, GOSSIP_DEFAULT_PORT)
-            };
-            let addrs: Vec<SocketAddr> = match peer_addr.to_socket_addrs() {
-                Ok(addrs) => addrs.collect(),
-                Err(e) => {
-                    outputln!("Failed to resolve peer: {}", peer_addr);
-                    return Err(sup_error!(Error::NameLookup(e)));
-                }
-            };
-            if let Some(addr) = addrs.get(0) {
-                gossip_peers.push(*addr);
+    let peers: Vec<&str> = match matches.values_of("PEER") {
+        Some(peers) => peers.collect(),
+        None => from_file.iter().map(String::as_str).collect(),
+    };
+    for peer in peers {
+        let peer_addr = if peer.find(':').is_some() {
+            peer.to_string()
+        } else {
+            format!("{}:{}", peer, GOSSIP_DEFAULT_PORT)
+        };
+        let addrs: Vec<SocketAddr> = match peer_addr.to_socket_addrs() {
+            Ok(addrs) => addrs.collect(),
+            Err(e) => {
+                outputln!("Failed to resolve peer: {}", peer_addr);
+                return Err(sup_error!(Error::NameLookup(e)));
             }
+        };
+        if let Some(addr) = addrs.get(0) {
+            gossip_peers.push(*addr);
         }
     }
     Ok(gossip_peers)
 }
 
+fn get_event_sinks(
+    m: &ArgMatches,
+    file_from_file: Option<PathBuf>,
+    socket_from_file: Option<PathBuf>,
+) -> Vec<EventSinkConfig> {
+    let mut sinks = Vec::new();
+    if let Some(path) = m
+        .value_of("EVENT_STREAM_FILE")
+        .map(PathBuf::from)
+        .or(file_from_file)
+    {
+        sinks.push(EventSinkConfig::File(path));
+    }
+    if let Some(path) = m
+        .value_of("EVENT_STREAM_SOCKET")
+        .map(PathBuf::from)
+        .or(socket_from_file)
+    {
+        sinks.push(EventSinkConfig::UnixSocket(path));
+    }
+    sinks
+}
+
+/// Resolve a listen address which has a default value, giving a value from the config file
+/// precedence over the default but not over a flag or the environment.
+fn get_listen_addr<T>(m: &ArgMatches, name: &str, envvar: &str, from_file: Option<T>) -> Result<T>
+where
+    T: FromStr + Default + fmt::Display,
+    SupError: From<T::Err>,
+{
+    let from_input =
+        m.occurrences_of(name) > 0 || henv::var(envvar).map_or(false, |v| !v.is_empty());
+    match from_file {
+        Some(addr) if !from_input => Ok(addr),
+        _ => match m.value_of(name) {
+            Some(v) => Ok(v.parse()?),
+            None => {
+                let default = T::default();
+                error!(
+                    "Value for {} has not been set. Using default: {}",
+                    name, default
+                );
+                Ok(default)
+            }
+        },
+    }
+}
+
 // TODO: Make this more testable.
 // The use of env variables here makes it difficult to unit test. Since tests are run in parallel, setting an env var in one test
 // can adversely effect the results in another test. We need some additional abstractions written around env vars in order to make
 // them more testable.
-fn get_ring_key(m: &ArgMatches) -> Result<Option<SymKey>> {
+fn get_ring_key(m: &ArgMatches, ring_from_file: Option<&str>) -> Result<Option<SymKey>> {
     match m.value_of("RING") {
         Some(val) => {
             let key = SymKey::get_latest_pair_for(&val, &default_cache_key_path(None))?;
@@ -325,7 +430,13 @@ fn get_ring_key(m: &ArgMatches) -> Result<Option<SymKey>> {
                 let (key, _) = SymKey::write_file_from_str(&val, &default_cache_key_path(None))?;
                 Ok(Some(key))
             }
-            None => Ok(None),
+            None => match ring_from_file {
+                Some(val) => {
+                    let key = SymKey::get_latest_pair_for(&val, &default_cache_key_path(None))?;
+                    Ok(Some(key))
+                }
+                None => Ok(None),
+            },
         },
     }
 }
@@ -388,6 +499,19 @@ fn get_strategy_from_input(m: &ArgMatches) -> Option<UpdateStrategy> {
         .and_then(|f| UpdateStrategy::from_str(f).ok())
 }
 
+fn get_idents_from_input(m: &ArgMatches, name: &str) -> Result<Vec<PackageIdent>> {
+    m.values_of(name)
+        .into_iter()
+        .flatten()
+        .map(|ident| Ok(hcore::package::PackageIdent::from_str(ident)?.into()))
+        .collect()
+}
+
+fn get_shutdown_signal_from_input(m: &ArgMatches) -> Option<ShutdownSignal> {
+    m.value_of("SHUTDOWN_SIGNAL")
+        .and_then(|s| ShutdownSignal::from_str(s).ok())
+}
+
 fn get_binds_from_input(m: &ArgMatches) -> Result<Option<ServiceBindList>> {
     match m.values_of("BIND") {
         Some(bind_strs) => {
@@ -407,6 +531,127 @@ fn get_binding_mode_from_input(m: &ArgMatches) -> Option<BindingMode> {
         .and_then(|b| BindingMode::from_str(b).ok())
 }
 
+fn get_update_windows(m: &ArgMatches) -> Vec<UpdateWindow> {
+    // Values will have already been validated by `valid_update_window`
+    m.values_of("AUTO_UPDATE_WINDOW")
+        .map(|w| w.filter_map(|w| w.parse().ok()).collect())
+        .unwrap_or_default()
+}
+
+fn get_update_settings_from_input(m: &ArgMatches) -> Option<UpdateSettings> {
+    // Values will have already been validated
+    let settings = UpdateSettings {
+        batch_size: m.value_of("UPDATE_BATCH_SIZE").map(ToString::to_string),
+        canary_soak: m.value_of("CANARY_SOAK").and_then(|s| s.parse().ok()),
+        windows: m
+            .values_of("UPDATE_WINDOW")
+            .map(|w| w.map(ToString::to_string).collect())
+            .unwrap_or_default(),
+        splay: m.value_of("UPDATE_SPLAY").and_then(|s| s.parse().ok()),
+        rollback_period: m.value_of("ROLLBACK_PERIOD").and_then(|s| s.parse().ok()),
+    };
+    if settings == UpdateSettings::default() {
+        None
+    } else {
+        Some(settings)
+    }
+}
+
+fn get_restart_policy_from_input(m: &ArgMatches) -> Option<RestartPolicy> {
+    // Values will have already been validated by `valid_numeric`
+    let policy = RestartPolicy {
+        max_restarts: m.value_of("MAX_RESTARTS").and_then(|s| s.parse().ok()),
+        window: m.value_of("RESTART_WINDOW").and_then(|s| s.parse().ok()),
+        backoff: m.value_of("RESTART_BACKOFF").and_then(|s| s.parse().ok()),
+        backoff_max: m
+            .value_of("RESTART_BACKOFF_MAX")
+            .and_then(|s| s.parse().ok()),
+    };
+    if policy == RestartPolicy::default() {
+        None
+    } else {
+        Some(policy)
+    }
+}
+
+fn get_health_check_thresholds_from_input(m: &ArgMatches) -> Option<HealthCheckThresholds> {
+    // Values will have already been validated by `valid_numeric`
+    let thresholds = HealthCheckThresholds {
+        failures: m
+            .value_of("HEALTH_CHECK_FAILURES")
+            .and_then(|s| s.parse().ok()),
+        successes: m
+            .value_of("HEALTH_CHECK_SUCCESSES")
+            .and_then(|s| s.parse().ok()),
+        grace_period: m
+            .value_of("HEALTH_CHECK_GRACE_PERIOD")
+            .and_then(|s| s.parse().ok()),
+        restart_after: m
+            .value_of("HEALTH_CHECK_RESTART_AFTER")
+            .and_then(|s| s.parse().ok()),
+    };
+    if thresholds == HealthCheckThresholds::default() {
+        None
+    } else {
+        Some(thresholds)
+    }
+}
+
+fn get_health_probe_from_input(m: &ArgMatches) -> Option<HealthProbe> {
+    // Values will have already been validated by `valid_health_probe`
+    m.value_of("HEALTH_PROBE").map(|target| HealthProbe {
+        target: target.to_string(),
+        timeout: m
+            .value_of("HEALTH_PROBE_TIMEOUT")
+            .and_then(|s| s.parse().ok()),
+    })
+}
+
+fn get_log_settings_from_input(m: &ArgMatches) -> Option<LogSettings> {
+    // Values will have already been validated by the cli
+    let settings = LogSettings {
+        enabled: if m.is_present("LOG_FILE") {
+            Some(true)
+        } else {
+            None
+        },
+        max_size: m
+            .value_of("LOG_MAX_SIZE")
+            .and_then(|s| ResourceLimits::parse_memory(s).ok()),
+        max_age: m.value_of("LOG_MAX_AGE").and_then(|s| s.parse().ok()),
+        retain: m.value_of("LOG_RETAIN").and_then(|s| s.parse().ok()),
+        compress: if m.is_present("NO_LOG_COMPRESS") {
+            Some(false)
+        } else {
+            None
+        },
+    };
+    if settings == LogSettings::default() {
+        None
+    } else {
+        Some(settings)
+    }
+}
+
+fn get_resource_limits_from_input(m: &ArgMatches) -> Option<ResourceLimits> {
+    // Values will have already been validated by the cli
+    let limits = ResourceLimits {
+        memory_max: m
+            .value_of("MEMORY_MAX")
+            .and_then(|s| ResourceLimits::parse_memory(s).ok()),
+        cpu_weight: m.value_of("CPU_WEIGHT").and_then(|s| s.parse().ok()),
+        cpu_quota: m
+            .value_of("CPU_QUOTA")
+            .and_then(|s| ResourceLimits::parse_cpu_quota(s).ok()),
+        pids_max: m.value_of("PIDS_MAX").and_then(|s| s.parse().ok()),
+    };
+    if limits == ResourceLimits::default() {
+        None
+    } else {
+        Some(limits)
+    }
+}
+
 fn get_config_from_input(m: &ArgMatches) -> Option<String> {
     if let Some(ref config_from) = m.value_of("CONFIG_DIR") {
         warn!("");
@@ -518,6 +763,21 @@ fn update_svc_load_from_input(m: &ArgMatches, msg: &mut protocol::ctl::SvcLoad)
     msg.binding_mode = get_binding_mode_from_input(m).map(|v| v as i32);
     msg.topology = get_topology_from_input(m).map(|v| v as i32);
     msg.update_strategy = get_strategy_from_input(m).map(|v| v as i32);
+    msg.update_settings = get_update_settings_from_input(m);
+    msg.restart_policy = get_restart_policy_from_input(m);
+    msg.resource_limits = get_resource_limits_from_input(m);
+    msg.log_settings = get_log_settings_from_input(m);
+    msg.health_check_thresholds = get_health_check_thresholds_from_input(m);
+    msg.health_probe = get_health_probe_from_input(m);
+    msg.shutdown_signal = get_shutdown_signal_from_input(m).map(|v| v as i32);
+    msg.shutdown_timeout = m.value_of("SHUTDOWN_TIMEOUT").and_then(|s| s.parse().ok());
+    msg.pre_start_timeout = m.value_of("PRE_START_TIMEOUT").and_then(|s| s.parse().ok());
+    msg.pre_stop_timeout = m.value_of("PRE_STOP_TIMEOUT").and_then(|s| s.parse().ok());
+    msg.after = get_idents_from_input(m, "AFTER")?;
+    msg.requires = get_idents_from_input(m, "REQUIRES")?;
+    if m.is_present("REQUIRE_HEALTHY") {
+        msg.require_healthy = Some(true);
+    }
     Ok(())
 }
 
@@ -532,6 +792,7 @@ mod test {
     mod manager_config {
 
         use super::*;
+        use std::fs::File;
         use std::iter::FromIterator;
 
         locked_env_var!(HAB_CACHE_KEY_PATH, lock_var);
@@ -588,6 +849,126 @@ mod test {
             assert_eq!(config.update_channel, ChannelIdent::stable());
         }
 
+        #[test]
+        fn update_windows_and_splay_should_be_set() {
+            let config = config_from_cmd_vec(vec![
+                "hab-sup",
+                "run",
+                "--auto-update",
+                "--auto-update-window",
+                "sat,sun 01:00-05:00",
+                "--auto-update-window",
+                "wed 12:00-13:00",
+                "--auto-update-splay",
+                "600",
+            ]);
+            assert_eq!(
+                config.update_windows,
+                vec![
+                    "sat,sun 01:00-05:00".parse::<UpdateWindow>().unwrap(),
+                    "wed 12:00-13:00".parse::<UpdateWindow>().unwrap(),
+                ]
+            );
+            assert_eq!(config.update_splay, 600);
+        }
+
+        #[test]
+        fn ctl_tls_files_should_be_set() {
+            let tmpdir = TempDir::new().expect("Could not create tempdir");
+            let path = |name: &str| {
+                let path = tmpdir.path().join(name);
+                File::create(&path).expect("Could not create file");
+                path
+            };
+            let (key, cert, ca_cert) = (path("ctl.key"), path("ctl.crt"), path("ca.crt"));
+            let config = config_from_cmd_vec(vec![
+                "hab-sup",
+                "run",
+                "--ctl-server-key",
+                key.to_str().unwrap(),
+                "--ctl-server-certificate",
+                cert.to_str().unwrap(),
+                "--ctl-client-ca-certificate",
+                ca_cert.to_str().unwrap(),
+            ]);
+            assert_eq!(
+                config.ctl_tls_files,
+                Some(CtlTlsFiles {
+                    key,
+                    cert,
+                    ca_cert,
+                })
+            );
+        }
+
+        fn config_file(tmpdir: &TempDir, contents: &str) -> String {
+            let path = tmpdir.path().join("config.toml");
+            let mut file = File::create(&path).expect("Could not create file");
+            file.write_all(contents.as_bytes())
+                .expect("Could not write file");
+            path.to_string_lossy().into_owned()
+        }
+
+        #[test]
+        fn config_file_settings_should_be_set() {
+            let tmpdir = TempDir::new().expect("Could not create tempdir");
+            let path = config_file(
+                &tmpdir,
+                r#"
+                organization = "acme"
+                auto_update = true
+                auto_update_splay = 600
+                channel = "unstable"
+                listen_http = "127.0.0.1:9876"
+                "#,
+            );
+            let config = config_from_cmd_vec(vec!["hab-sup", "run", "--config", &path]);
+            assert_eq!(config.organization, Some("acme".to_string()));
+            assert_eq!(config.auto_update, true);
+            assert_eq!(config.update_splay, 600);
+            assert_eq!(config.update_channel, ChannelIdent::unstable());
+            assert_eq!(
+                config.http_listen,
+                http_gateway::ListenAddr::from_str("127.0.0.1:9876").unwrap()
+            );
+            assert_eq!(config.update_url, default_bldr_url());
+        }
+
+        #[test]
+        fn flags_take_precedence_over_config_file() {
+            let tmpdir = TempDir::new().expect("Could not create tempdir");
+            let path = config_file(
+                &tmpdir,
+                r#"
+                organization = "acme"
+                channel = "unstable"
+                listen_http = "127.0.0.1:9876"
+                "#,
+            );
+            let config = config_from_cmd_vec(vec![
+                "hab-sup",
+                "run",
+                "--config",
+                &path,
+                "--org",
+                "globex",
+                "--listen-http",
+                "127.0.0.1:5432",
+            ]);
+            assert_eq!(config.organization, Some("globex".to_string()));
+            assert_eq!(config.update_channel, ChannelIdent::unstable());
+            assert_eq!(
+                config.http_listen,
+                http_gateway::ListenAddr::from_str("127.0.0.1:5432").unwrap()
+            );
+        }
+
+        #[test]
+        fn ctl_tls_files_are_not_set_by_default() {
+            let config = config_from_cmd_str("hab-sup run");
+            assert_eq!(config.ctl_tls_files, None);
+        }
+
         #[test]
         fn gossip_listen_should_be_set() {
             let config = config_from_cmd_str("hab-sup run --listen-gossip 1.1.1.1:1111");
@@ -690,6 +1071,24 @@ mod test {
             assert_eq!(config.watch_peer_file, None);
         }
 
+        #[test]
+        fn event_sinks_should_be_set() {
+            let config = config_from_cmd_str(
+                "hab-sup run --event-stream-file /tmp/events.ndjson \
+                 --event-stream-socket /tmp/events.sock",
+            );
+            assert_eq!(
+                config.event_sinks,
+                vec![
+                    EventSinkConfig::File(PathBuf::from("/tmp/events.ndjson")),
+                    EventSinkConfig::UnixSocket(PathBuf::from("/tmp/events.sock")),
+                ]
+            );
+
+            let config = config_from_cmd_str("hab-sup run");
+            assert_eq!(config.event_sinks, vec![]);
+        }
+
         #[test]
         fn ring_key_is_set_properly_by_name() {
             let key_cache = TempDir::new().expect("Could not create tempdir");
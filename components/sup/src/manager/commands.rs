@@ -14,21 +14,26 @@
 
 //! All the code for responding to Supervisor commands
 
+use crate::audit;
 use crate::butterfly;
 use crate::common::{command::package::install::InstallSource, ui::UIWriter};
 use crate::ctl_gateway::CtlRequest;
-use crate::error::{Error, Result};
+use crate::error::{Error, Result, SupError};
 use crate::hcore::{
+    crypto::default_cache_key_path,
     package::{Identifiable, PackageIdent, PackageTarget},
     service::ServiceGroup,
     ChannelIdent,
 };
 use crate::manager::{
     service::{
-        spec::{IntoServiceSpec, ServiceSpec},
-        DesiredState, Pkg, ProcessState,
+        secrets::SecretStore,
+        spec::{dependency_cycle, same_package, IntoServiceSpec, ServiceManifest, ServiceSpec},
+        DesiredState, LogReader, LogSettings, Pkg, ProcessState, Service, UpdateSettings,
+        UpdateStrategy,
     },
-    ManagerConfig, ManagerState,
+    spec_dir::SpecDir,
+    Manager, ManagerConfig, ManagerState,
 };
 use crate::protocol::{
     self,
@@ -36,12 +41,25 @@ use crate::protocol::{
 };
 use crate::util;
 use serde_json;
-use std::{fmt, fs, path::PathBuf, result};
+use std::{
+    fmt, fs,
+    path::PathBuf,
+    result,
+    str::FromStr,
+    thread,
+    time::{Duration, Instant},
+};
 use time::{self, Duration as TimeDuration, Timespec};
 use toml;
 
 static LOGKEY: &'static str = "CMD";
 
+/// How often a followed log file is checked for new lines.
+const LOG_FOLLOW_INTERVAL: Duration = Duration::from_millis(500);
+/// How long a followed log goes without sending the client anything. A client which has gone away
+/// is only noticed when something is sent to it, so a quiet log sends an empty line instead.
+const LOG_FOLLOW_HEARTBEAT: Duration = Duration::from_secs(5);
+
 pub fn service_cfg(
     mgr: &ManagerState,
     req: &mut CtlRequest,
@@ -174,6 +192,36 @@ pub fn service_cfg_set(
     }
 }
 
+pub fn service_secret_set(
+    mgr: &ManagerState,
+    req: &mut CtlRequest,
+    opts: protocol::ctl::SvcSecretSet,
+) -> NetResult<()> {
+    let service_group: ServiceGroup = opts.service_group.ok_or_else(err_update_client)?.into();
+    let name = opts.name.ok_or_else(err_update_client)?;
+    let value = opts.value.ok_or_else(err_update_client)?;
+    outputln!("Setting secret {} for {}", name, service_group);
+    SecretStore::new(mgr.cfg.sup_root(), &service_group).set(
+        &name,
+        &value,
+        &default_cache_key_path(None),
+    )?;
+    // The services of the group pick up the secret, and re-render their templates, on their next
+    // tick.
+    for service in mgr
+        .services
+        .write()
+        .expect("Services lock is poisoned")
+        .values_mut()
+        .filter(|service| service.service_group == service_group)
+    {
+        service.secrets_updated = true;
+    }
+    req.info(format!("Secret {} set for {}", name, service_group))?;
+    req.reply_complete(net::ok());
+    Ok(())
+}
+
 pub fn service_file_put(
     mgr: &ManagerState,
     req: &mut CtlRequest,
@@ -228,6 +276,12 @@ pub fn service_load(
         .map(ChannelIdent::from)
         .unwrap_or_default();
     let force = opts.force.unwrap_or(false);
+    if let Some(ref probe) = opts.health_probe {
+        protocol::types::HealthProbe::from_str(&probe.target)?;
+    }
+    if let Some(ref update_settings) = opts.update_settings {
+        UpdateSettings::validate(update_settings)?;
+    }
     let source = InstallSource::Ident(ident.clone(), *PackageTarget::active_target());
     match spec_for_ident(&mgr.cfg, source.as_ref()) {
         None => {
@@ -240,6 +294,7 @@ pub fn service_load(
             // desired package identifier, it will be used;
             // otherwise, we'll install the latest suitable
             // version from the specified Builder channel.
+            check_dependencies_with_loaded(&mgr.cfg, &spec)?;
             util::pkg::satisfy_or_install(req, &source, &bldr_url, &bldr_channel)?;
 
             save_spec_for(&mgr.cfg, &spec)?;
@@ -264,6 +319,7 @@ pub fn service_load(
             }
 
             opts.into_spec(&mut spec);
+            check_dependencies_with_loaded(&mgr.cfg, &spec)?;
 
             // Only install if we don't have something
             // locally; otherwise you could potentially
@@ -284,6 +340,82 @@ pub fn service_load(
     Ok(())
 }
 
+/// Brings the loaded services in line with a manifest of service specs: services missing from
+/// the Supervisor are loaded, those whose specs differ are updated, and, when pruning, those
+/// missing from the manifest are unloaded. The Supervisor then starts, restarts, or stops them
+/// just as it does when their spec files are edited.
+pub fn service_apply(
+    mgr: &ManagerState,
+    req: &mut CtlRequest,
+    opts: protocol::ctl::SvcApply,
+) -> NetResult<()> {
+    let manifest: ServiceManifest = opts
+        .manifest
+        .ok_or_else(err_update_client)?
+        .parse()
+        .map_err(|e: SupError| net::err(ErrCode::InvalidPayload, e.to_string()))?;
+    let prune = opts.prune.unwrap_or(false);
+    let dry_run = opts.dry_run.unwrap_or(false);
+
+    // The same checks as `service_load` makes of a single service.
+    for spec in manifest.services.iter() {
+        if let Some(ref probe) = spec.health_probe {
+            protocol::types::HealthProbe::from_str(&probe.target.to_string())?;
+        }
+    }
+    let loaded = SpecDir::new(specs_path(&mgr.cfg))?.specs()?;
+    let desired = manifest.desired_specs(&loaded, prune);
+    check_dependencies(&desired)?;
+    let running: Vec<ServiceSpec> = mgr
+        .services
+        .read()
+        .expect("Services lock is poisoned")
+        .values()
+        .map(Service::to_spec)
+        .collect();
+    let mut operations = Manager::specs_to_operations(running, desired);
+    operations.sort_by_key(ToString::to_string);
+
+    if !dry_run {
+        let changed: Vec<&ServiceSpec> = manifest
+            .services
+            .iter()
+            .filter(|spec| !loaded.contains(spec))
+            .collect();
+        // Every package is installed before any spec file is written, so that a failed install
+        // leaves the loaded services as they were rather than with part of the manifest applied.
+        for spec in changed.iter() {
+            let source = InstallSource::Ident(spec.ident.clone(), *PackageTarget::active_target());
+            util::pkg::satisfy_or_install(req, &source, &spec.bldr_url, &spec.channel)?;
+        }
+        for spec in changed {
+            save_spec_for(&mgr.cfg, spec)?;
+        }
+        if prune {
+            for spec in loaded.iter().filter(|spec| !manifest.lists(spec)) {
+                let file = spec_path_for(&mgr.cfg, spec);
+                if let Err(err) = fs::remove_file(&file) {
+                    return Err(net::err(
+                        ErrCode::Internal,
+                        format!("{}", sup_error!(Error::ServiceSpecFileIO(file, err))),
+                    ));
+                }
+            }
+        }
+    }
+
+    for operation in operations.iter() {
+        req.info(operation.to_string())?;
+    }
+    if dry_run {
+        req.info("Dry run, no services were changed")?;
+    } else {
+        req.info("The manifest was successfully applied")?;
+    }
+    req.reply_complete(net::ok());
+    Ok(())
+}
+
 pub fn service_unload(
     mgr: &ManagerState,
     req: &mut CtlRequest,
@@ -370,6 +502,62 @@ pub fn service_stop(
     Ok(())
 }
 
+pub fn service_update_pause(
+    mgr: &ManagerState,
+    req: &mut CtlRequest,
+    opts: protocol::ctl::SvcUpdatePause,
+) -> NetResult<()> {
+    let ident: PackageIdent = opts.ident.ok_or_else(err_update_client)?.into();
+    set_update_paused(mgr, req, &ident, true)
+}
+
+pub fn service_update_resume(
+    mgr: &ManagerState,
+    req: &mut CtlRequest,
+    opts: protocol::ctl::SvcUpdateResume,
+) -> NetResult<()> {
+    let ident: PackageIdent = opts.ident.ok_or_else(err_update_client)?.into();
+    set_update_paused(mgr, req, &ident, false)
+}
+
+pub fn service_update_now(
+    mgr: &ManagerState,
+    req: &mut CtlRequest,
+    opts: protocol::ctl::SvcUpdateNow,
+) -> NetResult<()> {
+    let ident: PackageIdent = opts.ident.ok_or_else(err_update_client)?.into();
+    let spec = updatable_spec_for_ident(&mgr.cfg, &ident)?;
+    if spec.update_paused {
+        return Err(net::err(
+            ErrCode::Conflict,
+            format!(
+                "Updates to {} are paused. Resume them with `hab svc update resume` first.",
+                &ident
+            ),
+        ));
+    }
+    match mgr
+        .services
+        .write()
+        .expect("Services lock is poisoned")
+        .get_mut(&spec.ident)
+    {
+        Some(service) => service.update_check_requested = true,
+        None => {
+            return Err(net::err(
+                ErrCode::NotFound,
+                format!("Service not running, {}", &ident),
+            ));
+        }
+    }
+    req.info(format!(
+        "Supervisor checking for updates to {}. See the Supervisor output for more details.",
+        &ident
+    ))?;
+    req.reply_complete(net::ok());
+    Ok(())
+}
+
 pub fn supervisor_depart(
     mgr: &ManagerState,
     req: &mut CtlRequest,
@@ -396,23 +584,105 @@ pub fn supervisor_depart(
     }
 }
 
+pub fn supervisor_audit(
+    mgr: &ManagerState,
+    req: &mut CtlRequest,
+    opts: protocol::ctl::SupAudit,
+) -> NetResult<()> {
+    let path = audit::path(mgr.cfg.sup_root());
+    if opts.verify.unwrap_or(false) {
+        let entries = audit::read(&path).map_err(|e| {
+            net::err(
+                ErrCode::Io,
+                format!("Unable to read audit log, {}, {}", path.display(), e),
+            )
+        })?;
+        let key = audit::load_key(&path).map_err(|e| {
+            net::err(
+                ErrCode::Io,
+                format!("Unable to read audit key for {}, {}", path.display(), e),
+            )
+        })?;
+        match audit::read_anchor(&path) {
+            Ok(anchor) => {
+                audit::verify(&entries, &key, anchor.as_ref())
+                    .map_err(|e| net::err(ErrCode::Internal, e))?;
+                req.reply_partial(console_line(format!(
+                    "Verified the MAC chain of {} audit log entries, up to its latest recorded \
+                     entry",
+                    entries.len()
+                )));
+            }
+            Err(err) => {
+                audit::verify(&entries, &key, None).map_err(|e| net::err(ErrCode::Internal, e))?;
+                return Err(net::err(
+                    ErrCode::Internal,
+                    format!(
+                        "Verified the MAC chain of {} audit log entries, but the audit log is \
+                         unanchored, so entries removed from its end would go unnoticed: {}",
+                        entries.len(),
+                        err
+                    ),
+                ));
+            }
+        }
+    }
+
+    let matching = audit::read_latest(
+        &path,
+        opts.tail.map(|tail| tail as usize),
+        opts.since,
+        |e| {
+            opts.actor.as_ref().map_or(true, |actor| &e.actor == actor)
+                && opts
+                    .request
+                    .as_ref()
+                    .map_or(true, |request| &e.request == request)
+                && opts
+                    .since
+                    .map_or(true, |since| e.recorded_at().map_or(true, |t| t >= since))
+        },
+    )
+    .map_err(|e| {
+        net::err(
+            ErrCode::Io,
+            format!("Unable to read audit log, {}, {}", path.display(), e),
+        )
+    })?;
+    for entry in &matching {
+        let line = serde_json::to_string(entry).map_err(|e| net::err(ErrCode::Internal, e))?;
+        req.reply_partial(console_line(line));
+    }
+    req.reply_complete(net::ok());
+    Ok(())
+}
+
 pub fn service_status(
     mgr: &ManagerState,
     req: &mut CtlRequest,
     opts: protocol::ctl::SvcStatus,
 ) -> NetResult<()> {
-    let services_data = &mgr
+    let gateway_state = mgr
         .gateway_state
         .read()
-        .expect("GatewayState lock is poisoned")
-        .services_data;
-    let statuses: Vec<ServiceStatus> = serde_json::from_str(&services_data)
+        .expect("GatewayState lock is poisoned");
+    let statuses: Vec<ServiceStatus> = serde_json::from_str(&gateway_state.services_data)
         .map_err(|e| sup_error!(Error::ServiceDeserializationError(e)))?;
+    let to_proto = |status: ServiceStatus| {
+        let waiting_on = gateway_state
+            .waiting_on
+            .iter()
+            .find(|(spec_ident, _)| status.pkg.ident.satisfies(*spec_ident))
+            .map(|(_, dep)| dep.clone().into());
+        let mut msg: protocol::types::ServiceStatus = status.into();
+        msg.waiting_on = waiting_on;
+        msg
+    };
 
     if let Some(ident) = opts.ident {
         for status in statuses {
             if status.pkg.ident.satisfies(&ident) {
-                let msg: protocol::types::ServiceStatus = status.into();
+                let msg = to_proto(status);
                 req.reply_complete(msg);
                 return Ok(());
             }
@@ -429,7 +699,7 @@ pub fn service_status(
     } else {
         let mut list = statuses.into_iter().peekable();
         while let Some(status) = list.next() {
-            let msg: protocol::types::ServiceStatus = status.into();
+            let msg = to_proto(status);
             if list.peek().is_some() {
                 req.reply_partial(msg);
             } else {
@@ -440,20 +710,174 @@ pub fn service_status(
     Ok(())
 }
 
+pub fn service_logs(
+    mgr: &ManagerState,
+    req: &mut CtlRequest,
+    opts: protocol::ctl::SvcLogs,
+) -> NetResult<()> {
+    let ident: PackageIdent = opts.ident.ok_or_else(err_update_client)?.into();
+    let spec = match spec_for_ident(&mgr.cfg, &ident) {
+        Some(spec) => spec,
+        None => {
+            return Err(net::err(
+                ErrCode::NotFound,
+                format!("Service not loaded, {}", &ident),
+            ));
+        }
+    };
+    if !spec.log_settings.enabled {
+        return Err(net::err(
+            ErrCode::NotSupported,
+            format!(
+                "{} doesn't write its output to a log file. Load it with --log-file to view its \
+                 logs.",
+                &ident
+            ),
+        ));
+    }
+
+    let mut reader = LogReader::new(LogSettings::path(&spec.ident.name), opts.since);
+    let lines = reader.read_existing(opts.tail.map(|t| t as usize))?;
+    if !opts.follow.unwrap_or(false) {
+        for line in lines {
+            req.reply_partial(console_line(line));
+        }
+        req.reply_complete(net::ok());
+        return Ok(());
+    }
+
+    // Following never completes, so it can't hold up the main thread. The transaction stays
+    // open until the client goes away.
+    let mut req = req.clone();
+    thread::Builder::new()
+        .name(format!("svc-logs-{}", &spec.ident.name))
+        .spawn(move || {
+            let mut lines = lines;
+            let mut last_sent = Instant::now();
+            loop {
+                if lines.is_empty() && last_sent.elapsed() >= LOG_FOLLOW_HEARTBEAT {
+                    let heartbeat = protocol::ctl::ConsoleLine {
+                        line: String::new(),
+                    };
+                    if !req.try_reply_partial(heartbeat) {
+                        return;
+                    }
+                    last_sent = Instant::now();
+                }
+                for line in lines.drain(..) {
+                    if !req.try_reply_partial(console_line(line)) {
+                        return;
+                    }
+                    last_sent = Instant::now();
+                }
+                thread::sleep(LOG_FOLLOW_INTERVAL);
+                match reader.read_new() {
+                    Ok(new_lines) => lines = new_lines,
+                    Err(err) => {
+                        req.reply_complete(net::err(
+                            ErrCode::Io,
+                            format!("Unable to read log file, {}", err),
+                        ));
+                        return;
+                    }
+                }
+            }
+        })?;
+    Ok(())
+}
+
 ////////////////////////////////////////////////////////////////////////
 // Private helper functions
+fn console_line(line: String) -> protocol::ctl::ConsoleLine {
+    protocol::ctl::ConsoleLine {
+        line: format!("{}\n", line),
+    }
+}
+
+/// Pauses or resumes the updates of a service by way of its spec, which the Supervisor applies
+/// without restarting the service.
+fn set_update_paused(
+    mgr: &ManagerState,
+    req: &mut CtlRequest,
+    ident: &PackageIdent,
+    paused: bool,
+) -> NetResult<()> {
+    let mut spec = updatable_spec_for_ident(&mgr.cfg, ident)?;
+    if spec.update_paused != paused {
+        spec.update_paused = paused;
+        save_spec_for(&mgr.cfg, &spec)?;
+        req.info(format!(
+            "Supervisor {} updates to {}. See the Supervisor output for more details.",
+            if paused { "pausing" } else { "resuming" },
+            ident
+        ))?;
+    }
+    req.reply_complete(net::ok());
+    Ok(())
+}
+
+/// The spec of a loaded service which has an update strategy.
+fn updatable_spec_for_ident(cfg: &ManagerConfig, ident: &PackageIdent) -> NetResult<ServiceSpec> {
+    let spec = spec_for_ident(cfg, ident)
+        .ok_or_else(|| net::err(ErrCode::NotFound, format!("Service not loaded, {}", ident)))?;
+    if spec.update_strategy == UpdateStrategy::None {
+        return Err(net::err(
+            ErrCode::NotSupported,
+            format!(
+                "{} has no update strategy. Load it with --strategy to update it automatically.",
+                ident
+            ),
+        ));
+    }
+    Ok(spec)
+}
+
 fn err_update_client() -> net::NetErr {
     net::err(ErrCode::UpdateClient, "client out of date")
 }
 
+fn specs_path(cfg: &ManagerConfig) -> PathBuf {
+    cfg.sup_root().join("specs")
+}
+
 fn spec_path_for(cfg: &ManagerConfig, spec: &ServiceSpec) -> PathBuf {
-    cfg.sup_root().join("specs").join(spec.file_name())
+    specs_path(cfg).join(spec.file_name())
 }
 
 fn save_spec_for(cfg: &ManagerConfig, spec: &ServiceSpec) -> Result<()> {
     spec.to_file(spec_path_for(cfg, spec))
 }
 
+/// Fails if loading `spec`, in place of any spec already loaded for its package, would leave
+/// services waiting on each other to start.
+fn check_dependencies_with_loaded(cfg: &ManagerConfig, spec: &ServiceSpec) -> NetResult<()> {
+    let mut specs: Vec<ServiceSpec> = SpecDir::new(specs_path(cfg))?
+        .specs()?
+        .into_iter()
+        .filter(|loaded| !same_package(&loaded.ident, &spec.ident))
+        .collect();
+    specs.push(spec.clone());
+    check_dependencies(&specs)
+}
+
+/// Fails if the services of `specs` would wait on each other to start.
+fn check_dependencies(specs: &[ServiceSpec]) -> NetResult<()> {
+    match dependency_cycle(specs) {
+        Some(cycle) => Err(net::err(
+            ErrCode::InvalidPayload,
+            format!(
+                "Services cannot depend on each other in a cycle, {}",
+                cycle
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(" -> ")
+            ),
+        )),
+        None => Ok(()),
+    }
+}
+
 /// Given a `PackageIdent`, return current spec if it exists.
 fn spec_for_ident(cfg: &ManagerConfig, ident: &PackageIdent) -> Option<ServiceSpec> {
     let default_spec = ServiceSpec::default_for(ident.clone());
@@ -470,14 +894,17 @@ struct ServiceStatus {
     process: ProcessStatus,
     service_group: ServiceGroup,
     desired_state: DesiredState,
+    // Absent from the state written by Supervisors which predate restart policies.
+    #[serde(default)]
+    restarts: RestartStatus,
 }
 
 impl fmt::Display for ServiceStatus {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
             f,
-            "{}, {}, group:{}",
-            self.pkg.ident, self.process, self.service_group,
+            "{}, {}, {}, group:{}",
+            self.pkg.ident, self.process, self.restarts, self.service_group,
         )
     }
 }
@@ -489,6 +916,8 @@ impl From<ServiceStatus> for protocol::types::ServiceStatus {
         proto.process = Some(other.process.into());
         proto.service_group = other.service_group.into();
         proto.desired_state = Some(other.desired_state.into());
+        proto.restarts = Some(other.restarts.count);
+        proto.crash_looping = Some(other.restarts.crash_looping);
         proto
     }
 }
@@ -526,6 +955,22 @@ impl From<ProcessStatus> for protocol::types::ProcessStatus {
     }
 }
 
+#[derive(Default, Deserialize)]
+struct RestartStatus {
+    count: u64,
+    crash_looping: bool,
+}
+
+impl fmt::Display for RestartStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.crash_looping {
+            write!(f, "restarts:{} (crash-looping)", self.count)
+        } else {
+            write!(f, "restarts:{}", self.count)
+        }
+    }
+}
+
 fn deserialize_time<'de, D>(d: D) -> result::Result<TimeDuration, D::Error>
 where
     D: serde::Deserializer<'de>,
@@ -28,6 +28,7 @@ mod user_config_watcher;
 
 use std;
 use std::collections::HashMap;
+use std::fmt;
 use std::fs::{self, File, OpenOptions};
 use std::io::{BufRead, BufReader, Read, Write};
 use std::mem;
@@ -66,7 +67,9 @@ use futures::sync::mpsc;
 #[cfg(unix)]
 use proc_self;
 use prometheus::{HistogramVec, IntGauge, IntGaugeVec};
-use rustls::{internal::pemfile, NoClientAuth, ServerConfig};
+use rustls::{
+    internal::pemfile, AllowAnyAuthenticatedClient, NoClientAuth, RootCertStore, ServerConfig,
+};
 use serde_json;
 use time::{self, Duration as TimeDuration, SteadyTime, Timespec};
 use tokio::{executor, runtime};
@@ -77,7 +80,8 @@ use self::peer_watcher::PeerWatcher;
 use self::self_updater::{SelfUpdater, SUP_PKG_IDENT};
 use self::service::{health::HealthCheck, DesiredState};
 pub use self::service::{
-    ConfigRendering, Service, ServiceProxy, ServiceSpec, Topology, UpdateStrategy,
+    same_package, ConfigRendering, Service, ServiceProxy, ServiceSpec, Topology, UpdateStrategy,
+    UpdateWindow,
 };
 use self::service_updater::ServiceUpdater;
 use self::spec_dir::SpecDir;
@@ -86,16 +90,21 @@ use self::spec_watcher::SpecWatcher;
 pub use self::sys::Sys;
 use self::user_config_watcher::UserConfigWatcher;
 use super::feat;
-use crate::census::{CensusRing, CensusRingProxy};
+use crate::audit;
+use crate::census::{CensusChangeLog, CensusRing, CensusRingProxy, CensusSnapshot};
 use crate::config::GossipListenAddr;
-use crate::ctl_gateway::{self, CtlRequest};
+use crate::ctl_gateway::{self, CtlRequest, CtlTlsFiles};
 use crate::error::{Error, Result, SupError};
+use crate::event::{self, Event, EventSinkConfig};
 use crate::http_gateway;
 use crate::ShutdownReason;
 use crate::VERSION;
 
 const MEMBER_ID_FILE: &str = "MEMBER_ID";
 const PROC_LOCK_FILE: &str = "LOCK";
+/// How often starts waiting on the health of the services they depend on are tried again. They
+/// are also tried again whenever a service changes state.
+const DEFERRED_START_CHECK_SECS: i64 = 5;
 
 static LOGKEY: &'static str = "MR";
 
@@ -132,6 +141,22 @@ pub enum ServiceOperation {
         to_stop: ServiceSpec,
         to_start: ServiceSpec,
     },
+    /// The spec changed in a way that can be applied to the running service without restarting
+    /// it.
+    Update(ServiceSpec),
+}
+
+impl fmt::Display for ServiceOperation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            ServiceOperation::Start(ref spec) => write!(f, "start {}", spec.ident),
+            ServiceOperation::Stop(ref spec) => write!(f, "stop {}", spec.ident),
+            ServiceOperation::Restart { ref to_start, .. } => {
+                write!(f, "restart {}", to_start.ident)
+            }
+            ServiceOperation::Update(ref spec) => write!(f, "update {}", spec.ident),
+        }
+    }
 }
 
 /// FileSystem paths that the Manager uses to persist data to disk.
@@ -170,6 +195,12 @@ pub struct ManagerConfig {
     pub custom_state_path: Option<PathBuf>,
     pub update_url: String,
     pub update_channel: ChannelIdent,
+    /// Periods during which the Supervisor may update itself. It may do so at any time if there
+    /// are none.
+    pub update_windows: Vec<UpdateWindow>,
+    /// Upper bound, in seconds, of the random delay before the Supervisor polls for and applies
+    /// updates to itself.
+    pub update_splay: u64,
     pub gossip_listen: GossipListenAddr,
     pub ctl_listen: ListenCtlAddr,
     pub http_listen: http_gateway::ListenAddr,
@@ -180,6 +211,10 @@ pub struct ManagerConfig {
     pub organization: Option<String>,
     pub watch_peer_file: Option<String>,
     pub tls_files: Option<(PathBuf, PathBuf)>,
+    /// The private key, certificate, and client CA certificate files for the CtlGateway. When
+    /// set, clients have to connect over TLS with a certificate issued by the CA.
+    pub ctl_tls_files: Option<CtlTlsFiles>,
+    pub event_sinks: Vec<EventSinkConfig>,
 }
 
 impl ManagerConfig {
@@ -195,6 +230,8 @@ impl Default for ManagerConfig {
             custom_state_path: None,
             update_url: "".to_string(),
             update_channel: ChannelIdent::default(),
+            update_windows: vec![],
+            update_splay: 0,
             gossip_listen: GossipListenAddr::default(),
             ctl_listen: ListenCtlAddr::default(),
             http_listen: http_gateway::ListenAddr::default(),
@@ -205,6 +242,8 @@ impl Default for ManagerConfig {
             organization: None,
             watch_peer_file: None,
             tls_files: None,
+            ctl_tls_files: None,
+            event_sinks: vec![],
         }
     }
 }
@@ -244,13 +283,17 @@ pub struct GatewayState {
     pub butterfly_data: String,
     pub services_data: String,
     pub health_check_data: HashMap<ServiceGroup, HealthCheck>,
+    pub census_changes: CensusChangeLog,
     pub auth_token: Option<String>,
+    /// The services whose start is waiting, by spec ident, and the package each is waiting on.
+    pub waiting_on: HashMap<PackageIdent, PackageIdent>,
 }
 
 pub struct Manager {
     pub state: Arc<ManagerState>,
     butterfly: butterfly::Server,
     census_ring: CensusRing,
+    census_snapshot: CensusSnapshot,
     fs_cfg: Arc<FsCfg>,
     launcher: LauncherCli,
     updater: ServiceUpdater,
@@ -261,6 +304,10 @@ pub struct Manager {
     organization: Option<String>,
     self_updater: Option<SelfUpdater>,
     service_states: HashMap<PackageIdent, Timespec>,
+    /// Services whose start is waiting, and the package each is waiting on. They are tried again
+    /// once a service changes state or `next_deferred_start_check` has passed.
+    deferred_starts: HashMap<PackageIdent, PackageIdent>,
+    next_deferred_start_check: SteadyTime,
     sys: Arc<Sys>,
     http_disable: bool,
 }
@@ -305,6 +352,8 @@ impl Manager {
                     current,
                     cfg.update_url,
                     cfg.update_channel,
+                    cfg.update_windows,
+                    cfg.update_splay,
                 ))
             } else {
                 warn!("Supervisor version not fully qualified, unable to start self-updater");
@@ -337,6 +386,7 @@ impl Manager {
             Box::new(SuitabilityLookup(services.clone())),
         )?;
         outputln!("Supervisor Member-ID {}", sys.member_id);
+        Self::init_event_stream(&sys, &cfg.event_sinks)?;
         for peer_addr in &cfg.gossip_peers {
             let mut peer = Member::default();
             peer.address = format!("{}", peer_addr.ip());
@@ -365,6 +415,7 @@ impl Manager {
             self_updater: self_updater,
             updater: ServiceUpdater::new(server.clone()),
             census_ring: CensusRing::new(sys.member_id.clone()),
+            census_snapshot: CensusSnapshot::default(),
             butterfly: server,
             launcher: launcher,
             peer_watcher: peer_watcher,
@@ -374,11 +425,26 @@ impl Manager {
             fs_cfg: Arc::new(fs_cfg),
             organization: cfg.organization,
             service_states: HashMap::new(),
+            deferred_starts: HashMap::new(),
+            next_deferred_start_check: SteadyTime::now(),
             sys: Arc::new(sys),
             http_disable: cfg.http_disable,
         })
     }
 
+    /// Open each configured event sink and start publishing lifecycle events to them.
+    fn init_event_stream(sys: &Sys, sink_cfgs: &[EventSinkConfig]) -> Result<()> {
+        let mut sinks = Vec::with_capacity(sink_cfgs.len());
+        for sink_cfg in sink_cfgs {
+            let sink = sink_cfg.open().map_err(|err| {
+                sup_error!(Error::EventSinkIO(sink_cfg.path().to_path_buf(), err))
+            })?;
+            sinks.push(sink);
+        }
+        event::init(&sys.member_id, sinks);
+        Ok(())
+    }
+
     /// Load the initial Butterly Member which is used in initializing the Butterfly server. This
     /// will load the member-id for the initial Member from disk if a previous manager has been
     /// run.
@@ -525,6 +591,10 @@ impl Manager {
         }
 
         self.updater.add(&service);
+        event::publish(Event::ServiceLoaded {
+            service_group: service.service_group.to_string(),
+            pkg_ident: service.pkg.ident.to_string(),
+        });
         self.state
             .services
             .write()
@@ -567,9 +637,29 @@ impl Manager {
         self.persist_state();
         let http_listen_addr = self.sys.http_listen();
         let ctl_listen_addr = self.sys.ctl_listen();
-        let ctl_secret_key = ctl_gateway::readgen_secret_key(&self.fs_cfg.sup_root)?;
-        outputln!("Starting ctl-gateway on {}", &ctl_listen_addr);
-        ctl_gateway::server::run(ctl_listen_addr, ctl_secret_key, ctl_tx);
+        let ctl_credentials = ctl_gateway::read_credentials(&self.fs_cfg.sup_root)?;
+        audit::init(&self.fs_cfg.sup_root).map_err(|err| {
+            sup_error!(Error::AuditLogIO(audit::path(&self.fs_cfg.sup_root), err))
+        })?;
+        let ctl_tls_config = match self.state.cfg.ctl_tls_files {
+            Some(ref files) => Some(ctl_tls_config(files)?),
+            None => None,
+        };
+        outputln!(
+            "Starting ctl-gateway on {}{}",
+            &ctl_listen_addr,
+            if ctl_tls_config.is_some() {
+                " with TLS"
+            } else {
+                ""
+            }
+        );
+        ctl_gateway::server::run(
+            ctl_listen_addr,
+            ctl_credentials,
+            ctl_tls_config,
+            ctl_tx.clone(),
+        );
         debug!("ctl-gateway started");
 
         if self.http_disable {
@@ -600,6 +690,7 @@ impl Manager {
                 http_listen_addr.clone(),
                 tls_server_config,
                 self.state.gateway_state.clone(),
+                ctl_tx,
                 pair.clone(),
             );
 
@@ -703,7 +794,7 @@ impl Manager {
                 return Ok(());
             }
 
-            if self.spec_watcher.has_events() {
+            if self.spec_watcher.has_events() || self.deferred_starts_due() {
                 self.take_action_on_services()?;
             }
 
@@ -721,10 +812,12 @@ impl Manager {
             );
 
             if self.check_for_changed_services() {
+                self.next_deferred_start_check = SteadyTime::now();
                 self.persist_state();
             }
 
             if self.census_ring.changed() {
+                self.record_census_changes();
                 self.persist_state();
             }
 
@@ -871,6 +964,22 @@ impl Manager {
             .census_data = json;
     }
 
+    /// Compare the census with the way it was when it last changed, and record the differences
+    /// for the watchers of the HTTP gateway's `/census/watch` endpoint.
+    fn record_census_changes(&mut self) {
+        let snapshot = CensusSnapshot::new(&self.census_ring);
+        let changes = snapshot.changes_since(&self.census_snapshot);
+        self.census_snapshot = snapshot;
+        if !changes.is_empty() {
+            self.state
+                .gateway_state
+                .write()
+                .expect("GatewayState lock is poisoned")
+                .census_changes
+                .extend(changes);
+        }
+    }
+
     fn persist_butterfly_state(&self) {
         let bs = ServerProxy::new(&self.butterfly);
         let json = serde_json::to_string(&bs).unwrap();
@@ -960,6 +1069,11 @@ impl Manager {
         }
 
         self.updater.remove(service);
+        event::publish(Event::ServiceUnloaded {
+            service_group: service.service_group.to_string(),
+            pkg_ident: service.pkg.ident.to_string(),
+            reason: cause.to_string(),
+        });
     }
 
     /// Check if any elections need restarting.
@@ -990,9 +1104,9 @@ impl Manager {
                 // Thus, for these given ShutdownReasons, we just
                 // won't send any Membership rumors out.
             }
-            ShutdownReason::SvcStopCmd => {
+            ShutdownReason::SvcStopCmd | ShutdownReason::Restarting => {
                 // Just to call it out specifically, we shouldn't ever
-                // be called with this ShutdownReason.
+                // be called with these ShutdownReasons.
                 //
                 // This is all being refactored elsewhere right now,
                 // for what it's worth.
@@ -1023,7 +1137,12 @@ impl Manager {
             mem::swap(services.deref_mut(), &mut svcs);
         }
 
-        for mut service in svcs.drain().map(|(_ident, service)| service) {
+        let svcs = Self::dependency_order(
+            svcs.drain().map(|(_ident, service)| service).collect(),
+            |a, b| a.depends_on(b),
+        );
+        // Dependents stop before the services they depend on.
+        for mut service in svcs.into_iter().rev() {
             self.remove_service(&mut service, cause);
         }
         release_process_lock(&self.fs_cfg);
@@ -1033,27 +1152,125 @@ impl Manager {
 
     /// Start, stop, or restart services to bring what's running in
     /// line with what our spec files say.
+    ///
+    /// Services are stopped before the services they depend on, and
+    /// only started once those are up; a start that has to wait is
+    /// tried again on a later pass.
     fn take_action_on_services(&mut self) -> Result<()> {
+        let mut to_stop = vec![];
+        let mut to_start = vec![];
         for op in self.reconcile_spec_files()? {
             match op {
-                ServiceOperation::Stop(spec) => {
-                    self.remove_service_for_spec(&spec);
-                }
-                ServiceOperation::Start(spec) => {
-                    self.add_service(spec);
-                }
+                ServiceOperation::Stop(spec) => to_stop.push(spec),
+                ServiceOperation::Start(spec) => to_start.push(spec),
                 ServiceOperation::Restart {
                     to_stop: running,
                     to_start: desired,
                 } => {
-                    self.remove_service_for_spec(&running);
-                    self.add_service(desired);
+                    to_stop.push(running);
+                    to_start.push(desired);
+                }
+                ServiceOperation::Update(spec) => self.update_service_for_spec(&spec),
+            }
+        }
+
+        let to_stop = Self::dependency_order(to_stop, |a, b| a.depends_on(&b.ident));
+        for spec in to_stop.iter().rev() {
+            self.remove_service_for_spec(spec);
+        }
+
+        let to_start = Self::dependency_order(to_start, |a, b| a.depends_on(&b.ident));
+        let pending: Vec<PackageIdent> = to_start.iter().map(|s| s.ident.clone()).collect();
+        let mut deferred = HashMap::new();
+        for spec in to_start {
+            match self.unready_prerequisite(&spec, &pending) {
+                Some(prerequisite) => {
+                    if self.deferred_starts.get(&spec.ident) != Some(&prerequisite) {
+                        let state = if spec.require_healthy {
+                            "healthy"
+                        } else {
+                            "up"
+                        };
+                        outputln!(
+                            "Waiting to start {} until {} is {}",
+                            spec.ident,
+                            prerequisite,
+                            state
+                        );
+                    }
+                    deferred.insert(spec.ident, prerequisite);
                 }
+                None => self.add_service(spec),
             }
         }
+        self.state
+            .gateway_state
+            .write()
+            .expect("GatewayState lock is poisoned")
+            .waiting_on = deferred.clone();
+        self.deferred_starts = deferred;
+        self.next_deferred_start_check =
+            SteadyTime::now() + TimeDuration::seconds(DEFERRED_START_CHECK_SECS);
         Ok(())
     }
 
+    /// Whether the starts waiting on other services should be tried again.
+    fn deferred_starts_due(&self) -> bool {
+        !self.deferred_starts.is_empty() && SteadyTime::now() >= self.next_deferred_start_check
+    }
+
+    /// Returns a package whose service the spec's service has to wait for before it starts, if
+    /// there is one. Services it `requires` must be loaded and ready, while those it starts
+    /// `after` only need to be ready if they are loaded, or about to be.
+    fn unready_prerequisite(
+        &self,
+        spec: &ServiceSpec,
+        pending: &[PackageIdent],
+    ) -> Option<PackageIdent> {
+        let services = self
+            .state
+            .services
+            .read()
+            .expect("Services lock is poisoned");
+        let ready = |dep: &PackageIdent| {
+            services
+                .values()
+                .find(|service| same_package(dep, &service.spec_ident))
+                .map(|service| service.ready_for_dependents(spec.require_healthy))
+        };
+        spec.requires
+            .iter()
+            .find(|dep| !ready(dep).unwrap_or(false))
+            .or_else(|| {
+                spec.after.iter().find(|dep| match ready(dep) {
+                    Some(ready) => !ready,
+                    None => pending.iter().any(|ident| same_package(dep, ident)),
+                })
+            })
+            .cloned()
+    }
+
+    /// Sorts `items` so that each comes after everything it depends on, otherwise keeping their
+    /// order. Items caught in a dependency cycle are taken in the order they were given.
+    fn dependency_order<T, F>(mut items: Vec<T>, depends_on: F) -> Vec<T>
+    where
+        F: Fn(&T, &T) -> bool,
+    {
+        let mut ordered = Vec::with_capacity(items.len());
+        while !items.is_empty() {
+            let next = (0..items.len())
+                .find(|&i| {
+                    !items
+                        .iter()
+                        .enumerate()
+                        .any(|(j, other)| i != j && depends_on(&items[i], other))
+                })
+                .unwrap_or(0);
+            ordered.push(items.remove(next));
+        }
+        ordered
+    }
+
     /// Determine what services we need to start, stop, or restart in
     /// order to be running what our on-disk spec files tell us we
     /// should be running.
@@ -1128,6 +1345,15 @@ impl Manager {
                     if running_spec == disk_spec {
                         debug!("Reconciliation: '{}' unchanged", ident);
                         None
+                    } else if running_spec
+                        == (ServiceSpec {
+                            update_paused: running_spec.update_paused,
+                            ..disk_spec.clone()
+                        })
+                    {
+                        // Pausing or resuming updates doesn't need a restart.
+                        debug!("Reconciliation: '{}' queued for update in place", ident);
+                        Some(ServiceOperation::Update(disk_spec))
                     } else {
                         // TODO (CM): In the future, this would be the
                         // place where we can evaluate what has changed
@@ -1205,6 +1431,33 @@ impl Manager {
         }
     }
 
+    /// Applies the parts of a spec that can change without restarting its service.
+    fn update_service_for_spec(&mut self, spec: &ServiceSpec) {
+        let mut services = self
+            .state
+            .services
+            .write()
+            .expect("Services lock is poisoned");
+        let service = match services.get_mut(&spec.ident) {
+            Some(service) => service,
+            None => {
+                outputln!(
+                    "Tried to update service for {} but could not find it running, skipping",
+                    &spec.ident
+                );
+                return;
+            }
+        };
+        if service.update_paused != spec.update_paused {
+            service.update_paused = spec.update_paused;
+            if spec.update_paused {
+                outputln!(preamble service.service_group, "Automatic updates paused");
+            } else {
+                outputln!(preamble service.service_group, "Automatic updates resumed");
+            }
+        }
+    }
+
     fn remove_service_for_spec(&mut self, spec: &ServiceSpec) {
         let svc = self
             .state
@@ -1251,6 +1504,27 @@ where
     Ok(config)
 }
 
+/// The TLS configuration of the CtlGateway, which only accepts clients presenting a certificate
+/// issued by the configured CA.
+fn ctl_tls_config(files: &CtlTlsFiles) -> Result<ServerConfig> {
+    let ca_cert_file = &mut BufReader::new(File::open(&files.ca_cert)?);
+    let mut client_roots = RootCertStore::empty();
+    match client_roots.add_pem_file(ca_cert_file) {
+        Ok((added, _)) if added > 0 => (),
+        _ => return Err(sup_error!(Error::InvalidCertFile(files.ca_cert.clone()))),
+    }
+    let mut config = ServerConfig::new(AllowAnyAuthenticatedClient::new(client_roots));
+
+    let cert_file = &mut BufReader::new(File::open(&files.cert)?);
+    let cert_chain = pemfile::certs(cert_file)
+        .and_then(|c| if c.is_empty() { Err(()) } else { Ok(c) })
+        .map_err(|_| sup_error!(Error::InvalidCertFile(files.cert.clone())))?;
+    let key = protocol::tls::read_private_key(&files.key)?
+        .ok_or_else(|| sup_error!(Error::InvalidKeyFile(files.key.clone())))?;
+    config.set_single_cert(cert_chain, key)?;
+    Ok(config)
+}
+
 /// Represents how many threads to start for our main Tokio runtime
 #[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Eq)]
 struct TokioThreadCount(usize);
@@ -1489,7 +1763,11 @@ impl Future for CtlHandler {
     type Error = ();
 
     fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
-        match self.cmd.run(&self.state) {
+        let result = self.cmd.run(&self.state);
+        if let Some(ref audit) = self.cmd.audit {
+            audit::record(audit, &result);
+        }
+        match result {
             Ok(()) => (),
             Err(err) => {
                 debug!("CtlHandler failed, {:?}", err);
@@ -1681,6 +1959,23 @@ mod test {
             }
         }
 
+        #[test]
+        fn pausing_updates_on_disk_means_update_in_place() {
+            let running_spec = new_spec("core/foo");
+
+            let on_disk_spec = {
+                let mut s = running_spec.clone();
+                s.update_paused = true;
+                s
+            };
+
+            let running = vec![running_spec];
+            let on_disk = vec![on_disk_spec.clone()];
+
+            let operations = Manager::specs_to_operations(running, on_disk);
+            assert_eq!(operations, vec![ServiceOperation::Update(on_disk_spec)]);
+        }
+
         #[test]
         fn multiple_operations_can_be_determined_at_once() {
             // Nothing should happen with this; it's already how it
@@ -1766,4 +2061,60 @@ mod test {
             }
         }
     }
+
+    mod dependency_order {
+        use super::super::*;
+
+        fn new_spec(ident: &str, after: &[&str]) -> ServiceSpec {
+            let mut spec = ServiceSpec::default_for(
+                PackageIdent::from_str(ident).expect("couldn't parse ident str"),
+            );
+            spec.after = after
+                .iter()
+                .map(|i| PackageIdent::from_str(i).expect("couldn't parse ident str"))
+                .collect();
+            spec
+        }
+
+        fn order(specs: Vec<ServiceSpec>) -> Vec<String> {
+            Manager::dependency_order(specs, |a, b| a.depends_on(&b.ident))
+                .into_iter()
+                .map(|s| s.ident.to_string())
+                .collect()
+        }
+
+        #[test]
+        fn independent_specs_keep_their_order() {
+            let specs = vec![new_spec("core/foo", &[]), new_spec("core/bar", &[])];
+            assert_eq!(order(specs), vec!["core/foo", "core/bar"]);
+        }
+
+        #[test]
+        fn dependencies_come_first() {
+            let specs = vec![
+                new_spec("core/app", &["core/db", "core/cache"]),
+                new_spec("core/cache", &["core/db"]),
+                new_spec("core/db", &[]),
+            ];
+            assert_eq!(order(specs), vec!["core/db", "core/cache", "core/app"]);
+        }
+
+        #[test]
+        fn dependencies_match_on_origin_and_name() {
+            let specs = vec![
+                new_spec("core/app", &["core/db/1.0.0"]),
+                new_spec("core/db/2.0.0", &[]),
+            ];
+            assert_eq!(order(specs), vec!["core/db/2.0.0", "core/app"]);
+        }
+
+        #[test]
+        fn cycles_keep_their_order() {
+            let specs = vec![
+                new_spec("core/foo", &["core/bar"]),
+                new_spec("core/bar", &["core/foo"]),
+            ];
+            assert_eq!(order(specs), vec!["core/foo", "core/bar"]);
+        }
+    }
 }
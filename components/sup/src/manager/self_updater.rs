@@ -14,6 +14,9 @@
 
 //! Encapsulates logic required for updating the Habitat Supervisor
 //! itself.
+//!
+//! Like service updates, a new Supervisor is only installed while one of
+//! the configured update windows is open, and after a random splay.
 
 use std::sync::mpsc::{sync_channel, Receiver, SyncSender, TryRecvError};
 use std::thread;
@@ -28,6 +31,7 @@ use crate::hcore::{
     package::{PackageIdent, PackageInstall},
     ChannelIdent,
 };
+use crate::manager::service::update::{self, UpdateWindow};
 use crate::util;
 
 pub const SUP_PKG_IDENT: &str = "core/hab-sup";
@@ -39,19 +43,35 @@ pub struct SelfUpdater {
     current: PackageIdent,
     update_url: String,
     update_channel: ChannelIdent,
+    windows: Vec<UpdateWindow>,
+    splay: u64,
 }
 
 // TODO (CM): Want to use the Periodic trait here, but can't due to
 // how things are currently structured (The service updater had a worker)
 
 impl SelfUpdater {
-    pub fn new(current: PackageIdent, update_url: String, update_channel: ChannelIdent) -> Self {
-        let rx = Self::init(current.clone(), update_url.clone(), update_channel.clone());
+    pub fn new(
+        current: PackageIdent,
+        update_url: String,
+        update_channel: ChannelIdent,
+        windows: Vec<UpdateWindow>,
+        splay: u64,
+    ) -> Self {
+        let rx = Self::init(
+            current.clone(),
+            update_url.clone(),
+            update_channel.clone(),
+            windows.clone(),
+            splay,
+        );
         SelfUpdater {
             rx: rx,
             current: current,
             update_url: update_url,
             update_channel: update_channel,
+            windows: windows,
+            splay: splay,
         }
     }
 
@@ -60,11 +80,13 @@ impl SelfUpdater {
         current: PackageIdent,
         update_url: String,
         update_channel: ChannelIdent,
+        windows: Vec<UpdateWindow>,
+        splay: u64,
     ) -> Receiver<PackageInstall> {
         let (tx, rx) = sync_channel(0);
         thread::Builder::new()
             .name("self-updater".to_string())
-            .spawn(move || Self::run(tx, current, update_url, update_channel))
+            .spawn(move || Self::run(tx, current, update_url, update_channel, windows, splay))
             .expect("Unable to start self-updater thread");
         rx
     }
@@ -74,11 +96,15 @@ impl SelfUpdater {
         current: PackageIdent,
         builder_url: String,
         channel: ChannelIdent,
+        windows: Vec<UpdateWindow>,
+        splay: u64,
     ) {
         debug!("Self updater current package, {}", current);
         // SUP_PKG_IDENT will always parse as a valid PackageIdent,
         // and thus a valid InstallSource
         let install_source: InstallSource = SUP_PKG_IDENT.parse().unwrap();
+        // Spread out the polling of Supervisors that started at the same time.
+        thread::sleep(update::random_splay(splay));
         loop {
             let next_check = SteadyTime::now() + TimeDuration::milliseconds(update_frequency());
 
@@ -91,6 +117,14 @@ impl SelfUpdater {
             ) {
                 Ok(package) => {
                     if current < *package.ident() {
+                        if !update::window_open(&windows, &time::now_utc()) {
+                            outputln!(
+                                "Holding the Supervisor update to {} until an update window \
+                                 opens",
+                                package.ident()
+                            );
+                        }
+                        update::wait_for_window(&windows, splay, || false);
                         debug!(
                             "Self updater installing newer Supervisor, {}",
                             package.ident()
@@ -123,6 +157,8 @@ impl SelfUpdater {
                     self.current.clone(),
                     self.update_url.clone(),
                     self.update_channel.clone(),
+                    self.windows.clone(),
+                    self.splay,
                 );
                 None
             }
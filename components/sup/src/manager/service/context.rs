@@ -64,6 +64,7 @@ use crate::hcore::package::PackageIdent;
 use crate::hcore::service::ServiceGroup;
 
 use crate::census::{CensusGroup, CensusMember, CensusRing, ElectionStatus, MemberId};
+use crate::manager::service::secrets::Secrets;
 use crate::manager::service::ServiceBind;
 use crate::manager::Sys;
 
@@ -86,6 +87,7 @@ pub struct RenderContext<'a> {
     cfg: Cow<'a, Cfg>,
     svc: Svc<'a>,
     bind: Binds<'a>,
+    secret: Secret<'a>,
 }
 
 impl<'a> RenderContext<'a> {
@@ -103,6 +105,7 @@ impl<'a> RenderContext<'a> {
         sys: &'a Sys,
         pkg: &'a Pkg,
         cfg: &'a Cfg,
+        secrets: &'a Secrets,
         census: &'a CensusRing,
         bindings: T,
     ) -> RenderContext<'a>
@@ -118,6 +121,7 @@ impl<'a> RenderContext<'a> {
             cfg: Cow::Borrowed(cfg),
             svc: Svc::new(census_group),
             bind: Binds::new(bindings, census),
+            secret: Secret(Cow::Borrowed(secrets)),
         }
     }
 
@@ -425,6 +429,28 @@ impl<'a> BindGroup<'a> {
 
 ////////////////////////////////////////////////////////////////////////
 
+/// Templating proxy for the Supervisor-local `Secrets` of the service.
+///
+/// Exposed to users under the `secret` key, as a map of each secret's name to its value. This is
+/// the only place the values are ever serialized.
+#[derive(Clone, Debug)]
+struct Secret<'a>(Cow<'a, Secrets>);
+
+impl<'a> Serialize for Secret<'a> {
+    fn serialize<S>(&self, serializer: S) -> result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut map = serializer.serialize_map(None)?;
+        for (name, value) in self.0.iter() {
+            map.serialize_entry(name, value)?;
+        }
+        map.end()
+    }
+}
+
+////////////////////////////////////////////////////////////////////////
+
 /// Templating proxy for a `census::CensusMember` struct.
 ///
 /// Not exposed via a top-level key, but ultimately available through
@@ -812,6 +838,7 @@ two = 2
             cfg: Cow::Owned(cfg),
             svc: svc,
             bind: binds,
+            secret: Secret(Cow::Owned(Secrets::default())),
         }
     }
 
@@ -922,6 +949,20 @@ two = 2
         assert_eq!(output, "deadbeefdeadbeefdeadbeefdeadbeef");
     }
 
+    #[test]
+    fn secrets_render_correctly() {
+        let mut ctx = default_render_context();
+        let mut secrets = BTreeMap::new();
+        secrets.insert("db_password".to_string(), "hunter2".to_string());
+        ctx.secret = Secret(Cow::Owned(Secrets::from(secrets)));
+
+        let j = serde_json::to_string(&ctx).expect("can't serialize to JSON");
+        assert_valid(&j, "render_context_schema.json");
+
+        let output = render("password = \"{{secret.db_password}}\"", &ctx);
+        assert_eq!(output, "password = \"hunter2\"");
+    }
+
     // Technically, `bind.<SERVICE>.first` could be None, according to
     // the typing of the code.  This was always been technically
     // possible, even though for practical purposes, it will be
@@ -13,6 +13,9 @@
 // limitations under the License.
 
 use std::fmt;
+use std::time::{Duration, Instant};
+
+use crate::protocol;
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize)]
 pub enum HealthCheck {
@@ -22,6 +25,26 @@ pub enum HealthCheck {
     Unknown,
 }
 
+impl HealthCheck {
+    /// The more severe of two results, for a service checked in more than one way.
+    pub fn worst(self, other: HealthCheck) -> HealthCheck {
+        if other.severity() > self.severity() {
+            other
+        } else {
+            self
+        }
+    }
+
+    fn severity(self) -> u8 {
+        match self {
+            HealthCheck::Ok => 0,
+            HealthCheck::Unknown => 1,
+            HealthCheck::Warning => 2,
+            HealthCheck::Critical => 3,
+        }
+    }
+}
+
 impl Default for HealthCheck {
     fn default() -> HealthCheck {
         HealthCheck::Unknown
@@ -51,3 +74,310 @@ impl fmt::Display for HealthCheck {
         write!(f, "{}", msg)
     }
 }
+
+/// How many results a health check needs before the health it reports changes.
+#[derive(Copy, Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(default)]
+pub struct HealthCheckThresholds {
+    /// Consecutive failing results before an `Ok` health degrades.
+    pub failures: u32,
+    /// Consecutive `Ok` results before a degraded health recovers.
+    pub successes: u32,
+    /// Seconds after the service starts during which failing results are ignored.
+    pub grace_period: u64,
+    /// Consecutive `Critical` results after which the service's process is restarted. Zero
+    /// leaves the process alone.
+    pub restart_after: u32,
+}
+
+impl HealthCheckThresholds {
+    /// Overwrite any fields that were set in the given protocol message.
+    pub fn merge(&mut self, proto: &protocol::types::HealthCheckThresholds) {
+        if let Some(failures) = proto.failures {
+            self.failures = failures;
+        }
+        if let Some(successes) = proto.successes {
+            self.successes = successes;
+        }
+        if let Some(grace_period) = proto.grace_period {
+            self.grace_period = grace_period;
+        }
+        if let Some(restart_after) = proto.restart_after {
+            self.restart_after = restart_after;
+        }
+    }
+}
+
+impl Default for HealthCheckThresholds {
+    fn default() -> Self {
+        HealthCheckThresholds {
+            failures: 1,
+            successes: 1,
+            grace_period: 0,
+            restart_after: 0,
+        }
+    }
+}
+
+/// Smooths the raw results of a service's health checks into the health that is reported for
+/// it, according to its `HealthCheckThresholds`.
+#[derive(Debug, Default)]
+pub struct HealthTracker {
+    reported: HealthCheck,
+    consecutive_failures: u32,
+    consecutive_successes: u32,
+    consecutive_critical: u32,
+    started: Option<Instant>,
+}
+
+impl HealthTracker {
+    /// The health currently reported for the service.
+    pub fn reported(&self) -> HealthCheck {
+        self.reported
+    }
+
+    /// Note that the service's process was (re)started at `now`, which begins a grace period.
+    pub fn service_started(&mut self, now: Instant) {
+        self.started = Some(now);
+        self.consecutive_failures = 0;
+        self.consecutive_successes = 0;
+        self.consecutive_critical = 0;
+    }
+
+    /// The number of `Critical` results in a row, not counting any during a grace period.
+    pub fn consecutive_critical(&self) -> u32 {
+        self.consecutive_critical
+    }
+
+    /// Whether the service has been `Critical` for long enough that its process should be
+    /// restarted.
+    pub fn liveness_failed(&self, thresholds: &HealthCheckThresholds) -> bool {
+        thresholds.restart_after > 0
+            && self.reported == HealthCheck::Critical
+            && self.consecutive_critical >= thresholds.restart_after
+    }
+
+    /// Take a raw health check result into account, returning the health to report.
+    pub fn observe(
+        &mut self,
+        thresholds: &HealthCheckThresholds,
+        result: HealthCheck,
+        now: Instant,
+    ) -> HealthCheck {
+        if result == HealthCheck::Ok {
+            self.consecutive_failures = 0;
+            self.consecutive_critical = 0;
+            self.consecutive_successes = self.consecutive_successes.saturating_add(1);
+            if self.reported == HealthCheck::Unknown
+                || self.consecutive_successes >= thresholds.successes
+            {
+                self.reported = HealthCheck::Ok;
+            }
+            return self.reported;
+        }
+
+        self.consecutive_successes = 0;
+        if self.in_grace_period(thresholds, now) {
+            return self.reported;
+        }
+        self.consecutive_failures = self.consecutive_failures.saturating_add(1);
+        if result == HealthCheck::Critical {
+            self.consecutive_critical = self.consecutive_critical.saturating_add(1);
+        } else {
+            self.consecutive_critical = 0;
+        }
+        // A health that is already degraded (or was never known) follows the results directly;
+        // only a healthy service is given the benefit of the doubt.
+        if self.reported != HealthCheck::Ok || self.consecutive_failures >= thresholds.failures {
+            self.reported = result;
+        }
+        self.reported
+    }
+
+    fn in_grace_period(&self, thresholds: &HealthCheckThresholds, now: Instant) -> bool {
+        self.started.map_or(false, |started| {
+            now.duration_since(started) < Duration::from_secs(thresholds.grace_period)
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn thresholds(failures: u32, successes: u32, grace_period: u64) -> HealthCheckThresholds {
+        HealthCheckThresholds {
+            failures: failures,
+            successes: successes,
+            grace_period: grace_period,
+            restart_after: 0,
+        }
+    }
+
+    fn observe_all(
+        tracker: &mut HealthTracker,
+        thresholds: &HealthCheckThresholds,
+        results: &[HealthCheck],
+    ) -> Vec<HealthCheck> {
+        let now = Instant::now();
+        results
+            .iter()
+            .map(|r| tracker.observe(thresholds, *r, now))
+            .collect()
+    }
+
+    #[test]
+    fn worst_result_wins() {
+        assert_eq!(
+            HealthCheck::Ok.worst(HealthCheck::Warning),
+            HealthCheck::Warning
+        );
+        assert_eq!(
+            HealthCheck::Critical.worst(HealthCheck::Unknown),
+            HealthCheck::Critical
+        );
+        assert_eq!(
+            HealthCheck::Unknown.worst(HealthCheck::Ok),
+            HealthCheck::Unknown
+        );
+    }
+
+    #[test]
+    fn default_thresholds_report_every_result() {
+        let mut tracker = HealthTracker::default();
+        let reported = observe_all(
+            &mut tracker,
+            &HealthCheckThresholds::default(),
+            &[HealthCheck::Ok, HealthCheck::Critical, HealthCheck::Ok],
+        );
+
+        assert_eq!(
+            reported,
+            vec![HealthCheck::Ok, HealthCheck::Critical, HealthCheck::Ok]
+        );
+    }
+
+    #[test]
+    fn health_degrades_after_consecutive_failures() {
+        let mut tracker = HealthTracker::default();
+        let reported = observe_all(
+            &mut tracker,
+            &thresholds(3, 1, 0),
+            &[
+                HealthCheck::Ok,
+                HealthCheck::Critical,
+                HealthCheck::Critical,
+                HealthCheck::Ok,
+                HealthCheck::Critical,
+                HealthCheck::Warning,
+                HealthCheck::Critical,
+            ],
+        );
+
+        assert_eq!(
+            reported,
+            vec![
+                HealthCheck::Ok,
+                HealthCheck::Ok,
+                HealthCheck::Ok,
+                HealthCheck::Ok,
+                HealthCheck::Ok,
+                HealthCheck::Ok,
+                HealthCheck::Critical,
+            ]
+        );
+    }
+
+    #[test]
+    fn health_recovers_after_consecutive_successes() {
+        let mut tracker = HealthTracker::default();
+        let reported = observe_all(
+            &mut tracker,
+            &thresholds(1, 2, 0),
+            &[
+                HealthCheck::Critical,
+                HealthCheck::Ok,
+                HealthCheck::Warning,
+                HealthCheck::Ok,
+                HealthCheck::Ok,
+            ],
+        );
+
+        assert_eq!(
+            reported,
+            vec![
+                HealthCheck::Critical,
+                HealthCheck::Critical,
+                HealthCheck::Warning,
+                HealthCheck::Warning,
+                HealthCheck::Ok,
+            ]
+        );
+    }
+
+    #[test]
+    fn liveness_fails_after_consecutive_critical_results() {
+        let thresholds = HealthCheckThresholds {
+            restart_after: 3,
+            ..thresholds(2, 1, 0)
+        };
+        let mut tracker = HealthTracker::default();
+        let now = Instant::now();
+
+        for result in &[
+            HealthCheck::Ok,
+            HealthCheck::Critical,
+            HealthCheck::Critical,
+            HealthCheck::Warning,
+            HealthCheck::Critical,
+            HealthCheck::Critical,
+        ] {
+            tracker.observe(&thresholds, *result, now);
+            assert!(!tracker.liveness_failed(&thresholds));
+        }
+        tracker.observe(&thresholds, HealthCheck::Critical, now);
+        assert!(tracker.liveness_failed(&thresholds));
+
+        tracker.service_started(now);
+        assert!(!tracker.liveness_failed(&thresholds));
+    }
+
+    #[test]
+    fn liveness_is_opt_in() {
+        let thresholds = HealthCheckThresholds::default();
+        let mut tracker = HealthTracker::default();
+        let reported = observe_all(&mut tracker, &thresholds, &[HealthCheck::Critical; 10]);
+
+        assert_eq!(reported.last(), Some(&HealthCheck::Critical));
+        assert!(!tracker.liveness_failed(&thresholds));
+    }
+
+    #[test]
+    fn failures_are_ignored_during_the_grace_period() {
+        let thresholds = thresholds(1, 1, 30);
+        let mut tracker = HealthTracker::default();
+        let start = Instant::now();
+        tracker.service_started(start);
+
+        assert_eq!(
+            tracker.observe(&thresholds, HealthCheck::Critical, start),
+            HealthCheck::Unknown
+        );
+        assert_eq!(
+            tracker.observe(
+                &thresholds,
+                HealthCheck::Critical,
+                start + Duration::from_secs(10)
+            ),
+            HealthCheck::Unknown
+        );
+        assert_eq!(
+            tracker.observe(
+                &thresholds,
+                HealthCheck::Critical,
+                start + Duration::from_secs(30)
+            ),
+            HealthCheck::Critical
+        );
+    }
+}
@@ -574,6 +574,126 @@ impl Hook for PostStopHook {
     }
 }
 
+#[derive(Debug, Serialize)]
+pub struct PreStartHook {
+    render_pair: RenderPair,
+    stdout_log_path: PathBuf,
+    stderr_log_path: PathBuf,
+}
+
+impl Hook for PreStartHook {
+    type ExitValue = bool;
+
+    fn file_name() -> &'static str {
+        "pre-start"
+    }
+
+    fn new(package_name: &str, pair: RenderPair) -> Self {
+        PreStartHook {
+            render_pair: pair,
+            stdout_log_path: hooks::stdout_log_path::<Self>(package_name),
+            stderr_log_path: hooks::stderr_log_path::<Self>(package_name),
+        }
+    }
+
+    fn handle_exit<'a>(
+        &self,
+        pkg: &Pkg,
+        _: &'a HookOutput,
+        status: &ExitStatus,
+    ) -> Self::ExitValue {
+        let pkg_name = &pkg.name;
+        match status.code() {
+            Some(0) => true,
+            Some(code) => {
+                outputln!(preamble pkg_name, "Pre start failed! '{}' exited with \
+                    status code {}", Self::file_name(), code);
+                false
+            }
+            None => {
+                Self::output_termination_message(pkg_name, status);
+                false
+            }
+        }
+    }
+
+    fn path(&self) -> &Path {
+        &self.render_pair.path
+    }
+
+    fn renderer(&self) -> &TemplateRenderer {
+        &self.render_pair.renderer
+    }
+
+    fn stdout_log_path(&self) -> &Path {
+        &self.stdout_log_path
+    }
+
+    fn stderr_log_path(&self) -> &Path {
+        &self.stderr_log_path
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct PreStopHook {
+    render_pair: RenderPair,
+    stdout_log_path: PathBuf,
+    stderr_log_path: PathBuf,
+}
+
+impl Hook for PreStopHook {
+    type ExitValue = bool;
+
+    fn file_name() -> &'static str {
+        "pre-stop"
+    }
+
+    fn new(package_name: &str, pair: RenderPair) -> Self {
+        PreStopHook {
+            render_pair: pair,
+            stdout_log_path: hooks::stdout_log_path::<Self>(package_name),
+            stderr_log_path: hooks::stderr_log_path::<Self>(package_name),
+        }
+    }
+
+    fn handle_exit<'a>(
+        &self,
+        pkg: &Pkg,
+        _: &'a HookOutput,
+        status: &ExitStatus,
+    ) -> Self::ExitValue {
+        let pkg_name = &pkg.name;
+        match status.code() {
+            Some(0) => true,
+            Some(code) => {
+                outputln!(preamble pkg_name, "Pre stop failed! '{}' exited with \
+                    status code {}", Self::file_name(), code);
+                false
+            }
+            None => {
+                Self::output_termination_message(pkg_name, status);
+                false
+            }
+        }
+    }
+
+    fn path(&self) -> &Path {
+        &self.render_pair.path
+    }
+
+    fn renderer(&self) -> &TemplateRenderer {
+        &self.render_pair.renderer
+    }
+
+    fn stdout_log_path(&self) -> &Path {
+        &self.stdout_log_path
+    }
+
+    fn stderr_log_path(&self) -> &Path {
+        &self.stderr_log_path
+    }
+}
+
 #[derive(Debug, Default, Serialize)]
 pub struct HookTable {
     pub health_check: Option<HealthCheckHook>,
@@ -585,6 +705,8 @@ pub struct HookTable {
     pub run: Option<RunHook>,
     pub post_run: Option<PostRunHook>,
     pub post_stop: Option<PostStopHook>,
+    pub pre_start: Option<PreStartHook>,
+    pub pre_stop: Option<PreStopHook>,
 }
 
 impl HookTable {
@@ -606,6 +728,8 @@ impl HookTable {
                 table.run = RunHook::load(package_name, &hooks_path, &templates);
                 table.post_run = PostRunHook::load(package_name, &hooks_path, &templates);
                 table.post_stop = PostStopHook::load(package_name, &hooks_path, &templates);
+                table.pre_start = PreStartHook::load(package_name, &hooks_path, &templates);
+                table.pre_stop = PreStopHook::load(package_name, &hooks_path, &templates);
             }
         }
         debug!(
@@ -654,6 +778,12 @@ impl HookTable {
         if let Some(ref hook) = self.post_stop {
             changed = self.compile_one(hook, service_group, ctx) || changed;
         }
+        if let Some(ref hook) = self.pre_start {
+            changed = self.compile_one(hook, service_group, ctx) || changed;
+        }
+        if let Some(ref hook) = self.pre_stop {
+            changed = self.compile_one(hook, service_group, ctx) || changed;
+        }
         changed
     }
 
@@ -700,6 +830,7 @@ mod tests {
     use crate::common::types::ListenCtlAddr;
     use crate::config::GossipListenAddr;
     use crate::http_gateway;
+    use crate::manager::service::secrets::Secrets;
     use crate::manager::service::spec::ServiceBind;
     use crate::manager::sys::Sys;
 
@@ -725,7 +856,9 @@ mod tests {
                       ReloadHook
                       RunHook
                       SuitabilityHook
-                      PostStopHook);
+                      PostStopHook
+                      PreStartHook
+                      PreStopHook);
 
     fn hook_templates_path() -> PathBuf {
         PathBuf::from(env!("CARGO_MANIFEST_DIR"))
@@ -834,8 +967,9 @@ mod tests {
         );
 
         let bindings = iter::empty::<&ServiceBind>();
+        let secrets = Secrets::default();
 
-        let ctx = RenderContext::new(&service_group, &sys, &pkg, &cfg, &ring, bindings);
+        let ctx = RenderContext::new(&service_group, &sys, &pkg, &cfg, &secrets, &ring, bindings);
 
         // END RENDER CONTEXT SETUP
         ////////////////////////////////////////////////////////////////////////
@@ -0,0 +1,400 @@
+// Copyright (c) 2019 Chef Software Inc. and/or applicable contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Where a service's output ends up.
+//!
+//! By default the Launcher copies a service's output into its own stdout and stderr. With log
+//! files enabled, the Launcher writes it to `/hab/svc/<name>/logs/<name>.log` instead, rotating
+//! the file by size and age and keeping a bounded number of old files around. `LogReader` reads
+//! that file back, for `hab svc logs`.
+
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom};
+use std::path::PathBuf;
+
+use crate::hcore::fs::svc_logs_path;
+use crate::launcher_client::LogConfig;
+use crate::protocol;
+use time;
+
+const DEFAULT_MAX_SIZE: u64 = 10 * 1024 * 1024;
+const DEFAULT_MAX_AGE_SECS: u64 = 24 * 60 * 60;
+const DEFAULT_RETAIN: u32 = 7;
+/// How much of the file is read at a time when scanning back from its end for a tail.
+const TAIL_CHUNK_SIZE: u64 = 8 * 1024;
+
+#[derive(Copy, Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(default)]
+pub struct LogSettings {
+    /// Whether the service's output is written to a log file rather than the Supervisor's own
+    /// output.
+    pub enabled: bool,
+    /// Size, in bytes, at which the log file is rotated. Zero means no limit.
+    pub max_size: u64,
+    /// Age, in seconds, at which the log file is rotated. Zero means no limit.
+    pub max_age: u64,
+    /// Number of rotated files to keep. Zero keeps them all.
+    pub retain: u32,
+    /// Whether rotated files are gzipped.
+    pub compress: bool,
+}
+
+impl LogSettings {
+    /// The path of the live log file of the given package's service.
+    pub fn path(pkg_name: &str) -> PathBuf {
+        svc_logs_path(pkg_name).join(format!("{}.log", pkg_name))
+    }
+
+    /// The instructions to give the Launcher, if the service logs to a file at all.
+    pub fn launcher_config(&self, pkg_name: &str) -> Option<LogConfig> {
+        if !self.enabled {
+            return None;
+        }
+        Some(LogConfig {
+            path: Self::path(pkg_name).to_string_lossy().into_owned(),
+            max_size: non_zero(self.max_size),
+            max_age: non_zero(self.max_age),
+            retain: if self.retain == 0 {
+                None
+            } else {
+                Some(self.retain)
+            },
+            compress: self.compress,
+        })
+    }
+
+    /// Overwrite any fields that were set in the given protocol message.
+    pub fn merge(&mut self, proto: &protocol::types::LogSettings) {
+        if let Some(enabled) = proto.enabled {
+            self.enabled = enabled;
+        }
+        if let Some(max_size) = proto.max_size {
+            self.max_size = max_size;
+        }
+        if let Some(max_age) = proto.max_age {
+            self.max_age = max_age;
+        }
+        if let Some(retain) = proto.retain {
+            self.retain = retain;
+        }
+        if let Some(compress) = proto.compress {
+            self.compress = compress;
+        }
+    }
+}
+
+impl Default for LogSettings {
+    fn default() -> Self {
+        LogSettings {
+            enabled: false,
+            max_size: DEFAULT_MAX_SIZE,
+            max_age: DEFAULT_MAX_AGE_SECS,
+            retain: DEFAULT_RETAIN,
+            compress: true,
+        }
+    }
+}
+
+/// Reads the lines of a service's live log file, and any that are appended to it later.
+///
+/// Only the live file is read; rotated files are left alone. When the file is rotated while
+/// being followed, reading starts over at the beginning of the new file.
+pub struct LogReader {
+    path: PathBuf,
+    reader: Option<BufReader<File>>,
+    /// Bytes of the current file consumed so far.
+    position: u64,
+    /// The start of a line whose end hasn't been written yet.
+    partial: String,
+    /// Only lines stamped at or after this time, in seconds since the Unix epoch, are returned.
+    since: Option<i64>,
+}
+
+impl LogReader {
+    pub fn new(path: PathBuf, since: Option<i64>) -> Self {
+        LogReader {
+            path: path,
+            reader: None,
+            position: 0,
+            partial: String::new(),
+            since: since,
+        }
+    }
+
+    /// Read every line currently in the file, or only the last `tail` lines if given. The tail
+    /// is found by scanning back from the end of the file, so only as much of the file as the
+    /// tail takes up is read.
+    pub fn read_existing(&mut self, tail: Option<usize>) -> io::Result<Vec<String>> {
+        let tail = match tail {
+            Some(tail) => tail,
+            None => return self.read_new(),
+        };
+        if !self.open()? {
+            return Ok(Vec::new());
+        }
+        {
+            let reader = self.reader.as_mut().unwrap();
+            let start = tail_start(reader.get_mut(), tail)?;
+            reader.seek(SeekFrom::Start(start))?;
+            self.position = start;
+        }
+        self.read_new()
+    }
+
+    /// Read the complete lines written since the previous read. A missing file has no lines.
+    pub fn read_new(&mut self) -> io::Result<Vec<String>> {
+        if !self.open()? {
+            return Ok(Vec::new());
+        }
+        let reader = self.reader.as_mut().unwrap();
+        let mut lines = Vec::new();
+        loop {
+            let read = reader.read_line(&mut self.partial)?;
+            if read == 0 {
+                break;
+            }
+            self.position += read as u64;
+            if !self.partial.ends_with('\n') {
+                break;
+            }
+            let line = self.partial.trim_end_matches(|c| c == '\r' || c == '\n');
+            if self.since.map_or(true, |since| !logged_before(line, since)) {
+                lines.push(line.to_string());
+            }
+            self.partial.clear();
+        }
+        Ok(lines)
+    }
+
+    /// Make sure the file at our path is the one open, opening it again if it was rotated.
+    /// Returns false if there is no file.
+    fn open(&mut self) -> io::Result<bool> {
+        if self.rotated()? {
+            self.reader = None;
+        }
+        if self.reader.is_none() {
+            match File::open(&self.path) {
+                Ok(file) => {
+                    self.reader = Some(BufReader::new(file));
+                    self.position = 0;
+                    self.partial.clear();
+                }
+                Err(ref err) if err.kind() == io::ErrorKind::NotFound => return Ok(false),
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(true)
+    }
+
+    /// Whether the file at our path is no longer the one we have open.
+    fn rotated(&self) -> io::Result<bool> {
+        let reader = match self.reader {
+            Some(ref reader) => reader,
+            None => return Ok(false),
+        };
+        let current = match fs::metadata(&self.path) {
+            Ok(metadata) => metadata,
+            Err(ref err) if err.kind() == io::ErrorKind::NotFound => return Ok(false),
+            Err(err) => return Err(err),
+        };
+        if current.len() < self.position {
+            return Ok(true);
+        }
+        Ok(!same_file(&reader.get_ref().metadata()?, &current))
+    }
+}
+
+/// The offset of the first of the last `tail` complete lines of the file: just past the line end
+/// `tail + 1` line ends back from the end of the file, or the start of the file if it has fewer.
+fn tail_start(file: &mut File, tail: usize) -> io::Result<u64> {
+    let mut end = file.seek(SeekFrom::End(0))?;
+    let mut line_ends = 0;
+    let mut buf = vec![0; TAIL_CHUNK_SIZE as usize];
+    while end > 0 {
+        let start = end.saturating_sub(TAIL_CHUNK_SIZE);
+        let chunk = &mut buf[..(end - start) as usize];
+        file.seek(SeekFrom::Start(start))?;
+        file.read_exact(chunk)?;
+        for (i, byte) in chunk.iter().enumerate().rev() {
+            if *byte == b'\n' {
+                line_ends += 1;
+                if line_ends > tail {
+                    return Ok(start + i as u64 + 1);
+                }
+            }
+        }
+        end = start;
+    }
+    Ok(0)
+}
+
+#[cfg(unix)]
+fn same_file(a: &fs::Metadata, b: &fs::Metadata) -> bool {
+    use std::os::unix::fs::MetadataExt;
+
+    a.dev() == b.dev() && a.ino() == b.ino()
+}
+
+#[cfg(not(unix))]
+fn same_file(_a: &fs::Metadata, _b: &fs::Metadata) -> bool {
+    // Rotation is detected by the file shrinking instead.
+    true
+}
+
+/// Whether a line, stamped with an RFC 3339 time by the Launcher, was logged before `since`.
+/// Lines without a stamp are never considered old.
+fn logged_before(line: &str, since: i64) -> bool {
+    line.split_whitespace()
+        .next()
+        .and_then(|stamp| time::strptime(stamp, "%Y-%m-%dT%H:%M:%SZ").ok())
+        .map_or(false, |tm| tm.to_timespec().sec < since)
+}
+
+fn non_zero(value: u64) -> Option<u64> {
+    if value == 0 {
+        None
+    } else {
+        Some(value)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::fs::OpenOptions;
+    use std::io::Write;
+
+    use tempfile::TempDir;
+
+    fn append(path: &PathBuf, content: &str) {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .unwrap();
+        file.write_all(content.as_bytes()).unwrap();
+    }
+
+    #[test]
+    fn disabled_settings_leave_output_to_the_launcher() {
+        assert_eq!(LogSettings::default().launcher_config("redis"), None);
+    }
+
+    #[test]
+    fn zero_means_unlimited() {
+        let settings = LogSettings {
+            enabled: true,
+            max_size: 1024,
+            max_age: 0,
+            retain: 0,
+            compress: false,
+        };
+        let config = settings.launcher_config("redis").unwrap();
+
+        assert_eq!(PathBuf::from(&config.path), LogSettings::path("redis"));
+        assert!(config.path.ends_with("redis.log"));
+        assert_eq!(config.max_size, Some(1024));
+        assert_eq!(config.max_age, None);
+        assert_eq!(config.retain, None);
+        assert!(!config.compress);
+    }
+
+    #[test]
+    fn reader_tails_the_existing_lines() {
+        let tmpdir = TempDir::new().unwrap();
+        let path = tmpdir.path().join("redis.log");
+        append(&path, "one\ntwo\nthree\n");
+        let mut reader = LogReader::new(path, None);
+
+        assert_eq!(reader.read_existing(Some(2)).unwrap(), vec!["two", "three"]);
+    }
+
+    #[test]
+    fn reader_tails_from_the_end_of_a_large_file() {
+        let tmpdir = TempDir::new().unwrap();
+        let path = tmpdir.path().join("redis.log");
+        let lines: Vec<String> = (0..10_000).map(|n| format!("line {}", n)).collect();
+        append(&path, &format!("{}\npartial", lines.join("\n")));
+        let mut reader = LogReader::new(path.clone(), None);
+
+        assert_eq!(
+            reader.read_existing(Some(3)).unwrap(),
+            vec!["line 9997", "line 9998", "line 9999"]
+        );
+        append(&path, " line\n");
+        assert_eq!(reader.read_new().unwrap(), vec!["partial line"]);
+    }
+
+    #[test]
+    fn reader_tails_fewer_lines_than_asked_for() {
+        let tmpdir = TempDir::new().unwrap();
+        let path = tmpdir.path().join("redis.log");
+        append(&path, "one\ntwo\n");
+
+        assert_eq!(
+            LogReader::new(path.clone(), None)
+                .read_existing(Some(5))
+                .unwrap(),
+            vec!["one", "two"]
+        );
+        assert!(LogReader::new(path, None)
+            .read_existing(Some(0))
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn reader_follows_appended_lines() {
+        let tmpdir = TempDir::new().unwrap();
+        let path = tmpdir.path().join("redis.log");
+        let mut reader = LogReader::new(path.clone(), None);
+
+        assert!(reader.read_existing(None).unwrap().is_empty());
+        append(&path, "one\ntw");
+        assert_eq!(reader.read_new().unwrap(), vec!["one"]);
+        append(&path, "o\n");
+        assert_eq!(reader.read_new().unwrap(), vec!["two"]);
+    }
+
+    #[test]
+    fn reader_starts_over_after_rotation() {
+        let tmpdir = TempDir::new().unwrap();
+        let path = tmpdir.path().join("redis.log");
+        append(&path, "old line\n");
+        let mut reader = LogReader::new(path.clone(), None);
+        reader.read_existing(None).unwrap();
+
+        fs::rename(&path, tmpdir.path().join("redis.log.20190101T000000Z")).unwrap();
+        append(&path, "new line\n");
+        assert_eq!(reader.read_new().unwrap(), vec!["new line"]);
+    }
+
+    #[test]
+    fn reader_skips_lines_logged_before_since() {
+        let tmpdir = TempDir::new().unwrap();
+        let path = tmpdir.path().join("redis.log");
+        append(
+            &path,
+            "2019-01-01T00:00:00Z O: early\n2019-01-01T00:01:00Z E: late\nunstamped\n",
+        );
+        // 2019-01-01T00:00:30Z
+        let mut reader = LogReader::new(path, Some(1_546_300_830));
+
+        assert_eq!(
+            reader.read_existing(None).unwrap(),
+            vec!["2019-01-01T00:01:00Z E: late", "unstamped"]
+        );
+    }
+}
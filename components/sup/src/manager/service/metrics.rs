@@ -0,0 +1,281 @@
+// Copyright (c) 2018 Chef Software Inc. and/or applicable contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Prometheus metrics describing a single service, labelled by its service group.
+//!
+//! Restart counts and cgroup usage are reported by the [`restart`] and [`resources`] modules;
+//! this module covers the state of the service's process, its health, and its package.
+
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use prometheus::{GaugeVec, IntGaugeVec};
+use time;
+
+use super::supervisor::Supervisor;
+use super::HealthCheck;
+use crate::hcore::os::process::Pid;
+use crate::hcore::package::PackageIdent;
+use crate::hcore::service::ServiceGroup;
+use crate::protocol::types::ProcessState;
+
+lazy_static! {
+    static ref SERVICE_UP: IntGaugeVec = register_int_gauge_vec!(
+        "hab_sup_service_up",
+        "Whether a service's process is up (1) or not (0)",
+        &["service_group"]
+    )
+    .unwrap();
+    static ref SERVICE_UPTIME_SECONDS: IntGaugeVec = register_int_gauge_vec!(
+        "hab_sup_service_uptime_seconds",
+        "How long a service's process has been up, in seconds",
+        &["service_group"]
+    )
+    .unwrap();
+    static ref SERVICE_HEALTH_STATUS: IntGaugeVec = register_int_gauge_vec!(
+        "hab_sup_service_health_status",
+        "The current health of a service: 1 for its status, 0 for the others",
+        &["service_group", "status"]
+    )
+    .unwrap();
+    static ref SERVICE_LAST_UPDATE_TIMESTAMP_SECONDS: IntGaugeVec = register_int_gauge_vec!(
+        "hab_sup_service_last_update_timestamp_seconds",
+        "When a service was last updated to a new package, in seconds since the Unix epoch",
+        &["service_group"]
+    )
+    .unwrap();
+    static ref SERVICE_PACKAGE_INFO: IntGaugeVec = register_int_gauge_vec!(
+        "hab_sup_service_package_info",
+        "The package a service is running, as labels; always 1",
+        &["service_group", "ident", "version", "release"]
+    )
+    .unwrap();
+    static ref SERVICE_PROCESS_CPU_SECONDS: GaugeVec = register_gauge_vec!(
+        "hab_sup_service_process_cpu_seconds",
+        "CPU time consumed by a service's main process, in seconds",
+        &["service_group"]
+    )
+    .unwrap();
+    static ref SERVICE_PROCESS_RESIDENT_MEMORY_BYTES: IntGaugeVec = register_int_gauge_vec!(
+        "hab_sup_service_process_resident_memory_bytes",
+        "Resident memory of a service's main process, in bytes",
+        &["service_group"]
+    )
+    .unwrap();
+}
+
+/// How often the service's process is sampled from `/proc`.
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(10);
+
+const HEALTH_STATUSES: [HealthCheck; 4] = [
+    HealthCheck::Ok,
+    HealthCheck::Warning,
+    HealthCheck::Critical,
+    HealthCheck::Unknown,
+];
+
+/// CPU time and resident memory of a single process.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct ProcessUsage {
+    cpu_seconds: f64,
+    resident_memory_bytes: i64,
+}
+
+/// Keeps the metrics of a single service up to date. The service's labels are removed once it
+/// is dropped, so that unloaded services disappear from `/metrics`.
+#[derive(Debug)]
+pub struct ServiceMetrics {
+    service_group: String,
+    package: Option<[String; 3]>,
+    last_update: Option<i64>,
+    next_sample: Instant,
+}
+
+impl ServiceMetrics {
+    pub fn new(service_group: &ServiceGroup) -> Self {
+        ServiceMetrics {
+            service_group: service_group.to_string(),
+            package: None,
+            last_update: None,
+            next_sample: Instant::now(),
+        }
+    }
+
+    /// Record that the service was just updated to a new package.
+    pub fn updated(&mut self) {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or_default();
+        self.last_update = Some(now);
+    }
+
+    /// Bring the metrics in line with the current state of the service.
+    pub fn update(
+        &mut self,
+        supervisor: &Supervisor,
+        health: HealthCheck,
+        ident: &PackageIdent,
+        now: Instant,
+    ) {
+        let sg = &[self.service_group.as_str()];
+        let up = supervisor.state == ProcessState::Up;
+        SERVICE_UP.with_label_values(sg).set(up as i64);
+        let uptime = if up {
+            (time::get_time() - supervisor.state_entered).num_seconds()
+        } else {
+            0
+        };
+        SERVICE_UPTIME_SECONDS.with_label_values(sg).set(uptime);
+
+        for status in HEALTH_STATUSES.iter() {
+            let label = status.to_string().to_lowercase();
+            SERVICE_HEALTH_STATUS
+                .with_label_values(&[&self.service_group, &label])
+                .set((*status == health) as i64);
+        }
+
+        if let Some(last_update) = self.last_update {
+            SERVICE_LAST_UPDATE_TIMESTAMP_SECONDS
+                .with_label_values(sg)
+                .set(last_update);
+        }
+
+        if now >= self.next_sample {
+            self.next_sample = now + SAMPLE_INTERVAL;
+            let usage = supervisor.pid().and_then(ProcessUsage::read);
+            let usage = usage.unwrap_or(ProcessUsage {
+                cpu_seconds: 0.0,
+                resident_memory_bytes: 0,
+            });
+            SERVICE_PROCESS_CPU_SECONDS
+                .with_label_values(sg)
+                .set(usage.cpu_seconds);
+            SERVICE_PROCESS_RESIDENT_MEMORY_BYTES
+                .with_label_values(sg)
+                .set(usage.resident_memory_bytes);
+        }
+
+        self.set_package(ident);
+    }
+
+    fn set_package(&mut self, ident: &PackageIdent) {
+        let package = [
+            format!("{}/{}", ident.origin, ident.name),
+            ident.version.clone().unwrap_or_default(),
+            ident.release.clone().unwrap_or_default(),
+        ];
+        if self.package.as_ref() != Some(&package) {
+            self.remove_package();
+            self.package = Some(package);
+        }
+        if let Some(ref package) = self.package {
+            SERVICE_PACKAGE_INFO
+                .with_label_values(&self.package_labels(package))
+                .set(1);
+        }
+    }
+
+    fn remove_package(&self) {
+        if let Some(ref package) = self.package {
+            let _ = SERVICE_PACKAGE_INFO.remove_label_values(&self.package_labels(package));
+        }
+    }
+
+    fn package_labels<'a>(&'a self, package: &'a [String; 3]) -> [&'a str; 4] {
+        [&self.service_group, &package[0], &package[1], &package[2]]
+    }
+}
+
+impl Drop for ServiceMetrics {
+    fn drop(&mut self) {
+        let sg = &[self.service_group.as_str()];
+        // Removing labels that were never set is an error we don't care about.
+        let _ = SERVICE_UP.remove_label_values(sg);
+        let _ = SERVICE_UPTIME_SECONDS.remove_label_values(sg);
+        let _ = SERVICE_LAST_UPDATE_TIMESTAMP_SECONDS.remove_label_values(sg);
+        let _ = SERVICE_PROCESS_CPU_SECONDS.remove_label_values(sg);
+        let _ = SERVICE_PROCESS_RESIDENT_MEMORY_BYTES.remove_label_values(sg);
+        for status in HEALTH_STATUSES.iter() {
+            let label = status.to_string().to_lowercase();
+            let _ = SERVICE_HEALTH_STATUS.remove_label_values(&[&self.service_group, &label]);
+        }
+        self.remove_package();
+    }
+}
+
+impl ProcessUsage {
+    #[cfg(target_os = "linux")]
+    fn read(pid: Pid) -> Option<Self> {
+        use std::fs;
+
+        let stat = fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+        // These are constant for the lifetime of the system, and can't fail on Linux.
+        let (ticks_per_second, page_size) = unsafe {
+            (
+                libc::sysconf(libc::_SC_CLK_TCK),
+                libc::sysconf(libc::_SC_PAGESIZE),
+            )
+        };
+        Self::parse(&stat, ticks_per_second as u64, page_size as u64)
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn read(_pid: Pid) -> Option<Self> {
+        None
+    }
+
+    /// Parse the contents of `/proc/<pid>/stat`; see proc(5).
+    #[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+    fn parse(stat: &str, ticks_per_second: u64, page_size: u64) -> Option<Self> {
+        // The process name is in parentheses and may contain spaces, so the remaining fields are
+        // counted from the last closing parenthesis, starting with the state (field 3).
+        let fields: Vec<&str> = stat[stat.rfind(')')? + 1..].split_whitespace().collect();
+        let field = |n: usize| fields.get(n - 3).and_then(|f| f.parse::<u64>().ok());
+        let ticks = field(14)? + field(15)?;
+        let rss_pages = field(24)?;
+        Some(ProcessUsage {
+            cpu_seconds: ticks as f64 / ticks_per_second.max(1) as f64,
+            resident_memory_bytes: (rss_pages * page_size) as i64,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const STAT: &str = "4242 (redis server) S 1 4242 4242 0 -1 4194560 2212 0 0 0 250 150 0 0 \
+                        20 0 4 0 2371466 54210560 1536 18446744073709551615 1 1 0 0 0 0 0 \
+                        4096 17987 0 0 0 17 1 0 0 0 0 0 0 0 0 0 0 0 0 0";
+
+    #[test]
+    fn parses_proc_stat() {
+        assert_eq!(
+            ProcessUsage::parse(STAT, 100, 4096),
+            Some(ProcessUsage {
+                cpu_seconds: 4.0,
+                resident_memory_bytes: 1536 * 4096,
+            })
+        );
+    }
+
+    #[test]
+    fn truncated_proc_stat_is_ignored() {
+        assert_eq!(
+            ProcessUsage::parse("4242 (redis) S 1 4242", 100, 4096),
+            None
+        );
+        assert_eq!(ProcessUsage::parse("", 100, 4096), None);
+    }
+}
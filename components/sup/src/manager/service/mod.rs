@@ -15,8 +15,17 @@
 mod context;
 pub mod health;
 pub mod hooks;
+mod logging;
+mod metrics;
+mod probe;
+mod resources;
+mod restart;
+mod rollback;
+pub mod secrets;
+mod soak;
 pub mod spec;
 mod supervisor;
+pub mod update;
 
 use std;
 use std::collections::HashSet;
@@ -34,32 +43,56 @@ pub use crate::common::templating::config::{Cfg, UserConfigPath};
 use crate::common::templating::hooks::Hook;
 pub use crate::common::templating::package::{Env, Pkg, PkgProxy};
 use crate::hcore;
-use crate::hcore::crypto::hash;
+use crate::hcore::crypto::{default_cache_key_path, hash};
 use crate::hcore::fs::{svc_hooks_path, SvcDir, FS_ROOT_PATH};
 use crate::hcore::package::metadata::Bind;
 use crate::hcore::package::{PackageIdent, PackageInstall};
 use crate::hcore::service::{HealthCheckInterval, ServiceGroup};
 use crate::hcore::ChannelIdent;
-use crate::launcher_client::LauncherCli;
-pub use crate::protocol::types::{BindingMode, ProcessState, Topology, UpdateStrategy};
+use crate::launcher_client::{LauncherCli, ShutdownSpec};
+pub use crate::protocol::types::{
+    BindingMode, ProcessState, ShutdownSignal, Topology, UpdateStrategy,
+};
 use prometheus::{HistogramTimer, HistogramVec};
 use serde::ser::SerializeStruct;
 use serde::{Serialize, Serializer};
 use time::Timespec;
 
 use self::context::RenderContext;
-pub use self::health::HealthCheck;
+use self::health::HealthTracker;
+pub use self::health::{HealthCheck, HealthCheckThresholds};
 use self::hooks::HookTable;
-pub use self::spec::{DesiredState, IntoServiceSpec, ServiceBind, ServiceSpec};
+pub use self::logging::{LogReader, LogSettings};
+use self::metrics::ServiceMetrics;
+pub use self::probe::HealthProbe;
+use self::probe::RunningProbe;
+pub use self::resources::ResourceLimits;
+use self::resources::ResourceMonitor;
+pub use self::restart::RestartPolicy;
+use self::restart::{RestartDecision, RestartReason, RestartSteps, RestartTracker};
+use self::rollback::RollbackTracker;
+use self::secrets::{SecretStore, Secrets};
+use self::soak::SoakTracker;
+pub use self::spec::{same_package, DesiredState, IntoServiceSpec, ServiceBind, ServiceSpec};
 use self::supervisor::Supervisor;
+pub use self::update::{BatchSize, UpdateSettings, UpdateWindow};
 use super::ShutdownReason;
 use super::Sys;
 use crate::census::{CensusGroup, CensusRing, ElectionStatus, ServiceFile};
 use crate::error::{Error, Result, SupError};
+use crate::event::{self, Event};
 use crate::manager;
 
 static LOGKEY: &'static str = "SR";
 
+/// How long the pre-start hook may run, unless the spec says otherwise, before it is killed and
+/// the service's process is left down. The hook runs on the Manager's main loop, so this is also
+/// how long it may hold up every other service and the gateways.
+const DEFAULT_PRE_START_TIMEOUT_SECS: u32 = 30;
+/// How long the pre-stop hook may run, unless the spec says otherwise, before the service's
+/// process is terminated regardless.
+const DEFAULT_PRE_STOP_TIMEOUT_SECS: u32 = 30;
+
 #[cfg(not(windows))]
 pub const GOSSIP_FILE_PERMISSIONS: u32 = 0o640;
 
@@ -104,6 +137,18 @@ pub struct Service {
     pub spec_ident: PackageIdent,
     pub topology: Topology,
     pub update_strategy: UpdateStrategy,
+    pub update_settings: UpdateSettings,
+    /// Whether automatic updates are paused. The updater leaves the service alone until they
+    /// are resumed.
+    pub update_paused: bool,
+    /// Set when an operator asks for an update check right away; cleared once the updater has
+    /// seen it.
+    #[serde(skip_serializing)]
+    pub update_check_requested: bool,
+    /// The canary update the service is soaking, if any. It is gossiped so that the rest of the
+    /// service group doesn't follow the canary too early.
+    #[serde(rename = "soaking_from")]
+    soak: SoakTracker,
     pub cfg: Cfg,
     pub pkg: Pkg,
     pub sys: Arc<Sys>,
@@ -128,6 +173,11 @@ pub struct Service {
     /// Controls how the presence or absence of bound service groups
     /// impacts the service's start-up.
     binding_mode: BindingMode,
+    /// Packages whose services on this Supervisor start before this one and stop after it.
+    after: Vec<PackageIdent>,
+    /// Packages whose services must be up on this Supervisor before this one starts.
+    requires: Vec<PackageIdent>,
+    require_healthy: bool,
     /// Binds specified by the user that are currently mapped to
     /// service groups that do _not_ satisfy the bind's contract, as
     /// defined in the service's current package.
@@ -150,6 +200,26 @@ pub struct Service {
     supervisor: Supervisor,
     svc_encrypted_password: Option<String>,
     health_check_interval: HealthCheckInterval,
+    health_check_thresholds: HealthCheckThresholds,
+    health_probe: Option<HealthProbe>,
+    /// The probe of the health check under way, along with the result of its hook, if any.
+    #[serde(skip_serializing)]
+    running_probe: Option<(RunningProbe, Option<HealthCheck>)>,
+    #[serde(skip_serializing)]
+    health_tracker: HealthTracker,
+    restart_policy: RestartPolicy,
+    restarts: RestartTracker,
+    rollback: RollbackTracker,
+    resource_limits: ResourceLimits,
+    #[serde(skip_serializing)]
+    resources: ResourceMonitor,
+    #[serde(skip_serializing)]
+    metrics: ServiceMetrics,
+    log_settings: LogSettings,
+    shutdown_signal: Option<ShutdownSignal>,
+    shutdown_timeout: Option<u32>,
+    pre_start_timeout: Option<u32>,
+    pre_stop_timeout: Option<u32>,
 
     #[serde(skip_serializing)]
     /// Whether a service's default configuration changed on a package
@@ -157,6 +227,14 @@ pub struct Service {
     defaults_updated: bool,
     #[serde(skip_serializing)]
     gateway_state: Arc<RwLock<manager::GatewayState>>,
+    /// The Supervisor-local secrets of the service group. They are only ever exposed to the
+    /// service's templates, and so are never serialized.
+    #[serde(skip_serializing)]
+    secrets: Secrets,
+    /// Set when the secrets of the service group may have changed; cleared once they have been
+    /// read from the secret store again.
+    #[serde(skip_serializing)]
+    pub secrets_updated: bool,
 }
 
 impl Service {
@@ -180,6 +258,11 @@ impl Service {
         )?;
         let config_root = Self::config_root(&pkg, spec.config_from.as_ref());
         let hooks_root = Self::hooks_root(&pkg, spec.config_from.as_ref());
+        let restarts = RestartTracker::load(&service_group, &manager_fs_cfg.data_path);
+        let rollback = RollbackTracker::load(&service_group, &manager_fs_cfg.data_path);
+        let soak = SoakTracker::load(&service_group, &manager_fs_cfg.data_path);
+        let resources = ResourceMonitor::new(&service_group);
+        let metrics = ServiceMetrics::new(&service_group);
         Ok(Service {
             sys: sys,
             cfg: Cfg::new(&pkg, spec.config_from.as_ref())?,
@@ -206,16 +289,40 @@ impl Service {
             all_pkg_binds: all_pkg_binds,
             unsatisfied_binds: HashSet::new(),
             binding_mode: spec.binding_mode,
+            after: spec.after,
+            requires: spec.requires,
+            require_healthy: spec.require_healthy,
             spec_ident: spec.ident,
             spec_file: spec_file,
             topology: spec.topology,
             update_strategy: spec.update_strategy,
+            update_settings: spec.update_settings,
+            update_paused: spec.update_paused,
+            update_check_requested: false,
+            soak: soak,
             config_from: spec.config_from,
             scheduled_health_check: Some(Instant::now()),
             svc_encrypted_password: spec.svc_encrypted_password,
             health_check_interval: spec.health_check_interval,
+            health_check_thresholds: spec.health_check_thresholds,
+            health_probe: spec.health_probe,
+            running_probe: None,
+            health_tracker: HealthTracker::default(),
+            restart_policy: spec.restart_policy,
+            restarts: restarts,
+            rollback: rollback,
+            resource_limits: spec.resource_limits,
+            resources: resources,
+            metrics: metrics,
+            log_settings: spec.log_settings,
+            shutdown_signal: spec.shutdown_signal,
+            shutdown_timeout: spec.shutdown_timeout,
+            pre_start_timeout: spec.pre_start_timeout,
+            pre_stop_timeout: spec.pre_stop_timeout,
             defaults_updated: false,
             gateway_state: gateway_state,
+            secrets: Secrets::default(),
+            secrets_updated: true,
         })
     }
 
@@ -263,6 +370,11 @@ impl Service {
     }
 
     fn start(&mut self, launcher: &LauncherCli) {
+        if !self.pre_start() {
+            // The process stays down, and is started again under the restart policy.
+            return;
+        }
+        let shutdown = self.shutdown_spec();
         if let Some(err) = self
             .supervisor
             .start(
@@ -270,6 +382,9 @@ impl Service {
                 &self.service_group,
                 launcher,
                 self.svc_encrypted_password.as_ref(),
+                self.resource_limits,
+                self.log_settings,
+                shutdown,
             )
             .err()
         {
@@ -277,12 +392,32 @@ impl Service {
         } else {
             self.needs_reload = false;
             self.needs_reconfiguration = false;
+            self.health_tracker.service_started(Instant::now());
+            event::publish(Event::ServiceStarted {
+                service_group: self.service_group.to_string(),
+                pkg_ident: self.pkg.ident.to_string(),
+                pid: self.supervisor.pid(),
+            });
         }
     }
 
     pub fn stop(&mut self, launcher: &LauncherCli, cause: ShutdownReason) {
-        match self.supervisor.stop(launcher, cause) {
-            Ok(_) => self.post_stop(),
+        match cause {
+            // The Launcher is terminating every service on its own already.
+            ShutdownReason::LauncherStopping => (),
+            _ if self.process_down() => (),
+            _ => self.pre_stop(),
+        }
+        let shutdown = self.shutdown_spec();
+        match self.supervisor.stop(launcher, cause, shutdown) {
+            Ok(_) => {
+                self.post_stop();
+                event::publish(Event::ServiceStopped {
+                    service_group: self.service_group.to_string(),
+                    pkg_ident: self.pkg.ident.to_string(),
+                    reason: cause.to_string(),
+                });
+            }
             Err(err) => outputln!(preamble self.service_group, "Service stop failed: {}", err),
         }
     }
@@ -292,18 +427,7 @@ impl Service {
         let _timer = hook_timer("reload");
         self.needs_reload = false;
         if self.process_down() || self.hooks.reload.is_none() {
-            if let Some(err) = self
-                .supervisor
-                .restart(
-                    &self.pkg,
-                    &self.service_group,
-                    launcher,
-                    self.svc_encrypted_password.as_ref(),
-                )
-                .err()
-            {
-                outputln!(preamble self.service_group, "Service restart failed: {}", err);
-            }
+            self.restart_process(launcher);
         } else {
             let hook = self.hooks.reload.as_ref().unwrap();
             hook.run(
@@ -314,6 +438,15 @@ impl Service {
         }
     }
 
+    /// Restarts the service's process (see `restart::restart_process` for the order of the
+    /// steps). Returns whether the process was restarted.
+    fn restart_process(&mut self, launcher: &LauncherCli) -> bool {
+        restart::restart_process(&mut ProcessRestart {
+            service: self,
+            launcher: launcher,
+        })
+    }
+
     pub fn last_state_change(&self) -> Timespec {
         self.supervisor.state_entered
     }
@@ -390,6 +523,10 @@ impl Service {
                             outputln!(preamble self.service_group,
                                       "Executing hooks; {} is the leader",
                                       leader_id.to_string());
+                            event::publish(Event::ElectionFinished {
+                                service_group: self.service_group.to_string(),
+                                leader_id: leader_id.to_string(),
+                            });
                             self.last_election_status = census_group.election_status;
                         }
                         self.execute_hooks(launcher)
@@ -400,8 +537,15 @@ impl Service {
         if svc_updated {
             self.schedule_health_check_at_next_tick();
         }
+        let rolled_back = self.roll_back_failed_update(launcher);
+        self.metrics.update(
+            &self.supervisor,
+            self.health_check,
+            &self.pkg.ident,
+            Instant::now(),
+        );
 
-        svc_updated
+        svc_updated || rolled_back
     }
 
     pub fn to_spec(&self) -> ServiceSpec {
@@ -414,13 +558,27 @@ impl Service {
         spec.channel = self.channel.clone();
         spec.topology = self.topology;
         spec.update_strategy = self.update_strategy;
+        spec.update_paused = self.update_paused;
+        spec.update_settings = self.update_settings.clone();
         spec.binds = self.binds.clone();
         spec.binding_mode = self.binding_mode;
+        spec.after = self.after.clone();
+        spec.requires = self.requires.clone();
+        spec.require_healthy = self.require_healthy;
         spec.config_from = self.config_from.clone();
         if let Some(ref password) = self.svc_encrypted_password {
             spec.svc_encrypted_password = Some(password.clone())
         }
         spec.health_check_interval = self.health_check_interval;
+        spec.health_check_thresholds = self.health_check_thresholds;
+        spec.health_probe = self.health_probe.clone();
+        spec.restart_policy = self.restart_policy;
+        spec.resource_limits = self.resource_limits;
+        spec.log_settings = self.log_settings;
+        spec.shutdown_signal = self.shutdown_signal;
+        spec.shutdown_timeout = self.shutdown_timeout;
+        spec.pre_start_timeout = self.pre_start_timeout;
+        spec.pre_stop_timeout = self.pre_stop_timeout;
         spec
     }
 
@@ -568,6 +726,74 @@ impl Service {
         self.supervisor.check_process()
     }
 
+    /// Whether this service starts after, and stops before, the other one.
+    pub fn depends_on(&self, other: &Service) -> bool {
+        self.after
+            .iter()
+            .chain(self.requires.iter())
+            .any(|dep| same_package(dep, &other.spec_ident))
+    }
+
+    /// Whether services that start after this one may start: its process is up and, if
+    /// `require_healthy`, its health checks pass.
+    pub fn ready_for_dependents(&self, require_healthy: bool) -> bool {
+        if require_healthy {
+            self.healthy()
+        } else {
+            !self.process_down()
+        }
+    }
+
+    /// Whether the service's process is up and its reported health is `Ok`.
+    pub fn healthy(&self) -> bool {
+        !self.process_down() && self.health_check == HealthCheck::Ok
+    }
+
+    /// The release this service was last rolled back from, which it won't be updated to again.
+    pub fn rejected_release(&self) -> Option<&PackageIdent> {
+        self.rollback.rejected()
+    }
+
+    /// Whether the service is a canary soaking an update.
+    pub fn soaking(&self) -> bool {
+        self.soak.soaking()
+    }
+
+    /// Hold the rest of the service group back while the canary soaks its update from
+    /// `previous`.
+    pub fn start_soaking(&mut self, previous: PackageIdent) {
+        self.soak.start(previous);
+    }
+
+    /// Let the rest of the service group follow the canary.
+    pub fn finish_soaking(&mut self) {
+        self.soak.finish();
+    }
+
+    /// How the Launcher stops the service's process. The spec's settings win over the package's,
+    /// and the Launcher's defaults apply to whatever neither sets.
+    fn shutdown_spec(&self) -> ShutdownSpec {
+        let mut shutdown = ShutdownSpec::default();
+        let signal = self.shutdown_signal.or_else(|| {
+            self.pkg.shutdown_signal.as_ref().and_then(|name| {
+                match name.parse::<ShutdownSignal>() {
+                    Ok(signal) => Some(signal),
+                    Err(err) => {
+                        warn!("Ignoring shutdown signal from {}: {}", self.pkg.ident, err);
+                        None
+                    }
+                }
+            })
+        });
+        if let Some(signal) = signal {
+            shutdown.signal = signal.to_string();
+        }
+        if let Some(timeout) = self.shutdown_timeout.or(self.pkg.shutdown_timeout) {
+            shutdown.timeout = timeout;
+        }
+        shutdown
+    }
+
     fn process_down(&self) -> bool {
         self.supervisor.state == ProcessState::Down
     }
@@ -584,14 +810,18 @@ impl Service {
                 }
                 self.cfg
                     .set_gossip(config.incarnation, config.value.clone());
+                event::publish(Event::ConfigApplied {
+                    service_group: self.service_group.to_string(),
+                    incarnation: config.incarnation,
+                });
                 true
             }
             None => false,
         }
     }
 
-    /// Compares the current state of the service to the current state of the census ring and the
-    /// user-config, and re-renders all templatable content to disk.
+    /// Compares the current state of the service to the current state of the census ring, the
+    /// user-config and the secrets, and re-renders all templatable content to disk.
     ///
     /// Returns `true` if any modifications were made.
     fn update_templates(&mut self, census_ring: &CensusRing) -> bool {
@@ -601,6 +831,7 @@ impl Service {
         let cfg_updated_from_rumors = self.update_gossip(census_group);
         let cfg_changed =
             self.defaults_updated || cfg_updated_from_rumors || self.user_config_updated;
+        let secrets_changed = self.secrets_updated && self.reload_secrets();
 
         if self.user_config_updated {
             if let Err(e) = self.cfg.reload_user() {
@@ -612,7 +843,7 @@ impl Service {
 
         self.defaults_updated = false;
 
-        if cfg_changed || census_ring.changed() {
+        if cfg_changed || secrets_changed || census_ring.changed() {
             let (reload, reconfigure) = {
                 let ctx = self.render_context(census_ring);
 
@@ -634,22 +865,103 @@ impl Service {
             self.needs_reconfiguration = reconfigure;
         }
 
-        cfg_changed
+        cfg_changed || secrets_changed
+    }
+
+    /// Reads the secrets of the service group from the Supervisor's secret store again.
+    ///
+    /// Returns `true` if they changed.
+    fn reload_secrets(&mut self) -> bool {
+        self.secrets_updated = false;
+        let store = SecretStore::new(&self.manager_fs_cfg.sup_root, &self.service_group);
+        match store.load(&default_cache_key_path(None)) {
+            Ok(ref secrets) if *secrets == self.secrets => false,
+            Ok(secrets) => {
+                self.secrets = secrets;
+                true
+            }
+            Err(e) => {
+                outputln!(preamble self.service_group, "Loading secrets failed: {}", e);
+                false
+            }
+        }
     }
 
     /// Replace the package of the running service and restart its system process.
+    ///
+    /// If the update settings have a rollback period, the service is rolled back should the new
+    /// package fail within it.
     pub fn update_package(&mut self, package: PackageInstall, launcher: &LauncherCli) {
+        let previous = self.pkg.ident.clone();
+        if !self.replace_package(package, launcher) {
+            return;
+        }
+        match self.update_settings.rollback_period() {
+            Some(period) if self.pkg.ident > previous => {
+                self.rollback
+                    .watch(previous, self.pkg.ident.clone(), period, Instant::now())
+            }
+            _ => self.rollback.stop_watching(),
+        }
+    }
+
+    /// Rolls the service back to its previous package if it is failing after an update.
+    ///
+    /// Returns `true` if the service was rolled back.
+    fn roll_back_failed_update(&mut self, launcher: &LauncherCli) -> bool {
+        let previous = match self.rollback.check(
+            self.healthy(),
+            self.restarts.is_crash_looping(),
+            Instant::now(),
+        ) {
+            Some(previous) => previous,
+            None => return false,
+        };
+        let failed = self.pkg.ident.clone();
+        let package = match PackageInstall::load(&previous, Some(Path::new(&*FS_ROOT_PATH))) {
+            Ok(package) => package,
+            Err(err) => {
+                outputln!(preamble self.service_group,
+                          "Update to {} failed, but {} can't be rolled back to: {}",
+                          failed, previous, err);
+                return false;
+            }
+        };
+        outputln!(preamble self.service_group,
+                  "Update to {} failed; rolling back to {}", failed, previous);
+        event::publish(Event::UpdateRolledBack {
+            service_group: self.service_group.to_string(),
+            previous: failed.to_string(),
+            current: previous.to_string(),
+        });
+        // A canary that failed has nothing left to hold back, and the previous package gets a
+        // fresh start under the restart policy.
+        self.soak.finish();
+        self.restarts.reset();
+        self.replace_package(package, launcher)
+    }
+
+    /// Swaps in the given package and stops the service's process, which is then started again on
+    /// the next tick.
+    ///
+    /// Returns `false` if the package couldn't be loaded, in which case nothing changes.
+    fn replace_package(&mut self, package: PackageInstall, launcher: &LauncherCli) -> bool {
         match Pkg::from_install(&package) {
             Ok(pkg) => {
                 outputln!(preamble self.service_group,
                             "Updating service {} to {}", self.pkg.ident, pkg.ident);
+                event::publish(Event::PackageUpdated {
+                    service_group: self.service_group.to_string(),
+                    previous: self.pkg.ident.to_string(),
+                    current: pkg.ident.to_string(),
+                });
 
                 match CfgRenderer::new(&Self::config_root(&pkg, self.config_from.as_ref())) {
                     Ok(renderer) => self.config_renderer = renderer,
                     Err(e) => {
                         outputln!(preamble self.service_group,
                                   "Failed to load config templates after updating package, {}", e);
-                        return;
+                        return false;
                     }
                 }
                 self.hooks = HookTable::load(
@@ -658,14 +970,19 @@ impl Service {
                     svc_hooks_path(self.service_group.service()),
                 );
                 self.pkg = pkg;
+                self.metrics.updated();
             }
             Err(err) => {
                 outputln!(preamble self.service_group,
                           "Unexpected error while updating package, {}", err);
-                return;
+                return false;
             }
         }
-        if let Err(err) = self.supervisor.stop(launcher, ShutdownReason::PkgUpdating) {
+        let shutdown = self.shutdown_spec();
+        if let Err(err) = self
+            .supervisor
+            .stop(launcher, ShutdownReason::PkgUpdating, shutdown)
+        {
             outputln!(preamble self.service_group,
                       "Error stopping process while updating package: {}", err);
         }
@@ -682,6 +999,7 @@ impl Service {
 
         self.initialized = false;
         self.schedule_health_check_at_next_tick();
+        true
     }
 
     pub fn to_rumor(&self, incarnation: u64) -> ServiceRumor {
@@ -702,6 +1020,7 @@ impl Service {
             exported,
         );
         rumor.incarnation = incarnation;
+        rumor.soaking = self.soak.soaking();
         rumor
     }
 
@@ -751,6 +1070,52 @@ impl Service {
         }
     }
 
+    /// Runs the pre-start hook, if any, returning whether the process may be started. A hook
+    /// still running after the pre-start timeout is killed, and the process isn't started.
+    ///
+    /// The Supervisor waits for the hook: no other service is ticked and no ctl or HTTP gateway
+    /// request is handled until it has finished or been killed.
+    fn pre_start(&mut self) -> bool {
+        let _timer = hook_timer("pre-start");
+        let timeout = self
+            .pre_start_timeout
+            .unwrap_or(DEFAULT_PRE_START_TIMEOUT_SECS);
+
+        match self.hooks.pre_start {
+            Some(ref hook) => {
+                let started = hook.run_with_timeout(
+                    &self.service_group,
+                    &self.pkg,
+                    self.svc_encrypted_password.as_ref(),
+                    Duration::from_secs(timeout.into()),
+                );
+                if started.is_none() {
+                    outputln!(preamble self.service_group,
+                              "Pre-start hook killed after {}s, not starting the service", timeout);
+                }
+                started.unwrap_or(false)
+            }
+            None => true,
+        }
+    }
+
+    /// Runs the pre-stop hook, if any, giving it at most the pre-stop timeout to finish.
+    fn pre_stop(&mut self) {
+        let _timer = hook_timer("pre-stop");
+        let timeout = self
+            .pre_stop_timeout
+            .unwrap_or(DEFAULT_PRE_STOP_TIMEOUT_SECS);
+
+        if let Some(ref hook) = self.hooks.pre_stop {
+            hook.run_with_timeout(
+                &self.service_group,
+                &self.pkg,
+                self.svc_encrypted_password.as_ref(),
+                Duration::from_secs(timeout.into()),
+            );
+        }
+    }
+
     fn post_stop(&mut self) {
         let _timer = hook_timer("post-stop");
 
@@ -887,23 +1252,51 @@ impl Service {
             }
         } else {
             self.check_process();
+            self.sample_resources();
 
             let now = Instant::now();
-            match self.scheduled_health_check {
-                Some(scheduled_check_instant) if scheduled_check_instant > now => {
-                    trace!(
-                        "Skipping health check; next scheduled for {:?} (now: {:?})",
-                        scheduled_check_instant,
-                        now
-                    );
+            if self.running_probe.is_some() {
+                self.check_running_probe();
+            } else {
+                match self.scheduled_health_check {
+                    Some(scheduled_check_instant) if scheduled_check_instant > now => {
+                        trace!(
+                            "Skipping health check; next scheduled for {:?} (now: {:?})",
+                            scheduled_check_instant,
+                            now
+                        );
+                    }
+                    _ => self.run_health_check_hook(),
                 }
-                _ => self.run_health_check_hook(),
+            }
+
+            // A service that opted in to liveness restarts and has been critical for too long is
+            // restarted, subject to the same restart policy as a process that died.
+            if !self.process_down()
+                && self
+                    .health_tracker
+                    .liveness_failed(&self.health_check_thresholds)
+                && self.restart_permitted()
+            {
+                self.restart_unhealthy(launcher);
+                return;
+            }
+
+            // A dead process is only brought back once the restart policy allows it; until then
+            // any pending reload or reconfiguration waits along with it.
+            let process_down = self.process_down();
+            if process_down && !self.restart_permitted() {
+                return;
             }
 
             // NOTE: if you need reconfiguration and you DON'T have a
             // reload script, you're going to restart anyway.
-            if self.needs_reload || self.process_down() || self.needs_reconfiguration {
+            if self.needs_reload || process_down || self.needs_reconfiguration {
                 self.reload(launcher);
+                if process_down {
+                    self.restarts
+                        .record(Instant::now(), RestartReason::ProcessExited);
+                }
                 if self.needs_reconfiguration {
                     // NOTE this only runs the hook if it's defined
                     self.reconfigure()
@@ -912,6 +1305,70 @@ impl Service {
         }
     }
 
+    /// Restarts a process whose health has stayed critical for too long.
+    fn restart_unhealthy(&mut self, launcher: &LauncherCli) {
+        let critical_checks = self.health_tracker.consecutive_critical();
+        outputln!(preamble self.service_group,
+                  "Restarting service after {} consecutive critical health checks",
+                  critical_checks);
+        event::publish(Event::ServiceUnhealthy {
+            service_group: self.service_group.to_string(),
+            pkg_ident: self.pkg.ident.to_string(),
+            critical_checks: critical_checks,
+        });
+        if self.restart_process(launcher) {
+            self.restarts
+                .record(Instant::now(), RestartReason::HealthCritical);
+        }
+    }
+
+    /// Consults the restart policy about the service's dead process, announcing it the first time
+    /// the service is found to be crash-looping.
+    fn restart_permitted(&mut self) -> bool {
+        let was_crash_looping = self.restarts.is_crash_looping();
+        match self.restarts.check(&self.restart_policy, Instant::now()) {
+            RestartDecision::Restart => true,
+            RestartDecision::Backoff(delay) => {
+                trace!("Delaying restart of {} for {:?}", self.service_group, delay);
+                false
+            }
+            RestartDecision::CrashLooping => {
+                if !was_crash_looping {
+                    outputln!(preamble self.service_group,
+                              "Service is crash-looping after {} restarts within {}s; \
+                              it will not be restarted again until it is stopped and started",
+                              self.restart_policy.max_restarts,
+                              self.restart_policy.window);
+                    event::publish(Event::ServiceCrashLooping {
+                        service_group: self.service_group.to_string(),
+                        pkg_ident: self.pkg.ident.to_string(),
+                        restarts: self.restarts.count(),
+                    });
+                }
+                false
+            }
+        }
+    }
+
+    /// Samples the usage of the service's cgroup, announcing any process the kernel killed for
+    /// running out of memory.
+    fn sample_resources(&mut self) {
+        if let Some(oom_kills) = self.resources.poll(Instant::now()) {
+            outputln!(preamble self.service_group,
+                      "Service process killed for exceeding its memory limit of {} bytes \
+                      ({} OOM kills in total)",
+                      self.resource_limits
+                          .memory_max
+                          .map_or_else(|| "unlimited".to_string(), |m| m.to_string()),
+                      oom_kills);
+            event::publish(Event::ServiceOomKilled {
+                service_group: self.service_group.to_string(),
+                pkg_ident: self.pkg.ident.to_string(),
+                oom_kills: oom_kills,
+            });
+        }
+    }
+
     /// Run file-updated hook if present.
     fn file_updated(&self) -> bool {
         let _timer = hook_timer("file-updated");
@@ -943,6 +1400,11 @@ impl Service {
             if self.cache_service_file(&service_file) {
                 outputln!(preamble self.service_group, "Service file updated, {}",
                     service_file.filename);
+                event::publish(Event::FileApplied {
+                    service_group: self.service_group.to_string(),
+                    filename: service_file.filename.clone(),
+                    incarnation: service_file.incarnation,
+                });
                 updated = true;
             }
         }
@@ -959,6 +1421,7 @@ impl Service {
             &self.sys,
             &self.pkg,
             &self.cfg,
+            &self.secrets,
             census,
             self.binds
                 .iter()
@@ -969,23 +1432,53 @@ impl Service {
     fn run_health_check_hook(&mut self) {
         let _timer = hook_timer("health-check");
         debug!("Running Health Check hook for ({})", self.spec_ident);
-        let check_result = if let Some(ref hook) = self.hooks.health_check {
+        let hook_result = self.hooks.health_check.as_ref().map(|hook| {
             hook.run(
                 &self.service_group,
                 &self.pkg,
                 self.svc_encrypted_password.as_ref(),
             )
-        } else {
-            match self.supervisor.status() {
-                (true, _) => HealthCheck::Ok,
-                (false, _) => HealthCheck::Critical,
-            }
-        };
+        });
 
-        // We have just ran a check; therefore we must unset the next scheduled check time
+        // We have just started a check; therefore we must unset the next scheduled check time
         // in anticipation of `None` value being used in the next scheduled check time calculation.
         self.scheduled_health_check = None;
 
+        // A probe may take as long as its timeout, so it runs on a thread of its own and the
+        // check is finished on whichever tick finds its result.
+        match self.health_probe {
+            Some(ref probe) => {
+                let running = probe.spawn(&self.service_group, &self.pkg);
+                self.running_probe = Some((running, hook_result));
+            }
+            None => self.finish_health_check(hook_result, None),
+        }
+    }
+
+    /// Finish the health check under way if its probe has completed.
+    fn check_running_probe(&mut self) {
+        if let Some((running, hook_result)) = self.running_probe.take() {
+            match running.result() {
+                Some(probe_result) => self.finish_health_check(hook_result, Some(probe_result)),
+                None => self.running_probe = Some((running, hook_result)),
+            }
+        }
+    }
+
+    fn finish_health_check(
+        &mut self,
+        hook_result: Option<HealthCheck>,
+        probe_result: Option<HealthCheck>,
+    ) {
+        let check_result = match (hook_result, probe_result) {
+            (Some(hook_result), Some(probe_result)) => hook_result.worst(probe_result),
+            (Some(result), None) | (None, Some(result)) => result,
+            (None, None) => match self.supervisor.status() {
+                (true, _) => HealthCheck::Ok,
+                (false, _) => HealthCheck::Critical,
+            },
+        };
+
         if check_result == HealthCheck::Ok {
             self.schedule_routine_health_check();
             debug!(
@@ -999,8 +1492,22 @@ impl Service {
             );
             self.schedule_special_health_check();
         }
-        self.health_check = check_result;
-        self.cache_health_check(check_result);
+        // The checks themselves are scheduled from the raw result, but what the rest of the
+        // world sees only changes once the thresholds have been crossed.
+        let reported = self.health_tracker.observe(
+            &self.health_check_thresholds,
+            check_result,
+            Instant::now(),
+        );
+        if reported != self.health_check {
+            event::publish(Event::HealthChanged {
+                service_group: self.service_group.to_string(),
+                previous: self.health_check,
+                current: reported,
+            });
+        }
+        self.health_check = reported;
+        self.cache_health_check(reported);
     }
 
     fn schedule_routine_health_check(&mut self) {
@@ -1131,6 +1638,70 @@ impl fmt::Display for Service {
     }
 }
 
+/// Restarts a service's process through the Launcher.
+struct ProcessRestart<'a> {
+    service: &'a mut Service,
+    launcher: &'a LauncherCli,
+}
+
+impl<'a> RestartSteps for ProcessRestart<'a> {
+    fn process_up(&self) -> bool {
+        !self.service.process_down()
+    }
+
+    fn pre_stop(&mut self) {
+        self.service.pre_stop();
+    }
+
+    fn terminate(&mut self) -> bool {
+        let shutdown = self.service.shutdown_spec();
+        match self
+            .service
+            .supervisor
+            .stop(self.launcher, ShutdownReason::Restarting, shutdown)
+        {
+            Ok(_) => true,
+            Err(err) => {
+                outputln!(preamble self.service.service_group,
+                          "Service restart failed, unable to stop the process: {}", err);
+                false
+            }
+        }
+    }
+
+    fn pre_start(&mut self) -> bool {
+        self.service.pre_start()
+    }
+
+    fn spawn(&mut self) -> bool {
+        let service = &mut *self.service;
+        let shutdown = service.shutdown_spec();
+        match service.supervisor.start(
+            &service.pkg,
+            &service.service_group,
+            self.launcher,
+            service.svc_encrypted_password.as_ref(),
+            service.resource_limits,
+            service.log_settings,
+            shutdown,
+        ) {
+            Ok(_) => {
+                service.health_tracker.service_started(Instant::now());
+                event::publish(Event::ServiceRestarted {
+                    service_group: service.service_group.to_string(),
+                    pkg_ident: service.pkg.ident.to_string(),
+                    pid: service.supervisor.pid(),
+                });
+                true
+            }
+            Err(err) => {
+                outputln!(preamble service.service_group, "Service restart failed: {}", err);
+                false
+            }
+        }
+    }
+}
+
 // This returns a HistogramTimer that we can use to track how long hooks take to execute. Note that
 // times will get tracked automatically when the HistogramTimer goes out of scope.
 fn hook_timer(name: &str) -> HistogramTimer {
@@ -1176,9 +1747,9 @@ impl<'a> Serialize for ServiceProxy<'a> {
         S: Serializer,
     {
         let num_fields: usize = if self.config_rendering == ConfigRendering::Full {
-            27
+            34
         } else {
-            26
+            33
         };
 
         let s = &self.service;
@@ -1196,9 +1767,12 @@ impl<'a> Serialize for ServiceProxy<'a> {
         strukt.serialize_field("config_from", &s.config_from)?;
         strukt.serialize_field("desired_state", &s.desired_state)?;
         strukt.serialize_field("health_check", &s.health_check)?;
+        strukt.serialize_field("health_check_thresholds", &s.health_check_thresholds)?;
+        strukt.serialize_field("health_probe", &s.health_probe)?;
         strukt.serialize_field("hooks", &s.hooks)?;
         strukt.serialize_field("initialized", &s.initialized)?;
         strukt.serialize_field("last_election_status", &s.last_election_status)?;
+        strukt.serialize_field("log_settings", &s.log_settings)?;
         strukt.serialize_field("manager_fs_cfg", &s.manager_fs_cfg)?;
         strukt.serialize_field("needs_reconfiguration", &s.needs_reconfiguration)?;
         strukt.serialize_field("needs_reload", &s.needs_reload)?;
@@ -1207,6 +1781,11 @@ impl<'a> Serialize for ServiceProxy<'a> {
         strukt.serialize_field("pkg", &pkg_proxy)?;
 
         strukt.serialize_field("process", &s.supervisor)?;
+        strukt.serialize_field("restart_policy", &s.restart_policy)?;
+        strukt.serialize_field("restarts", &s.restarts)?;
+        strukt.serialize_field("rollback", &s.rollback)?;
+        strukt.serialize_field("resource_limits", &s.resource_limits)?;
+        strukt.serialize_field("resource_usage", &s.resources.usage())?;
         strukt.serialize_field("service_group", &s.service_group)?;
         strukt.serialize_field("spec_file", &s.spec_file)?;
         strukt.serialize_field("spec_ident", &s.spec_ident)?;
@@ -1215,6 +1794,8 @@ impl<'a> Serialize for ServiceProxy<'a> {
         strukt.serialize_field("health_check_interval", &s.health_check_interval)?;
         strukt.serialize_field("sys", &s.sys)?;
         strukt.serialize_field("topology", &s.topology)?;
+        strukt.serialize_field("update_paused", &s.update_paused)?;
+        strukt.serialize_field("update_settings", &s.update_settings)?;
         strukt.serialize_field("update_strategy", &s.update_strategy)?;
         strukt.serialize_field("user_config_updated", &s.user_config_updated)?;
         strukt.end()
@@ -1225,6 +1806,7 @@ impl<'a> Serialize for ServiceProxy<'a> {
 mod tests {
     use super::*;
 
+    use std::collections::BTreeMap;
     use std::path::PathBuf;
     use std::str::FromStr;
     use std::time::Instant;
@@ -1309,4 +1891,18 @@ mod tests {
             .expect("Expected to convert proxies_without_config to JSON but failed");
         assert_valid(&json_without_config, "http_gateway_services_schema.json");
     }
+
+    #[test]
+    fn service_proxy_leaves_out_secrets() {
+        let mut service = initialize_test_service();
+        let mut secrets = BTreeMap::new();
+        secrets.insert("db_password".to_string(), "hunter2".to_string());
+        service.secrets = Secrets::from(secrets);
+
+        for rendering in &[ConfigRendering::Full, ConfigRendering::Redacted] {
+            let json = serde_json::to_string(&vec![ServiceProxy::new(&service, *rendering)])
+                .expect("Expected to convert the service proxy to JSON but failed");
+            assert!(!json.contains("hunter2"));
+        }
+    }
 }
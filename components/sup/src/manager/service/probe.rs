@@ -0,0 +1,456 @@
+// Copyright (c) 2019 Chef Software Inc. and/or applicable contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Health checks the Supervisor performs itself, so that a package doesn't need a
+//! `health-check` hook just to see whether a port is open or an endpoint answers.
+//!
+//! A probe is one of:
+//!
+//! * `http://<host>[:<port>]/<path>`: `Ok` if a `GET` answers with a 2xx status, `Critical`
+//!   otherwise.
+//! * `tcp://<host>:<port>`: `Ok` if a connection is accepted, `Critical` otherwise.
+//! * `exec:<command>`: the command is run through the shell, as the service's user, and its exit
+//!   code is read the same way as that of a `health-check` hook.
+//!
+//! A probe that doesn't complete within its timeout is `Critical`. Probes run on a thread of
+//! their own, so that a slow target never holds up the Supervisor's main loop, and an `exec`
+//! probe that times out is killed along with every process it started.
+
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::process::{Child, Command, Stdio};
+use std::str::FromStr;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::health::HealthCheck;
+use super::Pkg;
+use crate::error::{Error, Result, SupError};
+use crate::hcore::util::{deserialize_using_from_str, serialize_using_to_string};
+use crate::protocol;
+
+static LOGKEY: &'static str = "HP";
+
+/// How long a probe may take when no timeout is given.
+const DEFAULT_TIMEOUT_SECS: u64 = 5;
+
+/// How often a running `exec` probe is checked for completion.
+const EXEC_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct HealthProbe {
+    #[serde(
+        deserialize_with = "deserialize_using_from_str",
+        serialize_with = "serialize_using_to_string"
+    )]
+    pub target: ProbeTarget,
+    /// Seconds to wait for the probe to complete.
+    #[serde(default = "default_timeout")]
+    pub timeout: u64,
+}
+
+impl HealthProbe {
+    pub fn from_proto(proto: &protocol::types::HealthProbe) -> Result<Self> {
+        Ok(HealthProbe {
+            target: proto.target.parse()?,
+            timeout: proto.timeout.unwrap_or(DEFAULT_TIMEOUT_SECS),
+        })
+    }
+
+    /// Start the probe against the given package's service on a thread of its own.
+    pub fn spawn(&self, service_group: &str, pkg: &Pkg) -> RunningProbe {
+        let (tx, rx) = mpsc::channel();
+        let probe = self.clone();
+        let group = service_group.to_string();
+        let pkg = pkg.clone();
+        if let Err(err) = thread::Builder::new()
+            .name(format!("probe-{}", service_group))
+            .spawn(move || {
+                // The service may have moved on to another check by the time this one is done.
+                tx.send(probe.run(&group, &pkg)).ok();
+            })
+        {
+            outputln!(preamble service_group,
+                      "Unable to start health probe {}: {}", self.target, err);
+        }
+        RunningProbe(rx)
+    }
+
+    /// Perform the probe against the given package's service.
+    pub fn run(&self, service_group: &str, pkg: &Pkg) -> HealthCheck {
+        let timeout = Duration::from_secs(self.timeout);
+        let result = match self.target {
+            ProbeTarget::Http {
+                ref address,
+                ref host,
+                ref path,
+            } => http_get(address, host, path, timeout),
+            ProbeTarget::Tcp { ref address } => connect(address, timeout).map(|_| HealthCheck::Ok),
+            ProbeTarget::Exec { ref command } => exec(command, pkg, timeout),
+        };
+        match result {
+            Ok(health) => health,
+            Err(err) => {
+                outputln!(preamble service_group, "Health probe {} failed: {}", self.target, err);
+                HealthCheck::Critical
+            }
+        }
+    }
+}
+
+/// A probe started with `HealthProbe::spawn`.
+#[derive(Debug)]
+pub struct RunningProbe(Receiver<HealthCheck>);
+
+impl RunningProbe {
+    /// The probe's result, once it has completed.
+    pub fn result(&self) -> Option<HealthCheck> {
+        match self.0.try_recv() {
+            Ok(health) => Some(health),
+            Err(TryRecvError::Empty) => None,
+            // The probe's thread is gone without a result.
+            Err(TryRecvError::Disconnected) => Some(HealthCheck::Critical),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum ProbeTarget {
+    Http {
+        /// The `<host>:<port>` to connect to.
+        address: String,
+        /// The value of the `Host` header.
+        host: String,
+        path: String,
+    },
+    Tcp {
+        address: String,
+    },
+    Exec {
+        command: String,
+    },
+}
+
+impl FromStr for ProbeTarget {
+    type Err = SupError;
+
+    fn from_str(value: &str) -> Result<Self> {
+        // Reject anything the CLI would have rejected.
+        protocol::types::HealthProbe::from_str(value)
+            .map_err(|err| sup_error!(Error::InvalidHealthProbe(err.msg)))?;
+        if value.starts_with("http://") {
+            let rest = &value["http://".len()..];
+            let (host, path) = match rest.find('/') {
+                Some(i) => (&rest[..i], &rest[i..]),
+                None => (rest, "/"),
+            };
+            let address = if host.contains(':') {
+                host.to_string()
+            } else {
+                format!("{}:80", host)
+            };
+            Ok(ProbeTarget::Http {
+                address: address,
+                host: host.to_string(),
+                path: path.to_string(),
+            })
+        } else if value.starts_with("tcp://") {
+            Ok(ProbeTarget::Tcp {
+                address: value["tcp://".len()..].to_string(),
+            })
+        } else {
+            Ok(ProbeTarget::Exec {
+                command: value["exec:".len()..].trim().to_string(),
+            })
+        }
+    }
+}
+
+impl fmt::Display for ProbeTarget {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ProbeTarget::Http {
+                ref host, ref path, ..
+            } => write!(f, "http://{}{}", host, path),
+            ProbeTarget::Tcp { ref address } => write!(f, "tcp://{}", address),
+            ProbeTarget::Exec { ref command } => write!(f, "exec:{}", command),
+        }
+    }
+}
+
+fn default_timeout() -> u64 {
+    DEFAULT_TIMEOUT_SECS
+}
+
+/// Connect to the first of the address's resolved addresses that accepts a connection.
+fn connect(address: &str, timeout: Duration) -> io::Result<TcpStream> {
+    let mut last_err = io::Error::new(
+        io::ErrorKind::NotFound,
+        format!("{} did not resolve to any address", address),
+    );
+    for addr in address.to_socket_addrs()? {
+        match TcpStream::connect_timeout(&addr, timeout) {
+            Ok(stream) => return Ok(stream),
+            Err(err) => last_err = err,
+        }
+    }
+    Err(last_err)
+}
+
+fn http_get(address: &str, host: &str, path: &str, timeout: Duration) -> io::Result<HealthCheck> {
+    let mut stream = connect(address, timeout)?;
+    stream.set_read_timeout(Some(timeout))?;
+    stream.set_write_timeout(Some(timeout))?;
+    write!(
+        stream,
+        "GET {} HTTP/1.0\r\nHost: {}\r\nUser-Agent: habitat-supervisor\r\nConnection: close\r\n\r\n",
+        path, host
+    )?;
+    let mut status_line = String::new();
+    BufReader::new(stream).read_line(&mut status_line)?;
+    Ok(health_from_status_line(&status_line))
+}
+
+fn health_from_status_line(status_line: &str) -> HealthCheck {
+    match status_line
+        .split_whitespace()
+        .nth(1)
+        .and_then(|code| code.parse::<u16>().ok())
+    {
+        Some(code) if code >= 200 && code < 300 => HealthCheck::Ok,
+        Some(_) => HealthCheck::Critical,
+        None => HealthCheck::Unknown,
+    }
+}
+
+fn exec(command: &str, pkg: &Pkg, timeout: Duration) -> io::Result<HealthCheck> {
+    let mut child = spawn(command, pkg)?;
+    let deadline = Instant::now() + timeout;
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(match status.code() {
+                Some(code) if code >= 0 && code <= 3 => HealthCheck::from(code as i8),
+                _ => HealthCheck::default(),
+            });
+        }
+        if Instant::now() >= deadline {
+            kill(&mut child)?;
+            child.wait()?;
+            return Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                format!("did not exit within {}s", timeout.as_secs()),
+            ));
+        }
+        thread::sleep(EXEC_POLL_INTERVAL);
+    }
+}
+
+#[cfg(unix)]
+fn spawn(command: &str, pkg: &Pkg) -> io::Result<Child> {
+    use crate::hcore::os::users;
+    use std::os::unix::process::CommandExt;
+
+    let mut cmd = Command::new("/bin/sh");
+    cmd.arg("-c")
+        .arg(command)
+        .envs(pkg.env.iter())
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        // The shell leads a process group of its own, so that `kill` reaches whatever it started.
+        .before_exec(|| {
+            unsafe {
+                libc::setpgid(0, 0);
+            }
+            Ok(())
+        });
+    if users::can_run_services_as_svc_user() {
+        let uid = users::get_uid_by_name(&pkg.svc_user).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                format!("No uid for user '{}' could be found", pkg.svc_user),
+            )
+        })?;
+        let gid = users::get_gid_by_name(&pkg.svc_group).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                format!("No gid for group '{}' could be found", pkg.svc_group),
+            )
+        })?;
+        cmd.uid(uid).gid(gid);
+    }
+    cmd.spawn()
+}
+
+/// Kill a timed out `exec` probe along with every process it started.
+#[cfg(unix)]
+fn kill(child: &mut Child) -> io::Result<()> {
+    use crate::hcore::os::process::{signal, Pid, Signal};
+
+    signal(-(child.id() as Pid), Signal::KILL)
+        .map_err(|err| io::Error::new(io::ErrorKind::Other, err.to_string()))
+}
+
+#[cfg(windows)]
+fn kill(child: &mut Child) -> io::Result<()> {
+    child.kill()
+}
+
+#[cfg(windows)]
+fn spawn(command: &str, pkg: &Pkg) -> io::Result<Child> {
+    Command::new("cmd.exe")
+        .arg("/C")
+        .arg(command)
+        .envs(pkg.env.iter())
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::net::TcpListener;
+    use std::path::PathBuf;
+
+    use crate::hcore::package::{PackageIdent, PackageInstall};
+
+    fn pkg() -> Pkg {
+        let pkg_install = PackageInstall::new_from_parts(
+            PackageIdent::new("testing", "probe", Some("1.0.0"), Some("20170712000000")),
+            PathBuf::from("/tmp"),
+            PathBuf::from("/tmp"),
+            PathBuf::from("/tmp"),
+        );
+        Pkg::from_install(&pkg_install).expect("Could not create package!")
+    }
+
+    fn wait_for(running: &RunningProbe) -> HealthCheck {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        loop {
+            if let Some(health) = running.result() {
+                return health;
+            }
+            assert!(Instant::now() < deadline, "Probe never completed");
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    #[test]
+    fn target_round_trips() {
+        for target in &[
+            "http://localhost:8080/healthz",
+            "tcp://127.0.0.1:5432",
+            "exec:pg_isready -q",
+        ] {
+            assert_eq!(ProbeTarget::from_str(target).unwrap().to_string(), *target);
+        }
+    }
+
+    #[test]
+    fn http_target_defaults_port_and_path() {
+        assert_eq!(
+            ProbeTarget::from_str("http://localhost").unwrap(),
+            ProbeTarget::Http {
+                address: "localhost:80".to_string(),
+                host: "localhost".to_string(),
+                path: "/".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn invalid_target_is_an_error() {
+        assert!(ProbeTarget::from_str("localhost:5432").is_err());
+    }
+
+    #[test]
+    fn status_line_maps_to_health() {
+        assert_eq!(
+            health_from_status_line("HTTP/1.1 204 No Content\r\n"),
+            HealthCheck::Ok
+        );
+        assert_eq!(
+            health_from_status_line("HTTP/1.1 503 Service Unavailable\r\n"),
+            HealthCheck::Critical
+        );
+        assert_eq!(health_from_status_line(""), HealthCheck::Unknown);
+    }
+
+    #[test]
+    fn tcp_probe_connects() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+
+        assert!(connect(&address, Duration::from_secs(1)).is_ok());
+        drop(listener);
+        assert!(connect(&address, Duration::from_secs(1)).is_err());
+    }
+
+    #[test]
+    fn spawned_probe_completes_on_its_own_thread() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let target = format!("tcp://{}", listener.local_addr().unwrap());
+        let probe = HealthProbe {
+            target: ProbeTarget::from_str(&target).unwrap(),
+            timeout: 1,
+        };
+
+        assert_eq!(
+            wait_for(&probe.spawn("redis.default", &pkg())),
+            HealthCheck::Ok
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn spawned_probe_does_not_wait_for_the_target() {
+        let probe = HealthProbe {
+            target: ProbeTarget::from_str("exec:sleep 1; exit 1").unwrap(),
+            timeout: 5,
+        };
+
+        let running = probe.spawn("redis.default", &pkg());
+        assert_eq!(running.result(), None);
+        assert_eq!(wait_for(&running), HealthCheck::Warning);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn timed_out_exec_probe_is_killed_with_everything_it_started() {
+        use std::fs;
+
+        use tempfile::TempDir;
+
+        let tmpdir = TempDir::new().unwrap();
+        let pid_file = tmpdir.path().join("pid");
+        let command = format!("sleep 30 & echo $! > {}; wait", pid_file.display());
+
+        let err = exec(&command, &pkg(), Duration::from_secs(1)).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+
+        // The shell's background `sleep` is killed too, and is gone once it has been reaped.
+        let pid = fs::read_to_string(&pid_file).unwrap();
+        let stat = PathBuf::from("/proc").join(pid.trim()).join("stat");
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while fs::read_to_string(&stat).map_or(false, |stat| !stat.contains(") Z ")) {
+            assert!(Instant::now() < deadline, "Probe's child outlived it");
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+}
@@ -0,0 +1,283 @@
+// Copyright (c) 2019 Chef Software Inc. and/or applicable contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Resource limits for a service's process, and reporting on what it actually uses.
+//!
+//! The limits are enforced by the Launcher, which runs each service in its own cgroup (v2). The
+//! Supervisor periodically reads the usage counters of that cgroup, exposes them as metrics and
+//! reports any process the kernel killed for running out of memory.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use crate::hcore::service::ServiceGroup;
+use crate::launcher_client;
+use crate::protocol;
+use prometheus::{IntGauge, IntGaugeVec};
+
+lazy_static! {
+    static ref SERVICE_MEMORY_BYTES: IntGaugeVec = register_int_gauge_vec!(
+        "hab_sup_service_memory_bytes",
+        "Memory currently used by a service's processes",
+        &["service_group"]
+    )
+    .unwrap();
+    static ref SERVICE_CPU_USAGE_USEC: IntGaugeVec = register_int_gauge_vec!(
+        "hab_sup_service_cpu_usage_usec",
+        "CPU time consumed by a service's processes, in microseconds",
+        &["service_group"]
+    )
+    .unwrap();
+    static ref SERVICE_PIDS: IntGaugeVec = register_int_gauge_vec!(
+        "hab_sup_service_pids",
+        "Number of processes and threads a service is running",
+        &["service_group"]
+    )
+    .unwrap();
+    static ref SERVICE_OOM_KILLS: IntGaugeVec = register_int_gauge_vec!(
+        "hab_sup_service_oom_kills",
+        "Number of a service's processes killed for running out of memory",
+        &["service_group"]
+    )
+    .unwrap();
+}
+
+/// How often the usage of a service's cgroup is sampled.
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Limits on the resources a service's processes may use. `None` means unlimited.
+#[derive(Copy, Clone, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(default)]
+pub struct ResourceLimits {
+    /// Maximum memory, in bytes.
+    pub memory_max: Option<u64>,
+    /// Relative CPU weight, from 1 to 10000.
+    pub cpu_weight: Option<u64>,
+    /// CPU time, as a percentage of a single CPU.
+    pub cpu_quota: Option<u64>,
+    /// Maximum number of processes and threads.
+    pub pids_max: Option<u64>,
+}
+
+impl ResourceLimits {
+    pub fn is_unlimited(&self) -> bool {
+        *self == ResourceLimits::default()
+    }
+
+    /// Overwrite any fields that were set in the given protocol message.
+    pub fn merge(&mut self, proto: &protocol::types::ResourceLimits) {
+        if proto.memory_max.is_some() {
+            self.memory_max = proto.memory_max;
+        }
+        if proto.cpu_weight.is_some() {
+            self.cpu_weight = proto.cpu_weight;
+        }
+        if proto.cpu_quota.is_some() {
+            self.cpu_quota = proto.cpu_quota;
+        }
+        if proto.pids_max.is_some() {
+            self.pids_max = proto.pids_max;
+        }
+    }
+}
+
+impl From<ResourceLimits> for launcher_client::ResourceLimits {
+    fn from(limits: ResourceLimits) -> Self {
+        launcher_client::ResourceLimits {
+            memory_max: limits.memory_max,
+            cpu_weight: limits.cpu_weight,
+            cpu_quota: limits.cpu_quota,
+            pids_max: limits.pids_max,
+        }
+    }
+}
+
+/// A snapshot of the resources used by a service's cgroup.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize)]
+pub struct ResourceUsage {
+    /// Memory in use, in bytes.
+    pub memory_current: u64,
+    /// Total CPU time consumed, in microseconds.
+    pub cpu_usage_usec: u64,
+    /// Number of processes and threads.
+    pub pids_current: u64,
+    /// Number of processes killed by the kernel for running out of memory.
+    pub oom_kills: u64,
+}
+
+impl ResourceUsage {
+    /// Read the usage counters of the cgroup at `path`.
+    pub fn read<P>(path: P) -> io::Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        Ok(ResourceUsage {
+            memory_current: read_value(&path.join("memory.current"))?,
+            cpu_usage_usec: read_key(&path.join("cpu.stat"), "usage_usec")?,
+            pids_current: read_value(&path.join("pids.current"))?,
+            oom_kills: read_key(&path.join("memory.events"), "oom_kill")?,
+        })
+    }
+}
+
+/// Periodically samples the usage of a single service's cgroup.
+#[derive(Debug)]
+pub struct ResourceMonitor {
+    service_group: String,
+    usage: Option<ResourceUsage>,
+    next_sample: Instant,
+}
+
+impl ResourceMonitor {
+    pub fn new(service_group: &ServiceGroup) -> Self {
+        ResourceMonitor {
+            service_group: service_group.to_string(),
+            usage: None,
+            next_sample: Instant::now(),
+        }
+    }
+
+    pub fn usage(&self) -> Option<&ResourceUsage> {
+        self.usage.as_ref()
+    }
+
+    /// Sample the service's cgroup if it is time to do so. Returns the new total number of OOM
+    /// kills if it went up since the previous sample.
+    pub fn poll(&mut self, now: Instant) -> Option<u64> {
+        if now < self.next_sample {
+            return None;
+        }
+        self.next_sample = now + SAMPLE_INTERVAL;
+        let path = launcher_client::service_cgroup_path(&self.service_group);
+        match ResourceUsage::read(&path) {
+            Ok(usage) => self.update(usage),
+            Err(err) => {
+                // Services without limits, or on platforms without cgroups, have nothing to read.
+                debug!("Unable to read usage from {}: {}", path.display(), err);
+                self.usage = None;
+                None
+            }
+        }
+    }
+
+    fn update(&mut self, usage: ResourceUsage) -> Option<u64> {
+        let previous_oom_kills = self.usage.map(|u| u.oom_kills);
+        self.usage = Some(usage);
+        self.gauge(&SERVICE_MEMORY_BYTES)
+            .set(usage.memory_current as i64);
+        self.gauge(&SERVICE_CPU_USAGE_USEC)
+            .set(usage.cpu_usage_usec as i64);
+        self.gauge(&SERVICE_PIDS).set(usage.pids_current as i64);
+        self.gauge(&SERVICE_OOM_KILLS).set(usage.oom_kills as i64);
+        match previous_oom_kills {
+            Some(previous) if usage.oom_kills > previous => Some(usage.oom_kills),
+            // The first sample may pick up kills from before a Supervisor restart; those were
+            // reported by the previous Supervisor.
+            _ => None,
+        }
+    }
+
+    fn gauge(&self, vec: &IntGaugeVec) -> IntGauge {
+        vec.with_label_values(&[&self.service_group])
+    }
+}
+
+/// Read a file containing a single number.
+fn read_value(file: &Path) -> io::Result<u64> {
+    parse(fs::read_to_string(file)?.trim())
+}
+
+/// Read the value of `key` from a flat-keyed file, such as `cpu.stat`.
+fn read_key(file: &Path, key: &str) -> io::Result<u64> {
+    fs::read_to_string(file)?
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.split_whitespace();
+            match (parts.next(), parts.next()) {
+                (Some(k), Some(v)) if k == key => Some(v.to_string()),
+                _ => None,
+            }
+        })
+        .next()
+        .map_or(Ok(0), |v| parse(&v))
+}
+
+fn parse(value: &str) -> io::Result<u64> {
+    value
+        .parse()
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::str::FromStr;
+
+    use tempfile::TempDir;
+
+    fn write_cgroup(dir: &Path, oom_kills: u64) {
+        fs::write(dir.join("memory.current"), "1048576\n").unwrap();
+        fs::write(
+            dir.join("cpu.stat"),
+            "usage_usec 2500\nuser_usec 2000\nsystem_usec 500\n",
+        )
+        .unwrap();
+        fs::write(dir.join("pids.current"), "3\n").unwrap();
+        fs::write(
+            dir.join("memory.events"),
+            format!("low 0\nhigh 0\nmax 4\noom 1\noom_kill {}\n", oom_kills),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn reads_cgroup_usage() {
+        let tmpdir = TempDir::new().unwrap();
+        write_cgroup(tmpdir.path(), 2);
+
+        assert_eq!(
+            ResourceUsage::read(tmpdir.path()).unwrap(),
+            ResourceUsage {
+                memory_current: 1048576,
+                cpu_usage_usec: 2500,
+                pids_current: 3,
+                oom_kills: 2,
+            }
+        );
+    }
+
+    #[test]
+    fn missing_cgroup_is_an_error() {
+        let tmpdir = TempDir::new().unwrap();
+        assert!(ResourceUsage::read(tmpdir.path().join("nope")).is_err());
+    }
+
+    #[test]
+    fn only_new_oom_kills_are_reported() {
+        let group = ServiceGroup::from_str("redis.default").unwrap();
+        let mut monitor = ResourceMonitor::new(&group);
+        let usage = |oom_kills| ResourceUsage {
+            oom_kills: oom_kills,
+            ..Default::default()
+        };
+
+        assert_eq!(monitor.update(usage(1)), None);
+        assert_eq!(monitor.update(usage(1)), None);
+        assert_eq!(monitor.update(usage(3)), Some(3));
+    }
+}
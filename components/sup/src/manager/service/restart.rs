@@ -0,0 +1,467 @@
+// Copyright (c) 2018 Chef Software Inc. and/or applicable contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Governs how eagerly a service's process is restarted after it dies.
+//!
+//! Consecutive restarts are spaced out by an exponential backoff. A process that has to be
+//! restarted more than `max_restarts` times within `window` seconds is declared crash-looping,
+//! and is left down until the service is stopped and started again.
+//!
+//! The same policy applies to a process that is restarted because its health stayed `Critical`
+//! for too long (see `HealthCheckThresholds::restart_after`).
+
+use std::collections::VecDeque;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crate::hcore::service::ServiceGroup;
+use crate::protocol;
+use prometheus::IntGaugeVec;
+
+lazy_static! {
+    static ref SERVICE_RESTARTS: IntGaugeVec = register_int_gauge_vec!(
+        "hab_sup_service_restarts",
+        "The number of times a service's process has been restarted by the Supervisor",
+        &["service_group"]
+    )
+    .unwrap();
+    static ref SERVICE_CRASH_LOOPING: IntGaugeVec = register_int_gauge_vec!(
+        "hab_sup_service_crash_looping",
+        "Whether a service has exceeded its restart policy (1) or not (0)",
+        &["service_group"]
+    )
+    .unwrap();
+}
+
+const DEFAULT_WINDOW_SECS: u64 = 300;
+const DEFAULT_BACKOFF_SECS: u64 = 1;
+const DEFAULT_BACKOFF_MAX_SECS: u64 = 60;
+
+#[derive(Copy, Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(default)]
+pub struct RestartPolicy {
+    /// Restarts allowed within `window` before the service is considered crash-looping. Zero
+    /// means there is no limit.
+    pub max_restarts: u32,
+    /// Length, in seconds, of the sliding window in which restarts are counted.
+    pub window: u64,
+    /// Delay, in seconds, before the second restart within a window. The delay doubles with each
+    /// further restart.
+    pub backoff: u64,
+    /// Upper bound, in seconds, of the delay between restarts.
+    pub backoff_max: u64,
+}
+
+impl RestartPolicy {
+    /// Returns how long to wait after the most recent restart, given how many restarts have
+    /// already happened within the current window.
+    pub fn backoff_for(&self, recent_restarts: usize) -> Duration {
+        if recent_restarts == 0 {
+            return Duration::from_secs(0);
+        }
+        let exponent = (recent_restarts - 1).min(63) as u32;
+        let secs = self
+            .backoff
+            .checked_mul(1 << exponent)
+            .unwrap_or(u64::max_value())
+            .min(self.backoff_max);
+        Duration::from_secs(secs)
+    }
+
+    /// Overwrite any fields that were set in the given protocol message.
+    pub fn merge(&mut self, proto: &protocol::types::RestartPolicy) {
+        if let Some(max_restarts) = proto.max_restarts {
+            self.max_restarts = max_restarts;
+        }
+        if let Some(window) = proto.window {
+            self.window = window;
+        }
+        if let Some(backoff) = proto.backoff {
+            self.backoff = backoff;
+        }
+        if let Some(backoff_max) = proto.backoff_max {
+            self.backoff_max = backoff_max;
+        }
+    }
+
+    fn window(&self) -> Duration {
+        Duration::from_secs(self.window)
+    }
+}
+
+impl Default for RestartPolicy {
+    fn default() -> Self {
+        RestartPolicy {
+            max_restarts: 0,
+            window: DEFAULT_WINDOW_SECS,
+            backoff: DEFAULT_BACKOFF_SECS,
+            backoff_max: DEFAULT_BACKOFF_MAX_SECS,
+        }
+    }
+}
+
+/// What to do about a service whose process is down.
+#[derive(Debug, PartialEq)]
+pub enum RestartDecision {
+    /// Restart the process now.
+    Restart,
+    /// Leave the process down for (at least) this much longer.
+    Backoff(Duration),
+    /// Leave the process down for good.
+    CrashLooping,
+}
+
+/// Why the Supervisor restarted a service's process.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RestartReason {
+    /// The process died.
+    ProcessExited,
+    /// The service's health stayed `Critical` for too many checks in a row.
+    HealthCritical,
+}
+
+/// The steps the Supervisor takes to restart a service's process.
+pub trait RestartSteps {
+    /// Whether the process is up, and so must be stopped first.
+    fn process_up(&self) -> bool;
+    /// Runs the pre-stop hook.
+    fn pre_stop(&mut self);
+    /// Stops the process, returning whether it is now down.
+    fn terminate(&mut self) -> bool;
+    /// Runs the pre-start hook, returning whether the process may be started.
+    fn pre_start(&mut self) -> bool;
+    /// Starts the process, returning whether it is now up.
+    fn spawn(&mut self) -> bool;
+}
+
+/// Restarts a process: pre-stop hook, terminate, pre-start hook, then spawn. The pre-stop hook
+/// runs against the old process before it goes away, whether or not the pre-start hook then lets
+/// the new one start. Returns whether the new process was started.
+pub fn restart_process<R>(steps: &mut R) -> bool
+where
+    R: RestartSteps,
+{
+    if steps.process_up() {
+        steps.pre_stop();
+        if !steps.terminate() {
+            return false;
+        }
+    }
+    steps.pre_start() && steps.spawn()
+}
+
+/// Keeps track of the restarts of a single service's process.
+///
+/// The total number of restarts is persisted to the Supervisor's data directory so that it
+/// survives Supervisor restarts.
+#[derive(Debug, Serialize)]
+pub struct RestartTracker {
+    #[serde(rename = "count")]
+    total: u64,
+    crash_looping: bool,
+    last_reason: Option<RestartReason>,
+    #[serde(skip_serializing)]
+    recent: VecDeque<Instant>,
+    #[serde(skip_serializing)]
+    service_group: String,
+    #[serde(skip_serializing)]
+    state_file: PathBuf,
+}
+
+impl RestartTracker {
+    /// Create a tracker for the given service group, picking up the restart count recorded by a
+    /// previous Supervisor, if any.
+    pub fn load<P>(service_group: &ServiceGroup, data_path: P) -> Self
+    where
+        P: AsRef<Path>,
+    {
+        let state_file = data_path
+            .as_ref()
+            .join(format!("{}.restarts", service_group.service()));
+        let total = fs::read_to_string(&state_file)
+            .ok()
+            .and_then(|s| s.trim().parse().ok())
+            .unwrap_or(0);
+        let tracker = RestartTracker {
+            total: total,
+            crash_looping: false,
+            last_reason: None,
+            recent: VecDeque::new(),
+            service_group: service_group.to_string(),
+            state_file: state_file,
+        };
+        tracker.update_metrics();
+        tracker
+    }
+
+    pub fn count(&self) -> u64 {
+        self.total
+    }
+
+    pub fn is_crash_looping(&self) -> bool {
+        self.crash_looping
+    }
+
+    /// Decide whether a dead process may be restarted at `now`.
+    pub fn check(&mut self, policy: &RestartPolicy, now: Instant) -> RestartDecision {
+        if self.crash_looping {
+            return RestartDecision::CrashLooping;
+        }
+        let window = policy.window();
+        while let Some(&oldest) = self.recent.front() {
+            if now.duration_since(oldest) < window {
+                break;
+            }
+            self.recent.pop_front();
+        }
+        if policy.max_restarts > 0 && self.recent.len() >= policy.max_restarts as usize {
+            self.crash_looping = true;
+            self.update_metrics();
+            return RestartDecision::CrashLooping;
+        }
+        if let Some(&last) = self.recent.back() {
+            let ready_at = last + policy.backoff_for(self.recent.len());
+            if ready_at > now {
+                return RestartDecision::Backoff(ready_at - now);
+            }
+        }
+        RestartDecision::Restart
+    }
+
+    /// Forget the recent restarts, e.g. because the service now runs a different package. The
+    /// total count is kept.
+    pub fn reset(&mut self) {
+        self.recent.clear();
+        self.crash_looping = false;
+        self.update_metrics();
+    }
+
+    /// Record that the process was restarted at `now`, and why.
+    pub fn record(&mut self, now: Instant, reason: RestartReason) {
+        self.recent.push_back(now);
+        self.last_reason = Some(reason);
+        self.total += 1;
+        self.update_metrics();
+        if let Err(err) = self.persist() {
+            warn!(
+                "Unable to record restart count for {} in {}: {}",
+                self.service_group,
+                self.state_file.display(),
+                err
+            );
+        }
+    }
+
+    fn persist(&self) -> io::Result<()> {
+        let tmpfile = self.state_file.with_extension("restarts.tmp");
+        {
+            let mut file = File::create(&tmpfile)?;
+            write!(file, "{}", self.total)?;
+        }
+        fs::rename(&tmpfile, &self.state_file)
+    }
+
+    fn update_metrics(&self) {
+        SERVICE_RESTARTS
+            .with_label_values(&[&self.service_group])
+            .set(self.total as i64);
+        SERVICE_CRASH_LOOPING
+            .with_label_values(&[&self.service_group])
+            .set(self.crash_looping as i64);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::str::FromStr;
+
+    use tempfile::TempDir;
+
+    fn policy(max_restarts: u32) -> RestartPolicy {
+        RestartPolicy {
+            max_restarts: max_restarts,
+            window: 60,
+            backoff: 2,
+            backoff_max: 10,
+        }
+    }
+
+    fn tracker(data_path: &Path) -> RestartTracker {
+        RestartTracker::load(&ServiceGroup::from_str("redis.default").unwrap(), data_path)
+    }
+
+    /// Records the steps of a restart, failing the ones it is told to.
+    #[derive(Default)]
+    struct RecordedSteps {
+        up: bool,
+        failing: Vec<&'static str>,
+        taken: Vec<&'static str>,
+    }
+
+    impl RecordedSteps {
+        fn step(&mut self, name: &'static str) -> bool {
+            self.taken.push(name);
+            !self.failing.contains(&name)
+        }
+    }
+
+    impl RestartSteps for RecordedSteps {
+        fn process_up(&self) -> bool {
+            self.up
+        }
+
+        fn pre_stop(&mut self) {
+            self.step("pre-stop");
+        }
+
+        fn terminate(&mut self) -> bool {
+            self.step("terminate")
+        }
+
+        fn pre_start(&mut self) -> bool {
+            self.step("pre-start")
+        }
+
+        fn spawn(&mut self) -> bool {
+            self.step("spawn")
+        }
+    }
+
+    #[test]
+    fn a_restart_stops_the_old_process_before_starting_the_new_one() {
+        let mut steps = RecordedSteps {
+            up: true,
+            ..Default::default()
+        };
+
+        assert!(restart_process(&mut steps));
+        assert_eq!(
+            steps.taken,
+            vec!["pre-stop", "terminate", "pre-start", "spawn"]
+        );
+    }
+
+    #[test]
+    fn a_failing_pre_start_hook_still_runs_the_pre_stop_hook() {
+        let mut steps = RecordedSteps {
+            up: true,
+            failing: vec!["pre-start"],
+            ..Default::default()
+        };
+
+        assert!(!restart_process(&mut steps));
+        assert_eq!(steps.taken, vec!["pre-stop", "terminate", "pre-start"]);
+    }
+
+    #[test]
+    fn a_process_that_is_down_is_only_started() {
+        let mut steps = RecordedSteps::default();
+
+        assert!(restart_process(&mut steps));
+        assert_eq!(steps.taken, vec!["pre-start", "spawn"]);
+    }
+
+    #[test]
+    fn a_process_that_fails_to_stop_is_not_started_again() {
+        let mut steps = RecordedSteps {
+            up: true,
+            failing: vec!["terminate"],
+            ..Default::default()
+        };
+
+        assert!(!restart_process(&mut steps));
+        assert_eq!(steps.taken, vec!["pre-stop", "terminate"]);
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_the_cap() {
+        let policy = policy(0);
+        let delays: Vec<u64> = (0..6).map(|n| policy.backoff_for(n).as_secs()).collect();
+
+        assert_eq!(delays, vec![0, 2, 4, 8, 10, 10]);
+        assert_eq!(policy.backoff_for(1000), Duration::from_secs(10));
+    }
+
+    #[test]
+    fn restarts_are_delayed_by_the_backoff() {
+        let tmpdir = TempDir::new().unwrap();
+        let mut tracker = tracker(tmpdir.path());
+        let policy = policy(0);
+        let start = Instant::now();
+
+        assert_eq!(tracker.check(&policy, start), RestartDecision::Restart);
+        tracker.record(start, RestartReason::ProcessExited);
+        assert_eq!(
+            tracker.check(&policy, start + Duration::from_secs(1)),
+            RestartDecision::Backoff(Duration::from_secs(1))
+        );
+        assert_eq!(
+            tracker.check(&policy, start + Duration::from_secs(2)),
+            RestartDecision::Restart
+        );
+    }
+
+    #[test]
+    fn too_many_restarts_within_the_window_is_a_crash_loop() {
+        let tmpdir = TempDir::new().unwrap();
+        let mut tracker = tracker(tmpdir.path());
+        let policy = policy(2);
+        let start = Instant::now();
+
+        tracker.record(start, RestartReason::ProcessExited);
+        tracker.record(start + Duration::from_secs(5), RestartReason::ProcessExited);
+        assert_eq!(
+            tracker.check(&policy, start + Duration::from_secs(30)),
+            RestartDecision::CrashLooping
+        );
+        assert!(tracker.is_crash_looping());
+        // Crash-looping is terminal, even once the window has passed.
+        assert_eq!(
+            tracker.check(&policy, start + Duration::from_secs(600)),
+            RestartDecision::CrashLooping
+        );
+    }
+
+    #[test]
+    fn restarts_outside_the_window_are_forgotten() {
+        let tmpdir = TempDir::new().unwrap();
+        let mut tracker = tracker(tmpdir.path());
+        let policy = policy(2);
+        let start = Instant::now();
+
+        tracker.record(start, RestartReason::ProcessExited);
+        tracker.record(start + Duration::from_secs(5), RestartReason::ProcessExited);
+        assert_eq!(
+            tracker.check(&policy, start + Duration::from_secs(90)),
+            RestartDecision::Restart
+        );
+        assert!(!tracker.is_crash_looping());
+    }
+
+    #[test]
+    fn restart_count_is_persisted() {
+        let tmpdir = TempDir::new().unwrap();
+        {
+            let mut tracker = tracker(tmpdir.path());
+            tracker.record(Instant::now(), RestartReason::ProcessExited);
+            tracker.record(Instant::now(), RestartReason::ProcessExited);
+        }
+        assert_eq!(tracker(tmpdir.path()).count(), 2);
+    }
+}
@@ -0,0 +1,252 @@
+// Copyright (c) 2018 Chef Software Inc. and/or applicable contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Rolls a service back to the package it ran before an update that left it failing.
+//!
+//! For `rollback_period` seconds after an update the service is watched. If it is crash-looping
+//! within that time, or isn't healthy once the time is up, the update has failed: the service is
+//! rolled back, and the release it was updated to is rejected so that it isn't applied again.
+//! The rejected release is persisted to the Supervisor's data directory so that it survives
+//! Supervisor restarts.
+
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::result;
+use std::time::{Duration, Instant};
+
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+
+use crate::hcore::package::PackageIdent;
+use crate::hcore::service::ServiceGroup;
+
+/// An update that is still being watched.
+#[derive(Debug)]
+struct Watch {
+    previous: PackageIdent,
+    updated: PackageIdent,
+    until: Instant,
+}
+
+/// Keeps track of the updates of a single service, and of the release that was last rolled
+/// back.
+#[derive(Debug)]
+pub struct RollbackTracker {
+    watching: Option<Watch>,
+    rejected: Option<PackageIdent>,
+    service_group: String,
+    state_file: PathBuf,
+}
+
+impl RollbackTracker {
+    /// Create a tracker for the given service group, picking up the release rejected by a
+    /// previous Supervisor, if any.
+    pub fn load<P>(service_group: &ServiceGroup, data_path: P) -> Self
+    where
+        P: AsRef<Path>,
+    {
+        let state_file = data_path
+            .as_ref()
+            .join(format!("{}.rejected", service_group.service()));
+        let rejected = fs::read_to_string(&state_file)
+            .ok()
+            .and_then(|s| s.trim().parse().ok());
+        RollbackTracker {
+            watching: None,
+            rejected: rejected,
+            service_group: service_group.to_string(),
+            state_file: state_file,
+        }
+    }
+
+    /// The release that was last rolled back, if any.
+    pub fn rejected(&self) -> Option<&PackageIdent> {
+        self.rejected.as_ref()
+    }
+
+    /// Watch a service that was just updated from `previous` to `updated`, for `period`.
+    pub fn watch(
+        &mut self,
+        previous: PackageIdent,
+        updated: PackageIdent,
+        period: Duration,
+        now: Instant,
+    ) {
+        self.watching = Some(Watch {
+            previous: previous,
+            updated: updated,
+            until: now + period,
+        });
+    }
+
+    /// Stop watching the last update, e.g. because the service was updated again.
+    pub fn stop_watching(&mut self) {
+        self.watching = None;
+    }
+
+    /// Decide whether the service has to be rolled back, given its current state. Returns the
+    /// package to roll back to if so, after rejecting the release it was updated to.
+    pub fn check(
+        &mut self,
+        healthy: bool,
+        crash_looping: bool,
+        now: Instant,
+    ) -> Option<PackageIdent> {
+        let over = match self.watching {
+            Some(ref watch) => now >= watch.until,
+            None => return None,
+        };
+        let failed = crash_looping || (over && !healthy);
+        if !failed {
+            if over {
+                self.watching = None;
+            }
+            return None;
+        }
+        let watch = self.watching.take().expect("watching an update");
+        self.rejected = Some(watch.updated);
+        if let Err(err) = self.persist() {
+            warn!(
+                "Unable to record the rejected release of {} in {}: {}",
+                self.service_group,
+                self.state_file.display(),
+                err
+            );
+        }
+        Some(watch.previous)
+    }
+
+    fn persist(&self) -> io::Result<()> {
+        let tmpfile = self.state_file.with_extension("rejected.tmp");
+        {
+            let mut file = File::create(&tmpfile)?;
+            if let Some(ref rejected) = self.rejected {
+                write!(file, "{}", rejected)?;
+            }
+        }
+        fs::rename(&tmpfile, &self.state_file)
+    }
+}
+
+impl Serialize for RollbackTracker {
+    fn serialize<S>(&self, serializer: S) -> result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut strukt = serializer.serialize_struct("rollback", 2)?;
+        strukt.serialize_field(
+            "rejected_release",
+            &self.rejected.as_ref().map(ToString::to_string),
+        )?;
+        strukt.serialize_field(
+            "watching_release",
+            &self.watching.as_ref().map(|w| w.updated.to_string()),
+        )?;
+        strukt.end()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::str::FromStr;
+
+    use tempfile::TempDir;
+
+    const PERIOD: Duration = Duration::from_secs(60);
+
+    fn tracker(data_path: &Path) -> RollbackTracker {
+        RollbackTracker::load(&ServiceGroup::from_str("redis.default").unwrap(), data_path)
+    }
+
+    fn ident(ident: &str) -> PackageIdent {
+        ident.parse().unwrap()
+    }
+
+    fn watching(data_path: &Path, start: Instant) -> RollbackTracker {
+        let mut tracker = tracker(data_path);
+        tracker.watch(
+            ident("core/redis/4.0.10/20180801003001"),
+            ident("core/redis/4.0.11/20180901003001"),
+            PERIOD,
+            start,
+        );
+        tracker
+    }
+
+    #[test]
+    fn nothing_is_rolled_back_without_an_update() {
+        let tmpdir = TempDir::new().unwrap();
+        assert_eq!(
+            tracker(tmpdir.path()).check(false, true, Instant::now()),
+            None
+        );
+    }
+
+    #[test]
+    fn unhealthy_services_are_given_the_whole_period() {
+        let tmpdir = TempDir::new().unwrap();
+        let start = Instant::now();
+        let mut tracker = watching(tmpdir.path(), start);
+
+        assert_eq!(tracker.check(false, false, start + PERIOD / 2), None);
+        assert_eq!(
+            tracker.check(false, false, start + PERIOD),
+            Some(ident("core/redis/4.0.10/20180801003001"))
+        );
+        assert_eq!(
+            tracker.rejected(),
+            Some(&ident("core/redis/4.0.11/20180901003001"))
+        );
+    }
+
+    #[test]
+    fn crash_looping_services_are_rolled_back_right_away() {
+        let tmpdir = TempDir::new().unwrap();
+        let start = Instant::now();
+        let mut tracker = watching(tmpdir.path(), start);
+
+        assert_eq!(
+            tracker.check(false, true, start),
+            Some(ident("core/redis/4.0.10/20180801003001"))
+        );
+        // Only once
+        assert_eq!(tracker.check(false, true, start), None);
+    }
+
+    #[test]
+    fn healthy_services_pass_once_the_period_is_over() {
+        let tmpdir = TempDir::new().unwrap();
+        let start = Instant::now();
+        let mut tracker = watching(tmpdir.path(), start);
+
+        assert_eq!(tracker.check(true, false, start + PERIOD), None);
+        assert_eq!(tracker.check(false, true, start + PERIOD * 2), None);
+        assert_eq!(tracker.rejected(), None);
+    }
+
+    #[test]
+    fn rejected_release_is_persisted() {
+        let tmpdir = TempDir::new().unwrap();
+        let start = Instant::now();
+        watching(tmpdir.path(), start).check(false, true, start);
+
+        assert_eq!(
+            tracker(tmpdir.path()).rejected(),
+            Some(&ident("core/redis/4.0.11/20180901003001"))
+        );
+    }
+}
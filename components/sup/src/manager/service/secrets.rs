@@ -0,0 +1,315 @@
+// Copyright (c) 2018 Chef Software Inc. and/or applicable contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Supervisor-local secrets of a service group.
+//!
+//! Unlike configuration applied with `hab config apply`, secrets are never gossiped. They are set
+//! through the ctl gateway and kept in `<sup_root>/secrets/<service_group>`, one file per secret,
+//! each encrypted with the service group's key. They are only decrypted to render the templates
+//! of the service, which reference them as `{{secret.<name>}}`.
+
+use std::collections::btree_map;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use crate::hcore;
+use crate::hcore::crypto::{keys::box_key_pair::WrappedSealedBox, BoxKeyPair};
+use crate::hcore::service::ServiceGroup;
+
+use crate::error::{Error, Result};
+
+static LOGKEY: &'static str = "SS";
+
+/// Directory of the Supervisor's root in which the secrets of every service group are kept.
+const SECRETS_DIR: &'static str = "secrets";
+
+#[cfg(not(windows))]
+const SECRET_PERMISSIONS: u32 = 0o600;
+
+/// The decrypted secrets of a service group, by name. The values are left out of the `Debug`
+/// output so that they can't end up in a log by accident.
+#[derive(Clone, Default, PartialEq)]
+pub struct Secrets(BTreeMap<String, String>);
+
+impl Secrets {
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.0.get(name).map(String::as_str)
+    }
+
+    pub fn iter(&self) -> btree_map::Iter<String, String> {
+        self.0.iter()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl From<BTreeMap<String, String>> for Secrets {
+    fn from(secrets: BTreeMap<String, String>) -> Self {
+        Secrets(secrets)
+    }
+}
+
+impl fmt::Debug for Secrets {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_map()
+            .entries(self.0.keys().map(|name| (name, "<redacted>")))
+            .finish()
+    }
+}
+
+#[derive(Debug)]
+pub struct SecretStore {
+    path: PathBuf,
+    service_group: ServiceGroup,
+}
+
+impl SecretStore {
+    pub fn new<P>(sup_root: P, service_group: &ServiceGroup) -> Self
+    where
+        P: AsRef<Path>,
+    {
+        SecretStore {
+            path: sup_root
+                .as_ref()
+                .join(SECRETS_DIR)
+                .join(service_group.to_string()),
+            service_group: service_group.clone(),
+        }
+    }
+
+    /// Encrypts `value` with the latest key of the service group in `cache_key_path` and stores
+    /// it as the secret `name`, replacing the previous value if there was one.
+    pub fn set<P>(&self, name: &str, value: &str, cache_key_path: P) -> Result<()>
+    where
+        P: AsRef<Path>,
+    {
+        validate_name(name)?;
+        let service_pair = BoxKeyPair::get_latest_pair_for(
+            &self.service_group.to_string(),
+            cache_key_path.as_ref(),
+        )?;
+        let sealed = service_pair.encrypt(value.as_bytes(), None)?.into_bytes();
+        fs::create_dir_all(&self.path)
+            .map_err(|e| sup_error!(Error::SecretStoreIO(self.path.clone(), e)))?;
+        // Written next to the secret first, so that a reader never sees half a value.
+        let tmp_path = self.path.join(format!(".{}.tmp", name));
+        let mut file = File::create(&tmp_path)
+            .map_err(|e| sup_error!(Error::SecretStoreIO(tmp_path.clone(), e)))?;
+        set_permissions(&tmp_path)?;
+        file.write_all(&sealed)
+            .and_then(|_| file.sync_all())
+            .map_err(|e| sup_error!(Error::SecretStoreIO(tmp_path.clone(), e)))?;
+        let path = self.path.join(name);
+        fs::rename(&tmp_path, &path).map_err(|e| sup_error!(Error::SecretStoreIO(path, e)))?;
+        Ok(())
+    }
+
+    /// Reads and decrypts every secret of the service group with the keys in `cache_key_path`.
+    /// A secret which can't be read or decrypted is logged and left out, so that the others are
+    /// still rendered.
+    pub fn load<P>(&self, cache_key_path: P) -> Result<Secrets>
+    where
+        P: AsRef<Path>,
+    {
+        let mut secrets = BTreeMap::new();
+        let entries = match fs::read_dir(&self.path) {
+            Ok(entries) => entries,
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(Secrets(secrets)),
+            Err(e) => return Err(sup_error!(Error::SecretStoreIO(self.path.clone(), e))),
+        };
+        for entry in entries {
+            let path = entry
+                .map_err(|e| sup_error!(Error::SecretStoreIO(self.path.clone(), e)))?
+                .path();
+            // Skips the temporary files of secrets being set.
+            let name = match path.file_name().and_then(|n| n.to_str()) {
+                Some(name) if is_valid_name(name) => name.to_string(),
+                _ => continue,
+            };
+            match read_secret(&path, cache_key_path.as_ref()) {
+                Ok(value) => {
+                    secrets.insert(name, value);
+                }
+                Err(err) => warn!(
+                    "Skipping secret {} of {}: {}",
+                    name, self.service_group, err
+                ),
+            }
+        }
+        Ok(Secrets(secrets))
+    }
+}
+
+fn read_secret(path: &Path, cache_key_path: &Path) -> Result<String> {
+    let bytes =
+        fs::read(path).map_err(|e| sup_error!(Error::SecretStoreIO(path.to_path_buf(), e)))?;
+    let value =
+        BoxKeyPair::decrypt_with_path(&WrappedSealedBox::from_bytes(&bytes)?, cache_key_path)?;
+    Ok(String::from_utf8(value)?)
+}
+
+/// Secret names are used as keys in templates, so they are restricted to letters, digits and
+/// underscores.
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn validate_name(name: &str) -> Result<()> {
+    if is_valid_name(name) {
+        Ok(())
+    } else {
+        Err(sup_error!(Error::InvalidSecretName(name.to_string())))
+    }
+}
+
+#[cfg(not(windows))]
+fn set_permissions<T: AsRef<Path>>(path: T) -> hcore::error::Result<()> {
+    use crate::hcore::util::posix_perm;
+
+    posix_perm::set_permissions(path.as_ref(), SECRET_PERMISSIONS)
+}
+
+#[cfg(windows)]
+fn set_permissions<T: AsRef<Path>>(path: T) -> hcore::error::Result<()> {
+    use crate::hcore::util::win_perm;
+
+    win_perm::harden_path(path.as_ref())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::str::FromStr;
+
+    use tempfile::TempDir;
+
+    fn service_group() -> ServiceGroup {
+        ServiceGroup::from_str("redis.default@acme").unwrap()
+    }
+
+    /// A Supervisor root and a key cache with a key for `service_group()`.
+    fn dirs() -> (TempDir, TempDir) {
+        let cache = TempDir::new().unwrap();
+        BoxKeyPair::generate_pair_for_service("acme", "redis.default")
+            .unwrap()
+            .to_pair_files(cache.path())
+            .unwrap();
+        (TempDir::new().unwrap(), cache)
+    }
+
+    #[test]
+    fn secrets_are_encrypted_at_rest() {
+        let (sup_root, cache) = dirs();
+        let store = SecretStore::new(sup_root.path(), &service_group());
+        store.set("password", "hunter2", cache.path()).unwrap();
+
+        let on_disk = fs::read(
+            sup_root
+                .path()
+                .join("secrets")
+                .join("redis.default@acme")
+                .join("password"),
+        )
+        .unwrap();
+        assert!(!String::from_utf8_lossy(&on_disk).contains("hunter2"));
+
+        let secrets = store.load(cache.path()).unwrap();
+        assert_eq!(secrets.get("password"), Some("hunter2"));
+    }
+
+    #[test]
+    fn setting_a_secret_again_replaces_it() {
+        let (sup_root, cache) = dirs();
+        let store = SecretStore::new(sup_root.path(), &service_group());
+        store.set("password", "hunter2", cache.path()).unwrap();
+        store
+            .set("password", "correct horse", cache.path())
+            .unwrap();
+        store.set("token", "abc123", cache.path()).unwrap();
+
+        let secrets = store.load(cache.path()).unwrap();
+        assert_eq!(secrets.get("password"), Some("correct horse"));
+        assert_eq!(secrets.get("token"), Some("abc123"));
+        assert_eq!(secrets.iter().count(), 2);
+    }
+
+    #[test]
+    fn a_secret_which_cannot_be_decrypted_is_skipped() {
+        let (sup_root, cache) = dirs();
+        let store = SecretStore::new(sup_root.path(), &service_group());
+        store.set("password", "hunter2", cache.path()).unwrap();
+        fs::write(
+            sup_root
+                .path()
+                .join("secrets")
+                .join("redis.default@acme")
+                .join("stale"),
+            "not a sealed box",
+        )
+        .unwrap();
+
+        let secrets = store.load(cache.path()).unwrap();
+        assert_eq!(secrets.get("password"), Some("hunter2"));
+        assert_eq!(secrets.get("stale"), None);
+    }
+
+    #[test]
+    fn a_service_group_without_secrets_has_none() {
+        let (sup_root, cache) = dirs();
+        let store = SecretStore::new(sup_root.path(), &service_group());
+        assert!(store.load(cache.path()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn setting_a_secret_requires_the_service_key() {
+        let (sup_root, cache) = dirs();
+        let store = SecretStore::new(
+            sup_root.path(),
+            &ServiceGroup::from_str("nginx.default@acme").unwrap(),
+        );
+        assert!(store.set("password", "hunter2", cache.path()).is_err());
+    }
+
+    #[test]
+    fn secret_names_must_be_usable_in_templates() {
+        let (sup_root, cache) = dirs();
+        let store = SecretStore::new(sup_root.path(), &service_group());
+        for name in &["", "db.password", "../password", "pass word", "-password"] {
+            match store.set(name, "hunter2", cache.path()) {
+                Err(e) => match e.err {
+                    Error::InvalidSecretName(_) => (),
+                    wrong => panic!("Unexpected error returned: {:?}", wrong),
+                },
+                Ok(_) => panic!("Secret name '{}' should be invalid", name),
+            }
+        }
+        store.set("db_password_2", "hunter2", cache.path()).unwrap();
+    }
+
+    #[test]
+    fn secret_values_are_not_in_debug_output() {
+        let (sup_root, cache) = dirs();
+        let store = SecretStore::new(sup_root.path(), &service_group());
+        store.set("password", "hunter2", cache.path()).unwrap();
+
+        let secrets = store.load(cache.path()).unwrap();
+        assert_eq!(format!("{:?}", secrets), "{\"password\": \"<redacted>\"}");
+    }
+}
@@ -0,0 +1,164 @@
+// Copyright (c) 2018 Chef Software Inc. and/or applicable contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Keeps track of the canary update a service is soaking.
+//!
+//! While the canary soaks, its service rumor says so, and the rest of the service group holds
+//! back. The soak is persisted to the Supervisor's data directory so that a restarted Supervisor
+//! keeps holding the group back instead of letting it follow the canary right away.
+
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::result;
+
+use serde::{Serialize, Serializer};
+
+use crate::hcore::package::PackageIdent;
+use crate::hcore::service::ServiceGroup;
+
+#[derive(Debug)]
+pub struct SoakTracker {
+    soaking_from: Option<PackageIdent>,
+    service_group: String,
+    state_file: PathBuf,
+}
+
+impl SoakTracker {
+    /// Create a tracker for the given service group, picking up the soak of a previous
+    /// Supervisor, if any.
+    pub fn load<P>(service_group: &ServiceGroup, data_path: P) -> Self
+    where
+        P: AsRef<Path>,
+    {
+        let state_file = data_path
+            .as_ref()
+            .join(format!("{}.soaking", service_group.service()));
+        let soaking_from = fs::read_to_string(&state_file)
+            .ok()
+            .and_then(|s| s.trim().parse().ok());
+        SoakTracker {
+            soaking_from: soaking_from,
+            service_group: service_group.to_string(),
+            state_file: state_file,
+        }
+    }
+
+    /// Whether the service is soaking an update.
+    pub fn soaking(&self) -> bool {
+        self.soaking_from.is_some()
+    }
+
+    /// Start soaking the update from `previous`.
+    pub fn start(&mut self, previous: PackageIdent) {
+        self.soaking_from = Some(previous);
+        self.record();
+    }
+
+    /// Stop soaking, because the update soaked long enough or was rolled back.
+    pub fn finish(&mut self) {
+        if self.soaking_from.take().is_some() {
+            self.record();
+        }
+    }
+
+    fn record(&self) {
+        if let Err(err) = self.persist() {
+            warn!(
+                "Unable to record the soak of {} in {}: {}",
+                self.service_group,
+                self.state_file.display(),
+                err
+            );
+        }
+    }
+
+    fn persist(&self) -> io::Result<()> {
+        match self.soaking_from {
+            Some(ref previous) => {
+                let tmpfile = self.state_file.with_extension("soaking.tmp");
+                {
+                    let mut file = File::create(&tmpfile)?;
+                    write!(file, "{}", previous)?;
+                }
+                fs::rename(&tmpfile, &self.state_file)
+            }
+            None => match fs::remove_file(&self.state_file) {
+                Err(ref err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+                res => res,
+            },
+        }
+    }
+}
+
+impl Serialize for SoakTracker {
+    fn serialize<S>(&self, serializer: S) -> result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.soaking_from
+            .as_ref()
+            .map(ToString::to_string)
+            .serialize(serializer)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::str::FromStr;
+
+    use tempfile::TempDir;
+
+    fn tracker(data_path: &Path) -> SoakTracker {
+        SoakTracker::load(&ServiceGroup::from_str("redis.default").unwrap(), data_path)
+    }
+
+    fn ident(ident: &str) -> PackageIdent {
+        ident.parse().unwrap()
+    }
+
+    #[test]
+    fn nothing_soaks_at_first() {
+        let tmpdir = TempDir::new().unwrap();
+        let tracker = tracker(tmpdir.path());
+
+        assert!(!tracker.soaking());
+    }
+
+    #[test]
+    fn a_soak_is_persisted() {
+        let tmpdir = TempDir::new().unwrap();
+        tracker(tmpdir.path()).start(ident("core/redis/4.0.10/20180801003001"));
+
+        let tracker = tracker(tmpdir.path());
+        assert!(tracker.soaking());
+        assert_eq!(
+            tracker.soaking_from,
+            Some(ident("core/redis/4.0.10/20180801003001"))
+        );
+    }
+
+    #[test]
+    fn a_finished_soak_is_forgotten() {
+        let tmpdir = TempDir::new().unwrap();
+        let mut soaking = tracker(tmpdir.path());
+        soaking.start(ident("core/redis/4.0.10/20180801003001"));
+        soaking.finish();
+
+        assert!(!soaking.soaking());
+        assert!(!tracker(tmpdir.path()).soaking());
+    }
+}
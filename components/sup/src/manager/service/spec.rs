@@ -12,7 +12,10 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use super::{BindingMode, Topology, UpdateStrategy};
+use super::{
+    BindingMode, HealthCheckThresholds, HealthProbe, LogSettings, ResourceLimits, RestartPolicy,
+    ShutdownSignal, Topology, UpdateSettings, UpdateStrategy,
+};
 use crate::error::{Error, Result, SupError};
 use crate::hcore::package::{PackageIdent, PackageInstall};
 use crate::hcore::service::{ApplicationEnvironment, HealthCheckInterval, ServiceGroup};
@@ -97,6 +100,66 @@ where
     }
 }
 
+fn deserialize_idents<'de, D>(d: D) -> result::Result<Vec<PackageIdent>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let idents: Vec<String> = Vec::deserialize(d)?;
+    idents
+        .iter()
+        .map(|s| FromStr::from_str(s).map_err(serde::de::Error::custom))
+        .collect()
+}
+
+fn serialize_idents<S>(idents: &[PackageIdent], s: S) -> result::Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    s.collect_seq(idents.iter().map(ToString::to_string))
+}
+
+/// Whether both idents name the same package. A Supervisor runs at most one service per package
+/// name, so only the origin and name are compared.
+pub fn same_package(a: &PackageIdent, b: &PackageIdent) -> bool {
+    a.origin == b.origin && a.name == b.name
+}
+
+/// Returns a cycle of services that depend on each other through their `requires` and `after`,
+/// starting and ending with the same package, if the specs contain one. The services in it would
+/// all wait on each other to start, forever.
+pub fn dependency_cycle(specs: &[ServiceSpec]) -> Option<Vec<PackageIdent>> {
+    fn visit<'a>(
+        spec: &'a ServiceSpec,
+        specs: &'a [ServiceSpec],
+        path: &mut Vec<&'a PackageIdent>,
+        acyclic: &mut Vec<&'a PackageIdent>,
+    ) -> Option<Vec<PackageIdent>> {
+        if let Some(start) = path.iter().position(|i| same_package(i, &spec.ident)) {
+            let mut cycle: Vec<PackageIdent> = path[start..].iter().cloned().cloned().collect();
+            cycle.push(spec.ident.clone());
+            return Some(cycle);
+        }
+        if acyclic.iter().any(|i| same_package(i, &spec.ident)) {
+            return None;
+        }
+        path.push(&spec.ident);
+        for dep in specs.iter().filter(|other| spec.depends_on(&other.ident)) {
+            if let Some(cycle) = visit(dep, specs, path, acyclic) {
+                return Some(cycle);
+            }
+        }
+        path.pop();
+        acyclic.push(&spec.ident);
+        None
+    }
+
+    let mut acyclic = Vec::new();
+    specs
+        .iter()
+        .filter_map(|spec| visit(spec, specs, &mut Vec::new(), &mut acyclic))
+        .next()
+}
+
 pub trait IntoServiceSpec {
     fn into_spec(&self, spec: &mut ServiceSpec);
 }
@@ -138,6 +201,49 @@ impl IntoServiceSpec for protocol::ctl::SvcLoad {
         if let Some(ref interval) = self.health_check_interval {
             spec.health_check_interval = Duration::from_secs(interval.seconds).into()
         }
+        if let Some(ref restart_policy) = self.restart_policy {
+            spec.restart_policy.merge(restart_policy);
+        }
+        if let Some(ref resource_limits) = self.resource_limits {
+            spec.resource_limits.merge(resource_limits);
+        }
+        if let Some(ref log_settings) = self.log_settings {
+            spec.log_settings.merge(log_settings);
+        }
+        if let Some(ref thresholds) = self.health_check_thresholds {
+            spec.health_check_thresholds.merge(thresholds);
+        }
+        if let Some(ref probe) = self.health_probe {
+            // The target has already been validated by the time a request gets here.
+            match HealthProbe::from_proto(probe) {
+                Ok(probe) => spec.health_probe = Some(probe),
+                Err(err) => warn!("Ignoring health probe: {}", err),
+            }
+        }
+        if let Some(shutdown_signal) = self.shutdown_signal {
+            spec.shutdown_signal = ShutdownSignal::from_i32(shutdown_signal);
+        }
+        if let Some(shutdown_timeout) = self.shutdown_timeout {
+            spec.shutdown_timeout = Some(shutdown_timeout);
+        }
+        if let Some(pre_start_timeout) = self.pre_start_timeout {
+            spec.pre_start_timeout = Some(pre_start_timeout);
+        }
+        if let Some(pre_stop_timeout) = self.pre_stop_timeout {
+            spec.pre_stop_timeout = Some(pre_stop_timeout);
+        }
+        if !self.after.is_empty() {
+            spec.after = self.after.iter().cloned().map(Into::into).collect();
+        }
+        if !self.requires.is_empty() {
+            spec.requires = self.requires.iter().cloned().map(Into::into).collect();
+        }
+        if let Some(require_healthy) = self.require_healthy {
+            spec.require_healthy = require_healthy;
+        }
+        if let Some(ref update_settings) = self.update_settings {
+            spec.update_settings.merge(update_settings);
+        }
     }
 }
 
@@ -159,15 +265,47 @@ pub struct ServiceSpec {
     pub channel: ChannelIdent,
     pub topology: Topology,
     pub update_strategy: UpdateStrategy,
+    /// Whether automatic updates are paused, e.g. with `hab svc update pause`.
+    pub update_paused: bool,
     pub binds: Vec<ServiceBind>,
     pub binding_mode: BindingMode,
+    /// Packages whose services, if loaded on this Supervisor, start before this one and stop
+    /// after it.
+    #[serde(
+        deserialize_with = "deserialize_idents",
+        serialize_with = "serialize_idents"
+    )]
+    pub after: Vec<PackageIdent>,
+    /// Packages whose services must be loaded and up on this Supervisor before this one starts.
+    #[serde(
+        deserialize_with = "deserialize_idents",
+        serialize_with = "serialize_idents"
+    )]
+    pub requires: Vec<PackageIdent>,
+    /// Whether the services this one starts after must also be healthy before it starts.
+    pub require_healthy: bool,
     pub config_from: Option<PathBuf>,
     #[serde(
         deserialize_with = "deserialize_using_from_str",
         serialize_with = "serialize_using_to_string"
     )]
     pub desired_state: DesiredState,
+    /// Overrides the package's shutdown signal, if it sets one, or the Launcher's default.
+    pub shutdown_signal: Option<ShutdownSignal>,
+    /// Overrides the package's shutdown timeout, in seconds, if it sets one, or the Launcher's
+    /// default.
+    pub shutdown_timeout: Option<u32>,
+    /// Seconds the pre-start hook may run before it is killed and the process isn't started.
+    pub pre_start_timeout: Option<u32>,
+    /// Seconds the pre-stop hook may run before the process is stopped regardless.
+    pub pre_stop_timeout: Option<u32>,
     pub health_check_interval: HealthCheckInterval,
+    pub health_check_thresholds: HealthCheckThresholds,
+    pub health_probe: Option<HealthProbe>,
+    pub restart_policy: RestartPolicy,
+    pub update_settings: UpdateSettings,
+    pub resource_limits: ResourceLimits,
+    pub log_settings: LogSettings,
     pub svc_encrypted_password: Option<String>,
 }
 
@@ -231,6 +369,14 @@ impl ServiceSpec {
         Ok(())
     }
 
+    /// Whether this service starts after, and stops before, the given package's service.
+    pub fn depends_on(&self, ident: &PackageIdent) -> bool {
+        self.after
+            .iter()
+            .chain(self.requires.iter())
+            .any(|dep| same_package(dep, ident))
+    }
+
     pub fn file_name(&self) -> String {
         format!("{}.{}", &self.ident.name, SPEC_FILE_EXT)
     }
@@ -294,11 +440,25 @@ impl Default for ServiceSpec {
             channel: ChannelIdent::stable(),
             topology: Topology::default(),
             update_strategy: UpdateStrategy::default(),
+            update_paused: false,
             binds: Vec::default(),
             binding_mode: BindingMode::Strict,
+            after: Vec::default(),
+            requires: Vec::default(),
+            require_healthy: false,
             config_from: None,
             desired_state: DesiredState::default(),
+            shutdown_signal: None,
+            shutdown_timeout: None,
+            pre_start_timeout: None,
+            pre_stop_timeout: None,
             health_check_interval: HealthCheckInterval::default(),
+            health_check_thresholds: HealthCheckThresholds::default(),
+            health_probe: None,
+            restart_policy: RestartPolicy::default(),
+            update_settings: UpdateSettings::default(),
+            resource_limits: ResourceLimits::default(),
+            log_settings: LogSettings::default(),
             svc_encrypted_password: None,
         }
     }
@@ -317,6 +477,56 @@ impl FromStr for ServiceSpec {
     }
 }
 
+/// The specs of many services, applied together with `hab svc apply`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ServiceManifest {
+    #[serde(default)]
+    pub services: Vec<ServiceSpec>,
+}
+
+impl ServiceManifest {
+    /// Whether the manifest has a spec for the same service as the given spec.
+    pub fn lists(&self, spec: &ServiceSpec) -> bool {
+        self.services
+            .iter()
+            .any(|s| s.ident.name == spec.ident.name)
+    }
+
+    /// The specs the Supervisor should have loaded once the manifest is applied over the given
+    /// loaded specs: those of the manifest, plus, unless pruning, the loaded ones it doesn't list.
+    pub fn desired_specs(&self, loaded: &[ServiceSpec], prune: bool) -> Vec<ServiceSpec> {
+        let mut desired = self.services.clone();
+        if !prune {
+            desired.extend(loaded.iter().filter(|spec| !self.lists(spec)).cloned());
+        }
+        desired
+    }
+}
+
+impl FromStr for ServiceManifest {
+    type Err = SupError;
+
+    fn from_str(toml: &str) -> result::Result<Self, Self::Err> {
+        let manifest: ServiceManifest =
+            toml::from_str(toml).map_err(|e| sup_error!(Error::ServiceManifestParse(e)))?;
+        let mut names = HashSet::new();
+        for spec in manifest.services.iter() {
+            if spec.ident == PackageIdent::default() {
+                return Err(sup_error!(Error::MissingRequiredIdent));
+            }
+            // Spec files are named after the package, so a package may only be listed once.
+            if !names.insert(&spec.ident.name) {
+                return Err(sup_error!(Error::InvalidServiceManifest(format!(
+                    "{} is listed more than once",
+                    spec.ident.name
+                ))));
+            }
+        }
+        Ok(manifest)
+    }
+}
+
 #[derive(Clone, Debug, Eq, Hash, PartialEq)]
 pub struct ServiceBind {
     pub name: String,
@@ -394,6 +604,7 @@ mod test {
     use tempfile::TempDir;
     use toml;
 
+    use super::super::BatchSize;
     use super::*;
     use crate::error::Error::*;
 
@@ -427,12 +638,41 @@ mod test {
             bldr_url = "http://example.com/depot"
             topology = "leader"
             update_strategy = "rolling"
+            update_paused = true
             binds = ["cache:redis.cache@acmecorp", "db:postgres.app@acmecorp"]
+            after = ["core/consul"]
+            requires = ["acmecorp/migrations"]
+            require_healthy = true
             config_from = "/only/for/development"
 
             [health_check_interval]
             secs = 5
             nanos = 0
+
+            [health_check_thresholds]
+            failures = 3
+            grace_period = 30
+
+            [health_probe]
+            target = "http://localhost:8080/healthz"
+
+            [restart_policy]
+            max_restarts = 3
+            window = 60
+
+            [update_settings]
+            batch_size = "20%"
+            windows = ["sat,sun 01:00-05:00"]
+            splay = 600
+            rollback_period = 120
+
+            [resource_limits]
+            memory_max = 536870912
+            cpu_quota = 150
+
+            [log_settings]
+            enabled = true
+            retain = 3
             "#;
         let spec = ServiceSpec::from_str(toml).unwrap();
 
@@ -448,6 +688,7 @@ mod test {
         assert_eq!(spec.bldr_url, String::from("http://example.com/depot"));
         assert_eq!(spec.topology, Topology::Leader);
         assert_eq!(spec.update_strategy, UpdateStrategy::Rolling);
+        assert!(spec.update_paused);
         assert_eq!(
             spec.binds,
             vec![
@@ -455,6 +696,15 @@ mod test {
                 ServiceBind::from_str("db:postgres.app@acmecorp").unwrap(),
             ]
         );
+        assert_eq!(
+            spec.after,
+            vec![PackageIdent::from_str("core/consul").unwrap()]
+        );
+        assert_eq!(
+            spec.requires,
+            vec![PackageIdent::from_str("acmecorp/migrations").unwrap()]
+        );
+        assert!(spec.require_healthy);
         assert_eq!(
             spec.config_from,
             Some(PathBuf::from("/only/for/development"))
@@ -463,6 +713,55 @@ mod test {
             spec.health_check_interval,
             HealthCheckInterval::from_str("5").unwrap()
         );
+        assert_eq!(
+            spec.health_check_thresholds,
+            HealthCheckThresholds {
+                failures: 3,
+                grace_period: 30,
+                ..HealthCheckThresholds::default()
+            }
+        );
+        assert_eq!(
+            spec.health_probe,
+            Some(HealthProbe {
+                target: "http://localhost:8080/healthz".parse().unwrap(),
+                timeout: 5,
+            })
+        );
+        assert_eq!(
+            spec.restart_policy,
+            RestartPolicy {
+                max_restarts: 3,
+                window: 60,
+                ..RestartPolicy::default()
+            }
+        );
+        assert_eq!(
+            spec.update_settings,
+            UpdateSettings {
+                batch_size: BatchSize::Percent(20),
+                windows: vec!["sat,sun 01:00-05:00".parse().unwrap()],
+                splay: 600,
+                rollback_period: 120,
+                ..UpdateSettings::default()
+            }
+        );
+        assert_eq!(
+            spec.resource_limits,
+            ResourceLimits {
+                memory_max: Some(536870912),
+                cpu_quota: Some(150),
+                ..ResourceLimits::default()
+            }
+        );
+        assert_eq!(
+            spec.log_settings,
+            LogSettings {
+                enabled: true,
+                retain: 3,
+                ..LogSettings::default()
+            }
+        );
     }
 
     #[test]
@@ -511,6 +810,128 @@ mod test {
         }
     }
 
+    #[test]
+    fn service_spec_from_str_invalid_update_settings() {
+        for settings in &["batch_size = \"0\"", "windows = [\"whenever\"]"] {
+            let toml = format!(
+                "ident = \"origin/name/1.2.3/20170223130020\"\n[update_settings]\n{}\n",
+                settings
+            );
+
+            match ServiceSpec::from_str(&toml) {
+                Err(e) => match e.err {
+                    ServiceSpecParse(_) => assert!(true),
+                    e => panic!("Unexpected error returned: {:?}", e),
+                },
+                Ok(_) => panic!("Spec TOML with {} should fail to parse", settings),
+            }
+        }
+    }
+
+    #[test]
+    fn service_manifest_from_str() {
+        let toml = r#"
+            [[services]]
+            ident = "core/redis"
+            group = "cache"
+
+            [[services]]
+            ident = "core/postgresql"
+            desired_state = "down"
+            "#;
+        let manifest = ServiceManifest::from_str(toml).unwrap();
+
+        assert_eq!(manifest.services.len(), 2);
+        assert_eq!(
+            manifest.services[0],
+            ServiceSpec {
+                group: String::from("cache"),
+                ..ServiceSpec::default_for(PackageIdent::from_str("core/redis").unwrap())
+            }
+        );
+        assert_eq!(manifest.services[1].desired_state, DesiredState::Down);
+        assert!(manifest.lists(&ServiceSpec::default_for(
+            PackageIdent::from_str("core/redis/4.0.10").unwrap()
+        )));
+        assert!(!manifest.lists(&ServiceSpec::default_for(
+            PackageIdent::from_str("core/nginx").unwrap()
+        )));
+    }
+
+    #[test]
+    fn service_manifest_desired_specs() {
+        let toml = r#"
+            [[services]]
+            ident = "core/redis"
+            group = "cache"
+
+            [[services]]
+            ident = "core/postgresql"
+            "#;
+        let manifest = ServiceManifest::from_str(toml).unwrap();
+        let loaded = vec![
+            ServiceSpec::default_for(PackageIdent::from_str("core/redis/4.0.10").unwrap()),
+            ServiceSpec::default_for(PackageIdent::from_str("core/nginx").unwrap()),
+        ];
+        let idents = |specs: Vec<ServiceSpec>| {
+            specs
+                .iter()
+                .map(|spec| (spec.ident.to_string(), spec.group.clone()))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            idents(manifest.desired_specs(&loaded, false)),
+            vec![
+                ("core/redis".to_string(), "cache".to_string()),
+                ("core/postgresql".to_string(), "default".to_string()),
+                ("core/nginx".to_string(), "default".to_string()),
+            ]
+        );
+        assert_eq!(
+            idents(manifest.desired_specs(&loaded, true)),
+            vec![
+                ("core/redis".to_string(), "cache".to_string()),
+                ("core/postgresql".to_string(), "default".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn service_manifest_from_str_missing_ident() {
+        let toml = r#"
+            [[services]]
+            group = "cache"
+            "#;
+
+        match ServiceManifest::from_str(toml) {
+            Err(e) => match e.err {
+                MissingRequiredIdent => assert!(true),
+                e => panic!("Unexpected error returned: {:?}", e),
+            },
+            Ok(_) => panic!("Manifest TOML should fail to parse"),
+        }
+    }
+
+    #[test]
+    fn service_manifest_from_str_duplicate_service() {
+        let toml = r#"
+            [[services]]
+            ident = "core/redis"
+
+            [[services]]
+            ident = "core/redis/4.0.10"
+            "#;
+
+        match ServiceManifest::from_str(toml) {
+            Err(e) => match e.err {
+                InvalidServiceManifest(_) => assert!(true),
+                e => panic!("Unexpected error returned: {:?}", e),
+            },
+            Ok(_) => panic!("Manifest TOML should fail to parse"),
+        }
+    }
+
     #[test]
     fn service_spec_to_toml_string() {
         let spec = ServiceSpec {
@@ -523,14 +944,54 @@ mod test {
             channel: ChannelIdent::unstable(),
             topology: Topology::Leader,
             update_strategy: UpdateStrategy::AtOnce,
+            update_paused: true,
             binds: vec![
                 ServiceBind::from_str("cache:redis.cache@acmecorp").unwrap(),
                 ServiceBind::from_str("db:postgres.app@acmecorp").unwrap(),
             ],
             binding_mode: BindingMode::Relaxed,
+            after: vec![PackageIdent::from_str("core/consul").unwrap()],
+            requires: vec![],
+            require_healthy: true,
             health_check_interval: HealthCheckInterval::from_str("123").unwrap(),
+            health_check_thresholds: HealthCheckThresholds {
+                failures: 2,
+                successes: 4,
+                grace_period: 60,
+                restart_after: 3,
+            },
+            health_probe: Some(HealthProbe {
+                target: "tcp://localhost:5432".parse().unwrap(),
+                timeout: 2,
+            }),
+            restart_policy: RestartPolicy {
+                max_restarts: 5,
+                window: 120,
+                backoff: 2,
+                backoff_max: 30,
+            },
+            update_settings: UpdateSettings {
+                batch_size: BatchSize::Count(3),
+                canary_soak: 600,
+                windows: vec!["mon-fri 22:00-02:00".parse().unwrap()],
+                splay: 120,
+                rollback_period: 300,
+            },
+            resource_limits: ResourceLimits {
+                pids_max: Some(64),
+                ..ResourceLimits::default()
+            },
+            log_settings: LogSettings {
+                enabled: true,
+                max_size: 2048,
+                ..LogSettings::default()
+            },
             config_from: Some(PathBuf::from("/only/for/development")),
             desired_state: DesiredState::Down,
+            shutdown_signal: Some(ShutdownSignal::Int),
+            shutdown_timeout: Some(60),
+            pre_start_timeout: Some(600),
+            pre_stop_timeout: Some(45),
             svc_encrypted_password: None,
         };
         let toml = spec.to_toml_string().unwrap();
@@ -542,14 +1003,46 @@ mod test {
         assert!(toml.contains(r#"channel = "unstable""#));
         assert!(toml.contains(r#"topology = "leader""#));
         assert!(toml.contains(r#"update_strategy = "at-once""#));
+        assert!(toml.contains(r#"update_paused = true"#));
         assert!(toml.contains(r#""cache:redis.cache@acmecorp""#));
         assert!(toml.contains(r#""db:postgres.app@acmecorp""#));
         assert!(toml.contains(r#"desired_state = "down""#));
         assert!(toml.contains(r#"config_from = "/only/for/development""#));
         assert!(toml.contains(r#"binding_mode = "relaxed""#));
+        assert!(toml.contains(r#"after = ["core/consul"]"#));
+        assert!(toml.contains(r#"require_healthy = true"#));
         assert!(toml.contains(r#"[health_check_interval]"#));
+        assert!(toml.contains(r#"shutdown_signal = "int""#));
+        assert!(toml.contains(r#"shutdown_timeout = 60"#));
+        assert!(toml.contains(r#"pre_start_timeout = 600"#));
+        assert!(toml.contains(r#"pre_stop_timeout = 45"#));
         assert!(toml.contains(r#"secs = 123"#));
         assert!(toml.contains(r#"nanos = 0"#));
+        assert!(toml.contains(r#"[health_check_thresholds]"#));
+        assert!(toml.contains(r#"failures = 2"#));
+        assert!(toml.contains(r#"successes = 4"#));
+        assert!(toml.contains(r#"grace_period = 60"#));
+        assert!(toml.contains(r#"restart_after = 3"#));
+        assert!(toml.contains(r#"[health_probe]"#));
+        assert!(toml.contains(r#"target = "tcp://localhost:5432""#));
+        assert!(toml.contains(r#"timeout = 2"#));
+        assert!(toml.contains(r#"[restart_policy]"#));
+        assert!(toml.contains(r#"max_restarts = 5"#));
+        assert!(toml.contains(r#"window = 120"#));
+        assert!(toml.contains(r#"backoff = 2"#));
+        assert!(toml.contains(r#"backoff_max = 30"#));
+        assert!(toml.contains(r#"[update_settings]"#));
+        assert!(toml.contains(r#"batch_size = "3""#));
+        assert!(toml.contains(r#"canary_soak = 600"#));
+        assert!(toml.contains(r#"windows = ["mon,tue,wed,thu,fri 22:00-02:00"]"#));
+        assert!(toml.contains(r#"splay = 120"#));
+        assert!(toml.contains(r#"rollback_period = 300"#));
+        assert!(toml.contains(r#"[resource_limits]"#));
+        assert!(toml.contains(r#"pids_max = 64"#));
+        assert!(!toml.contains(r#"memory_max"#));
+        assert!(toml.contains(r#"[log_settings]"#));
+        assert!(toml.contains(r#"enabled = true"#));
+        assert!(toml.contains(r#"max_size = 2048"#));
     }
 
     #[test]
@@ -643,6 +1136,21 @@ mod test {
         let spec = ServiceSpec::from_file(path).unwrap();
 
         assert_eq!(spec.health_check_interval, HealthCheckInterval::default());
+        assert_eq!(
+            spec.health_check_thresholds,
+            HealthCheckThresholds::default()
+        );
+        assert_eq!(spec.health_probe, None);
+        assert_eq!(spec.restart_policy, RestartPolicy::default());
+        assert_eq!(spec.update_settings, UpdateSettings::default());
+        assert_eq!(spec.resource_limits, ResourceLimits::default());
+        assert_eq!(spec.log_settings, LogSettings::default());
+        assert_eq!(spec.shutdown_signal, None);
+        assert_eq!(spec.shutdown_timeout, None);
+        assert_eq!(spec.pre_start_timeout, None);
+        assert_eq!(spec.pre_stop_timeout, None);
+        assert!(spec.after.is_empty());
+        assert!(spec.requires.is_empty());
     }
 
     #[test]
@@ -703,14 +1211,28 @@ mod test {
             channel: ChannelIdent::unstable(),
             topology: Topology::Leader,
             update_strategy: UpdateStrategy::AtOnce,
+            update_paused: false,
             binds: vec![
                 ServiceBind::from_str("cache:redis.cache@acmecorp").unwrap(),
                 ServiceBind::from_str("db:postgres.app@acmecorp").unwrap(),
             ],
             binding_mode: BindingMode::Relaxed,
+            after: vec![],
+            requires: vec![],
+            require_healthy: false,
             health_check_interval: HealthCheckInterval::from_str("23").unwrap(),
+            health_check_thresholds: HealthCheckThresholds::default(),
+            health_probe: None,
+            restart_policy: RestartPolicy::default(),
+            update_settings: UpdateSettings::default(),
+            resource_limits: ResourceLimits::default(),
+            log_settings: LogSettings::default(),
             config_from: Some(PathBuf::from("/only/for/development")),
             desired_state: DesiredState::Down,
+            shutdown_signal: None,
+            shutdown_timeout: None,
+            pre_start_timeout: None,
+            pre_stop_timeout: None,
             svc_encrypted_password: None,
         };
         spec.to_file(&path).unwrap();
@@ -750,6 +1272,67 @@ mod test {
         }
     }
 
+    #[test]
+    fn service_spec_depends_on_origin_and_name() {
+        let mut spec = ServiceSpec::default_for(PackageIdent::from_str("acmecorp/app").unwrap());
+        spec.after = vec![PackageIdent::from_str("core/consul").unwrap()];
+        spec.requires = vec![PackageIdent::from_str("acmecorp/migrations/1.0.0").unwrap()];
+
+        assert!(
+            spec.depends_on(&PackageIdent::from_str("core/consul/1.4.0/20190101000000").unwrap())
+        );
+        assert!(spec.depends_on(&PackageIdent::from_str("acmecorp/migrations").unwrap()));
+        assert!(!spec.depends_on(&PackageIdent::from_str("acmecorp/consul").unwrap()));
+    }
+
+    #[test]
+    fn service_spec_dependency_cycles() {
+        let spec = |ident: &str, requires: &[&str], after: &[&str]| {
+            let mut spec = ServiceSpec::default_for(PackageIdent::from_str(ident).unwrap());
+            spec.requires = requires
+                .iter()
+                .map(|i| PackageIdent::from_str(i).unwrap())
+                .collect();
+            spec.after = after
+                .iter()
+                .map(|i| PackageIdent::from_str(i).unwrap())
+                .collect();
+            spec
+        };
+        let idents =
+            |cycle: Vec<PackageIdent>| cycle.iter().map(ToString::to_string).collect::<Vec<_>>();
+
+        let specs = vec![
+            spec("acmecorp/app", &["acmecorp/migrations"], &["core/consul"]),
+            spec("acmecorp/migrations", &["core/postgresql/9.6.11"], &[]),
+            spec("core/postgresql/9.6.11", &[], &["core/consul"]),
+            spec("core/consul", &[], &[]),
+        ];
+        assert_eq!(dependency_cycle(&specs), None);
+
+        let specs = vec![
+            spec("core/consul", &[], &[]),
+            spec("acmecorp/app", &["acmecorp/migrations"], &[]),
+            spec("acmecorp/migrations", &["core/postgresql"], &[]),
+            spec("core/postgresql", &[], &["acmecorp/app"]),
+        ];
+        assert_eq!(
+            idents(dependency_cycle(&specs).unwrap()),
+            vec![
+                "acmecorp/app",
+                "acmecorp/migrations",
+                "core/postgresql",
+                "acmecorp/app"
+            ]
+        );
+
+        let specs = vec![spec("acmecorp/app", &["acmecorp/app/1.0.0"], &[])];
+        assert_eq!(
+            idents(dependency_cycle(&specs).unwrap()),
+            vec!["acmecorp/app", "acmecorp/app"]
+        );
+    }
+
     #[test]
     fn service_spec_file_name() {
         let spec = ServiceSpec::default_for(PackageIdent::from_str("origin/hoopa/1.2.3").unwrap());
@@ -30,12 +30,14 @@ use crate::hcore::os::process::{self, Pid};
 #[cfg(unix)]
 use crate::hcore::os::users;
 use crate::hcore::service::ServiceGroup;
-use crate::launcher_client::LauncherCli;
+use crate::launcher_client::{LauncherCli, ShutdownSpec};
 use serde::ser::SerializeStruct;
 use serde::{Serialize, Serializer};
 use time::{self, Timespec};
 
+use super::LogSettings;
 use super::ProcessState;
+use super::ResourceLimits;
 use super::ShutdownReason;
 use crate::error::{Error, Result};
 
@@ -78,6 +80,10 @@ impl Supervisor {
         }
     }
 
+    pub fn pid(&self) -> Option<Pid> {
+        self.pid
+    }
+
     /// Check if the child process is running
     pub fn check_process(&mut self) -> bool {
         let pid = match self.pid {
@@ -166,6 +172,9 @@ impl Supervisor {
         group: &ServiceGroup,
         launcher: &LauncherCli,
         svc_password: Option<T>,
+        limits: ResourceLimits,
+        log_settings: LogSettings,
+        shutdown: ShutdownSpec,
     ) -> Result<()>
     where
         T: ToString,
@@ -210,6 +219,13 @@ impl Supervisor {
             service_group_id, // Linux preferred
             svc_password,     // Windows optional
             (*pkg.env).clone(),
+            if limits.is_unlimited() {
+                None
+            } else {
+                Some(limits.into())
+            },
+            log_settings.launcher_config(&pkg.name),
+            shutdown,
         )?;
         self.pid = Some(pid);
         self.create_pidfile()?;
@@ -231,7 +247,12 @@ impl Supervisor {
         (healthy, status)
     }
 
-    pub fn stop(&mut self, launcher: &LauncherCli, cause: ShutdownReason) -> Result<()> {
+    pub fn stop(
+        &mut self,
+        launcher: &LauncherCli,
+        cause: ShutdownReason,
+        shutdown: ShutdownSpec,
+    ) -> Result<()> {
         if self.pid.is_none() {
             return Ok(());
         }
@@ -240,41 +261,19 @@ impl Supervisor {
             // we'll avoid this knowing that launcher will gratuitously kill off
             // all services as part of its shutdown routine
         } else {
-            launcher.terminate(self.pid.unwrap())?;
+            let reply = launcher.terminate(self.pid.unwrap(), shutdown)?;
+            match reply.shutdown_signal {
+                Some(signal) => outputln!(preamble self.preamble,
+                                          "Shutdown OK: {} (SIG{})", reply.shutdown_method, signal),
+                None => outputln!(preamble self.preamble,
+                                  "Shutdown OK: {}", reply.shutdown_method),
+            }
         }
         self.cleanup_pidfile();
         self.change_state(ProcessState::Down);
         Ok(())
     }
 
-    pub fn restart<T>(
-        &mut self,
-        pkg: &Pkg,
-        group: &ServiceGroup,
-        launcher: &LauncherCli,
-        svc_password: Option<T>,
-    ) -> Result<()>
-    where
-        T: ToString,
-    {
-        match self.pid {
-            Some(pid) => match launcher.restart(pid) {
-                Ok(pid) => {
-                    self.pid = Some(pid);
-                    self.create_pidfile()?;
-                    self.change_state(ProcessState::Up);
-                    Ok(())
-                }
-                Err(err) => {
-                    self.cleanup_pidfile();
-                    self.change_state(ProcessState::Down);
-                    Err(sup_error!(Error::Launcher(err)))
-                }
-            },
-            None => self.start(pkg, group, launcher, svc_password),
-        }
-    }
-
     /// Create a PID file for a running service
     fn create_pidfile(&mut self) -> Result<()> {
         match self.pid {
@@ -0,0 +1,414 @@
+// Copyright (c) 2018 Chef Software Inc. and/or applicable contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Settings for how and when an update is rolled out to the members of a service group.
+//!
+//! With the `batched` strategy the update leader updates first, then the remaining members
+//! update `batch_size` at a time. The `canary` strategy does the same, except that the update
+//! leader (the canary) only announces its new package once it has stayed healthy on it for
+//! `canary_soak` seconds.
+//!
+//! Whatever the strategy, an update is only applied while one of the `windows` is open, and
+//! after a random delay of up to `splay` seconds, so that a large number of Supervisors don't
+//! all restart their services at once.
+//!
+//! If `rollback_period` is set, a service that doesn't come up healthy on its new package is
+//! rolled back to the previous one (see the `rollback` module).
+
+use std::fmt;
+use std::result;
+use std::str::FromStr;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use rand::{thread_rng, Rng};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::error::{Error, SupError};
+pub use crate::protocol::types::UpdateWindow;
+use crate::protocol::{
+    self,
+    net::{self, ErrCode, NetResult},
+};
+
+static LOGKEY: &str = "UP";
+
+const DEFAULT_CANARY_SOAK_SECS: u64 = 300;
+/// How often a held update checks whether it may be applied yet.
+const WINDOW_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(default)]
+pub struct UpdateSettings {
+    /// How many members update at a time.
+    pub batch_size: BatchSize,
+    /// Seconds the canary must stay healthy on the new package before the rest of the group
+    /// updates.
+    pub canary_soak: u64,
+    /// Periods during which updates may be applied. Updates may be applied at any time if
+    /// there are none.
+    pub windows: Vec<UpdateWindow>,
+    /// Upper bound, in seconds, of the random delay before polling for and applying updates.
+    pub splay: u64,
+    /// Seconds after an update during which a failing service is rolled back to its previous
+    /// package. Zero disables rollbacks.
+    pub rollback_period: u64,
+}
+
+impl UpdateSettings {
+    /// Check the fields of the given protocol message which `merge` parses, so that a request
+    /// with an invalid one can be refused rather than have it ignored.
+    pub fn validate(proto: &protocol::types::UpdateSettings) -> NetResult<()> {
+        if let Some(ref batch_size) = proto.batch_size {
+            batch_size
+                .parse::<BatchSize>()
+                .map_err(|e| net::err(ErrCode::InvalidPayload, e.to_string()))?;
+        }
+        for window in proto.windows.iter() {
+            window.parse::<UpdateWindow>()?;
+        }
+        Ok(())
+    }
+
+    /// Overwrite any fields that were set in the given protocol message. Invalid fields are
+    /// ignored; requests are checked with `validate` before they get here.
+    pub fn merge(&mut self, proto: &protocol::types::UpdateSettings) {
+        if let Some(ref batch_size) = proto.batch_size {
+            match batch_size.parse() {
+                Ok(batch_size) => self.batch_size = batch_size,
+                Err(err) => warn!("Ignoring update batch size: {}", err),
+            }
+        }
+        if let Some(canary_soak) = proto.canary_soak {
+            self.canary_soak = canary_soak;
+        }
+        if !proto.windows.is_empty() {
+            let windows: result::Result<Vec<UpdateWindow>, _> =
+                proto.windows.iter().map(|w| w.parse()).collect();
+            match windows {
+                Ok(windows) => self.windows = windows,
+                Err(err) => warn!("Ignoring update windows: {}", err),
+            }
+        }
+        if let Some(splay) = proto.splay {
+            self.splay = splay;
+        }
+        if let Some(rollback_period) = proto.rollback_period {
+            self.rollback_period = rollback_period;
+        }
+    }
+
+    pub fn canary_soak(&self) -> Duration {
+        Duration::from_secs(self.canary_soak)
+    }
+
+    /// How long to watch a service after an update, if failed updates are to be rolled back.
+    pub fn rollback_period(&self) -> Option<Duration> {
+        if self.rollback_period == 0 {
+            None
+        } else {
+            Some(Duration::from_secs(self.rollback_period))
+        }
+    }
+}
+
+impl Default for UpdateSettings {
+    fn default() -> Self {
+        UpdateSettings {
+            batch_size: BatchSize::default(),
+            canary_soak: DEFAULT_CANARY_SOAK_SECS,
+            windows: Vec::new(),
+            splay: 0,
+            rollback_period: 0,
+        }
+    }
+}
+
+/// Whether one of the windows is open at the given UTC time. Updates may be applied at any time
+/// if there are no windows.
+pub fn window_open(windows: &[UpdateWindow], now: &time::Tm) -> bool {
+    let weekday = ((now.tm_wday + 6) % 7) as u8;
+    let minute = (now.tm_hour * 60 + now.tm_min) as u16;
+    windows.is_empty() || windows.iter().any(|w| w.contains(weekday, minute))
+}
+
+/// A random delay of up to `splay` seconds.
+pub fn random_splay(splay: u64) -> Duration {
+    if splay == 0 {
+        return Duration::from_secs(0);
+    }
+    Duration::from_millis(thread_rng().gen_range(0, splay * 1000 + 1))
+}
+
+/// Blocks until an update may be applied: one of the windows is open, and a random delay of up
+/// to `splay` seconds has passed since it opened. Returns `false`, without waiting any longer,
+/// as soon as `cancelled` returns `true`.
+pub fn wait_for_window<F>(windows: &[UpdateWindow], splay: u64, mut cancelled: F) -> bool
+where
+    F: FnMut() -> bool,
+{
+    loop {
+        while !window_open(windows, &time::now_utc()) {
+            if cancelled() {
+                return false;
+            }
+            thread::sleep(WINDOW_CHECK_INTERVAL);
+        }
+        let apply_at = Instant::now() + random_splay(splay);
+        loop {
+            let now = Instant::now();
+            if now >= apply_at {
+                break;
+            }
+            if cancelled() {
+                return false;
+            }
+            thread::sleep(WINDOW_CHECK_INTERVAL.min(apply_at - now));
+        }
+        // The window may have closed while we waited out the splay.
+        if window_open(windows, &time::now_utc()) {
+            return true;
+        }
+    }
+}
+
+/// The number of members that update at the same time, either as a count or as a percentage of
+/// the service group.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum BatchSize {
+    Count(u32),
+    Percent(u8),
+}
+
+impl BatchSize {
+    /// The number of members in each batch for a group of the given size. Every batch has at
+    /// least one member.
+    pub fn members(self, group_size: usize) -> usize {
+        let members = match self {
+            BatchSize::Count(count) => count as usize,
+            BatchSize::Percent(percent) => (group_size * percent as usize + 99) / 100,
+        };
+        members.max(1)
+    }
+}
+
+impl Default for BatchSize {
+    fn default() -> Self {
+        BatchSize::Count(1)
+    }
+}
+
+impl FromStr for BatchSize {
+    type Err = SupError;
+
+    fn from_str(value: &str) -> result::Result<Self, Self::Err> {
+        let invalid = || {
+            sup_error!(Error::InvalidUpdateBatchSize(format!(
+                "{}; expected a number of members or a percentage, like 3 or 20%",
+                value
+            )))
+        };
+        let value = value.trim();
+        let batch_size = if value.ends_with('%') {
+            match value.trim_end_matches('%').parse() {
+                Ok(percent) if percent > 0 && percent <= 100 => BatchSize::Percent(percent),
+                _ => return Err(invalid()),
+            }
+        } else {
+            match value.parse() {
+                Ok(count) if count > 0 => BatchSize::Count(count),
+                _ => return Err(invalid()),
+            }
+        };
+        Ok(batch_size)
+    }
+}
+
+impl fmt::Display for BatchSize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            BatchSize::Count(count) => write!(f, "{}", count),
+            BatchSize::Percent(percent) => write!(f, "{}%", percent),
+        }
+    }
+}
+
+impl Serialize for BatchSize {
+    fn serialize<S>(&self, serializer: S) -> result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for BatchSize {
+    fn deserialize<D>(deserializer: D) -> result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value = String::deserialize(deserializer)?;
+        value.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn batch_size_from_str() {
+        assert_eq!("3".parse::<BatchSize>().unwrap(), BatchSize::Count(3));
+        assert_eq!("20%".parse::<BatchSize>().unwrap(), BatchSize::Percent(20));
+        assert_eq!(
+            " 100% ".parse::<BatchSize>().unwrap(),
+            BatchSize::Percent(100)
+        );
+    }
+
+    #[test]
+    fn batch_size_from_str_invalid() {
+        for value in &["", "0", "0%", "101%", "-1", "%", "twenty", "2.5"] {
+            assert!(
+                value.parse::<BatchSize>().is_err(),
+                "{} should not parse",
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn batch_size_to_string() {
+        assert_eq!(BatchSize::Count(3).to_string(), "3");
+        assert_eq!(BatchSize::Percent(20).to_string(), "20%");
+    }
+
+    #[test]
+    fn batch_size_members() {
+        assert_eq!(BatchSize::Count(3).members(10), 3);
+        assert_eq!(BatchSize::Percent(20).members(10), 2);
+        assert_eq!(BatchSize::Percent(20).members(11), 3);
+        assert_eq!(BatchSize::Percent(1).members(3), 1);
+        assert_eq!(BatchSize::Percent(100).members(0), 1);
+    }
+
+    #[test]
+    fn merge_overwrites_only_set_fields() {
+        let mut settings = UpdateSettings::default();
+        settings.merge(&protocol::types::UpdateSettings {
+            batch_size: Some("25%".to_string()),
+            windows: vec!["sat,sun 01:00-05:00".to_string()],
+            ..protocol::types::UpdateSettings::default()
+        });
+        assert_eq!(settings.batch_size, BatchSize::Percent(25));
+        assert_eq!(settings.canary_soak, DEFAULT_CANARY_SOAK_SECS);
+        assert_eq!(
+            settings.windows,
+            vec!["sat,sun 01:00-05:00".parse::<UpdateWindow>().unwrap()]
+        );
+        assert_eq!(settings.splay, 0);
+        assert_eq!(settings.rollback_period(), None);
+    }
+
+    #[test]
+    fn validate_rejects_invalid_settings() {
+        let valid = protocol::types::UpdateSettings {
+            batch_size: Some("20%".to_string()),
+            windows: vec!["sat,sun 01:00-05:00".to_string()],
+            ..protocol::types::UpdateSettings::default()
+        };
+        assert!(UpdateSettings::validate(&valid).is_ok());
+
+        let invalid_batch_size = protocol::types::UpdateSettings {
+            batch_size: Some("0".to_string()),
+            ..protocol::types::UpdateSettings::default()
+        };
+        let err = UpdateSettings::validate(&invalid_batch_size).unwrap_err();
+        assert_eq!(err.code, ErrCode::InvalidPayload as i32);
+
+        let invalid_windows = protocol::types::UpdateSettings {
+            windows: vec!["01:00-05:00".to_string(), "whenever".to_string()],
+            ..protocol::types::UpdateSettings::default()
+        };
+        let err = UpdateSettings::validate(&invalid_windows).unwrap_err();
+        assert_eq!(err.code, ErrCode::InvalidPayload as i32);
+    }
+
+    #[test]
+    fn merge_ignores_invalid_windows() {
+        let mut settings = UpdateSettings::default();
+        settings.merge(&protocol::types::UpdateSettings {
+            windows: vec!["01:00-05:00".to_string(), "whenever".to_string()],
+            ..protocol::types::UpdateSettings::default()
+        });
+        assert!(settings.windows.is_empty());
+    }
+
+    fn at(weekday: i32, hour: i32, minute: i32) -> time::Tm {
+        time::Tm {
+            tm_wday: weekday,
+            tm_hour: hour,
+            tm_min: minute,
+            ..time::empty_tm()
+        }
+    }
+
+    #[test]
+    fn no_windows_are_always_open() {
+        assert!(window_open(&[], &at(0, 12, 0)));
+    }
+
+    #[test]
+    fn window_open_uses_the_utc_weekday_and_time() {
+        let windows = vec![
+            "sat,sun 01:00-05:00".parse::<UpdateWindow>().unwrap(),
+            "wed 12:00-13:00".parse::<UpdateWindow>().unwrap(),
+        ];
+        // `tm_wday` counts from Sunday
+        assert!(window_open(&windows, &at(0, 1, 30)));
+        assert!(window_open(&windows, &at(3, 12, 59)));
+        assert!(!window_open(&windows, &at(1, 1, 30)));
+        assert!(!window_open(&windows, &at(3, 13, 0)));
+    }
+
+    #[test]
+    fn random_splay_stays_within_bounds() {
+        assert_eq!(random_splay(0), Duration::from_secs(0));
+        for _ in 0..100 {
+            assert!(random_splay(5) <= Duration::from_secs(5));
+        }
+    }
+
+    #[test]
+    fn wait_for_window_gives_up_when_cancelled() {
+        // Open all day, every day, so only the splay can hold the update up
+        let always_open = vec!["00:00-00:00".parse::<UpdateWindow>().unwrap()];
+        assert!(!wait_for_window(&always_open, 3600, || true));
+        assert!(wait_for_window(&always_open, 0, || true));
+    }
+
+    #[test]
+    fn update_settings_toml_round_trip() {
+        let settings = UpdateSettings {
+            batch_size: BatchSize::Percent(20),
+            canary_soak: 60,
+            windows: vec!["mon-fri 22:00-02:00".parse().unwrap()],
+            splay: 120,
+            rollback_period: 600,
+        };
+        let toml = toml::to_string(&settings).unwrap();
+        assert!(toml.contains(r#"batch_size = "20%""#));
+        assert_eq!(toml::from_str::<UpdateSettings>(&toml).unwrap(), settings);
+    }
+}
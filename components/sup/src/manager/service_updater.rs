@@ -14,12 +14,13 @@
 
 use std::cmp::{Ordering, PartialOrd};
 use std::collections::HashMap;
+use std::mem;
 use std::num::ParseIntError;
 use std::result;
 use std::str::FromStr;
 use std::sync::mpsc::{channel, Receiver, Sender, TryRecvError};
 use std::thread;
-use std::time;
+use std::time::{self, Instant};
 
 use time_crate::Duration;
 
@@ -31,10 +32,11 @@ use crate::hcore::service::ServiceGroup;
 use crate::hcore::ChannelIdent;
 use crate::launcher_client::LauncherCli;
 
-use crate::census::CensusRing;
+use crate::census::{CensusMember, CensusRing};
 use crate::common::types::EnvConfig;
 use crate::manager::periodic::Periodic;
-use crate::manager::service::{Service, Topology, UpdateStrategy};
+use crate::manager::service::update::{self, UpdateWindow};
+use crate::manager::service::{BatchSize, Service, Topology, UpdateStrategy};
 use crate::util;
 use time_crate::SteadyTime;
 
@@ -62,6 +64,9 @@ enum RollingState {
 
 enum LeaderState {
     Polling(Receiver<PackageInstall>, Sender<()>),
+    /// The canary has updated, and holds back its new package until it has been healthy on it
+    /// for long enough. Records when it was last seen becoming healthy.
+    Soaking(Option<Instant>),
     Waiting,
 }
 
@@ -106,7 +111,7 @@ impl ServiceUpdater {
                     });
                 true
             }
-            UpdateStrategy::Rolling => {
+            UpdateStrategy::Rolling | UpdateStrategy::Batched | UpdateStrategy::Canary => {
                 self.states
                     .entry(service.service_group.clone())
                     .or_insert(UpdaterState::Rolling(RollingState::AwaitingElection));
@@ -130,7 +135,7 @@ impl ServiceUpdater {
                             debug!("Tried to kill the updater thread but it's already dead.");
                         }
                     }
-                    LeaderState::Waiting => {}
+                    LeaderState::Soaking(_) | LeaderState::Waiting => {}
                 },
                 RollingState::Follower(fs) => match fs {
                     FollowerState::Updating(_rx, kill_tx) => {
@@ -151,6 +156,29 @@ impl ServiceUpdater {
         }
     }
 
+    /// Restarts the worker polling for updates to the service, so that it checks right away.
+    /// Returns `false` if the service isn't polling for updates at the moment, e.g. because it
+    /// follows an update leader.
+    fn restart_polling(&mut self, service: &Service) -> bool {
+        let (rx, kill_tx) = match self.states.get_mut(&service.service_group) {
+            Some(&mut UpdaterState::AtOnce(ref mut rx, ref mut kill_tx))
+            | Some(&mut UpdaterState::Rolling(RollingState::Leader(LeaderState::Polling(
+                ref mut rx,
+                ref mut kill_tx,
+            )))) => (rx, kill_tx),
+            _ => return false,
+        };
+        if kill_tx.send(()).is_err() {
+            debug!("Tried to kill the updater thread but it's already dead.");
+        }
+        let (ktx, krx) = channel();
+        *rx = Worker::new(service)
+            .without_splay()
+            .start(&service.service_group, None, krx);
+        *kill_tx = ktx;
+        true
+    }
+
     /// See if the given service has an update. Returns `true` if a
     /// new version was installed, thus signalling that the service
     /// should be restarted
@@ -160,6 +188,19 @@ impl ServiceUpdater {
         census_ring: &CensusRing,
         launcher: &LauncherCli,
     ) -> bool {
+        if mem::replace(&mut service.update_check_requested, false) {
+            if self.restart_polling(service) {
+                outputln!(preamble service.service_group, "Checking for updates now");
+            } else {
+                outputln!(preamble service.service_group,
+                          "Not checking for updates now; this member is taking part in a \
+                           coordinated update, or follows its update leader");
+            }
+        }
+        // Anything found while updates are paused waits until they are resumed.
+        if service.update_paused {
+            return false;
+        }
         let mut updated = false;
         match self.states.get_mut(&service.service_group) {
             Some(&mut UpdaterState::AtOnce(ref mut rx, ref mut kill_tx)) => match rx.try_recv() {
@@ -213,11 +254,19 @@ impl ServiceUpdater {
                         (Some(me), Some(leader)) => {
                             if me.member_id == leader.member_id {
                                 debug!("We're the leader");
-                                // Start in waiting state to ensure all members agree with our
-                                // version before attempting a new rolling upgrade.
-                                *st = RollingState::Leader(LeaderState::Waiting);
+                                if service.soaking() {
+                                    // Picks up the soak of a canary from before a restart.
+                                    *st = RollingState::Leader(LeaderState::Soaking(None));
+                                } else {
+                                    // Start in waiting state to ensure all members agree with
+                                    // our version before attempting a new rolling upgrade.
+                                    *st = RollingState::Leader(LeaderState::Waiting);
+                                }
                             } else {
                                 debug!("We're a follower");
+                                // Another member leads updates now; whatever this one was
+                                // soaking no longer holds the group back.
+                                service.finish_soaking();
                                 *st = RollingState::Follower(FollowerState::Waiting);
                             }
                         }
@@ -227,11 +276,22 @@ impl ServiceUpdater {
                 }
             }
             Some(&mut UpdaterState::Rolling(RollingState::Leader(ref mut state))) => {
+                let mut soaking = false;
+                let mut rolled_back = false;
                 match *state {
                     LeaderState::Polling(ref mut rx, ref mut kill_tx) => match rx.try_recv() {
                         Ok(package) => {
                             debug!("Rolling Update, polling found a new package");
+                            let previous = service.pkg.ident.clone();
                             service.update_package(package, launcher);
+                            if service.update_strategy == UpdateStrategy::Canary {
+                                outputln!(preamble service.service_group,
+                                          "Canary updated; waiting for it to stay healthy for \
+                                           {}s before the rest of the group updates",
+                                          service.update_settings.canary_soak);
+                                service.start_soaking(previous);
+                                soaking = true;
+                            }
                             updated = true;
                         }
                         Err(TryRecvError::Empty) => return false,
@@ -242,6 +302,25 @@ impl ServiceUpdater {
                             *kill_tx = ktx;
                         }
                     },
+                    LeaderState::Soaking(ref mut healthy_since) => {
+                        if !service.soaking() {
+                            // The canary failed and was rolled back, which ends the roll-out.
+                            rolled_back = true;
+                        } else if !service.healthy() {
+                            *healthy_since = None;
+                            return false;
+                        } else {
+                            let since = *healthy_since.get_or_insert_with(Instant::now);
+                            if since.elapsed() < service.update_settings.canary_soak() {
+                                return false;
+                            }
+                            outputln!(preamble service.service_group,
+                                      "Canary stayed healthy on {}; updating the rest of the group",
+                                      service.pkg.ident);
+                            service.finish_soaking();
+                            updated = true;
+                        }
+                    }
                     LeaderState::Waiting => match census_ring
                         .census_group_for(&service.service_group)
                     {
@@ -264,39 +343,63 @@ impl ServiceUpdater {
                         ),
                     },
                 }
-                if updated {
+                if soaking {
+                    *state = LeaderState::Soaking(None);
+                } else if updated || rolled_back {
                     *state = LeaderState::Waiting;
                 }
             }
             Some(&mut UpdaterState::Rolling(RollingState::Follower(ref mut state))) => {
+                let mut abandoned = false;
                 match *state {
                     FollowerState::Waiting => {
                         match census_ring.census_group_for(&service.service_group) {
-                            Some(census_group) => match (
-                                census_group.update_leader(),
-                                census_group.previous_peer(),
-                                census_group.me(),
-                            ) {
-                                (Some(leader), Some(peer), Some(me)) => {
-                                    if leader.pkg == me.pkg {
-                                        debug!("We're not in an update");
-                                        return false;
+                            Some(census_group) => {
+                                match (census_group.update_leader(), census_group.me()) {
+                                    (Some(leader), Some(me)) => {
+                                        if leader.pkg == me.pkg {
+                                            debug!("We're not in an update");
+                                            return false;
+                                        }
+                                        if leader.soaking() {
+                                            debug!("The update leader's canary is soaking");
+                                            return false;
+                                        }
+                                        if let Some(ref pkg) = leader.pkg {
+                                            if service.rejected_release() == Some(pkg) {
+                                                debug!("We rolled back from {} before", pkg);
+                                                return false;
+                                            }
+                                        }
+                                        let our_turn = match service.update_strategy {
+                                            UpdateStrategy::Batched | UpdateStrategy::Canary => {
+                                                batch_turn(
+                                                    census_group.members().filter(|cm| cm.alive()),
+                                                    leader,
+                                                    me,
+                                                    service.update_settings.batch_size,
+                                                )
+                                            }
+                                            _ => census_group
+                                                .previous_peer()
+                                                .map_or(false, |peer| leader.pkg == peer.pkg),
+                                        };
+                                        if !our_turn {
+                                            debug!("We're in an update but it's not our turn");
+                                            return false;
+                                        }
+                                        debug!("We're in an update and it's our turn");
+                                        let (kill_tx, kill_rx) = channel();
+                                        let rx = Worker::new(service).start(
+                                            &service.service_group,
+                                            leader.pkg.clone(),
+                                            kill_rx,
+                                        );
+                                        *state = FollowerState::Updating(rx, kill_tx);
                                     }
-                                    if leader.pkg != peer.pkg {
-                                        debug!("We're in an update but it's not our turn");
-                                        return false;
-                                    }
-                                    debug!("We're in an update and it's our turn");
-                                    let (kill_tx, kill_rx) = channel();
-                                    let rx = Worker::new(service).start(
-                                        &service.service_group,
-                                        leader.pkg.clone(),
-                                        kill_rx,
-                                    );
-                                    *state = FollowerState::Updating(rx, kill_tx);
+                                    _ => return false,
                                 }
-                                _ => return false,
-                            },
+                            }
                             None => panic!(
                                 "Expected census list to have service group '{}'!",
                                 &*service.service_group
@@ -307,8 +410,20 @@ impl ServiceUpdater {
                         match census_ring.census_group_for(&service.service_group) {
                             Some(census_group) => match rx.try_recv() {
                                 Ok(package) => {
-                                    service.update_package(package, launcher);
-                                    updated = true
+                                    let leader_pkg = census_group
+                                        .update_leader()
+                                        .and_then(|leader| leader.pkg.as_ref());
+                                    if leader_pkg == Some(package.ident()) {
+                                        service.update_package(package, launcher);
+                                        updated = true
+                                    } else {
+                                        // The update leader rolled back, or moved on, while we
+                                        // were fetching its package.
+                                        outputln!(preamble service.service_group,
+                                                  "Update leader no longer runs {}; not updating",
+                                                  package.ident());
+                                        abandoned = true
+                                    }
                                 }
                                 Err(TryRecvError::Empty) => return false,
                                 Err(TryRecvError::Disconnected) => {
@@ -330,7 +445,7 @@ impl ServiceUpdater {
                         }
                     }
                 }
-                if updated {
+                if updated || abandoned {
                     *state = FollowerState::Waiting;
                 }
             }
@@ -340,6 +455,27 @@ impl ServiceUpdater {
     }
 }
 
+/// Whether it's `me`'s turn to update in a batched roll-out. Followers of the update leader
+/// update in batches, in member id order, and a batch may go once every follower in the batches
+/// before it runs the update leader's package.
+fn batch_turn<'a>(
+    members: impl Iterator<Item = &'a CensusMember>,
+    leader: &CensusMember,
+    me: &CensusMember,
+    batch_size: BatchSize,
+) -> bool {
+    let followers: Vec<&CensusMember> = members
+        .filter(|cm| cm.member_id != leader.member_id)
+        .collect();
+    let batch = batch_size.members(followers.len() + 1);
+    match followers.iter().position(|cm| cm.member_id == me.member_id) {
+        Some(position) => followers[..position / batch * batch]
+            .iter()
+            .all(|cm| cm.pkg == leader.pkg),
+        None => false,
+    }
+}
+
 /// Represents how far apart checks for updates to individual services
 /// are, in milliseconds.
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -395,6 +531,10 @@ struct Worker {
     spec_ident: PackageIdent,
     builder_url: String,
     channel: ChannelIdent,
+    windows: Vec<UpdateWindow>,
+    splay: u64,
+    /// A release that was rolled back, and must not be updated to again.
+    rejected: Option<PackageIdent>,
 }
 
 impl Periodic for Worker {
@@ -417,7 +557,32 @@ impl Worker {
             spec_ident: service.spec_ident.clone(),
             builder_url: service.bldr_url.clone(),
             channel: service.channel.clone(),
+            windows: service.update_settings.windows.clone(),
+            splay: service.update_settings.splay,
+            rejected: service.rejected_release().cloned(),
+        }
+    }
+
+    /// Applies updates as soon as they are found, and update windows permit, for checks an
+    /// operator asked for.
+    fn without_splay(mut self) -> Self {
+        self.splay = 0;
+        self
+    }
+
+    /// Blocks until the update to `ident` may be applied. Returns `false` if the worker was
+    /// killed in the meantime.
+    fn wait_for_window(&self, ident: &PackageIdent, kill_rx: &Receiver<()>) -> bool {
+        if !update::window_open(&self.windows, &time_crate::now_utc()) {
+            outputln!(
+                "Holding the update to {} until an update window opens",
+                ident
+            );
         }
+        update::wait_for_window(&self.windows, self.splay, || match kill_rx.try_recv() {
+            Err(TryRecvError::Empty) => false,
+            Ok(_) | Err(TryRecvError::Disconnected) => true,
+        })
     }
 
     /// Start a new update worker.
@@ -499,8 +664,10 @@ impl Worker {
                     &self.channel,
                 ) {
                     Ok(package) => {
-                        self.current = package.ident().clone();
-                        sender.send(package).expect("Main thread has gone away!");
+                        if self.wait_for_window(package.ident(), &kill_rx) {
+                            self.current = package.ident().clone();
+                            sender.send(package).expect("Main thread has gone away!");
+                        }
                         break;
                     }
                     Err(e) => warn!("Failed to install updated package: {:?}", e),
@@ -517,7 +684,10 @@ impl Worker {
     /// when found.
     fn run_poll(&mut self, sender: Sender<PackageInstall>, kill_rx: Receiver<()>) {
         let install_source = (self.spec_ident.clone(), *PackageTarget::active_target()).into();
-        let mut next_time = SteadyTime::now();
+        // Spread out the polling of Supervisors that started at the same time.
+        let mut next_time = SteadyTime::now()
+            + Duration::from_std(update::random_splay(self.splay))
+                .unwrap_or_else(|_| Duration::zero());
 
         loop {
             match kill_rx.try_recv() {
@@ -541,7 +711,15 @@ impl Worker {
                     &self.channel,
                 ) {
                     Ok(maybe_newer_package) => {
-                        if self.current < *maybe_newer_package.ident() {
+                        if self.rejected.as_ref() == Some(maybe_newer_package.ident()) {
+                            debug!(
+                                "Package found {} was rolled back from before",
+                                maybe_newer_package.ident()
+                            );
+                        } else if self.current < *maybe_newer_package.ident() {
+                            if !self.wait_for_window(maybe_newer_package.ident(), &kill_rx) {
+                                break;
+                            }
                             outputln!(
                                 "Updating from {} to {}",
                                 self.current,
@@ -591,9 +769,80 @@ mod tests {
             spec_ident: "core/testing".parse().expect("Can't parse ident!"),
             builder_url: String::from("https://bldr.habitat.sh"),
             channel: ChannelIdent::stable(),
+            windows: vec![],
+            splay: 0,
+            rejected: None,
         }
     }
 
+    fn member(id: &str, pkg: &str) -> CensusMember {
+        let mut member = CensusMember::default();
+        member.member_id = id.to_string();
+        member.pkg = Some(pkg.parse().expect("Can't parse ident!"));
+        member
+    }
+
+    #[test]
+    fn batch_turn_waits_for_earlier_batches() {
+        let leader = member("a", "core/testing/2.0.0/20181109125930");
+        let members = vec![
+            leader.clone(),
+            member("b", "core/testing/2.0.0/20181109125930"),
+            member("c", "core/testing/1.0.0/20181109125930"),
+            member("d", "core/testing/1.0.0/20181109125930"),
+            member("e", "core/testing/1.0.0/20181109125930"),
+        ];
+        let turn =
+            |me: usize| batch_turn(members.iter(), &leader, &members[me], BatchSize::Count(2));
+
+        // b and c make up the first batch, d and e the second
+        assert!(turn(1));
+        assert!(turn(2));
+        assert!(!turn(3));
+        assert!(!turn(4));
+    }
+
+    #[test]
+    fn batch_turn_goes_once_earlier_batches_have_updated() {
+        let leader = member("a", "core/testing/2.0.0/20181109125930");
+        let members = vec![
+            leader.clone(),
+            member("b", "core/testing/2.0.0/20181109125930"),
+            member("c", "core/testing/2.0.0/20181109125930"),
+            member("d", "core/testing/1.0.0/20181109125930"),
+            member("e", "core/testing/1.0.0/20181109125930"),
+        ];
+        let turn =
+            |me: usize| batch_turn(members.iter(), &leader, &members[me], BatchSize::Count(2));
+
+        assert!(turn(3));
+        assert!(turn(4));
+    }
+
+    #[test]
+    fn batch_turn_sizes_percentages_by_the_whole_group() {
+        let leader = member("a", "core/testing/2.0.0/20181109125930");
+        let members = vec![
+            leader.clone(),
+            member("b", "core/testing/1.0.0/20181109125930"),
+            member("c", "core/testing/1.0.0/20181109125930"),
+            member("d", "core/testing/1.0.0/20181109125930"),
+            member("e", "core/testing/1.0.0/20181109125930"),
+        ];
+        let turn = |me: usize| {
+            batch_turn(
+                members.iter(),
+                &leader,
+                &members[me],
+                BatchSize::Percent(40),
+            )
+        };
+
+        assert!(turn(1));
+        assert!(turn(2));
+        assert!(!turn(3));
+    }
+
     #[test]
     fn service_update_period_must_be_positive() {
         assert!(ServiceUpdatePeriod::from_str("-123").is_err());